@@ -10,7 +10,7 @@ use indradb::util::{
     generate_uuid_v1,
 };
 use indradb::{
-    AllEdgeQuery, AllVertexQuery, CountQueryExt, Edge, Error, Identifier, Json, QueryExt, SpecificEdgeQuery,
+    AllEdgeQuery, AllVertexQuery, CountQueryExt, Edge, Error, Identifier, Json, Query, QueryExt, SpecificEdgeQuery,
     SpecificVertexQuery, Vertex,
 };
 use indradb_proto as proto;
@@ -43,6 +43,23 @@ pub async fn main() -> Result<(), Box<dyn StdError>> {
         .help("the property value as JSON")
         .required(true);
 
+    let get_subcommands = vec![
+        SubCommand::with_name("vertex")
+            .about("gets vertices by query")
+            .arg(&vertex_id_arg),
+        SubCommand::with_name("edge")
+            .about("gets edges by query")
+            .args(&edge_query_arg),
+        SubCommand::with_name("vertex-property")
+            .about("gets vertex properties")
+            .arg(&vertex_id_arg)
+            .arg(&optional_property_name_arg),
+        SubCommand::with_name("edge-property")
+            .about("gets edge properties")
+            .args(&edge_query_arg)
+            .arg(&optional_property_name_arg),
+    ];
+
     let matches = App::new("indradb-client")
         .setting(AppSettings::SubcommandRequiredElseHelp)
         .arg(
@@ -96,29 +113,28 @@ pub async fn main() -> Result<(), Box<dyn StdError>> {
         .subcommand(
             SubCommand::with_name("get")
                 .setting(AppSettings::SubcommandRequiredElseHelp)
-                .subcommand(
-                    SubCommand::with_name("vertex")
-                        .about("gets vertices by query")
-                        .arg(&vertex_id_arg),
-                )
-                .subcommand(
-                    SubCommand::with_name("edge")
-                        .about("gets edges by query")
-                        .args(&edge_query_arg),
-                )
-                .subcommand(
-                    SubCommand::with_name("vertex-property")
-                        .about("gets vertex properties")
-                        .arg(&vertex_id_arg)
-                        .arg(&optional_property_name_arg),
-                )
-                .subcommand(
-                    SubCommand::with_name("edge-property")
-                        .about("gets edge properties")
-                        .args(&edge_query_arg)
-                        .arg(&optional_property_name_arg),
+                .subcommands(get_subcommands.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("query")
+                .about("gets values by a query written in the text query language")
+                .arg(
+                    Arg::with_name("text")
+                        .help("the query, e.g. 'V(type=person).out(follows).props()'")
+                        .required(true),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("explain")
+                .about("explains how a get would be run")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .arg(
+                    Arg::with_name("analyze")
+                        .help("runs the query, and includes the actual rows and time taken by each stage")
+                        .long("analyze"),
+                )
+                .subcommands(get_subcommands),
+        )
         .subcommand(
             SubCommand::with_name("delete")
                 .setting(AppSettings::SubcommandRequiredElseHelp)
@@ -202,38 +218,29 @@ async fn run(matches: clap::ArgMatches<'_>) -> Result<(), Box<dyn StdError>> {
             println!("{}", extract_count(output).unwrap());
         }
     } else if let Some(matches) = matches.subcommand_matches("get") {
-        if let Some(matches) = matches.subcommand_matches("vertex") {
-            let vertex_query = build_vertex_query(matches)?;
-            let output = client.get(vertex_query).await?;
-            println!("{:?}", extract_vertices(output));
-        } else if let Some(matches) = matches.subcommand_matches("edge") {
-            let edge_query = SpecificEdgeQuery::single(build_edge(matches)?);
-            let output = client.get(edge_query).await?;
-            println!("{:?}", extract_edges(output));
-        } else if let Some(matches) = matches.subcommand_matches("vertex-property") {
-            let property_name = matches.value_of("name");
-            let q = match property_name {
-                Some(property_name) => {
-                    let property_name = Identifier::new(property_name)?;
-                    build_vertex_query(matches)?.properties()?.name(property_name)
-                }
-                None => build_vertex_query(matches)?.properties()?,
-            };
-            let output = client.get(q).await?;
-            println!("{:?}", extract_vertex_properties(output));
-        } else if let Some(matches) = matches.subcommand_matches("edge-property") {
-            let property_name = matches.value_of("name");
-            let edge_query = SpecificEdgeQuery::single(build_edge(matches)?);
-            let q = match property_name {
-                Some(property_name) => {
-                    let property_name = Identifier::new(property_name)?;
-                    edge_query.properties()?.name(property_name)
-                }
-                None => edge_query.properties()?,
-            };
-            let output = client.get(q).await?;
-            println!("{:?}", extract_edge_properties(output));
+        let (name, submatches) = matches.subcommand();
+        let output = client.get(build_get_query(name, submatches.unwrap())?).await?;
+        match name {
+            "vertex" => println!("{:?}", extract_vertices(output)),
+            "edge" => println!("{:?}", extract_edges(output)),
+            "vertex-property" => println!("{:?}", extract_vertex_properties(output)),
+            "edge-property" => println!("{:?}", extract_edge_properties(output)),
+            _ => unreachable!(),
+        }
+    } else if let Some(matches) = matches.subcommand_matches("query") {
+        let output = client.query(matches.value_of("text").unwrap()).await?;
+        for value in output {
+            println!("{value:?}");
         }
+    } else if let Some(matches) = matches.subcommand_matches("explain") {
+        let (name, submatches) = matches.subcommand();
+        let q = build_get_query(name, submatches.unwrap())?;
+        let plan = if matches.is_present("analyze") {
+            client.analyze(q).await?
+        } else {
+            client.explain(q).await?
+        };
+        print!("{plan}");
     } else if let Some(matches) = matches.subcommand_matches("delete") {
         if let Some(matches) = matches.subcommand_matches("vertex") {
             let q = build_vertex_query(matches)?;
@@ -257,6 +264,30 @@ async fn run(matches: clap::ArgMatches<'_>) -> Result<(), Box<dyn StdError>> {
     Ok(())
 }
 
+fn build_get_query(name: &str, matches: &clap::ArgMatches) -> Result<Query, Box<dyn StdError>> {
+    let property_name = matches.value_of("name").map(Identifier::new).transpose()?;
+    let q: Query = match name {
+        "vertex" => build_vertex_query(matches)?.into(),
+        "edge" => SpecificEdgeQuery::single(build_edge(matches)?).into(),
+        "vertex-property" => {
+            let q = build_vertex_query(matches)?.properties()?;
+            match property_name {
+                Some(property_name) => q.name(property_name).into(),
+                None => q.into(),
+            }
+        }
+        "edge-property" => {
+            let q = SpecificEdgeQuery::single(build_edge(matches)?).properties()?;
+            match property_name {
+                Some(property_name) => q.name(property_name).into(),
+                None => q.into(),
+            }
+        }
+        _ => unreachable!(),
+    };
+    Ok(q)
+}
+
 fn build_vertex_query(matches: &clap::ArgMatches) -> Result<SpecificVertexQuery, Box<dyn StdError>> {
     let vertex_id = Uuid::parse_str(matches.value_of("uuid").unwrap())?;
     Ok(SpecificVertexQuery::single(vertex_id))
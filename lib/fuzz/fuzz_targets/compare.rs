@@ -150,6 +150,7 @@ impl Into<indradb::RangeVertexQuery> for RangeVertexQuery {
             limit: self.limit,
             t: self.t.map(|t| t.into()),
             start_id: self.start_id.map(|id| id.into()),
+            limit_param: None,
         }
     }
 }
@@ -163,6 +164,7 @@ impl Into<indradb::SpecificVertexQuery> for SpecificVertexQuery {
     fn into(self) -> indradb::SpecificVertexQuery {
         indradb::SpecificVertexQuery {
             ids: self.ids.into_iter().map(|i| i.into()).collect(),
+            ids_param: None,
         }
     }
 }
@@ -191,6 +193,7 @@ impl Into<indradb::VertexWithPropertyValueQuery> for VertexWithPropertyValueQuer
         indradb::VertexWithPropertyValueQuery {
             name: self.name.into(),
             value: indradb::Json::new(self.value.into()),
+            value_param: None,
         }
     }
 }
@@ -204,6 +207,7 @@ impl Into<indradb::SpecificEdgeQuery> for SpecificEdgeQuery {
     fn into(self) -> indradb::SpecificEdgeQuery {
         indradb::SpecificEdgeQuery {
             edges: self.edges.into_iter().map(|e| e.into()).collect(),
+            edges_param: None,
         }
     }
 }
@@ -232,6 +236,7 @@ impl Into<indradb::EdgeWithPropertyValueQuery> for EdgeWithPropertyValueQuery {
         indradb::EdgeWithPropertyValueQuery {
             name: self.name.into(),
             value: indradb::Json::new(self.value.into()),
+            value_param: None,
         }
     }
 }
@@ -242,6 +247,7 @@ pub struct PipeQuery {
     pub direction: EdgeDirection,
     pub limit: u32,
     pub t: Option<Identifier>,
+    pub distinct: bool,
 }
 
 impl Into<indradb::PipeQuery> for PipeQuery {
@@ -251,6 +257,8 @@ impl Into<indradb::PipeQuery> for PipeQuery {
             direction: self.direction.into(),
             limit: self.limit,
             t: self.t.map(|t| t.into()),
+            distinct: self.distinct,
+            limit_param: None,
         }
     }
 }
@@ -275,6 +283,7 @@ pub struct PipeWithPropertyPresenceQuery {
     pub inner: Box<Query>,
     pub name: Identifier,
     pub exists: bool,
+    pub allow_scan: bool,
 }
 
 impl Into<indradb::PipeWithPropertyPresenceQuery> for PipeWithPropertyPresenceQuery {
@@ -283,6 +292,7 @@ impl Into<indradb::PipeWithPropertyPresenceQuery> for PipeWithPropertyPresenceQu
             inner: Box::new((*self.inner).into()),
             name: self.name.into(),
             exists: self.exists,
+            allow_scan: self.allow_scan,
         }
     }
 }
@@ -293,6 +303,7 @@ pub struct PipeWithPropertyValueQuery {
     pub name: Identifier,
     pub value: Json,
     pub equal: bool,
+    pub allow_scan: bool,
 }
 
 impl Into<indradb::PipeWithPropertyValueQuery> for PipeWithPropertyValueQuery {
@@ -302,6 +313,8 @@ impl Into<indradb::PipeWithPropertyValueQuery> for PipeWithPropertyValueQuery {
             name: self.name.into(),
             value: indradb::Json::new(self.value.into()),
             equal: self.equal,
+            allow_scan: self.allow_scan,
+            value_param: None,
         }
     }
 }
@@ -322,12 +335,14 @@ impl Into<indradb::IncludeQuery> for IncludeQuery {
 #[derive(Arbitrary, Clone, Debug, PartialEq)]
 pub struct CountQuery {
     pub inner: Box<Query>,
+    pub distinct: bool,
 }
 
 impl Into<indradb::CountQuery> for CountQuery {
     fn into(self) -> indradb::CountQuery {
         indradb::CountQuery {
             inner: Box::new((*self.inner).into()),
+            distinct: self.distinct,
         }
     }
 }
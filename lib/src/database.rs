@@ -1,9 +1,23 @@
-use crate::errors::{Error, Result};
+use crate::errors::{BudgetLimit, Error, Result, ValidationError};
 use crate::models::{
-    BulkInsertItem, Edge, EdgeDirection, EdgeProperties, Identifier, Json, NamedProperty, Query, QueryOutputValue,
-    Vertex, VertexProperties,
+    AggregateFunction, AggregateQuery, AggregationGroup, AggregationValue, BulkInsertItem, ContinuationPosition, Edge,
+    EdgeDirection, EdgeProperties, GroupBy, HistogramBucket, Identifier, Json, MissingWeight, NamedProperty,
+    OrderByQuery, Params, Path, PipePropertyQuery, PipeQuery, PipeWithPropertyPresenceQuery,
+    PipeWithPropertyValueQuery, PreparedQuery, Query, QueryBudget, QueryOutputChunk, QueryOutputValue, QueryPlan, Row,
+    RowValue, ShortestPathQuery, SortDirection, SpecificEdgeQuery, SpecificVertexQuery, Subgraph, SubgraphQuery,
+    Vertex, VertexDegree, VertexProperties, WeightedShortestPathQuery,
 };
-use std::collections::HashSet;
+use crate::optimizer::map_children;
+use crate::util::next_uuid;
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use std::hash::Hash;
+use std::iter::{self, Peekable};
+use std::ops::Bound;
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::Arc;
+use std::time::Instant;
 use std::vec::Vec;
 use uuid::Uuid;
 
@@ -11,6 +25,14 @@ use uuid::Uuid;
 /// transaction return types.
 pub type DynIter<'a, T> = Box<dyn Iterator<Item = Result<T>> + 'a>;
 
+// The maximum number of items in each chunk yielded when streaming query
+// results.
+const QUERY_OUTPUT_CHUNK_SIZE: usize = 1000;
+
+// How many values are read from the datastore between checks of whether a
+// query has run out of time.
+const BUDGET_CHECK_INTERVAL: usize = 1000;
+
 /// Specifies a datastore transaction, which contains nearly all of the
 /// datastore implementation-specific logic.
 ///
@@ -34,6 +56,28 @@ pub trait Transaction<'a> {
     fn range_vertices(&'a self, offset: Uuid) -> Result<DynIter<'a, Vertex>>;
     /// Gets a specific set of vertices with the given IDs.
     fn specific_vertices(&'a self, ids: Vec<Uuid>) -> Result<DynIter<'a, Vertex>>;
+    /// Returns all vertices of a given type, sorted by ID. By default, this
+    /// filters all vertices, but can be overridden in datastores that index
+    /// vertices by type.
+    ///
+    /// # Arguments
+    /// * `t` - The vertex type.
+    fn vertices_with_type(&'a self, t: Identifier) -> Result<DynIter<'a, Vertex>> {
+        let iter = self.all_vertices()?;
+        Ok(Box::new(iter.filter(move |r| !matches!(r, Ok(v) if v.t != t))))
+    }
+    /// Returns all vertices of a given type with `id >= offset`, sorted by
+    /// ID. By default, this filters `range_vertices`, but can be overridden
+    /// in datastores that index vertices by type.
+    ///
+    /// # Arguments
+    /// * `t` - The vertex type.
+    /// * `offset` - Only fetch vertices with an offset greater than or equal
+    ///   to this value.
+    fn range_vertices_with_type(&'a self, t: Identifier, offset: Uuid) -> Result<DynIter<'a, Vertex>> {
+        let iter = self.range_vertices(offset)?;
+        Ok(Box::new(iter.filter(move |r| !matches!(r, Ok(v) if v.t != t))))
+    }
     /// Get all vertices with a given property.
     ///
     /// # Arguments
@@ -45,6 +89,19 @@ pub trait Transaction<'a> {
     /// * `name` - The property name.
     /// * `value` - The property value.
     fn vertex_ids_with_property_value(&'a self, name: Identifier, value: &Json) -> Result<Option<DynIter<'a, Uuid>>>;
+    /// Get all vertices with a property value within a given range. Only
+    /// values of the same JSON type as the bound(s) should be returned.
+    ///
+    /// # Arguments
+    /// * `name` - The property name.
+    /// * `low` - The lower bound of the range.
+    /// * `high` - The upper bound of the range.
+    fn vertex_ids_with_property_range(
+        &'a self,
+        name: Identifier,
+        low: Bound<&Json>,
+        high: Bound<&Json>,
+    ) -> Result<Option<DynIter<'a, Uuid>>>;
 
     /// Gets the number of edges.
     fn edge_count(&self) -> u64;
@@ -67,6 +124,71 @@ pub trait Transaction<'a> {
     /// # Arguments
     /// * `edges` - The edges to get.
     fn specific_edges(&'a self, edges: Vec<Edge>) -> Result<DynIter<'a, Edge>>;
+    /// Gets the number of edges going out of or coming into a vertex. By
+    /// default, this counts the edges returned by `range_edges` or
+    /// `range_reversed_edges`, but can be overridden in datastores that can
+    /// count them more cheaply.
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the vertex.
+    /// * `direction` - Whether to count outbound or inbound edges. With
+    ///   `Both`, edges from the vertex to itself are counted twice.
+    /// * `t` - Only counts edges of this type, if set.
+    fn vertex_degree(&'a self, id: Uuid, direction: EdgeDirection, t: Option<Identifier>) -> Result<u64> {
+        let offset = Edge::new(id, t.unwrap_or_default(), Uuid::default());
+        let iter = match direction {
+            EdgeDirection::Outbound => self.range_edges(offset)?,
+            EdgeDirection::Inbound => self.range_reversed_edges(offset)?,
+            EdgeDirection::Both => {
+                return Ok(self.vertex_degree(id, EdgeDirection::Outbound, t)?
+                    + self.vertex_degree(id, EdgeDirection::Inbound, t)?)
+            }
+        };
+        let mut count = 0;
+        for result in iter {
+            let edge = result?;
+            if edge.outbound_id != id || t.is_some_and(|t| edge.t != t) {
+                break;
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+    /// Returns all edges of a given type, sorted the same way as
+    /// `all_edges`. By default, this filters all edges, but can be
+    /// overridden in datastores that index edges by type.
+    ///
+    /// # Arguments
+    /// * `t` - The edge type.
+    fn edges_with_type(&'a self, t: Identifier) -> Result<DynIter<'a, Edge>> {
+        let iter = self.all_edges()?;
+        Ok(Box::new(iter.filter(move |r| !matches!(r, Ok(e) if e.t != t))))
+    }
+    /// Returns all edges with the same type as `offset` that are greater
+    /// than or equal to `offset`. By default, this filters `range_edges`,
+    /// but can be overridden in datastores that index edges by type.
+    ///
+    /// # Arguments
+    /// * `offset` - Only fetch edges greater than or equal to this value.
+    fn range_edges_with_type(&'a self, offset: Edge) -> Result<DynIter<'a, Edge>> {
+        let t = offset.t;
+        let iter = self.range_edges(offset)?;
+        Ok(Box::new(iter.filter(move |r| !matches!(r, Ok(e) if e.t != t))))
+    }
+    /// Gets the number of edges of a given type. By default, this counts
+    /// the edges returned by `edges_with_type`, but can be overridden in
+    /// datastores that can count them more cheaply.
+    ///
+    /// # Arguments
+    /// * `t` - The edge type.
+    fn edge_count_with_type(&'a self, t: Identifier) -> Result<u64> {
+        let mut count = 0;
+        for result in self.edges_with_type(t)? {
+            result?;
+            count += 1;
+        }
+        Ok(count)
+    }
     /// Get all edges with a given property.
     ///
     /// # Arguments
@@ -78,6 +200,19 @@ pub trait Transaction<'a> {
     /// * `name` - The property name.
     /// * `value` - The property value.
     fn edges_with_property_value(&'a self, name: Identifier, value: &Json) -> Result<Option<DynIter<'a, Edge>>>;
+    /// Get all edges with a property value within a given range. Only values
+    /// of the same JSON type as the bound(s) should be returned.
+    ///
+    /// # Arguments
+    /// * `name` - The property name.
+    /// * `low` - The lower bound of the range.
+    /// * `high` - The upper bound of the range.
+    fn edges_with_property_range(
+        &'a self,
+        name: Identifier,
+        low: Bound<&Json>,
+        high: Bound<&Json>,
+    ) -> Result<Option<DynIter<'a, Edge>>>;
 
     /// Gets the value of a vertex property if it exists, or `None` otherwise.
     ///
@@ -130,6 +265,21 @@ pub trait Transaction<'a> {
         Err(Error::Unsupported)
     }
 
+    /// Commits the changes made in the transaction. By default, this is a
+    /// no-op, for datastores that write changes as soon as they're made,
+    /// but can be overridden in datastores that hold them back until the
+    /// transaction is committed.
+    fn commit(&mut self) -> Result<()> {
+        Ok(())
+    }
+    /// Discards the changes made in the transaction since it was created or
+    /// last committed. By default, this errors out, but can be overridden in
+    /// datastores that support rolling back. Datastores that do should also
+    /// roll back transactions that are dropped without being committed.
+    fn rollback(&mut self) -> Result<()> {
+        Err(Error::Unsupported)
+    }
+
     /// Creates a new vertex. Returns whether the vertex was successfully
     /// created - if this is false, it's because a vertex with the same UUID
     /// already exists.
@@ -204,6 +354,19 @@ pub trait Datastore {
         Self: 'a;
     /// Creates a new transaction.
     fn transaction(&self) -> Self::Transaction<'_>;
+
+    /// Bulk inserts many vertices, edges, and/or properties, outside of any
+    /// transaction. By default, this inserts them in a new transaction and
+    /// commits it, but can be overridden to offer a more efficient
+    /// implementation.
+    ///
+    /// # Arguments
+    /// * `items`: The items to insert.
+    fn bulk_insert(&self, items: Vec<BulkInsertItem>) -> Result<()> {
+        let mut txn = self.transaction();
+        txn.bulk_insert(items)?;
+        txn.commit()
+    }
 }
 
 /// The IndraDB database.
@@ -216,6 +379,8 @@ pub trait Datastore {
 /// datastores.
 pub struct Database<D: Datastore> {
     pub datastore: D,
+    scan_fallback: bool,
+    budget: QueryBudget,
 }
 
 impl<D: Datastore> Database<D> {
@@ -224,7 +389,46 @@ impl<D: Datastore> Database<D> {
     /// # Arguments
     /// * `datastore`: The underlying datastore to use.
     pub fn new(datastore: D) -> Database<D> {
-        Self { datastore }
+        Self {
+            datastore,
+            scan_fallback: false,
+            budget: QueryBudget::default(),
+        }
+    }
+
+    /// Sets whether property presence and value filters read the property
+    /// of each value when the property isn't indexed, rather than failing
+    /// with `Error::NotIndexed`. This applies `allow_scan` to every such
+    /// filter of an unindexed property in queries run through the database.
+    ///
+    /// # Arguments
+    /// * `scan_fallback`: Whether to allow scanning.
+    pub fn with_scan_fallback(self, scan_fallback: bool) -> Self {
+        Self { scan_fallback, ..self }
+    }
+
+    /// Sets the budget for queries run through the database, unless they're
+    /// given their own with `get_with_budget` or `get_iter_with_budget`.
+    ///
+    /// # Arguments
+    /// * `budget`: The budget.
+    pub fn with_budget(self, budget: QueryBudget) -> Self {
+        Self { budget, ..self }
+    }
+
+    /// Gets the budget for queries run through the database.
+    pub fn budget(&self) -> &QueryBudget {
+        &self.budget
+    }
+
+    // Applies the database's options to a query before it's run.
+    unsafe fn apply_options<'a, T: Transaction<'a> + 'a>(&self, txn: *const T, q: Query) -> Result<Query> {
+        let mut err = None;
+        let q = resolve_scans(txn, q, self.scan_fallback, &mut err);
+        match err {
+            Some(err) => Err(err),
+            None => Ok(q),
+        }
     }
 
     /// Syncs persisted content. Depending on the datastore implementation,
@@ -241,8 +445,7 @@ impl<D: Datastore> Database<D> {
     /// # Arguments
     /// * `vertex`: The vertex to create.
     pub fn create_vertex(&self, vertex: &Vertex) -> Result<bool> {
-        let mut txn = self.datastore.transaction();
-        txn.create_vertex(vertex)
+        self.write(|txn| txn.create_vertex(vertex))
     }
 
     /// Creates a new vertex with just a type specification. As opposed to
@@ -252,13 +455,7 @@ impl<D: Datastore> Database<D> {
     /// # Arguments
     /// * `t`: The type of the vertex to create.
     pub fn create_vertex_from_type(&self, t: Identifier) -> Result<Uuid> {
-        let v = Vertex::new(t);
-
-        if !self.create_vertex(&v)? {
-            Err(Error::UuidTaken)
-        } else {
-            Ok(v.id)
-        }
+        self.write(|txn| txn.create_vertex_from_type(t))
     }
 
     /// Creates a new edge. Returns whether the edge was successfully
@@ -268,8 +465,7 @@ impl<D: Datastore> Database<D> {
     /// # Arguments
     /// * `edge`: The edge to create.
     pub fn create_edge(&self, edge: &Edge) -> Result<bool> {
-        let mut txn = self.datastore.transaction();
-        txn.create_edge(edge)
+        self.write(|txn| txn.create_edge(edge))
     }
 
     /// Gets values specified by a query.
@@ -277,13 +473,154 @@ impl<D: Datastore> Database<D> {
     /// # Arguments
     /// * `q`: The query to run.
     pub fn get<Q: Into<Query>>(&self, q: Q) -> Result<Vec<QueryOutputValue>> {
+        self.get_with_budget(q, &self.budget)
+    }
+
+    /// Gets values specified by a query, with a budget other than the
+    /// database's.
+    ///
+    /// # Arguments
+    /// * `q`: The query to run.
+    /// * `budget`: The limits on the resources the query can use.
+    pub fn get_with_budget<Q: Into<Query>>(&self, q: Q, budget: &QueryBudget) -> Result<Vec<QueryOutputValue>> {
+        let txn = self.datastore.transaction();
+        let txn = &txn as *const D::Transaction<'_>;
+        unsafe {
+            let q = self.apply_options(txn, q.into())?.optimize();
+            run(txn, &q, budget)
+        }
+    }
+
+    /// Prepares a query to be run many times with different parameters.
+    /// The query is validated once here, rather than every time it's run.
+    ///
+    /// # Arguments
+    /// * `q`: The query to prepare, with parameter placeholders.
+    pub fn prepare<Q: Into<Query>>(&self, q: Q) -> Result<PreparedQuery> {
         let q = q.into();
+        q.output_type()?;
+        Ok(PreparedQuery::new(q))
+    }
+
+    /// Runs a prepared query.
+    ///
+    /// # Arguments
+    /// * `prepared`: The prepared query.
+    /// * `params`: The values of the query's parameters.
+    pub fn get_prepared(&self, prepared: &PreparedQuery, params: &Params) -> Result<Vec<QueryOutputValue>> {
+        self.get(prepared.bind(params)?)
+    }
+
+    /// Gets values specified by a query, running it as-is rather than
+    /// optimizing it first. This is mostly useful for checking that an
+    /// optimization doesn't change a query's results.
+    ///
+    /// # Arguments
+    /// * `q`: The query to run.
+    pub fn get_unoptimized<Q: Into<Query>>(&self, q: Q) -> Result<Vec<QueryOutputValue>> {
+        let txn = self.datastore.transaction();
+        let txn = &txn as *const D::Transaction<'_>;
+        unsafe {
+            let q = self.apply_options(txn, q.into())?;
+            run(txn, &q, &self.budget)
+        }
+    }
+
+    /// Explains how a query would be run, without running it. The plan
+    /// includes which `Transaction` methods each stage calls, whether
+    /// property indexes exist, and estimates of how many values each stage
+    /// outputs.
+    ///
+    /// # Arguments
+    /// * `q`: The query to explain.
+    pub fn explain<Q: Into<Query>>(&self, q: Q) -> Result<QueryPlan> {
+        let txn = self.datastore.transaction();
+        let txn = &txn as *const D::Transaction<'_>;
+        unsafe {
+            let q = self.apply_options(txn, q.into())?.optimize();
+            explain(txn, &q, &Totals::default())
+        }
+    }
+
+    /// Runs a query, and explains how it was run. In addition to what
+    /// `explain` returns, each stage of the plan includes the number of
+    /// values it actually output, and how long it took.
+    ///
+    /// # Arguments
+    /// * `q`: The query to analyze.
+    pub fn analyze<Q: Into<Query>>(&self, q: Q) -> Result<QueryPlan> {
         let txn = self.datastore.transaction();
+        let txn = &txn as *const D::Transaction<'_>;
+        let q = unsafe { self.apply_options(txn, q.into())?.optimize() };
         let mut output = Vec::with_capacity(q.output_len());
+        let mut exec = Execution {
+            budget: Budget::new(&self.budget),
+            profiler: Some(Profiler {
+                stack: vec![Vec::new()],
+                totals: Totals::default(),
+            }),
+        };
         unsafe {
-            query(&txn as *const D::Transaction<'_>, &q, &mut output)?;
+            query(txn, &q, &mut output, &mut exec)?;
         }
-        Ok(output)
+        Ok(exec.profiler.unwrap().stack.pop().unwrap().pop().unwrap())
+    }
+
+    /// Gets values specified by a query, yielding them incrementally in
+    /// chunks rather than all at once. Queries that read directly from the
+    /// datastore (e.g. `AllVertexQuery`) are streamed as the datastore
+    /// produces results, so the full output never needs to be held in
+    /// memory. Other queries are run to completion before being yielded.
+    ///
+    /// # Arguments
+    /// * `q`: The query to run.
+    pub fn get_iter<Q: Into<Query>>(&self, q: Q) -> Result<QueryOutputIter<'_, D>> {
+        self.get_iter_with_budget(q, &self.budget)
+    }
+
+    /// Gets values specified by a query incrementally, like `get_iter`, with
+    /// a budget other than the database's. The budget is also enforced as
+    /// streamed values are read, so the query can fail partway through.
+    ///
+    /// # Arguments
+    /// * `q`: The query to run.
+    /// * `budget`: The limits on the resources the query can use.
+    pub fn get_iter_with_budget<Q: Into<Query>>(&self, q: Q, budget: &QueryBudget) -> Result<QueryOutputIter<'_, D>> {
+        let q = q.into();
+        // Whether the query can be resumed depends on the query as it was
+        // given, not how it's optimized.
+        let resumable = matches!(q, Query::Resume(_)) || q.is_resumable();
+        let txn = Box::new(self.datastore.transaction());
+        let chunks: DynIter<'_, QueryOutputChunk> = unsafe {
+            let txn_ptr = &*txn as *const D::Transaction<'_>;
+            let q = self.apply_options(txn_ptr, q)?.optimize();
+            if let Some(root_output) = root_query(txn_ptr, &q)? {
+                let chunks = Box::new(BudgetedChunks {
+                    chunks: root_output.chunks(),
+                    budget: Budget::new(budget),
+                    done: false,
+                });
+                if resumable {
+                    Box::new(chunks.map(|r| r.map(QueryOutputChunk::with_continuation)))
+                } else {
+                    chunks
+                }
+            } else {
+                let output = run(txn_ptr, &q, budget)?;
+                // Only the final output value is the result of the query
+                // itself, so it's the only one that can be resumed.
+                let last = output.len() - 1;
+                Box::new(output.into_iter().enumerate().map(move |(i, value)| {
+                    let chunk = QueryOutputChunk::new(value, false);
+                    Ok(if resumable && i == last {
+                        chunk.with_continuation()
+                    } else {
+                        chunk
+                    })
+                }))
+            }
+        };
+        Ok(QueryOutputIter { chunks, _txn: txn })
     }
 
     /// Deletes values specified by a query.
@@ -292,20 +629,146 @@ impl<D: Datastore> Database<D> {
     /// * `q`: The query to run.
     pub fn delete<Q: Into<Query>>(&self, q: Q) -> Result<()> {
         let q = q.into();
-        let mut txn = self.datastore.transaction();
-        let mut output = Vec::with_capacity(q.output_len());
+        self.write(|txn| txn.delete(q.clone()))
+    }
+
+    /// Sets properties.
+    ///
+    /// # Arguments
+    /// * `q`: The query to run.
+    /// * `name`: The property name.
+    /// * `value`: The property value.
+    pub fn set_properties<Q: Into<Query>>(&self, q: Q, name: Identifier, value: &Json) -> Result<()> {
+        let q = q.into();
+        self.write(|txn| txn.set_properties(q.clone(), name, value))
+    }
+
+    /// Bulk inserts many vertices, edges, and/or properties. Unlike
+    /// inserting them through a transaction, the RocksDB datastore writes
+    /// them directly rather than holding them in memory first, and other
+    /// transactions can't be committed until it's done.
+    ///
+    /// # Arguments
+    /// * `items`: The items to insert.
+    pub fn bulk_insert(&self, items: Vec<BulkInsertItem>) -> Result<()> {
+        self.datastore.bulk_insert(items)
+    }
+
+    /// Enables indexing on a specified property. When indexing is enabled on a
+    /// property, it's possible to query on its presence and values.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to index.
+    pub fn index_property(&self, name: Identifier) -> Result<()> {
+        self.write(|txn| txn.index_property(name))
+    }
+
+    // Runs `f` in a new transaction, and commits it. Transactions that
+    // conflict with another one are retried from the start, since there's
+    // nothing else the caller could do about it.
+    fn write<T>(&self, mut f: impl FnMut(&mut DatabaseTransaction<'_, D>) -> Result<T>) -> Result<T> {
+        loop {
+            let mut txn = self.begin();
+            let value = f(&mut txn)?;
+            match txn.commit() {
+                Err(Error::Conflict) => continue,
+                result => return result.map(|_| value),
+            }
+        }
+    }
+
+    /// Begins a transaction. Changes made through it are only committed
+    /// together, once `commit` is called, and are rolled back if it's
+    /// dropped first - in datastores that support rolling back, which
+    /// otherwise write changes as soon as they're made. Queries run through
+    /// the transaction see its changes before they're committed.
+    ///
+    /// Whether other transactions can see the changes before then depends
+    /// on the datastore. With the in-memory datastore, the transaction locks
+    /// the whole datastore until it's done, so nothing else should be run
+    /// through the database from the same thread in the meantime. The
+    /// RocksDB datastore doesn't lock anything; instead, committing fails
+    /// with `Error::Conflict` if anything the transaction read has been
+    /// changed by another transaction committed since. The transaction's
+    /// changes are then discarded, and it can be retried from the start.
+    pub fn begin(&self) -> DatabaseTransaction<'_, D> {
+        DatabaseTransaction {
+            db: self,
+            txn: self.datastore.transaction(),
+        }
+    }
+}
+
+/// A transaction on a database, started with `Database::begin`.
+pub struct DatabaseTransaction<'a, D: Datastore + 'a> {
+    db: &'a Database<D>,
+    txn: D::Transaction<'a>,
+}
+
+impl<'a, D: Datastore + 'a> DatabaseTransaction<'a, D> {
+    /// Creates a new vertex. Returns whether the vertex was successfully
+    /// created - if this is false, it's because a vertex with the same UUID
+    /// already exists.
+    ///
+    /// # Arguments
+    /// * `vertex`: The vertex to create.
+    pub fn create_vertex(&mut self, vertex: &Vertex) -> Result<bool> {
+        self.txn.create_vertex(vertex)
+    }
+
+    /// Creates a new vertex with just a type specification, returning its
+    /// UUID.
+    ///
+    /// # Arguments
+    /// * `t`: The type of the vertex to create.
+    pub fn create_vertex_from_type(&mut self, t: Identifier) -> Result<Uuid> {
+        let v = Vertex::new(t);
+
+        if !self.create_vertex(&v)? {
+            Err(Error::UuidTaken)
+        } else {
+            Ok(v.id)
+        }
+    }
+
+    /// Creates a new edge. Returns whether the edge was successfully
+    /// created - if this is false, it's because one of the specified vertices
+    /// is missing.
+    ///
+    /// # Arguments
+    /// * `edge`: The edge to create.
+    pub fn create_edge(&mut self, edge: &Edge) -> Result<bool> {
+        self.txn.create_edge(edge)
+    }
+
+    /// Gets values specified by a query, including the changes made in the
+    /// transaction.
+    ///
+    /// # Arguments
+    /// * `q`: The query to run.
+    pub fn get<Q: Into<Query>>(&self, q: Q) -> Result<Vec<QueryOutputValue>> {
+        let txn = &self.txn as *const D::Transaction<'a>;
         unsafe {
-            query(&txn as *const D::Transaction<'_>, &q, &mut output)?;
+            let q = self.db.apply_options(txn, q.into())?.optimize();
+            run(txn, &q, &self.db.budget)
         }
+    }
+
+    /// Deletes values specified by a query.
+    ///
+    /// # Arguments
+    /// * `q`: The query to run.
+    pub fn delete<Q: Into<Query>>(&mut self, q: Q) -> Result<()> {
+        let mut output = self.get(q)?;
         match output.pop().unwrap() {
             QueryOutputValue::Vertices(vertices) => {
-                txn.delete_vertices(vertices)?;
+                self.txn.delete_vertices(vertices)?;
             }
             QueryOutputValue::Edges(edges) => {
-                txn.delete_edges(edges)?;
+                self.txn.delete_edges(edges)?;
             }
             QueryOutputValue::VertexProperties(vertex_properties) => {
-                txn.delete_vertex_properties(
+                self.txn.delete_vertex_properties(
                     vertex_properties
                         .into_iter()
                         .flat_map(|vps| {
@@ -316,7 +779,7 @@ impl<D: Datastore> Database<D> {
                 )?;
             }
             QueryOutputValue::EdgeProperties(edge_properties) => {
-                txn.delete_edge_properties(
+                self.txn.delete_edge_properties(
                     edge_properties
                         .into_iter()
                         .flat_map(|eps| {
@@ -326,7 +789,12 @@ impl<D: Datastore> Database<D> {
                         .collect(),
                 )?;
             }
-            QueryOutputValue::Count(_) => return Err(Error::OperationOnQuery),
+            QueryOutputValue::Count(_)
+            | QueryOutputValue::Paths(_)
+            | QueryOutputValue::Aggregation(_)
+            | QueryOutputValue::Degrees(_)
+            | QueryOutputValue::Subgraph(_)
+            | QueryOutputValue::Rows(_) => return Err(Error::OperationOnQuery),
         }
         Ok(())
     }
@@ -337,20 +805,15 @@ impl<D: Datastore> Database<D> {
     /// * `q`: The query to run.
     /// * `name`: The property name.
     /// * `value`: The property value.
-    pub fn set_properties<Q: Into<Query>>(&self, q: Q, name: Identifier, value: &Json) -> Result<()> {
-        let q = q.into();
-        let mut txn = self.datastore.transaction();
-        let mut output = Vec::with_capacity(q.output_len());
-        unsafe {
-            query(&txn as *const D::Transaction<'_>, &q, &mut output)?;
-        }
-
+    pub fn set_properties<Q: Into<Query>>(&mut self, q: Q, name: Identifier, value: &Json) -> Result<()> {
+        let mut output = self.get(q)?;
         match output.pop().unwrap() {
             QueryOutputValue::Vertices(vertices) => {
-                txn.set_vertex_properties(vertices.into_iter().map(|v| v.id).collect(), name, value)?;
+                self.txn
+                    .set_vertex_properties(vertices.into_iter().map(|v| v.id).collect(), name, value)?;
             }
             QueryOutputValue::Edges(edges) => {
-                txn.set_edge_properties(edges, name, value)?;
+                self.txn.set_edge_properties(edges, name, value)?;
             }
             _ => return Err(Error::OperationOnQuery),
         }
@@ -361,135 +824,1885 @@ impl<D: Datastore> Database<D> {
     ///
     /// # Arguments
     /// * `items`: The items to insert.
-    pub fn bulk_insert(&self, items: Vec<BulkInsertItem>) -> Result<()> {
-        let mut txn = self.datastore.transaction();
-        txn.bulk_insert(items)
+    pub fn bulk_insert(&mut self, items: Vec<BulkInsertItem>) -> Result<()> {
+        self.txn.bulk_insert(items)
     }
 
-    /// Enables indexing on a specified property. When indexing is enabled on a
-    /// property, it's possible to query on its presence and values.
+    /// Enables indexing on a specified property.
     ///
     /// # Arguments
     /// * `name`: The name of the property to index.
-    pub fn index_property(&self, name: Identifier) -> Result<()> {
-        let mut txn = self.datastore.transaction();
-        txn.index_property(name)
+    pub fn index_property(&mut self, name: Identifier) -> Result<()> {
+        self.txn.index_property(name)
     }
-}
 
-unsafe fn query<'a, T: Transaction<'a> + 'a>(
-    txn: *const T,
-    q: &Query,
-    output: &mut Vec<QueryOutputValue>,
-) -> Result<()> {
-    let value = match q {
-        Query::AllVertex => {
-            let iter = (*txn).all_vertices()?;
-            QueryOutputValue::Vertices(iter.collect::<Result<Vec<Vertex>>>()?)
-        }
-        Query::RangeVertex(ref q) => {
-            let mut iter: DynIter<Vertex> = if let Some(start_id) = q.start_id {
-                (*txn).range_vertices(start_id)?
-            } else {
-                (*txn).all_vertices()?
-            };
+    /// Commits the changes made in the transaction.
+    pub fn commit(mut self) -> Result<()> {
+        self.txn.commit()
+    }
 
-            if let Some(ref t) = q.t {
-                iter = Box::new(iter.filter(move |r| match r {
-                    Ok(v) => &v.t == t,
-                    Err(_) => true,
-                }));
-            }
+    /// Discards the changes made in the transaction. This is the same as
+    /// dropping it, except that errors are returned - including
+    /// `Error::Unsupported` from datastores that can't roll back.
+    pub fn rollback(mut self) -> Result<()> {
+        self.txn.rollback()
+    }
+}
 
-            iter = Box::new(iter.take(q.limit as usize));
-            QueryOutputValue::Vertices(iter.collect::<Result<Vec<Vertex>>>()?)
-        }
-        Query::SpecificVertex(ref q) => {
-            let iter = (*txn).specific_vertices(q.ids.clone())?;
-            QueryOutputValue::Vertices(iter.collect::<Result<Vec<Vertex>>>()?)
-        }
-        Query::Pipe(ref q) => {
-            query(txn, &q.inner, output)?;
-            let piped_values = output.pop().unwrap();
+// Gets the edges adjacent to a vertex in the given direction, optionally
+// filtered by type. Edges are returned in their original orientation, i.e.
+// inbound edges are not reversed. In both directions, outbound edges come
+// first, and edges from the vertex to itself are only returned once.
+unsafe fn adjacent_edges<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    id: Uuid,
+    direction: EdgeDirection,
+    t: Option<Identifier>,
+) -> Result<DynIter<'a, Edge>> {
+    adjacent_edges_after(txn, id, direction, t, None)
+}
 
-            let values = match piped_values {
-                QueryOutputValue::Edges(ref piped_edges) => {
-                    let iter: Box<dyn Iterator<Item = Uuid>> = match q.direction {
-                        EdgeDirection::Outbound => Box::new(piped_edges.iter().map(|e| e.outbound_id)),
-                        EdgeDirection::Inbound => Box::new(piped_edges.iter().map(|e| e.inbound_id)),
-                    };
+// Like `adjacent_edges`, but only returns edges ordered after `after`, an
+// edge adjacent to the vertex in its original orientation. Edges in both
+// directions can't be resumed, so `after` must not be set with `Both`.
+unsafe fn adjacent_edges_after<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    id: Uuid,
+    direction: EdgeDirection,
+    t: Option<Identifier>,
+    after: Option<&Edge>,
+) -> Result<DynIter<'a, Edge>> {
+    if direction == EdgeDirection::Both {
+        debug_assert!(after.is_none());
+        let outbound = adjacent_edges(txn, id, EdgeDirection::Outbound, t)?;
+        let inbound = adjacent_edges(txn, id, EdgeDirection::Inbound, t)?;
+        let inbound = inbound.filter(move |r| !matches!(r, Ok(e) if e.outbound_id == id));
+        return Ok(Box::new(outbound.chain(inbound)));
+    }
 
-                    let mut iter: DynIter<Vertex> = (*txn).specific_vertices(iter.collect())?;
+    let lower_bound = match after {
+        Some(after) if direction == EdgeDirection::Outbound => after.clone(),
+        Some(after) => after.reversed(),
+        None => Edge::new(id, t.unwrap_or_default(), Uuid::default()),
+    };
 
-                    if let Some(ref t) = q.t {
-                        iter = Box::new(iter.filter(move |r| match r {
-                            Ok(v) => &v.t == t,
-                            Err(_) => true,
-                        }));
-                    }
+    let mut iter = if direction == EdgeDirection::Outbound {
+        (*txn).range_edges(lower_bound.clone())?
+    } else {
+        (*txn).range_reversed_edges(lower_bound.clone())?
+    };
 
-                    iter = Box::new(iter.take(q.limit as usize));
+    if after.is_some() {
+        iter = Box::new(iter.skip_while(move |r| matches!(r, Ok(e) if *e == lower_bound)));
+    }
 
-                    QueryOutputValue::Vertices(iter.collect::<Result<Vec<Vertex>>>()?)
-                }
-                QueryOutputValue::Vertices(ref piped_vertices) => {
-                    let mut edges = Vec::new();
+    iter = Box::new(iter.take_while(move |r| match r {
+        Ok(e) => e.outbound_id == id && (t.is_none() || Some(e.t) == t),
+        Err(_) => true,
+    }));
 
-                    for vertex in piped_vertices {
-                        let lower_bound = match &q.t {
-                            Some(t) => Edge::new(vertex.id, *t, Uuid::default()),
-                            None => Edge::new(vertex.id, Identifier::default(), Uuid::default()),
-                        };
+    if direction == EdgeDirection::Inbound {
+        iter = Box::new(iter.map(move |r| Ok(r?.reversed())));
+    }
 
-                        let mut iter = if q.direction == EdgeDirection::Outbound {
-                            (*txn).range_edges(lower_bound)?
-                        } else {
-                            (*txn).range_reversed_edges(lower_bound)?
-                        };
+    Ok(iter)
+}
 
-                        iter = Box::new(iter.take_while(move |r| match r {
-                            Ok(e) => e.outbound_id == vertex.id,
-                            Err(_) => true,
-                        }));
+// Gets the ID of the vertex at the other end of an edge.
+fn opposite_vertex_id(edge: &Edge, id: Uuid) -> Uuid {
+    if edge.outbound_id == id {
+        edge.inbound_id
+    } else {
+        edge.outbound_id
+    }
+}
 
-                        if let Some(ref t) = q.t {
-                            iter = Box::new(iter.filter(move |r| match r {
-                                Ok(e) => &e.t == t,
-                                Err(_) => true,
-                            }));
-                        }
+// Finds the shortest path between two vertices via a bidirectional
+// breadth-first search, expanding whichever side has the smaller frontier.
+// For each visited vertex, the search tracks its distance from the side's
+// starting vertex, and the edge it was reached through.
+unsafe fn shortest_path<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    q: &ShortestPathQuery,
+    budget: &Budget,
+) -> Result<Option<Path>> {
+    let endpoints = (*txn)
+        .specific_vertices(vec![q.source_id, q.target_id])?
+        .collect::<Result<Vec<Vertex>>>()?;
+    if !endpoints.iter().any(|v| v.id == q.source_id) || !endpoints.iter().any(|v| v.id == q.target_id) {
+        return Ok(None);
+    }
+    if q.source_id == q.target_id {
+        return Ok(Some(Path::new(vec![endpoints[0].clone()], Vec::new())));
+    }
 
-                        if q.direction == EdgeDirection::Inbound {
-                            iter = Box::new(iter.map(move |r| Ok(r?.reversed())));
-                        }
+    let reversed_direction = reverse_direction(q.direction);
+    let mut forward_visited: HashMap<Uuid, (u32, Option<Edge>)> = HashMap::from([(q.source_id, (0, None))]);
+    let mut backward_visited: HashMap<Uuid, (u32, Option<Edge>)> = HashMap::from([(q.target_id, (0, None))]);
+    let mut forward_frontier = vec![q.source_id];
+    let mut backward_frontier = vec![q.target_id];
+    let mut depth = (0, 0);
 
-                        iter = Box::new(iter.take((q.limit as usize) - edges.len()));
+    let meeting_id = loop {
+        if forward_frontier.is_empty() || backward_frontier.is_empty() {
+            return Ok(None);
+        }
 
-                        for result in iter {
-                            edges.push(result?);
-                        }
+        let is_forward = forward_frontier.len() <= backward_frontier.len();
+        let (visited, other_visited, frontier, direction, depth) = if is_forward {
+            (
+                &mut forward_visited,
+                &backward_visited,
+                &mut forward_frontier,
+                q.direction,
+                &mut depth.0,
+            )
+        } else {
+            (
+                &mut backward_visited,
+                &forward_visited,
+                &mut backward_frontier,
+                reversed_direction,
+                &mut depth.1,
+            )
+        };
+        *depth += 1;
 
-                        if edges.len() >= (q.limit as usize) {
-                            break;
-                        }
+        // Expand the whole level, so that the meeting point with the
+        // shortest total distance is picked
+        let mut next_frontier = Vec::new();
+        let mut best_meeting: Option<(u32, Uuid)> = None;
+        for id in frontier.iter() {
+            budget.check()?;
+            for edge in adjacent_edges(txn, *id, direction, q.t)? {
+                let edge = edge?;
+                let next_id = opposite_vertex_id(&edge, *id);
+                if visited.contains_key(&next_id) {
+                    continue;
+                }
+                visited.insert(next_id, (*depth, Some(edge)));
+                budget.check_stage_rows((visited.len() + other_visited.len()) as u64)?;
+                next_frontier.push(next_id);
+                if let Some(&(other_depth, _)) = other_visited.get(&next_id) {
+                    if !matches!(best_meeting, Some((best_depth, _)) if best_depth <= other_depth) {
+                        best_meeting = Some((other_depth, next_id));
                     }
+                }
+            }
+        }
 
-                    QueryOutputValue::Edges(edges)
+        if let Some((_, meeting_id)) = best_meeting {
+            break meeting_id;
+        }
+        *frontier = next_frontier;
+    };
+
+    // Walk back from the meeting point to both ends of the path
+    let mut ids = vec![meeting_id];
+    let mut edges = Vec::new();
+    let mut id = meeting_id;
+    while let Some((_, Some(edge))) = forward_visited.get(&id) {
+        id = opposite_vertex_id(edge, id);
+        ids.push(id);
+        edges.push(edge.clone());
+    }
+    ids.reverse();
+    edges.reverse();
+    id = meeting_id;
+    while let Some((_, Some(edge))) = backward_visited.get(&id) {
+        id = opposite_vertex_id(edge, id);
+        ids.push(id);
+        edges.push(edge.clone());
+    }
+
+    let vertices = (*txn).specific_vertices(ids)?.collect::<Result<Vec<Vertex>>>()?;
+    Ok(Some(Path::new(vertices, edges)))
+}
+
+// A vertex queued for a visit in a weighted shortest path search. Ordered so
+// that the lowest cost is at the top of a `BinaryHeap`.
+struct WeightedVisit {
+    cost: f64,
+    id: Uuid,
+}
+
+impl PartialEq for WeightedVisit {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for WeightedVisit {}
+
+impl PartialOrd for WeightedVisit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for WeightedVisit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost).then_with(|| self.id.cmp(&other.id))
+    }
+}
+
+// Gets the weight of an edge for a weighted shortest path search, or `None`
+// if the edge should not be walked.
+fn edge_weight<'a, T: Transaction<'a> + 'a>(
+    txn: &T,
+    edge: &Edge,
+    q: &WeightedShortestPathQuery,
+) -> Result<Option<f64>> {
+    let weight = txn.edge_property(edge, q.weight)?.and_then(|value| value.as_f64());
+    match (weight, &q.missing_weight) {
+        (Some(weight), _) if weight >= 0.0 => Ok(Some(weight)),
+        (_, MissingWeight::Skip) => Ok(None),
+        (_, MissingWeight::Default(value)) => Ok(value.as_f64()),
+        (_, MissingWeight::Error) => Err(Error::InvalidWeight),
+    }
+}
+
+// Walks back from a vertex to the start of a search, returning the IDs of
+// the vertices and the edges along the way, in the order they were walked.
+fn walk_back(parents: &HashMap<Uuid, Edge>, id: Uuid) -> (Vec<Uuid>, Vec<Edge>) {
+    let mut ids = vec![id];
+    let mut edges = Vec::new();
+    let mut id = id;
+    while let Some(edge) = parents.get(&id) {
+        id = opposite_vertex_id(edge, id);
+        ids.push(id);
+        edges.push(edge.clone());
+    }
+    ids.reverse();
+    edges.reverse();
+    (ids, edges)
+}
+
+// Finds the shortest weighted path between two vertices via Dijkstra's
+// algorithm.
+unsafe fn weighted_shortest_path<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    q: &WeightedShortestPathQuery,
+    budget: &Budget,
+) -> Result<Option<Path>> {
+    let endpoints = (*txn)
+        .specific_vertices(vec![q.source_id, q.target_id])?
+        .collect::<Result<Vec<Vertex>>>()?;
+    if !endpoints.iter().any(|v| v.id == q.source_id) || !endpoints.iter().any(|v| v.id == q.target_id) {
+        return Ok(None);
+    }
+
+    let mut costs: HashMap<Uuid, f64> = HashMap::from([(q.source_id, 0.0)]);
+    let mut parents: HashMap<Uuid, Edge> = HashMap::new();
+    let mut visited: HashSet<Uuid> = HashSet::new();
+    let mut queue = BinaryHeap::from([WeightedVisit {
+        cost: 0.0,
+        id: q.source_id,
+    }]);
+
+    while let Some(WeightedVisit { cost, id }) = queue.pop() {
+        if id == q.target_id {
+            let (ids, edges) = walk_back(&parents, id);
+            let vertices = (*txn).specific_vertices(ids)?.collect::<Result<Vec<Vertex>>>()?;
+            return Ok(Some(Path::new(vertices, edges)));
+        }
+        if !visited.insert(id) {
+            continue;
+        }
+
+        budget.check()?;
+        for edge in adjacent_edges(txn, id, q.direction, q.t)? {
+            let edge = edge?;
+            let next_id = opposite_vertex_id(&edge, id);
+            if visited.contains(&next_id) {
+                continue;
+            }
+            let weight = match edge_weight(&*txn, &edge, q)? {
+                Some(weight) => weight,
+                None => continue,
+            };
+            let next_cost = cost + weight;
+            if !matches!(costs.get(&next_id), Some(c) if *c <= next_cost) {
+                costs.insert(next_id, next_cost);
+                budget.check_stage_rows(costs.len() as u64)?;
+                parents.insert(next_id, edge);
+                queue.push(WeightedVisit {
+                    cost: next_cost,
+                    id: next_id,
+                });
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+// Gets the subgraph around a set of vertices via a breadth-first search.
+// Edges are added as they're walked, and vertices as they're first reached,
+// until either limit is hit.
+unsafe fn subgraph<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    q: &SubgraphQuery,
+    start_vertices: &[Vertex],
+    budget: &Budget,
+) -> Result<Subgraph> {
+    let vertex_limit = q.vertex_limit as usize;
+    let edge_limit = q.edge_limit as usize;
+
+    let mut visited = HashSet::new();
+    let mut vertices = Vec::new();
+    for vertex in start_vertices {
+        if visited.len() == vertex_limit {
+            break;
+        }
+        if visited.insert(vertex.id) {
+            vertices.push(vertex.clone());
+        }
+    }
+
+    let mut edges = Vec::new();
+    // With `EdgeDirection::Both`, an edge between two expanded vertices is
+    // walked from both ends, so the edges already added are tracked
+    let mut added_edges = HashSet::new();
+    let mut frontier: Vec<Uuid> = vertices.iter().map(|v| v.id).collect();
+    let mut full = false;
+    for _ in 0..q.depth {
+        let mut next_frontier = Vec::new();
+        'frontier: for id in frontier {
+            budget.check()?;
+            for edge in adjacent_edges(txn, id, q.direction, q.t)? {
+                let edge = edge?;
+                if added_edges.contains(&edge) {
+                    continue;
                 }
-                _ => {
-                    return Err(Error::OperationOnQuery);
+                if edges.len() == edge_limit {
+                    full = true;
+                    break 'frontier;
+                }
+                let next_id = opposite_vertex_id(&edge, id);
+                if !visited.contains(&next_id) {
+                    // Edges to vertices past the limit are skipped, so that
+                    // both ends of every edge are in the subgraph
+                    if visited.len() == vertex_limit {
+                        continue;
+                    }
+                    visited.insert(next_id);
+                    next_frontier.push(next_id);
+                }
+                added_edges.insert(edge.clone());
+                edges.push(edge);
+                budget.check_stage_rows((visited.len() + edges.len()) as u64)?;
+            }
+        }
+
+        if !next_frontier.is_empty() {
+            let iter = (*txn).specific_vertices(next_frontier.clone())?;
+            for vertex in iter {
+                vertices.push(vertex?);
+            }
+        }
+        if full || next_frontier.is_empty() {
+            break;
+        }
+        frontier = next_frontier;
+    }
+
+    let mut subgraph = Subgraph::new(vertices, edges);
+    if q.properties {
+        for vertex in &subgraph.vertices {
+            let mut props = Vec::new();
+            for result in (*txn).all_vertex_properties_for_vertex(vertex)? {
+                let (name, value) = result?;
+                props.push(NamedProperty::new(name, value.clone()));
+            }
+            if !props.is_empty() {
+                subgraph
+                    .vertex_properties
+                    .push(VertexProperties::new(vertex.clone(), props));
+            }
+        }
+        for edge in &subgraph.edges {
+            let mut props = Vec::new();
+            for result in (*txn).all_edge_properties_for_edge(edge)? {
+                let (name, value) = result?;
+                props.push(NamedProperty::new(name, value.clone()));
+            }
+            if !props.is_empty() {
+                subgraph.edge_properties.push(EdgeProperties::new(edge.clone(), props));
+            }
+        }
+    }
+    Ok(subgraph)
+}
+
+/// An iterator over the chunks of a query's output, returned by
+/// `Database::get_iter`. Chunks are yielded in output value order; a chunk
+/// with `more` set is followed by more chunks for the same output value.
+pub struct QueryOutputIter<'a, D: Datastore + 'a> {
+    // Borrows from the transaction, so this is declared first to ensure it's
+    // dropped first.
+    chunks: DynIter<'a, QueryOutputChunk>,
+    // Boxed so that the transaction's address is stable when the iterator is
+    // moved.
+    _txn: Box<D::Transaction<'a>>,
+}
+
+impl<'a, D: Datastore + 'a> Iterator for QueryOutputIter<'a, D> {
+    type Item = Result<QueryOutputChunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.chunks.next()
+    }
+}
+
+// The output of a root query, which reads directly from the datastore
+// rather than building off of another query. The output is produced lazily.
+enum RootOutput<'a> {
+    Vertices(DynIter<'a, Vertex>),
+    Edges(DynIter<'a, Edge>),
+}
+
+impl<'a> RootOutput<'a> {
+    fn collect(self, budget: &Budget) -> Result<QueryOutputValue> {
+        Ok(match self {
+            RootOutput::Vertices(iter) => QueryOutputValue::Vertices(budget.collect(iter)?),
+            RootOutput::Edges(iter) => QueryOutputValue::Edges(budget.collect(iter)?),
+        })
+    }
+
+    fn chunks(self) -> DynIter<'a, QueryOutputChunk> {
+        match self {
+            RootOutput::Vertices(iter) => Box::new(Chunks::new(iter, QueryOutputValue::Vertices)),
+            RootOutput::Edges(iter) => Box::new(Chunks::new(iter, QueryOutputValue::Edges)),
+        }
+    }
+}
+
+// Splits an iterator into chunks of up to `QUERY_OUTPUT_CHUNK_SIZE` items.
+struct Chunks<'a, T> {
+    iter: Peekable<DynIter<'a, T>>,
+    wrap: fn(Vec<T>) -> QueryOutputValue,
+    done: bool,
+}
+
+impl<'a, T> Chunks<'a, T> {
+    fn new(iter: DynIter<'a, T>, wrap: fn(Vec<T>) -> QueryOutputValue) -> Self {
+        Self {
+            iter: iter.peekable(),
+            wrap,
+            done: false,
+        }
+    }
+}
+
+impl<'a, T> Iterator for Chunks<'a, T> {
+    type Item = Result<QueryOutputChunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let mut items = Vec::new();
+        while items.len() < QUERY_OUTPUT_CHUNK_SIZE {
+            match self.iter.next() {
+                Some(Ok(item)) => items.push(item),
+                Some(Err(err)) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+                None => break,
+            }
+        }
+
+        let more = self.iter.peek().is_some();
+        self.done = !more;
+        Some(Ok(QueryOutputChunk::new((self.wrap)(items), more)))
+    }
+}
+
+// Runs a root query, or returns `None` if the query isn't a root query.
+unsafe fn root_query<'a, T: Transaction<'a> + 'a>(txn: *const T, q: &Query) -> Result<Option<RootOutput<'a>>> {
+    match q {
+        Query::Resume(ref q) => {
+            if !q.inner.is_resumable() {
+                return Err(Error::Invalid(ValidationError::InnerQuery));
+            }
+            root_query_after(txn, &q.inner, Some(&q.continuation.0))
+        }
+        q => root_query_after(txn, q, None),
+    }
+}
+
+// Runs a root query, only returning results ordered after the position
+// `after`. Returns `None` if the query isn't a root query.
+unsafe fn root_query_after<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    q: &Query,
+    after: Option<&ContinuationPosition>,
+) -> Result<Option<RootOutput<'a>>> {
+    let output = match q {
+        Query::AllVertex => match after_vertex(after)? {
+            Some(after) => match next_uuid(after) {
+                Ok(start_id) => RootOutput::Vertices((*txn).range_vertices(start_id)?),
+                Err(_) => RootOutput::Vertices(Box::new(iter::empty())),
+            },
+            None => RootOutput::Vertices((*txn).all_vertices()?),
+        },
+        Query::RangeVertex(ref q) => {
+            check_bound(q.limit_param)?;
+            let mut start_id = q.start_id;
+
+            if let Some(after) = after_vertex(after)? {
+                match next_uuid(after) {
+                    Ok(next_id) => start_id = Some(start_id.map_or(next_id, |start_id| start_id.max(next_id))),
+                    Err(_) => return Ok(Some(RootOutput::Vertices(Box::new(iter::empty())))),
+                }
+            }
+
+            let iter = match (q.t, start_id) {
+                (Some(t), Some(start_id)) => (*txn).range_vertices_with_type(t, start_id)?,
+                (Some(t), None) => (*txn).vertices_with_type(t)?,
+                (None, Some(start_id)) => (*txn).range_vertices(start_id)?,
+                (None, None) => (*txn).all_vertices()?,
+            };
+
+            RootOutput::Vertices(Box::new(iter.take(q.limit as usize)))
+        }
+        Query::SpecificVertex(ref q) => {
+            check_bound(q.ids_param)?;
+            RootOutput::Vertices((*txn).specific_vertices(q.ids.clone())?)
+        }
+        Query::VertexWithPropertyPresence(ref q) => match (*txn).vertex_ids_with_property(q.name)? {
+            Some(iter) => RootOutput::Vertices(vertices_by_id(txn, iter, after_vertex(after)?)?),
+            None => return Err(Error::NotIndexed),
+        },
+        Query::VertexWithPropertyValue(ref q) => {
+            check_bound(q.value_param)?;
+            match (*txn).vertex_ids_with_property_value(q.name, &q.value)? {
+                Some(iter) => RootOutput::Vertices(vertices_by_id(txn, iter, after_vertex(after)?)?),
+                None => return Err(Error::NotIndexed),
+            }
+        }
+        Query::VertexWithPropertyRange(ref q) => {
+            match (*txn).vertex_ids_with_property_range(q.name, q.low.as_ref(), q.high.as_ref())? {
+                Some(iter) => RootOutput::Vertices(vertices_by_id(txn, iter, after_vertex(after)?)?),
+                None => return Err(Error::NotIndexed),
+            }
+        }
+        Query::AllEdge => match after_edge(after)? {
+            Some(after) => {
+                let after = after.clone();
+                let iter = (*txn).range_edges(after.clone())?;
+                RootOutput::Edges(Box::new(iter.skip_while(move |r| matches!(r, Ok(e) if *e == after))))
+            }
+            None => RootOutput::Edges((*txn).all_edges()?),
+        },
+        Query::SpecificEdge(ref q) => {
+            check_bound(q.edges_param)?;
+            RootOutput::Edges((*txn).specific_edges(q.edges.clone())?)
+        }
+        Query::EdgesByType(ref q) => {
+            let mut offset = q
+                .start
+                .as_ref()
+                .map(|start| Edge::new(start.outbound_id, q.t, start.inbound_id));
+            let after = after_edge(after)?.cloned();
+
+            if let Some(ref after) = after {
+                if after.t != q.t {
+                    return Err(Error::Invalid(ValidationError::InvalidValue));
+                }
+                if offset.as_ref().is_none_or(|offset| offset < after) {
+                    offset = Some(after.clone());
+                }
+            }
+
+            let iter = match offset {
+                Some(offset) => (*txn).range_edges_with_type(offset)?,
+                None => (*txn).edges_with_type(q.t)?,
+            };
+            let iter = iter.skip_while(move |r| matches!((r, &after), (Ok(e), Some(after)) if e == after));
+            RootOutput::Edges(Box::new(iter.take(q.limit as usize)))
+        }
+        Query::EdgeWithPropertyPresence(ref q) => match (*txn).edges_with_property(q.name)? {
+            Some(iter) => RootOutput::Edges(sorted_edges(iter, after_edge(after)?)?),
+            None => return Err(Error::NotIndexed),
+        },
+        Query::EdgeWithPropertyValue(ref q) => {
+            check_bound(q.value_param)?;
+            match (*txn).edges_with_property_value(q.name, &q.value)? {
+                Some(iter) => RootOutput::Edges(sorted_edges(iter, after_edge(after)?)?),
+                None => return Err(Error::NotIndexed),
+            }
+        }
+        Query::EdgeWithPropertyRange(ref q) => {
+            match (*txn).edges_with_property_range(q.name, q.low.as_ref(), q.high.as_ref())? {
+                Some(iter) => RootOutput::Edges(sorted_edges(iter, after_edge(after)?)?),
+                None => return Err(Error::NotIndexed),
+            }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(output))
+}
+
+// Fails if a parameter placeholder wasn't filled in with `Query::bind`
+// before the query was run.
+fn check_bound(param: Option<Identifier>) -> Result<()> {
+    match param {
+        Some(_) => Err(Error::Invalid(ValidationError::MissingParameter)),
+        None => Ok(()),
+    }
+}
+
+// Gets the vertex ID a continuation position resumes after, erroring if the
+// position is for an edge.
+fn after_vertex(after: Option<&ContinuationPosition>) -> Result<Option<Uuid>> {
+    match after {
+        Some(ContinuationPosition::Vertex(id)) => Ok(Some(*id)),
+        Some(ContinuationPosition::Edge(_)) => Err(Error::Invalid(ValidationError::InvalidValue)),
+        None => Ok(None),
+    }
+}
+
+// Gets the edge a continuation position resumes after, erroring if the
+// position is for a vertex.
+fn after_edge(after: Option<&ContinuationPosition>) -> Result<Option<&Edge>> {
+    match after {
+        Some(ContinuationPosition::Edge(edge)) => Ok(Some(edge)),
+        Some(ContinuationPosition::Vertex(_)) => Err(Error::Invalid(ValidationError::InvalidValue)),
+        None => Ok(None),
+    }
+}
+
+// Gets vertices by ID. Property indexes don't return IDs in a stable order,
+// so they're sorted to allow results to be resumed after `after`.
+unsafe fn vertices_by_id<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    iter: DynIter<'a, Uuid>,
+    after: Option<Uuid>,
+) -> Result<DynIter<'a, Vertex>> {
+    let mut ids = iter.collect::<Result<Vec<Uuid>>>()?;
+    ids.sort_unstable();
+    if let Some(after) = after {
+        ids.retain(|id| *id > after);
+    }
+    (*txn).specific_vertices(ids)
+}
+
+// Sorts edges returned from a property index, so that results can be
+// resumed after `after`.
+fn sorted_edges<'a>(iter: DynIter<'a, Edge>, after: Option<&Edge>) -> Result<DynIter<'a, Edge>> {
+    let mut edges = iter.collect::<Result<Vec<Edge>>>()?;
+    edges.sort_unstable();
+    if let Some(after) = after {
+        edges.retain(|e| e > after);
+    }
+    Ok(Box::new(edges.into_iter().map(Ok)))
+}
+
+// Gets the edges associated with vertices, or vertices associated with
+// edges. When piping from vertices, only edges ordered after `after` are
+// returned, resuming from the piped vertex it's adjacent to.
+unsafe fn pipe<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    q: &PipeQuery,
+    output: &mut Vec<QueryOutputValue>,
+    after: Option<&Edge>,
+    exec: &mut Execution,
+) -> Result<QueryOutputValue> {
+    check_bound(q.limit_param)?;
+    query(txn, &q.inner, output, exec)?;
+    let piped_values = output.pop().unwrap();
+
+    let values = match piped_values {
+        QueryOutputValue::Edges(ref piped_edges) => {
+            let iter: Box<dyn Iterator<Item = Uuid>> = match q.direction {
+                EdgeDirection::Outbound => Box::new(piped_edges.iter().map(|e| e.outbound_id)),
+                EdgeDirection::Inbound => Box::new(piped_edges.iter().map(|e| e.inbound_id)),
+                EdgeDirection::Both => Box::new(piped_edges.iter().flat_map(|e| [e.outbound_id, e.inbound_id])),
+            };
+
+            let mut ids: Vec<Uuid> = iter.collect();
+            if q.distinct {
+                ids = dedupe_by_key(ids, |id| *id);
+            }
+
+            let mut iter: DynIter<Vertex> = (*txn).specific_vertices(ids)?;
+
+            if let Some(ref t) = q.t {
+                iter = Box::new(iter.filter(move |r| match r {
+                    Ok(v) => &v.t == t,
+                    Err(_) => true,
+                }));
+            }
+
+            iter = Box::new(iter.take(q.limit as usize));
+
+            QueryOutputValue::Vertices(exec.budget.collect(iter)?)
+        }
+        QueryOutputValue::Vertices(ref piped_vertices) => {
+            let mut edges = Vec::new();
+            let mut piped_vertices = &piped_vertices[..];
+            let mut after = after;
+
+            if let Some(after) = after {
+                let source_id = match q.direction {
+                    EdgeDirection::Outbound => after.outbound_id,
+                    EdgeDirection::Inbound => after.inbound_id,
+                    EdgeDirection::Both => unreachable!("pipes in both directions can't be resumed"),
+                };
+                piped_vertices = match piped_vertices.iter().position(|v| v.id == source_id) {
+                    Some(i) => &piped_vertices[i..],
+                    None => &[],
+                };
+            }
+
+            let mut seen = HashSet::new();
+
+            for vertex in piped_vertices {
+                if q.distinct && !seen.insert(vertex.id) {
+                    continue;
+                }
+                exec.budget.check()?;
+
+                let iter = adjacent_edges_after(txn, vertex.id, q.direction, q.t, after.take())?;
+                let iter = iter.take((q.limit as usize) - edges.len());
+
+                for result in iter {
+                    edges.push(result?);
+                    exec.budget.check_stage_rows(edges.len() as u64)?;
+                }
+
+                if edges.len() >= (q.limit as usize) {
+                    break;
+                }
+            }
+
+            QueryOutputValue::Edges(edges)
+        }
+        _ => {
+            return Err(Error::OperationOnQuery);
+        }
+    };
+
+    if let Query::Include(_) = *q.inner {
+        // keep the value exported
+        output.push(piped_values);
+    }
+
+    Ok(values)
+}
+
+// Gets the rows of a projection of a query. The query that the chain of
+// pipes starts from outputs a row for each of its values, and each stage
+// after it then extends or filters the rows output by the stage before it.
+unsafe fn project<'a, T: Transaction<'a> + 'a>(txn: *const T, q: &Query, exec: &mut Execution) -> Result<Vec<Row>> {
+    let rows = match q {
+        Query::Pipe(ref q) => {
+            check_bound(q.limit_param)?;
+            let rows = project(txn, &q.inner, exec)?;
+            project_pipe(txn, q, rows, exec)?
+        }
+        Query::PipeProperty(ref q) => {
+            let rows = project(txn, &q.inner, exec)?;
+            project_properties(txn, q, rows, exec)?
+        }
+        Query::PipeWithPropertyPresence(_)
+        | Query::PipeWithPropertyValue(_)
+        | Query::PipeWithPropertyRange(_)
+        | Query::PipeWithPropertyPredicate(_) => {
+            let rows = project(txn, stage_children(q)[0], exec)?;
+            project_filter(txn, q, rows, exec)?
+        }
+        Query::Include(_) => return Err(Error::Invalid(ValidationError::InnerQuery)),
+        q => {
+            let mut output = Vec::with_capacity(q.output_len());
+            query(txn, q, &mut output, exec)?;
+            match output.pop().unwrap() {
+                QueryOutputValue::Vertices(vertices) => vertices
+                    .into_iter()
+                    .map(|v| Row::new(vec![RowValue::Vertex(v)]))
+                    .collect(),
+                QueryOutputValue::Edges(edges) => {
+                    edges.into_iter().map(|e| Row::new(vec![RowValue::Edge(e)])).collect()
+                }
+                _ => return Err(Error::Invalid(ValidationError::InnerQuery)),
+            }
+        }
+    };
+    exec.budget.check_stage_rows(rows.len() as u64)?;
+    Ok(rows)
+}
+
+// Identifies a vertex or edge bound in a row, so that rows can be compared
+// without comparing property values.
+#[derive(PartialEq, Eq, Hash)]
+enum RowKey {
+    Vertex(Uuid),
+    Edge(Edge),
+}
+
+fn row_key(value: &RowValue) -> Option<RowKey> {
+    match value {
+        RowValue::Vertex(vertex) => Some(RowKey::Vertex(vertex.id)),
+        RowValue::Edge(edge) => Some(RowKey::Edge(edge.clone())),
+        RowValue::Property(_) => None,
+    }
+}
+
+// Appends a value to a copy of a row.
+fn extend_row(row: &Row, value: RowValue) -> Row {
+    let mut values = Vec::with_capacity(row.values.len() + 1);
+    values.extend(row.values.iter().cloned());
+    values.push(value);
+    Row::new(values)
+}
+
+// Extends rows with the values piped to from their last value, with a row
+// for each of them.
+unsafe fn project_pipe<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    q: &PipeQuery,
+    rows: Vec<Row>,
+    exec: &mut Execution,
+) -> Result<Vec<Row>> {
+    let limit = q.limit as usize;
+    let piped_ids = |edge: &Edge| match q.direction {
+        EdgeDirection::Outbound => vec![edge.outbound_id],
+        EdgeDirection::Inbound => vec![edge.inbound_id],
+        EdgeDirection::Both => vec![edge.outbound_id, edge.inbound_id],
+    };
+
+    // When piping from edges, the vertices are read all at once
+    let mut ids = Vec::new();
+    for row in &rows {
+        if let Some(RowValue::Edge(edge)) = row.values.last() {
+            ids.extend(piped_ids(edge));
+        }
+    }
+    let vertices: HashMap<Uuid, Vertex> = if ids.is_empty() {
+        HashMap::new()
+    } else {
+        let iter = (*txn).specific_vertices(dedupe_by_key(ids, |id| *id))?;
+        exec.budget.collect(iter)?.into_iter().map(|v| (v.id, v)).collect()
+    };
+
+    let mut projected = Vec::new();
+    let mut seen = HashSet::new();
+
+    for row in &rows {
+        if projected.len() >= limit {
+            break;
+        }
+        if q.distinct && !seen.insert(row.values.iter().filter_map(row_key).collect::<Vec<RowKey>>()) {
+            continue;
+        }
+        exec.budget.check()?;
+
+        match row.values.last() {
+            Some(RowValue::Vertex(vertex)) => {
+                let iter = adjacent_edges(txn, vertex.id, q.direction, q.t)?;
+                for result in iter.take(limit - projected.len()) {
+                    projected.push(extend_row(row, RowValue::Edge(result?)));
+                    exec.budget.check_stage_rows(projected.len() as u64)?;
+                }
+            }
+            Some(RowValue::Edge(edge)) => {
+                for id in piped_ids(edge) {
+                    match vertices.get(&id) {
+                        Some(vertex) if projected.len() < limit && q.t.is_none_or(|t| vertex.t == t) => {
+                            projected.push(extend_row(row, RowValue::Vertex(vertex.clone())));
+                            exec.budget.check_stage_rows(projected.len() as u64)?;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => return Err(Error::OperationOnQuery),
+        }
+    }
+
+    Ok(projected)
+}
+
+// Extends rows with the properties of their last value, with a row for each
+// property. Rows whose last value doesn't have any are dropped.
+unsafe fn project_properties<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    q: &PipePropertyQuery,
+    rows: Vec<Row>,
+    exec: &mut Execution,
+) -> Result<Vec<Row>> {
+    let mut projected = Vec::new();
+
+    for row in &rows {
+        exec.budget.check()?;
+        let mut props = Vec::new();
+        match (row.values.last(), q.name) {
+            (Some(RowValue::Vertex(vertex)), Some(name)) => {
+                if let Some(value) = (*txn).vertex_property(vertex, name)? {
+                    props.push(NamedProperty::new(name, value));
+                }
+            }
+            (Some(RowValue::Vertex(vertex)), None) => {
+                for result in (*txn).all_vertex_properties_for_vertex(vertex)? {
+                    let (name, value) = result?;
+                    props.push(NamedProperty::new(name, value));
+                }
+            }
+            (Some(RowValue::Edge(edge)), Some(name)) => {
+                if let Some(value) = (*txn).edge_property(edge, name)? {
+                    props.push(NamedProperty::new(name, value));
+                }
+            }
+            (Some(RowValue::Edge(edge)), None) => {
+                for result in (*txn).all_edge_properties_for_edge(edge)? {
+                    let (name, value) = result?;
+                    props.push(NamedProperty::new(name, value));
+                }
+            }
+            _ => return Err(Error::OperationOnQuery),
+        }
+
+        for prop in props {
+            projected.push(extend_row(row, RowValue::Property(prop)));
+            exec.budget.check_stage_rows(projected.len() as u64)?;
+        }
+    }
+
+    Ok(projected)
+}
+
+// Keeps the rows whose last value passes a property filter, by running the
+// filter over those values.
+unsafe fn project_filter<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    q: &Query,
+    mut rows: Vec<Row>,
+    exec: &mut Execution,
+) -> Result<Vec<Row>> {
+    let mut ids = Vec::new();
+    let mut edges = Vec::new();
+    for row in &rows {
+        match row.values.last() {
+            Some(RowValue::Vertex(vertex)) => ids.push(vertex.id),
+            Some(RowValue::Edge(edge)) => edges.push(edge.clone()),
+            _ => return Err(Error::OperationOnQuery),
+        }
+    }
+    if rows.is_empty() {
+        return Ok(rows);
+    }
+
+    let source: Query = if edges.is_empty() {
+        SpecificVertexQuery::new(dedupe_by_key(ids, |id| *id)).into()
+    } else {
+        SpecificEdgeQuery::new(dedupe_by_key(edges, |e| e.clone())).into()
+    };
+    let filter = map_children(q.clone(), |_| source.clone());
+    let mut output = Vec::with_capacity(1);
+    query(txn, &filter, &mut output, exec)?;
+    let passed: HashSet<RowKey> = match output.pop().unwrap() {
+        QueryOutputValue::Vertices(vertices) => vertices.into_iter().map(|v| RowKey::Vertex(v.id)).collect(),
+        QueryOutputValue::Edges(edges) => edges.into_iter().map(RowKey::Edge).collect(),
+        _ => return Err(Error::OperationOnQuery),
+    };
+
+    rows.retain(|row| {
+        row.values
+            .last()
+            .and_then(row_key)
+            .is_some_and(|key| passed.contains(&key))
+    });
+    Ok(rows)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SetOperation {
+    Union,
+    Intersect,
+    Except,
+}
+
+// Combines the values output by two queries with a set operation.
+unsafe fn set_operation<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    left: &Query,
+    right: &Query,
+    op: SetOperation,
+    output: &mut Vec<QueryOutputValue>,
+    exec: &mut Execution,
+) -> Result<QueryOutputValue> {
+    query(txn, left, output, exec)?;
+    let left_values = output.pop().unwrap();
+    if let Query::Include(_) = *left {
+        // keep the value exported
+        output.push(left_values.clone());
+    }
+
+    query(txn, right, output, exec)?;
+    let right_values = output.pop().unwrap();
+    if let Query::Include(_) = *right {
+        // keep the value exported
+        output.push(right_values.clone());
+    }
+
+    match (left_values, right_values) {
+        (QueryOutputValue::Vertices(left_vertices), QueryOutputValue::Vertices(right_vertices)) => Ok(
+            QueryOutputValue::Vertices(combine(left_vertices, right_vertices, op, |v| v.id)),
+        ),
+        (QueryOutputValue::Edges(left_edges), QueryOutputValue::Edges(right_edges)) => {
+            Ok(QueryOutputValue::Edges(combine(left_edges, right_edges, op, |e| {
+                e.clone()
+            })))
+        }
+        _ => Err(Error::OperationOnQuery),
+    }
+}
+
+// Applies a set operation to two lists of values, identified by `key`.
+// Values are deduplicated, and kept in the order they first appear.
+fn combine<V, K: Eq + Hash>(left: Vec<V>, right: Vec<V>, op: SetOperation, key: impl Fn(&V) -> K) -> Vec<V> {
+    if op == SetOperation::Union {
+        return dedupe_by_key(left.into_iter().chain(right).collect(), key);
+    }
+
+    let mut seen = HashSet::new();
+    let right_keys: HashSet<K> = right.iter().map(&key).collect();
+    left.into_iter()
+        .filter(|value| {
+            let k = key(value);
+            right_keys.contains(&k) == (op == SetOperation::Intersect) && seen.insert(k)
+        })
+        .collect()
+}
+
+// Removes values with duplicate keys, keeping the first occurrence of each.
+fn dedupe_by_key<V, K: Eq + Hash>(values: Vec<V>, key: impl Fn(&V) -> K) -> Vec<V> {
+    let mut seen = HashSet::new();
+    values.into_iter().filter(|value| seen.insert(key(value))).collect()
+}
+
+// Identifies a path by the vertices and edges along it.
+fn path_key(path: &Path) -> (Vec<Uuid>, Vec<Edge>) {
+    (path.vertices.iter().map(|v| v.id).collect(), path.edges.clone())
+}
+
+// Removes duplicate items from a query output value. Vertices are compared by
+// ID, and properties by the vertex or edge they belong to.
+fn dedupe(value: QueryOutputValue) -> Result<QueryOutputValue> {
+    Ok(match value {
+        QueryOutputValue::Vertices(vertices) => QueryOutputValue::Vertices(dedupe_by_key(vertices, |v| v.id)),
+        QueryOutputValue::Edges(edges) => QueryOutputValue::Edges(dedupe_by_key(edges, |e| e.clone())),
+        QueryOutputValue::VertexProperties(props) => {
+            QueryOutputValue::VertexProperties(dedupe_by_key(props, |p| p.vertex.id))
+        }
+        QueryOutputValue::EdgeProperties(props) => {
+            QueryOutputValue::EdgeProperties(dedupe_by_key(props, |p| p.edge.clone()))
+        }
+        QueryOutputValue::Paths(paths) => QueryOutputValue::Paths(dedupe_by_key(paths, path_key)),
+        QueryOutputValue::Count(_)
+        | QueryOutputValue::Aggregation(_)
+        | QueryOutputValue::Degrees(_)
+        | QueryOutputValue::Subgraph(_)
+        | QueryOutputValue::Rows(_) => return Err(Error::OperationOnQuery),
+    })
+}
+
+// Counts the distinct items in a query output value, compared the same way
+// as `dedupe`.
+fn distinct_len(value: &QueryOutputValue) -> Result<usize> {
+    Ok(match value {
+        QueryOutputValue::Vertices(vertices) => vertices.iter().map(|v| v.id).collect::<HashSet<Uuid>>().len(),
+        QueryOutputValue::Edges(edges) => edges.iter().collect::<HashSet<&Edge>>().len(),
+        QueryOutputValue::VertexProperties(props) => props.iter().map(|p| p.vertex.id).collect::<HashSet<Uuid>>().len(),
+        QueryOutputValue::EdgeProperties(props) => props.iter().map(|p| &p.edge).collect::<HashSet<&Edge>>().len(),
+        QueryOutputValue::Paths(paths) => paths.iter().map(path_key).collect::<HashSet<_>>().len(),
+        QueryOutputValue::Count(_)
+        | QueryOutputValue::Aggregation(_)
+        | QueryOutputValue::Degrees(_)
+        | QueryOutputValue::Subgraph(_)
+        | QueryOutputValue::Rows(_) => return Err(Error::OperationOnQuery),
+    })
+}
+
+// Sorts values by a property, keeping only the first `q.limit` of them.
+// Rather than sorting everything, the top values are selected first, so that
+// small limits are cheap.
+fn order_by<V>(values: Vec<V>, q: &OrderByQuery, property: impl Fn(&V) -> Result<Option<Json>>) -> Result<Vec<V>> {
+    let limit = q.limit as usize;
+    if limit == 0 {
+        return Ok(Vec::new());
+    }
+
+    // Each value is keyed by its original position as well, to keep the
+    // sort stable.
+    let mut keyed = Vec::with_capacity(values.len());
+    for (i, value) in values.into_iter().enumerate() {
+        keyed.push((property(&value)?, i, value));
+    }
+
+    let cmp = |(first, first_i, _): &(Option<Json>, usize, V), (second, second_i, _): &(Option<Json>, usize, V)| {
+        let ordering = match (first, second) {
+            (Some(first), Some(second)) if q.direction == SortDirection::Descending => second.total_cmp(first),
+            (Some(first), Some(second)) => first.total_cmp(second),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        ordering.then(first_i.cmp(second_i))
+    };
+
+    if limit < keyed.len() {
+        keyed.select_nth_unstable_by(limit - 1, cmp);
+        keyed.truncate(limit);
+    }
+    keyed.sort_unstable_by(cmp);
+
+    Ok(keyed.into_iter().map(|(_, _, value)| value).collect())
+}
+
+// Aggregates a property over values, grouping them first if the query asks
+// for it. Groups are returned in ascending order of their keys.
+fn aggregate<V>(
+    values: &[V],
+    q: &AggregateQuery,
+    t: impl Fn(&V) -> Identifier,
+    property: impl Fn(&V, Identifier) -> Result<Option<Json>>,
+) -> Result<Vec<AggregationGroup>> {
+    let mut groups: HashMap<Option<Json>, Vec<f64>> = HashMap::new();
+    if q.group_by.is_none() {
+        // Ungrouped aggregations always have exactly one group, even if
+        // there's nothing to aggregate
+        groups.insert(None, Vec::new());
+    }
+
+    for value in values {
+        let key = match q.group_by {
+            None => None,
+            Some(GroupBy::Type) => Some(Json::new(serde_json::Value::String(t(value).to_string()))),
+            Some(GroupBy::Property(name)) => {
+                Some(property(value, name)?.unwrap_or_else(|| Json::new(serde_json::Value::Null)))
+            }
+        };
+        let numbers = groups.entry(key).or_default();
+        if let Some(number) = property(value, q.name)?.and_then(|value| value.as_f64()) {
+            numbers.push(number);
+        }
+    }
+
+    let mut groups = groups
+        .into_iter()
+        .map(|(key, numbers)| {
+            let count = numbers.len() as u64;
+            Ok(AggregationGroup::new(
+                key,
+                count,
+                aggregate_numbers(&q.function, numbers)?,
+            ))
+        })
+        .collect::<Result<Vec<AggregationGroup>>>()?;
+    groups.sort_by(|a, b| match (&a.key, &b.key) {
+        (Some(a), Some(b)) => a.total_cmp(b),
+        _ => Ordering::Equal,
+    });
+    Ok(groups)
+}
+
+fn aggregate_numbers(function: &AggregateFunction, numbers: Vec<f64>) -> Result<AggregationValue> {
+    Ok(match function {
+        AggregateFunction::Sum => AggregationValue::Number(Some(numbers.iter().sum())),
+        AggregateFunction::Min => AggregationValue::Number(numbers.into_iter().reduce(f64::min)),
+        AggregateFunction::Max => AggregationValue::Number(numbers.into_iter().reduce(f64::max)),
+        AggregateFunction::Average => {
+            if numbers.is_empty() {
+                AggregationValue::Number(None)
+            } else {
+                AggregationValue::Number(Some(numbers.iter().sum::<f64>() / numbers.len() as f64))
+            }
+        }
+        AggregateFunction::Histogram(width) => {
+            let width = match width.as_f64() {
+                Some(width) if width > 0.0 => width,
+                _ => return Err(Error::Invalid(ValidationError::InvalidValue)),
+            };
+            let mut buckets: BTreeMap<i64, u64> = BTreeMap::new();
+            for number in numbers {
+                *buckets.entry((number / width).floor() as i64).or_default() += 1;
+            }
+            AggregationValue::Histogram(
+                buckets
+                    .into_iter()
+                    .map(|(i, count)| HistogramBucket::new(i as f64 * width, count))
+                    .collect(),
+            )
+        }
+    })
+}
+
+// Gets the queries that a stage runs to build off of, in the order they're
+// run. This mirrors how `query_stage` recurses, so that plans from `explain`
+// and `analyze` have the same shape.
+fn stage_children(q: &Query) -> Vec<&Query> {
+    match q {
+        Query::Pipe(q) => vec![&q.inner],
+        Query::Resume(q) => match *q.inner {
+            Query::Pipe(ref inner) => vec![&inner.inner],
+            _ => Vec::new(),
+        },
+        Query::PipeProperty(q) => vec![&q.inner],
+        Query::PipeWithPropertyPresence(q) => vec![&q.inner],
+        Query::PipeWithPropertyValue(q) => vec![&q.inner],
+        Query::PipeWithPropertyRange(q) => vec![&q.inner],
+        Query::PipeWithPropertyPredicate(q) => vec![&q.inner],
+        Query::Traversal(q) => vec![&q.inner],
+        Query::Union(q) => vec![&q.left, &q.right],
+        Query::Intersect(q) => vec![&q.left, &q.right],
+        Query::Except(q) => vec![&q.left, &q.right],
+        Query::Distinct(q) => vec![&q.inner],
+        Query::OrderBy(q) => vec![&q.inner],
+        Query::Include(q) => vec![&q.inner],
+        Query::Count(q) => match *q.inner {
+            Query::AllVertex | Query::AllEdge => Vec::new(),
+            Query::EdgesByType(ref inner) if inner.start.is_none() => Vec::new(),
+            ref inner => vec![inner],
+        },
+        Query::Aggregate(q) => vec![&q.inner],
+        Query::Degree(q) => vec![&q.inner],
+        Query::Subgraph(q) => vec![&q.inner],
+        Query::Project(q) => vec![&q.inner],
+        _ => Vec::new(),
+    }
+}
+
+unsafe fn explain<'a, T: Transaction<'a> + 'a>(txn: *const T, q: &Query, totals: &Totals) -> Result<QueryPlan> {
+    let children = stage_children(q)
+        .into_iter()
+        .map(|child| explain(txn, child, totals))
+        .collect::<Result<Vec<QueryPlan>>>()?;
+    plan_stage(txn, q, children, totals)
+}
+
+// The total numbers of vertices and edges, for estimating how many values
+// stages output. Counting can mean scanning the whole datastore, so each is
+// only counted once it's needed, and then kept for the rest of the plan.
+#[derive(Default)]
+struct Totals {
+    vertex_count: Cell<Option<u64>>,
+    edge_count: Cell<Option<u64>>,
+}
+
+impl Totals {
+    unsafe fn vertex_count<'a, T: Transaction<'a> + 'a>(&self, txn: *const T) -> u64 {
+        let count = self.vertex_count.get().unwrap_or_else(|| (*txn).vertex_count());
+        self.vertex_count.set(Some(count));
+        count
+    }
+
+    unsafe fn edge_count<'a, T: Transaction<'a> + 'a>(&self, txn: *const T) -> u64 {
+        let count = self.edge_count.get().unwrap_or_else(|| (*txn).edge_count());
+        self.edge_count.set(Some(count));
+        count
+    }
+}
+
+// Builds the plan for a single stage of a query, given the plans for its
+// children.
+unsafe fn plan_stage<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    q: &Query,
+    children: Vec<QueryPlan>,
+    totals: &Totals,
+) -> Result<QueryPlan> {
+    let child_rows: Vec<Option<u64>> = children.iter().map(|c| c.estimated_rows).collect();
+    let child_rows = |i: usize| child_rows.get(i).copied().flatten();
+
+    let (stage, calls, indexed, estimated_rows) = match q {
+        Query::AllVertex => ("AllVertex", vec!["all_vertices"], None, Some(totals.vertex_count(txn))),
+        Query::RangeVertex(q) => {
+            let call = match (q.t, q.start_id) {
+                (Some(_), Some(_)) => "range_vertices_with_type",
+                (Some(_), None) => "vertices_with_type",
+                (None, Some(_)) => "range_vertices",
+                (None, None) => "all_vertices",
+            };
+            let rows = min_rows(&[Some(q.limit as u64), Some(totals.vertex_count(txn))]);
+            ("RangeVertex", vec![call], None, rows)
+        }
+        Query::SpecificVertex(q) => (
+            "SpecificVertex",
+            vec!["specific_vertices"],
+            None,
+            Some(q.ids.len() as u64),
+        ),
+        Query::VertexWithPropertyPresence(q) => (
+            "VertexWithPropertyPresence",
+            vec!["vertex_ids_with_property", "specific_vertices"],
+            Some(vertex_index_exists(txn, q.name)?),
+            Some(totals.vertex_count(txn)),
+        ),
+        Query::VertexWithPropertyValue(q) => (
+            "VertexWithPropertyValue",
+            vec!["vertex_ids_with_property_value", "specific_vertices"],
+            Some(vertex_index_exists(txn, q.name)?),
+            Some(totals.vertex_count(txn)),
+        ),
+        Query::VertexWithPropertyRange(q) => (
+            "VertexWithPropertyRange",
+            vec!["vertex_ids_with_property_range", "specific_vertices"],
+            Some(vertex_index_exists(txn, q.name)?),
+            Some(totals.vertex_count(txn)),
+        ),
+        Query::AllEdge => ("AllEdge", vec!["all_edges"], None, Some(totals.edge_count(txn))),
+        Query::SpecificEdge(q) => ("SpecificEdge", vec!["specific_edges"], None, Some(q.edges.len() as u64)),
+        Query::EdgesByType(q) => {
+            let call = if q.start.is_some() {
+                "range_edges_with_type"
+            } else {
+                "edges_with_type"
+            };
+            let rows = min_rows(&[Some(q.limit as u64), Some(totals.edge_count(txn))]);
+            ("EdgesByType", vec![call], None, rows)
+        }
+        Query::EdgeWithPropertyPresence(q) => (
+            "EdgeWithPropertyPresence",
+            vec!["edges_with_property"],
+            Some(edge_index_exists(txn, q.name)?),
+            Some(totals.edge_count(txn)),
+        ),
+        Query::EdgeWithPropertyValue(q) => (
+            "EdgeWithPropertyValue",
+            vec!["edges_with_property_value"],
+            Some(edge_index_exists(txn, q.name)?),
+            Some(totals.edge_count(txn)),
+        ),
+        Query::EdgeWithPropertyRange(q) => (
+            "EdgeWithPropertyRange",
+            vec!["edges_with_property_range"],
+            Some(edge_index_exists(txn, q.name)?),
+            Some(totals.edge_count(txn)),
+        ),
+        Query::Pipe(q) => match q.inner.output_type()? {
+            QueryOutputValue::Vertices(_) => (
+                "Pipe",
+                adjacent_edges_calls(q.direction),
+                None,
+                min_rows(&[Some(q.limit as u64), Some(totals.edge_count(txn))]),
+            ),
+            _ => (
+                "Pipe",
+                vec!["specific_vertices"],
+                None,
+                min_rows(&[Some(q.limit as u64), child_rows(0)]),
+            ),
+        },
+        Query::Resume(q) => {
+            let mut plan = plan_stage(txn, &q.inner, children, totals)?;
+            plan.stage = "Resume".to_string();
+            match *q.inner {
+                Query::RangeVertex(ref inner) if inner.t.is_some() => {
+                    plan.calls = vec!["range_vertices_with_type".to_string()]
                 }
+                Query::AllVertex | Query::RangeVertex(_) => plan.calls = vec!["range_vertices".to_string()],
+                Query::AllEdge => plan.calls = vec!["range_edges".to_string()],
+                Query::EdgesByType(_) => plan.calls = vec!["range_edges_with_type".to_string()],
+                _ => {}
+            }
+            return Ok(plan);
+        }
+        Query::PipeProperty(q) => {
+            let call = match (q.inner.output_type()?, q.name) {
+                (QueryOutputValue::Vertices(_), Some(_)) => "vertex_property",
+                (QueryOutputValue::Vertices(_), None) => "all_vertex_properties_for_vertex",
+                (_, Some(_)) => "edge_property",
+                (_, None) => "all_edge_properties_for_edge",
+            };
+            ("PipeProperty", vec![call], None, child_rows(0))
+        }
+        Query::PipeWithPropertyPresence(q) => match q.inner.output_type()? {
+            QueryOutputValue::Vertices(_) => (
+                "PipeWithPropertyPresence",
+                vec!["vertex_ids_with_property"],
+                Some(vertex_index_exists(txn, q.name)?),
+                child_rows(0),
+            ),
+            _ => (
+                "PipeWithPropertyPresence",
+                vec!["edges_with_property"],
+                Some(edge_index_exists(txn, q.name)?),
+                child_rows(0),
+            ),
+        },
+        Query::PipeWithPropertyValue(q) => match q.inner.output_type()? {
+            QueryOutputValue::Vertices(_) => (
+                "PipeWithPropertyValue",
+                vec!["vertex_ids_with_property_value"],
+                Some(vertex_index_exists(txn, q.name)?),
+                child_rows(0),
+            ),
+            _ => (
+                "PipeWithPropertyValue",
+                vec!["edges_with_property_value"],
+                Some(edge_index_exists(txn, q.name)?),
+                child_rows(0),
+            ),
+        },
+        Query::PipeWithPropertyRange(q) => match q.inner.output_type()? {
+            QueryOutputValue::Vertices(_) => (
+                "PipeWithPropertyRange",
+                vec!["vertex_ids_with_property_range"],
+                Some(vertex_index_exists(txn, q.name)?),
+                child_rows(0),
+            ),
+            _ => (
+                "PipeWithPropertyRange",
+                vec!["edges_with_property_range"],
+                Some(edge_index_exists(txn, q.name)?),
+                child_rows(0),
+            ),
+        },
+        Query::PipeWithPropertyPredicate(q) => {
+            let call = match q.inner.output_type()? {
+                QueryOutputValue::Vertices(_) => "vertex_property",
+                _ => "edge_property",
+            };
+            ("PipeWithPropertyPredicate", vec![call], None, child_rows(0))
+        }
+        Query::Traversal(q) => (
+            "Traversal",
+            [adjacent_edges_calls(q.direction), vec!["specific_vertices"]].concat(),
+            None,
+            Some(totals.vertex_count(txn)),
+        ),
+        Query::ShortestPath(q) => {
+            let mut calls = vec!["specific_vertices"];
+            calls.extend(adjacent_edges_calls(q.direction));
+            // Searching backwards in both directions walks the same edges
+            if q.direction != EdgeDirection::Both {
+                calls.extend(adjacent_edges_calls(reverse_direction(q.direction)));
+            }
+            ("ShortestPath", calls, None, Some(1))
+        }
+        Query::WeightedShortestPath(q) => (
+            "WeightedShortestPath",
+            [
+                vec!["specific_vertices"],
+                adjacent_edges_calls(q.direction),
+                vec!["edge_property"],
+            ]
+            .concat(),
+            None,
+            Some(1),
+        ),
+        Query::Subgraph(q) => {
+            let mut calls = [adjacent_edges_calls(q.direction), vec!["specific_vertices"]].concat();
+            if q.properties {
+                calls.extend(["all_vertex_properties_for_vertex", "all_edge_properties_for_edge"]);
+            }
+            ("Subgraph", calls, None, Some(1))
+        }
+        Query::Union(_) => {
+            let rows = match (child_rows(0), child_rows(1)) {
+                (Some(left), Some(right)) => Some(left + right),
+                _ => None,
+            };
+            ("Union", Vec::new(), None, rows)
+        }
+        Query::Intersect(_) => ("Intersect", Vec::new(), None, min_rows(&[child_rows(0), child_rows(1)])),
+        Query::Except(_) => ("Except", Vec::new(), None, child_rows(0)),
+        Query::Distinct(_) => ("Distinct", Vec::new(), None, child_rows(0)),
+        Query::OrderBy(q) => {
+            let call = match q.inner.output_type()? {
+                QueryOutputValue::Vertices(_) => "vertex_property",
+                _ => "edge_property",
+            };
+            (
+                "OrderBy",
+                vec![call],
+                None,
+                min_rows(&[Some(q.limit as u64), child_rows(0)]),
+            )
+        }
+        Query::Include(_) => ("Include", Vec::new(), None, child_rows(0)),
+        Query::Count(q) => {
+            let calls = match *q.inner {
+                Query::AllVertex => vec!["vertex_count"],
+                Query::AllEdge => vec!["edge_count"],
+                Query::EdgesByType(ref inner) if inner.start.is_none() => vec!["edge_count_with_type"],
+                _ => Vec::new(),
+            };
+            ("Count", calls, None, Some(1))
+        }
+        Query::Aggregate(q) => {
+            let call = match q.inner.output_type()? {
+                QueryOutputValue::Vertices(_) => "vertex_property",
+                _ => "edge_property",
             };
+            let rows = if q.group_by.is_some() { child_rows(0) } else { Some(1) };
+            ("Aggregate", vec![call], None, rows)
+        }
+        Query::Degree(_) => ("Degree", vec!["vertex_degree"], None, child_rows(0)),
+        Query::Project(_) => ("Project", Vec::new(), None, child_rows(0)),
+    };
 
-            if let Query::Include(_) = *q.inner {
-                // keep the value exported
-                output.push(piped_values);
+    let mut plan = QueryPlan::new(stage, calls.into_iter().map(|call| call.to_string()).collect());
+    plan.indexed = indexed;
+    plan.estimated_rows = estimated_rows;
+    plan.children = children;
+
+    // Filters that allow scanning read the property of each value when the
+    // index doesn't exist
+    let scanned = match q {
+        Query::PipeWithPropertyPresence(q) if q.allow_scan => Some(&q.inner),
+        Query::PipeWithPropertyValue(q) if q.allow_scan => Some(&q.inner),
+        _ => None,
+    };
+    if let (Some(inner), Some(false)) = (scanned, indexed) {
+        let call = match inner.output_type()? {
+            QueryOutputValue::Vertices(_) => "vertex_property",
+            _ => "edge_property",
+        };
+        plan.calls.push(call.to_string());
+        plan.scan = true;
+    }
+    Ok(plan)
+}
+
+// Decides whether each property presence and value filter in a query can
+// scan. Scanning is only allowed for unindexed properties, so that filters of
+// indexed ones can still be rewritten into index lookups. Rather than
+// stopping at the first error, this records it in `err`, so that it can reuse
+// `map_children`.
+unsafe fn resolve_scans<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    q: Query,
+    scan_fallback: bool,
+    err: &mut Option<Error>,
+) -> Query {
+    let q = map_children(q, |q| resolve_scans(txn, q, scan_fallback, err));
+    let (inner, name, allow_scan) = match q {
+        Query::PipeWithPropertyPresence(ref q) => (&q.inner, q.name, q.allow_scan),
+        Query::PipeWithPropertyValue(ref q) => (&q.inner, q.name, q.allow_scan),
+        q => return q,
+    };
+    let allow_scan = if allow_scan || scan_fallback {
+        let exists = match inner.output_type() {
+            Ok(QueryOutputValue::Edges(_)) => edge_index_exists(txn, name),
+            _ => vertex_index_exists(txn, name),
+        };
+        match exists {
+            Ok(exists) => !exists,
+            Err(e) => {
+                *err = Some(e);
+                allow_scan
             }
+        }
+    } else {
+        false
+    };
+    match q {
+        Query::PipeWithPropertyPresence(q) => PipeWithPropertyPresenceQuery { allow_scan, ..q }.into(),
+        Query::PipeWithPropertyValue(q) => PipeWithPropertyValueQuery { allow_scan, ..q }.into(),
+        q => q,
+    }
+}
 
-            values
+// Gets the smallest of a set of row count bounds, ignoring missing ones.
+fn min_rows(bounds: &[Option<u64>]) -> Option<u64> {
+    bounds.iter().flatten().min().copied()
+}
+
+// Gets the `Transaction` methods used to walk edges in a direction.
+fn adjacent_edges_calls(direction: EdgeDirection) -> Vec<&'static str> {
+    match direction {
+        EdgeDirection::Outbound => vec!["range_edges"],
+        EdgeDirection::Inbound => vec!["range_reversed_edges"],
+        EdgeDirection::Both => vec!["range_edges", "range_reversed_edges"],
+    }
+}
+
+fn reverse_direction(direction: EdgeDirection) -> EdgeDirection {
+    match direction {
+        EdgeDirection::Outbound => EdgeDirection::Inbound,
+        EdgeDirection::Inbound => EdgeDirection::Outbound,
+        EdgeDirection::Both => EdgeDirection::Both,
+    }
+}
+
+// Checks whether a property is indexed.
+unsafe fn vertex_index_exists<'a, T: Transaction<'a> + 'a>(txn: *const T, name: Identifier) -> Result<bool> {
+    Ok(optional_index((*txn).vertex_ids_with_property(name))?.is_some())
+}
+
+unsafe fn edge_index_exists<'a, T: Transaction<'a> + 'a>(txn: *const T, name: Identifier) -> Result<bool> {
+    Ok(optional_index((*txn).edges_with_property(name))?.is_some())
+}
+
+// Gets the results of reading an index, or `None` if the index doesn't exist.
+// Datastores signal a missing index by returning `None`, but may also fail
+// with `Error::NotIndexed`.
+fn optional_index<T>(result: Result<Option<T>>) -> Result<Option<T>> {
+    match result {
+        Err(Error::NotIndexed) => Ok(None),
+        result => result,
+    }
+}
+
+// Reads a property of each vertex, for filters that scan rather than read
+// from an index. Gets the IDs of the vertices whose value satisfies `f`.
+unsafe fn scan_vertices<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    vertices: &[Vertex],
+    name: Identifier,
+    f: impl Fn(Option<&Json>) -> bool,
+) -> Result<HashSet<Uuid>> {
+    let mut ids = HashSet::new();
+    for vertex in vertices {
+        if f((*txn).vertex_property(vertex, name)?.as_ref()) {
+            ids.insert(vertex.id);
+        }
+    }
+    Ok(ids)
+}
+
+// Reads a property of each edge, for filters that scan rather than read from
+// an index. Gets the edges whose value satisfies `f`.
+unsafe fn scan_edges<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    edges: &[Edge],
+    name: Identifier,
+    f: impl Fn(Option<&Json>) -> bool,
+) -> Result<HashSet<Edge>> {
+    let mut matched = HashSet::new();
+    for edge in edges {
+        if f((*txn).edge_property(edge, name)?.as_ref()) {
+            matched.insert(edge.clone());
+        }
+    }
+    Ok(matched)
+}
+
+// Gets the number of values in a query output value.
+fn value_len(value: &QueryOutputValue) -> u64 {
+    let len = match value {
+        QueryOutputValue::Vertices(vertices) => vertices.len(),
+        QueryOutputValue::Edges(edges) => edges.len(),
+        QueryOutputValue::Count(_) => 1,
+        QueryOutputValue::VertexProperties(props) => props.len(),
+        QueryOutputValue::EdgeProperties(props) => props.len(),
+        QueryOutputValue::Paths(paths) => paths.len(),
+        QueryOutputValue::Aggregation(groups) => groups.len(),
+        QueryOutputValue::Degrees(degrees) => degrees.len(),
+        QueryOutputValue::Subgraph(subgraph) => subgraph.vertices.len() + subgraph.edges.len(),
+        QueryOutputValue::Rows(rows) => rows.len(),
+    };
+    len as u64
+}
+
+// Records the plan for each stage of a query as it runs, for
+// `Database::analyze`.
+struct Profiler {
+    // The finished child stages of each stage that's currently running, with
+    // the innermost stage last.
+    stack: Vec<Vec<QueryPlan>>,
+    totals: Totals,
+}
+
+// Tracks a running query's use of its budget.
+struct Budget {
+    deadline: Option<Instant>,
+    max_stage_rows: Option<u64>,
+    max_output_rows: Option<u64>,
+    cancelled: Option<Arc<AtomicBool>>,
+    // The number of values output so far.
+    output_rows: u64,
+}
+
+impl Budget {
+    // Starts tracking a budget, so the timeout starts counting down.
+    fn new(budget: &QueryBudget) -> Self {
+        Self {
+            deadline: budget.timeout.map(|timeout| Instant::now() + timeout),
+            max_stage_rows: budget.max_stage_rows,
+            max_output_rows: budget.max_output_rows,
+            cancelled: budget.cancelled.clone(),
+            output_rows: 0,
+        }
+    }
+
+    // Fails if the query has been cancelled or has run out of time.
+    fn check(&self) -> Result<()> {
+        if let Some(ref cancelled) = self.cancelled {
+            if cancelled.load(atomic::Ordering::Relaxed) {
+                return Err(Error::Cancelled);
+            }
+        }
+        if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Err(Error::BudgetExceeded(BudgetLimit::Timeout));
+        }
+        Ok(())
+    }
+
+    // Fails if a stage has output more values than allowed.
+    fn check_stage_rows(&self, rows: u64) -> Result<()> {
+        if self.max_stage_rows.is_some_and(|max| rows > max) {
+            return Err(Error::BudgetExceeded(BudgetLimit::StageRows));
+        }
+        Ok(())
+    }
+
+    // Records values output by the query, failing if more have been output
+    // in total than allowed.
+    fn add_output_rows(&mut self, rows: u64) -> Result<()> {
+        self.output_rows += rows;
+        if self.max_output_rows.is_some_and(|max| self.output_rows > max) {
+            return Err(Error::BudgetExceeded(BudgetLimit::OutputRows));
+        }
+        Ok(())
+    }
+
+    // Collects the values read from the datastore for a stage, checking the
+    // budget along the way.
+    fn collect<T>(&self, iter: impl Iterator<Item = Result<T>>) -> Result<Vec<T>> {
+        let mut values = Vec::new();
+        for result in iter {
+            values.push(result?);
+            self.check_stage_rows(values.len() as u64)?;
+            if values.len().is_multiple_of(BUDGET_CHECK_INTERVAL) {
+                self.check()?;
+            }
+        }
+        Ok(values)
+    }
+}
+
+// Enforces a budget on chunks of output streamed from a root query.
+struct BudgetedChunks<'a> {
+    chunks: DynIter<'a, QueryOutputChunk>,
+    budget: Budget,
+    done: bool,
+}
+
+impl<'a> Iterator for BudgetedChunks<'a> {
+    type Item = Result<QueryOutputChunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.budget.check().and_then(|_| match self.chunks.next() {
+            Some(Ok(chunk)) => {
+                // The root query is the only stage, so its values count
+                // towards both limits.
+                self.budget.add_output_rows(value_len(&chunk.value))?;
+                self.budget.check_stage_rows(self.budget.output_rows)?;
+                Ok(Some(chunk))
+            }
+            Some(Err(err)) => Err(err),
+            None => Ok(None),
+        });
+        match result {
+            Ok(Some(chunk)) => Some(Ok(chunk)),
+            Ok(None) => None,
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+// The state of a running query, shared by all of its stages.
+struct Execution {
+    budget: Budget,
+    // Records the plan of each stage, for `Database::analyze`.
+    profiler: Option<Profiler>,
+}
+
+// Runs a query to completion, enforcing a budget.
+unsafe fn run<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    q: &Query,
+    budget: &QueryBudget,
+) -> Result<Vec<QueryOutputValue>> {
+    let mut exec = Execution {
+        budget: Budget::new(budget),
+        profiler: None,
+    };
+    let mut output = Vec::with_capacity(q.output_len());
+    query(txn, q, &mut output, &mut exec)?;
+    exec.budget.add_output_rows(output.iter().map(value_len).sum())?;
+    Ok(output)
+}
+
+// Runs a query, pushing its output (and that of any included queries) onto
+// `output`. If the execution has a profiler, the plan for the query is
+// recorded in it.
+unsafe fn query<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    q: &Query,
+    output: &mut Vec<QueryOutputValue>,
+    exec: &mut Execution,
+) -> Result<()> {
+    exec.budget.check()?;
+    match exec.profiler {
+        Some(ref mut p) => p.stack.push(Vec::new()),
+        None => {
+            query_stage(txn, q, output, exec)?;
+            return exec.budget.check_stage_rows(output.last().map_or(0, value_len));
         }
+    }
+
+    let start = Instant::now();
+    let result = query_stage(txn, q, output, exec);
+    let elapsed = start.elapsed();
+    let profiler = exec.profiler.as_mut().unwrap();
+    let children = profiler.stack.pop().unwrap();
+    result?;
+
+    let mut plan = plan_stage(txn, q, children, &profiler.totals)?;
+    plan.actual_rows = output.last().map(value_len);
+    plan.elapsed = Some(elapsed);
+    profiler.stack.last_mut().unwrap().push(plan);
+    exec.budget.check_stage_rows(output.last().map_or(0, value_len))
+}
+
+unsafe fn query_stage<'a, T: Transaction<'a> + 'a>(
+    txn: *const T,
+    q: &Query,
+    output: &mut Vec<QueryOutputValue>,
+    exec: &mut Execution,
+) -> Result<()> {
+    if let Some(root_output) = root_query(txn, q)? {
+        output.push(root_output.collect(&exec.budget)?);
+        return Ok(());
+    }
+
+    let value = match q {
+        Query::AllVertex
+        | Query::RangeVertex(_)
+        | Query::SpecificVertex(_)
+        | Query::VertexWithPropertyPresence(_)
+        | Query::VertexWithPropertyValue(_)
+        | Query::VertexWithPropertyRange(_)
+        | Query::AllEdge
+        | Query::SpecificEdge(_)
+        | Query::EdgeWithPropertyPresence(_)
+        | Query::EdgeWithPropertyValue(_)
+        | Query::EdgeWithPropertyRange(_)
+        | Query::EdgesByType(_) => unreachable!("root queries are handled by `root_query`"),
+        Query::Pipe(ref q) => pipe(txn, q, output, None, exec)?,
+        Query::Resume(ref q) => match *q.inner {
+            Query::Pipe(ref inner) if q.inner.is_resumable() => {
+                pipe(txn, inner, output, after_edge(Some(&q.continuation.0))?, exec)?
+            }
+            _ => return Err(Error::Invalid(ValidationError::InnerQuery)),
+        },
         Query::PipeProperty(ref q) => {
-            query(txn, &q.inner, output)?;
+            query(txn, &q.inner, output, exec)?;
             let piped_values = output.pop().unwrap();
 
             let values = match piped_values {
@@ -547,44 +2760,15 @@ unsafe fn query<'a, T: Transaction<'a> + 'a>(
 
             values
         }
-        Query::VertexWithPropertyPresence(ref q) => {
-            if let Some(iter) = (*txn).vertex_ids_with_property(q.name)? {
-                let iter = (*txn).specific_vertices(iter.collect::<Result<Vec<Uuid>>>()?)?;
-                QueryOutputValue::Vertices(iter.collect::<Result<Vec<Vertex>>>()?)
-            } else {
-                return Err(Error::NotIndexed);
-            }
-        }
-        Query::VertexWithPropertyValue(ref q) => {
-            if let Some(iter) = (*txn).vertex_ids_with_property_value(q.name, &q.value)? {
-                let iter = (*txn).specific_vertices(iter.collect::<Result<Vec<Uuid>>>()?)?;
-                QueryOutputValue::Vertices(iter.collect::<Result<Vec<Vertex>>>()?)
-            } else {
-                return Err(Error::NotIndexed);
-            }
-        }
-        Query::EdgeWithPropertyPresence(ref q) => {
-            if let Some(iter) = (*txn).edges_with_property(q.name)? {
-                QueryOutputValue::Edges(iter.collect::<Result<Vec<Edge>>>()?)
-            } else {
-                return Err(Error::NotIndexed);
-            }
-        }
-        Query::EdgeWithPropertyValue(ref q) => {
-            if let Some(iter) = (*txn).edges_with_property_value(q.name, &q.value)? {
-                QueryOutputValue::Edges(iter.collect::<Result<Vec<Edge>>>()?)
-            } else {
-                return Err(Error::NotIndexed);
-            }
-        }
         Query::PipeWithPropertyPresence(ref q) => {
-            query(txn, &q.inner, output)?;
+            query(txn, &q.inner, output, exec)?;
             let piped_values = output.pop().unwrap();
 
             let values = match piped_values {
                 QueryOutputValue::Edges(ref piped_edges) => {
-                    let edges_with_property = match (*txn).edges_with_property(q.name)? {
+                    let edges_with_property = match optional_index((*txn).edges_with_property(q.name))? {
                         Some(iter) => iter.collect::<Result<HashSet<Edge>>>()?,
+                        None if q.allow_scan => scan_edges(txn, piped_edges, q.name, |value| value.is_some())?,
                         None => return Err(Error::NotIndexed),
                     };
                     let iter = piped_edges.iter().filter(move |e| {
@@ -594,8 +2778,9 @@ unsafe fn query<'a, T: Transaction<'a> + 'a>(
                     QueryOutputValue::Edges(iter.cloned().collect())
                 }
                 QueryOutputValue::Vertices(ref piped_vertices) => {
-                    let vertices_with_property = match (*txn).vertex_ids_with_property(q.name)? {
+                    let vertices_with_property = match optional_index((*txn).vertex_ids_with_property(q.name))? {
                         Some(iter) => iter.collect::<Result<HashSet<Uuid>>>()?,
+                        None if q.allow_scan => scan_vertices(txn, piped_vertices, q.name, |value| value.is_some())?,
                         None => return Err(Error::NotIndexed),
                     };
                     let iter = piped_vertices.iter().filter(move |v| {
@@ -617,13 +2802,15 @@ unsafe fn query<'a, T: Transaction<'a> + 'a>(
             values
         }
         Query::PipeWithPropertyValue(ref q) => {
-            query(txn, &q.inner, output)?;
+            check_bound(q.value_param)?;
+            query(txn, &q.inner, output, exec)?;
             let piped_values = output.pop().unwrap();
 
             let values = match piped_values {
                 QueryOutputValue::Edges(ref piped_edges) => {
-                    let edges = match (*txn).edges_with_property_value(q.name, &q.value)? {
+                    let edges = match optional_index((*txn).edges_with_property_value(q.name, &q.value))? {
                         Some(iter) => iter.collect::<Result<HashSet<Edge>>>()?,
+                        None if q.allow_scan => scan_edges(txn, piped_edges, q.name, |value| value == Some(&q.value))?,
                         None => return Err(Error::NotIndexed),
                     };
                     let iter = piped_edges.iter().filter(move |e| {
@@ -633,8 +2820,11 @@ unsafe fn query<'a, T: Transaction<'a> + 'a>(
                     QueryOutputValue::Edges(iter.cloned().collect())
                 }
                 QueryOutputValue::Vertices(ref piped_vertices) => {
-                    let vertex_ids = match (*txn).vertex_ids_with_property_value(q.name, &q.value)? {
+                    let vertex_ids = match optional_index((*txn).vertex_ids_with_property_value(q.name, &q.value))? {
                         Some(iter) => iter.collect::<Result<HashSet<Uuid>>>()?,
+                        None if q.allow_scan => {
+                            scan_vertices(txn, piped_vertices, q.name, |value| value == Some(&q.value))?
+                        }
                         None => return Err(Error::NotIndexed),
                     };
                     let iter = piped_vertices.iter().filter(move |v| {
@@ -655,31 +2845,218 @@ unsafe fn query<'a, T: Transaction<'a> + 'a>(
 
             values
         }
-        Query::AllEdge => {
-            let iter = (*txn).all_edges()?;
-            QueryOutputValue::Edges(iter.collect::<Result<Vec<Edge>>>()?)
+        Query::PipeWithPropertyRange(ref q) => {
+            query(txn, &q.inner, output, exec)?;
+            let piped_values = output.pop().unwrap();
+
+            let values = match piped_values {
+                QueryOutputValue::Edges(ref piped_edges) => {
+                    let edges = match (*txn).edges_with_property_range(q.name, q.low.as_ref(), q.high.as_ref())? {
+                        Some(iter) => iter.collect::<Result<HashSet<Edge>>>()?,
+                        None => return Err(Error::NotIndexed),
+                    };
+                    let iter = piped_edges.iter().filter(move |e| edges.contains(e));
+                    QueryOutputValue::Edges(iter.cloned().collect())
+                }
+                QueryOutputValue::Vertices(ref piped_vertices) => {
+                    let vertex_ids =
+                        match (*txn).vertex_ids_with_property_range(q.name, q.low.as_ref(), q.high.as_ref())? {
+                            Some(iter) => iter.collect::<Result<HashSet<Uuid>>>()?,
+                            None => return Err(Error::NotIndexed),
+                        };
+                    let iter = piped_vertices.iter().filter(move |v| vertex_ids.contains(&v.id));
+                    QueryOutputValue::Vertices(iter.cloned().collect())
+                }
+                _ => {
+                    return Err(Error::OperationOnQuery);
+                }
+            };
+
+            if let Query::Include(_) = *q.inner {
+                // keep the value exported
+                output.push(piped_values);
+            }
+
+            values
+        }
+        Query::PipeWithPropertyPredicate(ref q) => {
+            query(txn, &q.inner, output, exec)?;
+            let piped_values = output.pop().unwrap();
+            let matcher = q.predicate.matcher()?;
+
+            let values = match piped_values {
+                QueryOutputValue::Edges(ref piped_edges) => {
+                    let mut edges = Vec::new();
+                    for edge in piped_edges {
+                        if let Some(value) = (*txn).edge_property(edge, q.name)? {
+                            if matcher.matches(&value) {
+                                edges.push(edge.clone());
+                            }
+                        }
+                    }
+                    QueryOutputValue::Edges(edges)
+                }
+                QueryOutputValue::Vertices(ref piped_vertices) => {
+                    let mut vertices = Vec::new();
+                    for vertex in piped_vertices {
+                        if let Some(value) = (*txn).vertex_property(vertex, q.name)? {
+                            if matcher.matches(&value) {
+                                vertices.push(vertex.clone());
+                            }
+                        }
+                    }
+                    QueryOutputValue::Vertices(vertices)
+                }
+                _ => {
+                    return Err(Error::OperationOnQuery);
+                }
+            };
+
+            if let Query::Include(_) = *q.inner {
+                // keep the value exported
+                output.push(piped_values);
+            }
+
+            values
         }
-        Query::SpecificEdge(ref q) => {
-            let iter = (*txn).specific_edges(q.edges.clone())?;
-            QueryOutputValue::Edges(iter.collect::<Result<Vec<Edge>>>()?)
+        Query::Traversal(ref q) => {
+            query(txn, &q.inner, output, exec)?;
+            let piped_values = output.pop().unwrap();
+
+            let start_vertices = match piped_values {
+                QueryOutputValue::Vertices(ref piped_vertices) => piped_vertices,
+                _ => {
+                    return Err(Error::OperationOnQuery);
+                }
+            };
+
+            let mut visited = HashSet::new();
+            let mut frontier = Vec::new();
+            let mut values = Vec::new();
+            for vertex in start_vertices {
+                if visited.insert(vertex.id) {
+                    frontier.push(vertex.id);
+                    if q.min_depth == 0 {
+                        values.push(vertex.clone());
+                    }
+                }
+            }
+
+            let mut frontiers = Vec::new();
+            for depth in 1..=q.max_depth {
+                let mut next_frontier = Vec::new();
+                for id in frontier {
+                    exec.budget.check()?;
+                    for edge in adjacent_edges(txn, id, q.direction, q.t)? {
+                        let edge = edge?;
+                        let next_id = opposite_vertex_id(&edge, id);
+                        if visited.insert(next_id) {
+                            next_frontier.push(next_id);
+                        }
+                    }
+                }
+
+                if next_frontier.is_empty() {
+                    break;
+                }
+
+                let vertices = (*txn)
+                    .specific_vertices(next_frontier.clone())?
+                    .collect::<Result<Vec<Vertex>>>()?;
+                if depth >= q.min_depth {
+                    values.extend(vertices.iter().cloned());
+                    exec.budget.check_stage_rows(values.len() as u64)?;
+                }
+                if q.emit_frontiers {
+                    frontiers.push(QueryOutputValue::Vertices(vertices));
+                }
+                frontier = next_frontier;
+            }
+
+            if let Query::Include(_) = *q.inner {
+                // keep the value exported
+                output.push(piped_values);
+            }
+            output.extend(frontiers);
+
+            QueryOutputValue::Vertices(values)
+        }
+        Query::ShortestPath(ref q) => {
+            QueryOutputValue::Paths(shortest_path(txn, q, &exec.budget)?.into_iter().collect())
+        }
+        Query::WeightedShortestPath(ref q) => {
+            QueryOutputValue::Paths(weighted_shortest_path(txn, q, &exec.budget)?.into_iter().collect())
+        }
+        Query::Subgraph(ref q) => {
+            query(txn, &q.inner, output, exec)?;
+            let piped_values = output.pop().unwrap();
+            let value = match piped_values {
+                QueryOutputValue::Vertices(ref piped_vertices) => {
+                    QueryOutputValue::Subgraph(subgraph(txn, q, piped_vertices, &exec.budget)?)
+                }
+                _ => {
+                    return Err(Error::OperationOnQuery);
+                }
+            };
+            if let Query::Include(_) = *q.inner {
+                // keep the value exported
+                output.push(piped_values);
+            }
+            value
+        }
+        Query::Union(ref q) => set_operation(txn, &q.left, &q.right, SetOperation::Union, output, exec)?,
+        Query::Intersect(ref q) => set_operation(txn, &q.left, &q.right, SetOperation::Intersect, output, exec)?,
+        Query::Except(ref q) => set_operation(txn, &q.left, &q.right, SetOperation::Except, output, exec)?,
+        Query::Distinct(ref q) => {
+            query(txn, &q.inner, output, exec)?;
+            let values = output.pop().unwrap();
+            if let Query::Include(_) = *q.inner {
+                // keep the value exported
+                output.push(values.clone());
+            }
+            dedupe(values)?
+        }
+        Query::OrderBy(ref q) => {
+            query(txn, &q.inner, output, exec)?;
+            let piped_values = output.pop().unwrap();
+            if let Query::Include(_) = *q.inner {
+                // keep the value exported
+                output.push(piped_values.clone());
+            }
+
+            match piped_values {
+                QueryOutputValue::Vertices(piped_vertices) => {
+                    QueryOutputValue::Vertices(order_by(piped_vertices, q, |v| (*txn).vertex_property(v, q.name))?)
+                }
+                QueryOutputValue::Edges(piped_edges) => {
+                    QueryOutputValue::Edges(order_by(piped_edges, q, |e| (*txn).edge_property(e, q.name))?)
+                }
+                _ => {
+                    return Err(Error::OperationOnQuery);
+                }
+            }
         }
         Query::Include(ref q) => {
-            query(txn, &q.inner, output)?;
+            query(txn, &q.inner, output, exec)?;
             output.pop().unwrap()
         }
         Query::Count(ref q) => {
+            let distinct = q.distinct;
             let count = match &*q.inner {
                 // These paths are optimized
                 Query::AllVertex => (*txn).vertex_count(),
                 Query::AllEdge => (*txn).edge_count(),
+                Query::EdgesByType(q) if q.start.is_none() => (*txn).edge_count_with_type(q.t)?.min(q.limit as u64),
                 q => {
-                    query(txn, q, output)?;
+                    query(txn, q, output, exec)?;
                     let piped_values = output.pop().unwrap();
                     let len = match piped_values {
+                        _ if distinct => distinct_len(&piped_values)?,
                         QueryOutputValue::Vertices(ref v) => v.len(),
                         QueryOutputValue::Edges(ref e) => e.len(),
                         QueryOutputValue::VertexProperties(ref p) => p.len(),
                         QueryOutputValue::EdgeProperties(ref p) => p.len(),
+                        QueryOutputValue::Paths(ref p) => p.len(),
                         _ => return Err(Error::OperationOnQuery),
                     };
                     if let Query::Include(_) = q {
@@ -691,6 +3068,49 @@ unsafe fn query<'a, T: Transaction<'a> + 'a>(
             };
             QueryOutputValue::Count(count)
         }
+        Query::Aggregate(ref q) => {
+            query(txn, &q.inner, output, exec)?;
+            let piped_values = output.pop().unwrap();
+            let groups = match piped_values {
+                QueryOutputValue::Vertices(ref piped_vertices) => {
+                    aggregate(piped_vertices, q, |v| v.t, |v, name| (*txn).vertex_property(v, name))?
+                }
+                QueryOutputValue::Edges(ref piped_edges) => {
+                    aggregate(piped_edges, q, |e| e.t, |e, name| (*txn).edge_property(e, name))?
+                }
+                _ => {
+                    return Err(Error::OperationOnQuery);
+                }
+            };
+            if let Query::Include(_) = *q.inner {
+                // keep the value exported
+                output.push(piped_values);
+            }
+            QueryOutputValue::Aggregation(groups)
+        }
+        Query::Degree(ref q) => {
+            query(txn, &q.inner, output, exec)?;
+            let piped_values = output.pop().unwrap();
+            let degrees = match piped_values {
+                QueryOutputValue::Vertices(ref piped_vertices) => piped_vertices
+                    .iter()
+                    .map(|v| {
+                        let outbound = (*txn).vertex_degree(v.id, EdgeDirection::Outbound, q.t)?;
+                        let inbound = (*txn).vertex_degree(v.id, EdgeDirection::Inbound, q.t)?;
+                        Ok(VertexDegree::new(v.clone(), outbound, inbound))
+                    })
+                    .collect::<Result<Vec<VertexDegree>>>()?,
+                _ => {
+                    return Err(Error::OperationOnQuery);
+                }
+            };
+            if let Query::Include(_) = *q.inner {
+                // keep the value exported
+                output.push(piped_values);
+            }
+            QueryOutputValue::Degrees(degrees)
+        }
+        Query::Project(ref q) => QueryOutputValue::Rows(project(txn, &q.inner, exec)?),
     };
 
     output.push(value);
@@ -31,6 +31,19 @@ pub enum Error {
     /// The operation cannot work with the given query, based off it's output
     /// type (e.g. attempting to delete using a query that outputs a count.)
     OperationOnQuery,
+
+    /// An edge walked by a weighted query has a missing or invalid weight.
+    InvalidWeight,
+
+    /// A query went over one of the limits of its `QueryBudget`.
+    BudgetExceeded(BudgetLimit),
+
+    /// A query was cancelled while it was running.
+    Cancelled,
+
+    /// A transaction couldn't be committed, because data it read was
+    /// changed by another transaction committed while it was open.
+    Conflict,
 }
 
 impl StdError for Error {
@@ -52,6 +65,10 @@ impl fmt::Display for Error {
             Error::Unsupported => write!(f, "functionality not supported"),
             Error::Invalid(ref err) => write!(f, "{err}"),
             Error::OperationOnQuery => write!(f, "the operation cannot work with the given query"),
+            Error::InvalidWeight => write!(f, "edge weight is missing or invalid"),
+            Error::BudgetExceeded(ref limit) => write!(f, "query budget exceeded: {limit}"),
+            Error::Cancelled => write!(f, "query cancelled"),
+            Error::Conflict => write!(f, "transaction conflicted with another transaction"),
         }
     }
 }
@@ -104,6 +121,8 @@ pub enum ValidationError {
     /// a query that gets vertex properties from a query that outputs a
     /// count.)
     InnerQuery,
+    /// A parameter placeholder in the query wasn't given a value.
+    MissingParameter,
 }
 
 impl StdError for ValidationError {}
@@ -115,9 +134,57 @@ impl fmt::Display for ValidationError {
             ValidationError::ValueTooLong => write!(f, "value too long"),
             ValidationError::CannotIncrementUuid => write!(f, "could not increment the UUID"),
             ValidationError::InnerQuery => write!(f, "the given query combination cannot be nested"),
+            ValidationError::MissingParameter => write!(f, "a parameter of the query has no value"),
+        }
+    }
+}
+
+/// A limit of a `QueryBudget`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BudgetLimit {
+    /// The query ran for longer than its timeout.
+    Timeout,
+    /// A stage of the query output too many values.
+    StageRows,
+    /// The query output too many values in total.
+    OutputRows,
+}
+
+impl fmt::Display for BudgetLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BudgetLimit::Timeout => write!(f, "the query timed out"),
+            BudgetLimit::StageRows => write!(f, "a stage of the query output too many values"),
+            BudgetLimit::OutputRows => write!(f, "the query output too many values"),
         }
     }
 }
 
 /// A result that might be a `ValidationError`.
 pub type ValidationResult<T> = StdResult<T, ValidationError>;
+
+/// An error parsing a query from text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseError {
+    /// The byte offset in the text where the error was found.
+    pub position: usize,
+    /// A description of the error.
+    pub message: String,
+}
+
+impl ParseError {
+    pub(crate) fn new<S: Into<String>>(position: usize, message: S) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl StdError for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
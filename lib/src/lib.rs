@@ -23,6 +23,7 @@ mod database;
 mod errors;
 mod memory;
 mod models;
+mod optimizer;
 pub mod util;
 
 pub use crate::database::*;
@@ -1,25 +1,97 @@
+use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufReader, BufWriter};
+use std::ops::Bound;
 use std::path::PathBuf;
 use std::result::Result as StdResult;
 use std::sync::{Arc, Mutex, MutexGuard};
 
 use crate::errors::{Error, Result};
 use crate::util;
-use crate::{Database, Datastore, DynIter, Edge, Identifier, Json, Transaction, Vertex};
+use crate::{Database, Datastore, DynIter, Edge, EdgeDirection, Identifier, Json, Transaction, Vertex};
 
 use rmp_serde::decode::Error as RmpDecodeError;
 use serde::{Deserialize, Serialize};
 use tempfile::NamedTempFile;
 use uuid::Uuid;
 
-#[derive(Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
+#[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 enum IndexedPropertyMember {
     Vertex(Uuid),
     Edge(Edge),
 }
 
+// `Json` only has a partial ordering, since values of different types aren't
+// comparable. This wraps it with a total ordering so that it can be used as a
+// key in the ordered property value index.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+struct IndexedJson(Json);
+
+impl PartialEq for IndexedJson {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for IndexedJson {}
+
+impl PartialOrd for IndexedJson {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for IndexedJson {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+type PropertyValueContainer = BTreeMap<IndexedJson, HashSet<IndexedPropertyMember>>;
+
+// Iterates over the members of a property value index that have a value
+// within the given range, and of the same type as the range's bound(s).
+fn range_members<'a>(
+    container: &'a PropertyValueContainer,
+    low: Bound<&Json>,
+    high: Bound<&Json>,
+) -> Box<dyn Iterator<Item = &'a IndexedPropertyMember> + 'a> {
+    // `BTreeMap::range` panics on inverted ranges, so check for them first
+    let is_empty = match (low, high) {
+        (Bound::Included(low), Bound::Included(high)) => low.total_cmp(high) == Ordering::Greater,
+        (Bound::Included(low) | Bound::Excluded(low), Bound::Included(high) | Bound::Excluded(high)) => {
+            low.total_cmp(high) != Ordering::Less
+        }
+        _ => false,
+    };
+    if is_empty {
+        return Box::new(std::iter::empty());
+    }
+
+    let reference = match (low, high) {
+        (Bound::Included(v) | Bound::Excluded(v), _) | (_, Bound::Included(v) | Bound::Excluded(v)) => Some(v.clone()),
+        _ => None,
+    };
+    let is_same_type = move |value: &IndexedJson| match reference {
+        Some(ref reference) => value.0.is_same_type(reference),
+        None => true,
+    };
+
+    let range = (
+        low.map(|v| IndexedJson(v.clone())),
+        high.map(|v| IndexedJson(v.clone())),
+    );
+    let is_other_type = is_same_type.clone();
+    let iter = container
+        .range(range)
+        .skip_while(move |(value, _)| !is_other_type(value))
+        .take_while(move |(value, _)| is_same_type(value))
+        .flat_map(|(_, members)| members.iter());
+    Box::new(iter)
+}
+
 // All of the data is actually stored in this struct, which is stored
 // internally to the datastore itself. This way, we can wrap a mutex around
 // the entire datastore, rather than on a per-data structure basis, as the
@@ -31,12 +103,170 @@ struct InternalMemory {
     reversed_edges: BTreeSet<Edge>,
     vertex_properties: BTreeMap<(Uuid, Identifier), Json>,
     edge_properties: BTreeMap<(Edge, Identifier), Json>,
-    property_values: HashMap<Identifier, HashMap<Json, HashSet<IndexedPropertyMember>>>,
+    property_values: HashMap<Identifier, PropertyValueContainer>,
+    // The IDs of the vertices of each type, and the edges of each type.
+    // These aren't persisted, since they're rebuilt from `vertices` and
+    // `edges` when reading a persisted image.
+    #[serde(skip)]
+    vertex_types: BTreeMap<Identifier, BTreeSet<Uuid>>,
+    #[serde(skip)]
+    edge_types: BTreeMap<Identifier, BTreeSet<Edge>>,
+}
+
+impl InternalMemory {
+    fn build_type_indexes(&mut self) {
+        self.vertex_types.clear();
+        for (id, t) in self.vertices.iter() {
+            self.vertex_types.entry(*t).or_default().insert(*id);
+        }
+        self.edge_types.clear();
+        for edge in self.edges.iter() {
+            self.edge_types.entry(edge.t).or_default().insert(edge.clone());
+        }
+    }
+
+    fn insert_vertex(&mut self, id: Uuid, t: Identifier) {
+        self.vertices.insert(id, t);
+        self.vertex_types.entry(t).or_default().insert(id);
+    }
+
+    fn remove_vertex(&mut self, id: Uuid) -> Option<Identifier> {
+        let t = self.vertices.remove(&id)?;
+        if let Some(ids) = self.vertex_types.get_mut(&t) {
+            ids.remove(&id);
+            if ids.is_empty() {
+                self.vertex_types.remove(&t);
+            }
+        }
+        Some(t)
+    }
+
+    fn insert_edge(&mut self, edge: &Edge) -> bool {
+        if !self.edges.insert(edge.clone()) {
+            return false;
+        }
+        self.edge_types.entry(edge.t).or_default().insert(edge.clone());
+        self.reversed_edges.insert(edge.reversed());
+        true
+    }
+
+    fn remove_edge(&mut self, edge: &Edge) -> bool {
+        if !self.edges.remove(edge) {
+            return false;
+        }
+        if let Some(edges) = self.edge_types.get_mut(&edge.t) {
+            edges.remove(edge);
+            if edges.is_empty() {
+                self.edge_types.remove(&edge.t);
+            }
+        }
+        self.reversed_edges.remove(&edge.reversed());
+        true
+    }
+
+    // Sets a vertex property to a value, or deletes it if the value is
+    // `None`. Returns the value it had before.
+    fn replace_vertex_property(&mut self, key: (Uuid, Identifier), value: Option<Json>) -> Option<Json> {
+        let old_value = match value {
+            Some(ref value) => self.vertex_properties.insert(key, value.clone()),
+            None => self.vertex_properties.remove(&key),
+        };
+        let (id, name) = key;
+        self.reindex_property(name, IndexedPropertyMember::Vertex(id), old_value.as_ref(), value);
+        old_value
+    }
+
+    // Sets an edge property to a value, or deletes it if the value is
+    // `None`. Returns the value it had before.
+    fn replace_edge_property(&mut self, key: (Edge, Identifier), value: Option<Json>) -> Option<Json> {
+        let old_value = match value {
+            Some(ref value) => self.edge_properties.insert(key.clone(), value.clone()),
+            None => self.edge_properties.remove(&key),
+        };
+        let (edge, name) = key;
+        self.reindex_property(name, IndexedPropertyMember::Edge(edge), old_value.as_ref(), value);
+        old_value
+    }
+
+    // Moves a member of a property value index from its old value to its new
+    // one, if the property is indexed.
+    fn reindex_property(
+        &mut self,
+        name: Identifier,
+        member: IndexedPropertyMember,
+        old_value: Option<&Json>,
+        new_value: Option<Json>,
+    ) {
+        if let Some(property_container) = self.property_values.get_mut(&name) {
+            if let Some(old_value) = old_value {
+                let removed = property_container
+                    .get_mut(&IndexedJson(old_value.clone()))
+                    .is_some_and(|members| members.remove(&member));
+                debug_assert!(removed);
+            }
+            if let Some(new_value) = new_value {
+                property_container
+                    .entry(IndexedJson(new_value))
+                    .or_insert_with(HashSet::new)
+                    .insert(member);
+            }
+        }
+    }
+
+    fn undo(&mut self, change: Change) {
+        match change {
+            Change::CreateVertex(id) => {
+                self.remove_vertex(id);
+            }
+            Change::DeleteVertex(id, t) => self.insert_vertex(id, t),
+            Change::CreateEdge(edge) => {
+                self.remove_edge(&edge);
+            }
+            Change::DeleteEdge(edge) => {
+                self.insert_edge(&edge);
+            }
+            Change::VertexProperty(key, value) => {
+                self.replace_vertex_property(key, value);
+            }
+            Change::EdgeProperty(key, value) => {
+                self.replace_edge_property(key, value);
+            }
+            Change::IndexProperty(name) => {
+                self.property_values.remove(&name);
+            }
+        }
+    }
+}
+
+// A change made in a transaction, which is recorded so that it can be undone
+// if the transaction is rolled back.
+#[derive(Debug)]
+enum Change {
+    CreateVertex(Uuid),
+    DeleteVertex(Uuid, Identifier),
+    CreateEdge(Edge),
+    DeleteEdge(Edge),
+    // A property that was set or deleted, and the value it had before.
+    VertexProperty((Uuid, Identifier), Option<Json>),
+    EdgeProperty((Edge, Identifier), Option<Json>),
+    IndexProperty(Identifier),
 }
 
 pub struct MemoryTransaction<'a> {
     internal: MutexGuard<'a, InternalMemory>,
     path: Option<PathBuf>,
+    // The changes made since the transaction was created or last committed,
+    // in the order they were made.
+    changes: Vec<Change>,
+}
+
+impl<'a> Drop for MemoryTransaction<'a> {
+    fn drop(&mut self) {
+        // Changes that weren't committed are rolled back
+        while let Some(change) = self.changes.pop() {
+            self.internal.undo(change);
+        }
+    }
 }
 
 impl<'a> Transaction<'a> for MemoryTransaction<'a> {
@@ -72,6 +302,20 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
         Ok(Box::new(iter))
     }
 
+    fn vertices_with_type(&'a self, t: Identifier) -> Result<DynIter<'a, Vertex>> {
+        self.range_vertices_with_type(t, Uuid::default())
+    }
+
+    fn range_vertices_with_type(&'a self, t: Identifier, offset: Uuid) -> Result<DynIter<'a, Vertex>> {
+        match self.internal.vertex_types.get(&t) {
+            Some(ids) => {
+                let iter = ids.range(offset..).map(move |id| Ok(Vertex::with_id(*id, t)));
+                Ok(Box::new(iter))
+            }
+            None => Ok(Box::new(std::iter::empty())),
+        }
+    }
+
     fn vertex_ids_with_property(&'a self, name: Identifier) -> Result<Option<DynIter<'a, Uuid>>> {
         if let Some(container) = self.internal.property_values.get(&name) {
             let mut vertex_ids = HashSet::<Uuid>::default();
@@ -90,7 +334,7 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
 
     fn vertex_ids_with_property_value(&'a self, name: Identifier, value: &Json) -> Result<Option<DynIter<'a, Uuid>>> {
         if let Some(container) = self.internal.property_values.get(&name) {
-            if let Some(sub_container) = container.get(value) {
+            if let Some(sub_container) = container.get(&IndexedJson(value.clone())) {
                 let iter = Box::new(sub_container.iter().filter_map(move |member| match member {
                     IndexedPropertyMember::Vertex(id) => Some(Ok(*id)),
                     _ => None,
@@ -105,6 +349,23 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
         }
     }
 
+    fn vertex_ids_with_property_range(
+        &'a self,
+        name: Identifier,
+        low: Bound<&Json>,
+        high: Bound<&Json>,
+    ) -> Result<Option<DynIter<'a, Uuid>>> {
+        if let Some(container) = self.internal.property_values.get(&name) {
+            let iter = range_members(container, low, high).filter_map(|member| match member {
+                IndexedPropertyMember::Vertex(id) => Some(Ok(*id)),
+                _ => None,
+            });
+            Ok(Some(Box::new(iter)))
+        } else {
+            Ok(None)
+        }
+    }
+
     fn edge_count(&self) -> u64 {
         self.internal.edges.len() as u64
     }
@@ -132,6 +393,41 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
         Ok(Box::new(iter))
     }
 
+    fn vertex_degree(&'a self, id: Uuid, direction: EdgeDirection, t: Option<Identifier>) -> Result<u64> {
+        let edges = match direction {
+            EdgeDirection::Outbound => &self.internal.edges,
+            EdgeDirection::Inbound => &self.internal.reversed_edges,
+            EdgeDirection::Both => {
+                return Ok(self.vertex_degree(id, EdgeDirection::Outbound, t)?
+                    + self.vertex_degree(id, EdgeDirection::Inbound, t)?)
+            }
+        };
+        let offset = Edge::new(id, t.unwrap_or_default(), Uuid::default());
+        let count = edges
+            .range(offset..)
+            .take_while(|e| e.outbound_id == id && t.is_none_or(|t| e.t == t))
+            .count();
+        Ok(count as u64)
+    }
+
+    fn edges_with_type(&'a self, t: Identifier) -> Result<DynIter<'a, Edge>> {
+        match self.internal.edge_types.get(&t) {
+            Some(edges) => Ok(Box::new(edges.iter().map(|e| Ok(e.clone())))),
+            None => Ok(Box::new(std::iter::empty())),
+        }
+    }
+
+    fn range_edges_with_type(&'a self, offset: Edge) -> Result<DynIter<'a, Edge>> {
+        match self.internal.edge_types.get(&offset.t) {
+            Some(edges) => Ok(Box::new(edges.range(offset..).map(|e| Ok(e.clone())))),
+            None => Ok(Box::new(std::iter::empty())),
+        }
+    }
+
+    fn edge_count_with_type(&'a self, t: Identifier) -> Result<u64> {
+        Ok(self.internal.edge_types.get(&t).map_or(0, |edges| edges.len() as u64))
+    }
+
     fn edges_with_property(&'a self, name: Identifier) -> Result<Option<DynIter<'a, Edge>>> {
         if let Some(container) = self.internal.property_values.get(&name) {
             let mut edges = HashSet::<Edge>::default();
@@ -150,7 +446,7 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
 
     fn edges_with_property_value(&'a self, name: Identifier, value: &Json) -> Result<Option<DynIter<'a, Edge>>> {
         if let Some(container) = self.internal.property_values.get(&name) {
-            if let Some(sub_container) = container.get(value) {
+            if let Some(sub_container) = container.get(&IndexedJson(value.clone())) {
                 let iter = Box::new(sub_container.iter().filter_map(move |member| match member {
                     IndexedPropertyMember::Edge(edge) if self.internal.edges.contains(edge) => Some(edge),
                     _ => None,
@@ -165,6 +461,23 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
         }
     }
 
+    fn edges_with_property_range(
+        &'a self,
+        name: Identifier,
+        low: Bound<&Json>,
+        high: Bound<&Json>,
+    ) -> Result<Option<DynIter<'a, Edge>>> {
+        if let Some(container) = self.internal.property_values.get(&name) {
+            let iter = range_members(container, low, high).filter_map(move |member| match member {
+                IndexedPropertyMember::Edge(edge) if self.internal.edges.contains(edge) => Some(Ok(edge.clone())),
+                _ => None,
+            });
+            Ok(Some(Box::new(iter)))
+        } else {
+            Ok(None)
+        }
+    }
+
     fn vertex_property(&self, vertex: &Vertex, name: Identifier) -> Result<Option<Json>> {
         if let Some(value) = self.internal.vertex_properties.get(&(vertex.id, name)) {
             Ok(Some(value.clone()))
@@ -205,7 +518,9 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
 
     fn delete_vertices(&mut self, vertices: Vec<Vertex>) -> Result<()> {
         for vertex in vertices {
-            self.internal.vertices.remove(&vertex.id);
+            if let Some(t) = self.internal.remove_vertex(vertex.id) {
+                self.changes.push(Change::DeleteVertex(vertex.id, t));
+            }
 
             let mut deletable_vertex_properties: Vec<(Uuid, Identifier)> = Vec::new();
             for (property_key, _) in self
@@ -236,8 +551,9 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
 
     fn delete_edges(&mut self, edges: Vec<Edge>) -> Result<()> {
         for edge in edges {
-            self.internal.edges.remove(&edge);
-            self.internal.reversed_edges.remove(&edge.reversed());
+            if self.internal.remove_edge(&edge) {
+                self.changes.push(Change::DeleteEdge(edge.clone()));
+            }
 
             let mut deletable_edge_properties: Vec<(Edge, Identifier)> = Vec::new();
             for (property_key, _) in self
@@ -260,14 +576,8 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
 
     fn delete_vertex_properties(&mut self, props: Vec<(Uuid, Identifier)>) -> Result<()> {
         for prop in props {
-            if let Some(property_value) = self.internal.vertex_properties.remove(&prop) {
-                let (property_vertex_id, property_name) = prop;
-                if let Some(property_container) = self.internal.property_values.get_mut(&property_name) {
-                    debug_assert!(property_container
-                        .get_mut(&property_value)
-                        .unwrap()
-                        .remove(&IndexedPropertyMember::Vertex(property_vertex_id)));
-                }
+            if let Some(property_value) = self.internal.replace_vertex_property(prop, None) {
+                self.changes.push(Change::VertexProperty(prop, Some(property_value)));
             }
         }
         Ok(())
@@ -275,14 +585,8 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
 
     fn delete_edge_properties(&mut self, props: Vec<(Edge, Identifier)>) -> Result<()> {
         for prop in props {
-            if let Some(property_value) = self.internal.edge_properties.remove(&prop) {
-                let (property_edge, property_name) = prop;
-                if let Some(property_container) = self.internal.property_values.get_mut(&property_name) {
-                    debug_assert!(property_container
-                        .get_mut(&property_value)
-                        .unwrap()
-                        .remove(&IndexedPropertyMember::Edge(property_edge)));
-                }
+            if let Some(property_value) = self.internal.replace_edge_property(prop.clone(), None) {
+                self.changes.push(Change::EdgeProperty(prop, Some(property_value)));
             }
         }
         Ok(())
@@ -302,15 +606,26 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
         Ok(())
     }
 
-    fn create_vertex(&mut self, vertex: &Vertex) -> Result<bool> {
-        let mut inserted = false;
+    fn commit(&mut self) -> Result<()> {
+        self.changes.clear();
+        Ok(())
+    }
 
-        self.internal.vertices.entry(vertex.id).or_insert_with(|| {
-            inserted = true;
-            vertex.t
-        });
+    fn rollback(&mut self) -> Result<()> {
+        while let Some(change) = self.changes.pop() {
+            self.internal.undo(change);
+        }
+        Ok(())
+    }
+
+    fn create_vertex(&mut self, vertex: &Vertex) -> Result<bool> {
+        if self.internal.vertices.contains_key(&vertex.id) {
+            return Ok(false);
+        }
 
-        Ok(inserted)
+        self.internal.insert_vertex(vertex.id, vertex.t);
+        self.changes.push(Change::CreateVertex(vertex.id));
+        Ok(true)
     }
 
     fn create_edge(&mut self, edge: &Edge) -> Result<bool> {
@@ -320,17 +635,22 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
             return Ok(false);
         }
 
-        self.internal.edges.insert(edge.clone());
-        self.internal.reversed_edges.insert(edge.reversed());
+        if self.internal.insert_edge(edge) {
+            self.changes.push(Change::CreateEdge(edge.clone()));
+        }
         Ok(true)
     }
 
     fn index_property(&mut self, name: Identifier) -> Result<()> {
-        let mut property_container: HashMap<Json, HashSet<IndexedPropertyMember>> = HashMap::new();
+        if !self.internal.property_values.contains_key(&name) {
+            self.changes.push(Change::IndexProperty(name));
+        }
+
+        let mut property_container: PropertyValueContainer = BTreeMap::new();
         for id in self.internal.vertices.keys() {
             if let Some(value) = self.internal.vertex_properties.get(&(*id, name)) {
                 property_container
-                    .entry(value.clone())
+                    .entry(IndexedJson(value.clone()))
                     .or_insert_with(HashSet::new)
                     .insert(IndexedPropertyMember::Vertex(*id));
             }
@@ -338,13 +658,13 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
         for edge in self.internal.edges.iter() {
             if let Some(value) = self.internal.edge_properties.get(&(edge.clone(), name)) {
                 property_container
-                    .entry(value.clone())
+                    .entry(IndexedJson(value.clone()))
                     .or_insert_with(HashSet::new)
                     .insert(IndexedPropertyMember::Edge(edge.clone()));
             }
         }
 
-        let existing_property_container = self.internal.property_values.entry(name).or_insert_with(HashMap::new);
+        let existing_property_container = self.internal.property_values.entry(name).or_insert_with(BTreeMap::new);
         for (value, members) in property_container.into_iter() {
             let existing_members = existing_property_container.entry(value).or_insert_with(HashSet::new);
             for member in members {
@@ -356,48 +676,21 @@ impl<'a> Transaction<'a> for MemoryTransaction<'a> {
     }
 
     fn set_vertex_properties(&mut self, vertex_ids: Vec<Uuid>, name: Identifier, value: &Json) -> Result<()> {
-        let mut deletable_vertex_properties = Vec::new();
-        for vertex_id in &vertex_ids {
-            deletable_vertex_properties.push((*vertex_id, name));
-        }
-        self.delete_vertex_properties(deletable_vertex_properties)?;
-
-        for vertex_id in &vertex_ids {
-            self.internal
-                .vertex_properties
-                .insert((*vertex_id, name), value.clone());
-        }
-
-        if let Some(property_container) = self.internal.property_values.get_mut(&name) {
-            let property_container = property_container.entry(value.clone()).or_insert_with(HashSet::new);
-            for vertex_id in vertex_ids.into_iter() {
-                property_container.insert(IndexedPropertyMember::Vertex(vertex_id));
-            }
+        for vertex_id in vertex_ids {
+            let old_value = self
+                .internal
+                .replace_vertex_property((vertex_id, name), Some(value.clone()));
+            self.changes.push(Change::VertexProperty((vertex_id, name), old_value));
         }
-
         Ok(())
     }
 
     fn set_edge_properties(&mut self, edges: Vec<Edge>, name: Identifier, value: &Json) -> Result<()> {
-        let mut deletable_edge_properties = Vec::new();
-        for edge in &edges {
-            deletable_edge_properties.push((edge.clone(), name));
-        }
-        self.delete_edge_properties(deletable_edge_properties)?;
-
-        for edge in &edges {
-            self.internal
-                .edge_properties
-                .insert((edge.clone(), name), value.clone());
-        }
-
-        if let Some(property_container) = self.internal.property_values.get_mut(&name) {
-            let property_container = property_container.entry(value.clone()).or_insert_with(HashSet::new);
-            for edge in edges.into_iter() {
-                property_container.insert(IndexedPropertyMember::Edge(edge));
-            }
+        for edge in edges {
+            let key = (edge, name);
+            let old_value = self.internal.replace_edge_property(key.clone(), Some(value.clone()));
+            self.changes.push(Change::EdgeProperty(key, old_value));
         }
-
         Ok(())
     }
 }
@@ -427,7 +720,8 @@ impl MemoryDatastore {
         let path = path.into();
         let f = File::open(&path).map_err(RmpDecodeError::InvalidDataRead)?;
         let buf = BufReader::new(f);
-        let internal: InternalMemory = rmp_serde::from_read(buf)?;
+        let mut internal: InternalMemory = rmp_serde::from_read(buf)?;
+        internal.build_type_indexes();
         Ok(Database::new(MemoryDatastore {
             internal: Arc::new(Mutex::new(internal)),
             path: Some(path),
@@ -454,6 +748,7 @@ impl Datastore for MemoryDatastore {
         MemoryTransaction {
             internal: self.internal.lock().unwrap(),
             path: self.path.clone(),
+            changes: Vec::new(),
         }
     }
 }
@@ -14,8 +14,11 @@ full_bench_impl!(MemoryDatastore::new_db());
 #[cfg(test)]
 mod tests {
     use super::MemoryDatastore;
-    use crate::util::{extract_count, extract_vertices};
-    use crate::{ijson, AllVertexQuery, CountQueryExt, Database, Identifier, SpecificVertexQuery};
+    use crate::util::{extract_count, extract_edges, extract_vertices};
+    use crate::{
+        ijson, AllVertexQuery, CountQueryExt, Database, Edge, EdgesByTypeQuery, Error, Identifier, QueryExt,
+        RangeVertexQuery, SpecificVertexQuery,
+    };
 
     use tempfile::NamedTempFile;
     use uuid::Uuid;
@@ -42,8 +45,39 @@ mod tests {
         let path = NamedTempFile::new().unwrap();
         let db = MemoryDatastore::create_msgpack_db(path.path());
         let id = create_vertex_with_property(&db);
+        let edge = Edge::new(id, Identifier::default(), id);
+        db.create_edge(&edge).unwrap();
         db.sync().unwrap();
         let db = MemoryDatastore::read_msgpack_db(path.path()).unwrap();
         expect_vertex(&db, id);
+
+        // The type indexes aren't persisted, so they should be rebuilt
+        let q = RangeVertexQuery::new().t(Identifier::default());
+        let vertices = extract_vertices(db.get(q).unwrap()).unwrap();
+        assert_eq!(vertices.len(), 1);
+        assert_eq!(vertices[0].id, id);
+        let edges = extract_edges(db.get(EdgesByTypeQuery::new(Identifier::default())).unwrap()).unwrap();
+        assert_eq!(edges, vec![edge]);
+    }
+
+    #[test]
+    fn should_scan_with_database_fallback() {
+        let db = MemoryDatastore::new_db();
+        create_vertex_with_property(&db);
+        let q = AllVertexQuery.with_property(Identifier::default()).unwrap();
+        assert!(matches!(db.get(q.clone()), Err(Error::NotIndexed)));
+
+        let db = MemoryDatastore::new_db().with_scan_fallback(true);
+        let id = create_vertex_with_property(&db);
+        let vertices = extract_vertices(db.get(q.clone()).unwrap()).unwrap();
+        assert_eq!(vertices.len(), 1);
+        assert_eq!(vertices[0].id, id);
+        assert!(db.explain(q.clone()).unwrap().scan);
+
+        // Indexed properties are looked up in the index instead
+        db.index_property(Identifier::default()).unwrap();
+        let plan = db.explain(q).unwrap();
+        assert!(!plan.scan);
+        assert_eq!(plan.stage, "VertexWithPropertyPresence");
     }
 }
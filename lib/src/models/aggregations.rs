@@ -0,0 +1,80 @@
+use crate::Json;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// The result of aggregating a property over a group of vertices or edges.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AggregationGroup {
+    /// The value shared by everything in the group: either the vertex or
+    /// edge type as a string, or the grouped property value (null if the
+    /// property is missing). `None` if the values weren't grouped.
+    ///
+    /// With serde, the key is omitted if the values weren't grouped, so
+    /// that it can be told apart from a null key.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_key"
+    )]
+    pub key: Option<Json>,
+
+    /// The number of numeric property values that were aggregated.
+    pub count: u64,
+
+    /// The aggregated value.
+    pub value: AggregationValue,
+}
+
+impl AggregationGroup {
+    /// Creates a new aggregation group.
+    ///
+    /// # Arguments
+    /// * `key`: The value shared by everything in the group.
+    /// * `count`: The number of numeric property values that were
+    ///   aggregated.
+    /// * `value`: The aggregated value.
+    pub fn new(key: Option<Json>, count: u64, value: AggregationValue) -> Self {
+        Self { key, count, value }
+    }
+}
+
+// Deserializes a present group key, including null, as `Some`.
+fn deserialize_key<'de, D>(deserializer: D) -> Result<Option<Json>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Json::deserialize(deserializer).map(Some)
+}
+
+/// An aggregated value.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregationValue {
+    /// The result of a sum, min, max or average. `None` if there were no
+    /// values to take the min, max or average of.
+    Number(Option<f64>),
+    /// The non-empty buckets of a histogram, in ascending order.
+    Histogram(Vec<HistogramBucket>),
+}
+
+/// A bucket in a histogram.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistogramBucket {
+    /// The inclusive lower end of the bucket. The bucket covers values up to
+    /// `low` plus the bucket width.
+    pub low: f64,
+
+    /// The number of values that fall into the bucket.
+    pub count: u64,
+}
+
+impl HistogramBucket {
+    /// Creates a new histogram bucket.
+    ///
+    /// # Arguments
+    /// * `low`: The inclusive lower end of the bucket.
+    /// * `count`: The number of values that fall into the bucket.
+    pub fn new(low: f64, count: u64) -> Self {
+        Self { low, count }
+    }
+}
@@ -0,0 +1,80 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Limits on the resources a query can use. A query that goes over a limit
+/// fails with `Error::BudgetExceeded`, and one that's cancelled fails with
+/// `Error::Cancelled`. Limits that aren't set aren't enforced.
+///
+/// Limits are checked as values are read from the datastore, so a query
+/// that goes over them stops early, rather than after it's done all of its
+/// work.
+#[derive(Clone, Debug, Default)]
+pub struct QueryBudget {
+    /// How long the query can run for.
+    pub timeout: Option<Duration>,
+
+    /// The maximum number of values any one stage of the query can output.
+    /// This bounds how many values are held in memory at once.
+    pub max_stage_rows: Option<u64>,
+
+    /// The maximum number of values the query can output in total,
+    /// including the output of included queries.
+    pub max_output_rows: Option<u64>,
+
+    /// A flag that cancels the query once it's set.
+    pub cancelled: Option<Arc<AtomicBool>>,
+}
+
+impl QueryBudget {
+    /// Creates a new budget without any limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how long the query can run for.
+    ///
+    /// # Arguments
+    /// * `timeout`: The time limit.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of values any one stage of the query can
+    /// output.
+    ///
+    /// # Arguments
+    /// * `max_stage_rows`: The limit.
+    pub fn max_stage_rows(self, max_stage_rows: u64) -> Self {
+        Self {
+            max_stage_rows: Some(max_stage_rows),
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of values the query can output in total.
+    ///
+    /// # Arguments
+    /// * `max_output_rows`: The limit.
+    pub fn max_output_rows(self, max_output_rows: u64) -> Self {
+        Self {
+            max_output_rows: Some(max_output_rows),
+            ..self
+        }
+    }
+
+    /// Sets a flag that cancels the query once it's set, e.g. from another
+    /// thread when the client that asked for the results goes away.
+    ///
+    /// # Arguments
+    /// * `cancelled`: The flag.
+    pub fn cancelled_by(self, cancelled: Arc<AtomicBool>) -> Self {
+        Self {
+            cancelled: Some(cancelled),
+            ..self
+        }
+    }
+}
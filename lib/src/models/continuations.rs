@@ -0,0 +1,162 @@
+use std::convert::TryInto;
+
+use crate::{errors, Edge, Identifier, QueryOutputValue};
+
+use serde::de::Error as DeError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use uuid::Uuid;
+
+const VERTEX_TAG: u8 = 0;
+const EDGE_TAG: u8 = 1;
+
+/// The position a continuation token resumes after.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub(crate) enum ContinuationPosition {
+    Vertex(Uuid),
+    Edge(Edge),
+}
+
+/// An opaque token for resuming a query after the last result it returned.
+///
+/// Tokens are returned alongside streamed query results, and can be passed
+/// to `.resume()` on the same query to continue from where it left off.
+/// They can be serialized with `to_bytes` for use across process boundaries.
+/// With serde, tokens are serialized as hex-encoded strings of those bytes.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ContinuationToken(pub(crate) ContinuationPosition);
+
+impl ContinuationToken {
+    /// Creates a token that resumes after the last vertex or edge in a
+    /// query output value. Returns `None` if the value is empty, or if it
+    /// isn't vertices or edges.
+    ///
+    /// # Arguments
+    /// * `value`: The query output value.
+    pub fn after(value: &QueryOutputValue) -> Option<Self> {
+        match value {
+            QueryOutputValue::Vertices(vertices) => vertices.last().map(|v| Self(ContinuationPosition::Vertex(v.id))),
+            QueryOutputValue::Edges(edges) => edges.last().map(|e| Self(ContinuationPosition::Edge(e.clone()))),
+            _ => None,
+        }
+    }
+
+    /// Serializes the token.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self.0 {
+            ContinuationPosition::Vertex(id) => {
+                let mut bytes = vec![VERTEX_TAG];
+                bytes.extend_from_slice(id.as_bytes());
+                bytes
+            }
+            ContinuationPosition::Edge(ref edge) => {
+                let mut bytes = vec![EDGE_TAG];
+                bytes.extend_from_slice(edge.outbound_id.as_bytes());
+                bytes.extend_from_slice(edge.inbound_id.as_bytes());
+                bytes.extend_from_slice(edge.t.as_str().as_bytes());
+                bytes
+            }
+        }
+    }
+
+    /// Deserializes a token previously serialized with `to_bytes`.
+    ///
+    /// # Arguments
+    /// * `bytes`: The serialized token.
+    ///
+    /// # Errors
+    /// Returns a `ValidationError` if the bytes aren't a valid token.
+    pub fn from_bytes(bytes: &[u8]) -> errors::ValidationResult<Self> {
+        match bytes.split_first() {
+            Some((&VERTEX_TAG, rest)) if rest.len() == 16 => Ok(Self(ContinuationPosition::Vertex(read_uuid(rest)))),
+            Some((&EDGE_TAG, rest)) if rest.len() > 32 => {
+                let t = std::str::from_utf8(&rest[32..]).map_err(|_| errors::ValidationError::InvalidValue)?;
+                Ok(Self(ContinuationPosition::Edge(Edge::new(
+                    read_uuid(&rest[..16]),
+                    Identifier::new(t)?,
+                    read_uuid(&rest[16..32]),
+                ))))
+            }
+            _ => Err(errors::ValidationError::InvalidValue),
+        }
+    }
+
+    /// Serializes the token as a hex-encoded string.
+    pub(crate) fn to_hex(&self) -> String {
+        self.to_bytes().iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    /// Deserializes a token previously serialized with `to_hex`.
+    pub(crate) fn from_hex(s: &str) -> errors::ValidationResult<Self> {
+        if !s.len().is_multiple_of(2) {
+            return Err(errors::ValidationError::InvalidValue);
+        }
+        let bytes: Option<Vec<u8>> = (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+            .collect();
+        Self::from_bytes(&bytes.ok_or(errors::ValidationError::InvalidValue)?)
+    }
+}
+
+impl Serialize for ContinuationToken {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.to_hex().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ContinuationToken {
+    fn deserialize<D>(deserializer: D) -> Result<ContinuationToken, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let v: String = Deserialize::deserialize(deserializer)?;
+        ContinuationToken::from_hex(&v).map_err(DeError::custom)
+    }
+}
+
+fn read_uuid(bytes: &[u8]) -> Uuid {
+    Uuid::from_bytes(bytes[..16].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ContinuationToken;
+    use crate::{Edge, Identifier, QueryOutputValue, Vertex};
+
+    #[test]
+    fn should_round_trip_vertex_token() {
+        let vertex = Vertex::new(Identifier::new("foo").unwrap());
+        let token = ContinuationToken::after(&QueryOutputValue::Vertices(vec![vertex])).unwrap();
+        assert_eq!(ContinuationToken::from_bytes(&token.to_bytes()).unwrap(), token);
+    }
+
+    #[test]
+    fn should_round_trip_edge_token() {
+        let edge = Edge::new(
+            crate::util::generate_uuid_v1(),
+            Identifier::new("foo").unwrap(),
+            crate::util::generate_uuid_v1(),
+        );
+        let token = ContinuationToken::after(&QueryOutputValue::Edges(vec![edge])).unwrap();
+        assert_eq!(ContinuationToken::from_bytes(&token.to_bytes()).unwrap(), token);
+    }
+
+    #[test]
+    fn should_not_create_token_after_empty_value() {
+        assert_eq!(ContinuationToken::after(&QueryOutputValue::Vertices(Vec::new())), None);
+        assert_eq!(ContinuationToken::after(&QueryOutputValue::Count(1)), None);
+    }
+
+    #[test]
+    fn should_not_deserialize_invalid_token() {
+        assert!(ContinuationToken::from_bytes(&[]).is_err());
+        assert!(ContinuationToken::from_bytes(&[0, 1, 2]).is_err());
+        assert!(ContinuationToken::from_bytes(&[2; 17]).is_err());
+        assert!(ContinuationToken::from_bytes(&[1; 32]).is_err());
+        assert!(ContinuationToken::from_hex("0").is_err());
+        assert!(ContinuationToken::from_hex("zz").is_err());
+    }
+}
@@ -0,0 +1,32 @@
+use crate::Vertex;
+
+use serde::{Deserialize, Serialize};
+
+/// The number of edges connected to a vertex.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VertexDegree {
+    /// The vertex.
+    pub vertex: Vertex,
+
+    /// The number of edges going out of the vertex.
+    pub outbound: u64,
+
+    /// The number of edges coming into the vertex.
+    pub inbound: u64,
+}
+
+impl VertexDegree {
+    /// Creates a new vertex degree.
+    ///
+    /// # Arguments
+    /// * `vertex`: The vertex.
+    /// * `outbound`: The number of edges going out of the vertex.
+    /// * `inbound`: The number of edges coming into the vertex.
+    pub fn new(vertex: Vertex, outbound: u64, inbound: u64) -> Self {
+        Self {
+            vertex,
+            outbound,
+            inbound,
+        }
+    }
+}
@@ -114,6 +114,44 @@ fn partial_cmp(first: &serde_json::Value, second: &serde_json::Value) -> Option<
     }
 }
 
+// Ranks each JSON type, so that values of different types can still be
+// ordered relative to one another.
+fn type_rank(value: &serde_json::Value) -> u8 {
+    match value {
+        serde_json::Value::Null => 0,
+        serde_json::Value::Bool(_) => 1,
+        serde_json::Value::Number(_) => 2,
+        serde_json::Value::String(_) => 3,
+        serde_json::Value::Array(_) => 4,
+        serde_json::Value::Object(_) => 5,
+    }
+}
+
+fn total_cmp(first: &serde_json::Value, second: &serde_json::Value) -> Ordering {
+    match (first, second) {
+        (serde_json::Value::Array(v1), serde_json::Value::Array(v2)) => {
+            partial_cmp_by(v1.iter(), v2.iter(), |v1, v2| Some(total_cmp(v1, v2))).unwrap()
+        }
+        (serde_json::Value::Object(v1), serde_json::Value::Object(v2)) => {
+            partial_cmp_by(v1.iter(), v2.iter(), |v1, v2| {
+                let (v1_key, v1_value) = v1;
+                let (v2_key, v2_value) = v2;
+                match v1_key.cmp(v2_key) {
+                    Ordering::Equal => Some(total_cmp(v1_value, v2_value)),
+                    non_eq => Some(non_eq),
+                }
+            })
+            .unwrap()
+        }
+        _ => match type_rank(first).cmp(&type_rank(second)) {
+            // Scalars of the same type are always comparable, since
+            // `serde_json` does not allow for NaN numbers.
+            Ordering::Equal => partial_cmp(first, second).unwrap_or(Ordering::Equal),
+            non_eq => non_eq,
+        },
+    }
+}
+
 fn partial_cmp_by<I, F>(mut first: I, mut second: I, mut f: F) -> Option<Ordering>
 where
     I: Iterator,
@@ -156,6 +194,25 @@ impl Json {
     pub fn new(value: serde_json::Value) -> Self {
         Self(Arc::new(value))
     }
+
+    /// Compares against another JSON value with a total ordering. Values of
+    /// the same type compare as they would with `partial_cmp`, whereas values
+    /// of different types are ordered by their type: null, booleans, numbers,
+    /// strings, arrays, and then objects.
+    ///
+    /// # Arguments
+    /// * `other`: The value to compare against.
+    pub fn total_cmp(&self, other: &Self) -> Ordering {
+        total_cmp(&self.0, &other.0)
+    }
+
+    /// Returns whether another JSON value is of the same type as this one.
+    ///
+    /// # Arguments
+    /// * `other`: The value to check.
+    pub fn is_same_type(&self, other: &Self) -> bool {
+        type_rank(&self.0) == type_rank(&other.0)
+    }
 }
 
 impl From<serde_json::Value> for Json {
@@ -226,6 +283,7 @@ impl FromStr for Json {
 #[cfg(test)]
 mod tests {
     use super::Json;
+    use std::cmp::Ordering;
     use std::collections::HashSet;
 
     fn json_u64() -> Json {
@@ -280,4 +338,26 @@ mod tests {
         assert!(ijson!({}) < ijson!({"key": "value"}));
         assert!(ijson!({"key": "value"}) > ijson!({}));
     }
+
+    #[test]
+    fn should_total_cmp() {
+        assert_eq!(ijson!(3).total_cmp(&ijson!(3.0)), Ordering::Equal);
+        assert_eq!(ijson!(3).total_cmp(&ijson!(4)), Ordering::Less);
+        assert_eq!(ijson!("foo2").total_cmp(&ijson!("foo1")), Ordering::Greater);
+        assert_eq!(ijson!(null).total_cmp(&ijson!(false)), Ordering::Less);
+        assert_eq!(ijson!(true).total_cmp(&ijson!(0)), Ordering::Less);
+        assert_eq!(ijson!(1000).total_cmp(&ijson!("0")), Ordering::Less);
+        assert_eq!(ijson!("foo").total_cmp(&ijson!([])), Ordering::Less);
+        assert_eq!(ijson!([1]).total_cmp(&ijson!({})), Ordering::Less);
+        assert_eq!(ijson!([1, "a"]).total_cmp(&ijson!([1, 2])), Ordering::Greater);
+        assert_eq!(ijson!({"a": 1}).total_cmp(&ijson!({"a": "1"})), Ordering::Less);
+    }
+
+    #[test]
+    fn should_check_same_type() {
+        assert!(ijson!(3).is_same_type(&ijson!(4.5)));
+        assert!(ijson!("foo").is_same_type(&ijson!("bar")));
+        assert!(!ijson!(3).is_same_type(&ijson!("3")));
+        assert!(!ijson!(null).is_same_type(&ijson!(false)));
+    }
 }
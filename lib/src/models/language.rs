@@ -0,0 +1,1752 @@
+use std::fmt;
+use std::ops::Bound;
+use std::str::FromStr;
+
+use crate::errors::{ParseError, ValidationResult};
+use crate::models::{
+    AggregateFunction, AggregateQuery, ContinuationToken, CountQuery, DegreeQuery, DistinctQuery, Edge, EdgeDirection,
+    EdgeWithPropertyPresenceQuery, EdgeWithPropertyRangeQuery, EdgeWithPropertyValueQuery, EdgesByTypeQuery,
+    ExceptQuery, GroupBy, Identifier, IncludeQuery, IntersectQuery, Json, JsonType, MissingWeight, OrderByQuery,
+    PipePropertyQuery, PipeQuery, PipeWithPropertyPredicateQuery, PipeWithPropertyPresenceQuery,
+    PipeWithPropertyRangeQuery, PipeWithPropertyValueQuery, ProjectQuery, PropertyPredicate, Query, RangeVertexQuery,
+    ResumeQuery, ShortestPathQuery, SortDirection, SpecificEdgeQuery, SpecificVertexQuery, SubgraphQuery,
+    TraversalQuery, UnionQuery, VertexWithPropertyPresenceQuery, VertexWithPropertyRangeQuery,
+    VertexWithPropertyValueQuery, WeightedShortestPathQuery,
+};
+
+use uuid::Uuid;
+
+type ParseResult<T> = Result<T, ParseError>;
+
+// Comparison operators, ordered so that longer operators are matched first.
+const COMPARISONS: [(&str, Comparison); 6] = [
+    (">=", Comparison::GreaterOrEqual),
+    ("<=", Comparison::LessOrEqual),
+    ("==", Comparison::Equal),
+    ("!=", Comparison::NotEqual),
+    (">", Comparison::Greater),
+    ("<", Comparison::Less),
+];
+
+// Checks whether a character can be part of a bare word, e.g. an unquoted
+// name or a UUID.
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Parses a query from text.
+///
+/// A query starts with a source, followed by any number of steps, e.g.
+/// `V(type=person).out(follows).has(age).props()`.
+///
+/// Sources:
+/// * `V()`: all vertices.
+/// * `V(type=t, start=id, limit=n)`: a range of vertices. Each argument is
+///   optional.
+/// * `V([id, ...])`: specific vertices.
+/// * `E()`: all edges.
+/// * `E([(outbound_id, t, inbound_id), ...])`: specific edges.
+/// * `E(type=t, start=(outbound_id, t, inbound_id), limit=n)`: edges of a
+///   type. The start and limit are optional.
+/// * `V(filter)`, `E(filter)`: vertices or edges found through a property
+///   index, where the filter is one of `name`, `name == value`, or one or
+///   two range bounds like `name > value, name <= value`.
+/// * `path(source_id, target_id, in, type=t)`: the shortest path between
+///   two vertices. The direction and type are optional. With
+///   `weight=name`, the path is weighted by an edge property, and
+///   `missing=skip`, `missing=error` or `missing=value` sets how edges
+///   without a usable weight are handled.
+///
+/// Steps:
+/// * `.out(t, limit=n, distinct=true)`, `.in(...)`, `.both(...)`: pipes to
+///   outbound, inbound, or both outbound and inbound values. Each argument
+///   is optional.
+/// * `.has(filter)`: filters by a property, with the same filters as
+///   sources, plus `name != value` and predicates: `name startsWith "a"`,
+///   `name endsWith "a"`, `name contains "a"`, `name matches "regex"`,
+///   `name in [value, ...]`, `name arrayContains value`, `name isNull` and
+///   `name isType type`, where the type is one of `null`, `bool`, `number`,
+///   `string`, `array` or `object`.
+/// * `.hasNot(name)`: filters to values without a property.
+/// * `.has(name, scan=true)`, `.has(name == value, scan=true)`,
+///   `.hasNot(name, scan=true)`: presence and value filters that read the
+///   property of each value if it isn't indexed.
+/// * `.props()`, `.props(name)`: gets all properties, or one property.
+/// * `.traverse(out, min, max, type=t, frontiers=true)`: walks edges for a
+///   variable number of hops.
+/// * `.subgraph(out, depth, type=t, vertexLimit=n, edgeLimit=n, props=true)`:
+///   gets the vertices and edges within `depth` hops. The keyword arguments
+///   are optional.
+/// * `.union(query)`, `.intersect(query)`, `.except(query)`: set operations.
+/// * `.distinct()`, `.include()`, `.count()`, `.count(distinct=true)`.
+/// * `.orderBy(name, descending, limit=n)`: sorts by a property. The
+///   direction and limit are optional.
+/// * `.sum(name)`, `.min(name)`, `.max(name)`, `.avg(name)`,
+///   `.histogram(name, width)`: aggregates a property, optionally grouped
+///   with `by=name` or `byType=true`.
+/// * `.degree()`, `.degree(t)`: counts the outbound and inbound edges of
+///   each vertex, optionally of one type.
+/// * `.project()`: gets rows binding the value output by each stage.
+/// * `.resume(token)`: resumes after a hex-encoded continuation token.
+///
+/// Names can also be written as JSON strings, which is needed for empty
+/// names. Values are written as JSON.
+///
+/// Queries that will be prepared can use parameter placeholders, written as
+/// `$name`, in place of vertex IDs (`V($ids)`), edges (`E($edges)`), limits
+/// (`limit=$n`) and the values of `==` and `!=` filters (`name == $value`).
+impl FromStr for Query {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { text: s, pos: 0 };
+        let q = parser.query()?;
+        parser.skip_whitespace();
+        if parser.pos < s.len() {
+            return Err(parser.error("expected a `.` step or the end of the query"));
+        }
+        Ok(q)
+    }
+}
+
+/// Writes a query as text, which can be parsed back into an equivalent
+/// query.
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Query::AllVertex => write!(f, "V()"),
+            Query::RangeVertex(q) => {
+                let mut args = Vec::new();
+                if let Some(t) = q.t {
+                    args.push(format!("type={}", Name(t)));
+                }
+                if let Some(start_id) = q.start_id {
+                    args.push(format!("start={start_id}"));
+                }
+                // A range without any arguments would be read back as all
+                // vertices, so the limit is always written in that case
+                if let Some(param) = q.limit_param {
+                    args.push(format!("limit={}", Param(param)));
+                } else if q.limit != u32::MAX || args.is_empty() {
+                    args.push(format!("limit={}", q.limit));
+                }
+                write!(f, "V({})", args.join(", "))
+            }
+            Query::SpecificVertex(q) if q.ids_param.is_some() => {
+                write!(f, "V({})", Param(q.ids_param.unwrap()))
+            }
+            Query::SpecificVertex(q) => {
+                let ids: Vec<String> = q.ids.iter().map(|id| id.to_string()).collect();
+                write!(f, "V([{}])", ids.join(", "))
+            }
+            Query::VertexWithPropertyPresence(q) => write!(f, "V({})", Name(q.name)),
+            Query::VertexWithPropertyValue(q) => {
+                write!(f, "V({} == {})", Name(q.name), Operand(&q.value, q.value_param))
+            }
+            Query::VertexWithPropertyRange(q) => write!(f, "V({})", Range(q.name, &q.low, &q.high)),
+            Query::AllEdge => write!(f, "E()"),
+            Query::SpecificEdge(q) if q.edges_param.is_some() => {
+                write!(f, "E({})", Param(q.edges_param.unwrap()))
+            }
+            Query::SpecificEdge(q) => {
+                let edges: Vec<String> = q
+                    .edges
+                    .iter()
+                    .map(|e| format!("({}, {}, {})", e.outbound_id, Name(e.t), e.inbound_id))
+                    .collect();
+                write!(f, "E([{}])", edges.join(", "))
+            }
+            Query::EdgeWithPropertyPresence(q) => write!(f, "E({})", Name(q.name)),
+            Query::EdgeWithPropertyValue(q) => {
+                write!(f, "E({} == {})", Name(q.name), Operand(&q.value, q.value_param))
+            }
+            Query::EdgeWithPropertyRange(q) => write!(f, "E({})", Range(q.name, &q.low, &q.high)),
+            Query::EdgesByType(q) => {
+                let mut args = vec![format!("type={}", Name(q.t))];
+                if let Some(ref e) = q.start {
+                    args.push(format!("start=({}, {}, {})", e.outbound_id, Name(e.t), e.inbound_id));
+                }
+                if q.limit != u32::MAX {
+                    args.push(format!("limit={}", q.limit));
+                }
+                write!(f, "E({})", args.join(", "))
+            }
+            Query::Pipe(q) => {
+                let mut args = Vec::new();
+                if let Some(t) = q.t {
+                    args.push(Name(t).to_string());
+                }
+                if let Some(param) = q.limit_param {
+                    args.push(format!("limit={}", Param(param)));
+                } else if q.limit != u32::MAX {
+                    args.push(format!("limit={}", q.limit));
+                }
+                if q.distinct {
+                    args.push("distinct=true".to_string());
+                }
+                write!(f, "{}.{}({})", q.inner, direction_word(q.direction), args.join(", "))
+            }
+            Query::PipeProperty(q) => match q.name {
+                Some(name) => write!(f, "{}.props({})", q.inner, Name(name)),
+                None => write!(f, "{}.props()", q.inner),
+            },
+            Query::PipeWithPropertyPresence(q) => {
+                let step = if q.exists { "has" } else { "hasNot" };
+                write!(f, "{}.{}({}{})", q.inner, step, Name(q.name), scan_arg(q.allow_scan))
+            }
+            Query::PipeWithPropertyValue(q) => {
+                let op = if q.equal { "==" } else { "!=" };
+                let scan = scan_arg(q.allow_scan);
+                let value = Operand(&q.value, q.value_param);
+                write!(f, "{}.has({} {} {}{})", q.inner, Name(q.name), op, value, scan)
+            }
+            Query::PipeWithPropertyRange(q) => write!(f, "{}.has({})", q.inner, Range(q.name, &q.low, &q.high)),
+            Query::PipeWithPropertyPredicate(q) => {
+                let predicate = match q.predicate {
+                    PropertyPredicate::StartsWith(ref s) => format!("startsWith {}", quoted(s)),
+                    PropertyPredicate::EndsWith(ref s) => format!("endsWith {}", quoted(s)),
+                    PropertyPredicate::Contains(ref s) => format!("contains {}", quoted(s)),
+                    PropertyPredicate::Regex(ref s) => format!("matches {}", quoted(s)),
+                    PropertyPredicate::In(ref values) => {
+                        let values: Vec<String> = values.iter().map(|v| (**v).to_string()).collect();
+                        format!("in [{}]", values.join(", "))
+                    }
+                    PropertyPredicate::ArrayContains(ref value) => format!("arrayContains {}", **value),
+                    PropertyPredicate::IsNull => "isNull".to_string(),
+                    PropertyPredicate::IsType(t) => format!("isType {}", json_type_word(t)),
+                };
+                write!(f, "{}.has({} {})", q.inner, Name(q.name), predicate)
+            }
+            Query::Traversal(q) => {
+                let mut args = vec![
+                    direction_word(q.direction).to_string(),
+                    q.min_depth.to_string(),
+                    q.max_depth.to_string(),
+                ];
+                if let Some(t) = q.t {
+                    args.push(format!("type={}", Name(t)));
+                }
+                if q.emit_frontiers {
+                    args.push("frontiers=true".to_string());
+                }
+                write!(f, "{}.traverse({})", q.inner, args.join(", "))
+            }
+            Query::Subgraph(q) => {
+                let mut args = vec![direction_word(q.direction).to_string(), q.depth.to_string()];
+                if let Some(t) = q.t {
+                    args.push(format!("type={}", Name(t)));
+                }
+                if q.vertex_limit != u32::MAX {
+                    args.push(format!("vertexLimit={}", q.vertex_limit));
+                }
+                if q.edge_limit != u32::MAX {
+                    args.push(format!("edgeLimit={}", q.edge_limit));
+                }
+                if q.properties {
+                    args.push("props=true".to_string());
+                }
+                write!(f, "{}.subgraph({})", q.inner, args.join(", "))
+            }
+            Query::ShortestPath(q) => {
+                let args = path_args(q.source_id, q.target_id, q.direction, q.t);
+                write!(f, "path({})", args.join(", "))
+            }
+            Query::WeightedShortestPath(q) => {
+                let mut args = path_args(q.source_id, q.target_id, q.direction, q.t);
+                args.push(format!("weight={}", Name(q.weight)));
+                match q.missing_weight {
+                    MissingWeight::Skip => args.push("missing=skip".to_string()),
+                    MissingWeight::Default(ref value) => args.push(format!("missing={}", **value)),
+                    MissingWeight::Error => {}
+                }
+                write!(f, "path({})", args.join(", "))
+            }
+            Query::Resume(q) => write!(f, "{}.resume({})", q.inner, q.continuation.to_hex()),
+            Query::Union(q) => write!(f, "{}.union({})", q.left, q.right),
+            Query::Intersect(q) => write!(f, "{}.intersect({})", q.left, q.right),
+            Query::Except(q) => write!(f, "{}.except({})", q.left, q.right),
+            Query::Distinct(q) => write!(f, "{}.distinct()", q.inner),
+            Query::OrderBy(q) => {
+                let mut args = vec![Name(q.name).to_string()];
+                if q.direction == SortDirection::Descending {
+                    args.push(String::from(q.direction));
+                }
+                if q.limit != u32::MAX {
+                    args.push(format!("limit={}", q.limit));
+                }
+                write!(f, "{}.orderBy({})", q.inner, args.join(", "))
+            }
+            Query::Include(q) => write!(f, "{}.include()", q.inner),
+            Query::Count(q) if q.distinct => write!(f, "{}.count(distinct=true)", q.inner),
+            Query::Count(q) => write!(f, "{}.count()", q.inner),
+            Query::Aggregate(q) => {
+                let mut args = vec![Name(q.name).to_string()];
+                let step = match q.function {
+                    AggregateFunction::Sum => "sum",
+                    AggregateFunction::Min => "min",
+                    AggregateFunction::Max => "max",
+                    AggregateFunction::Average => "avg",
+                    AggregateFunction::Histogram(ref width) => {
+                        args.push((**width).to_string());
+                        "histogram"
+                    }
+                };
+                match q.group_by {
+                    Some(GroupBy::Type) => args.push("byType=true".to_string()),
+                    Some(GroupBy::Property(name)) => args.push(format!("by={}", Name(name))),
+                    None => {}
+                }
+                write!(f, "{}.{}({})", q.inner, step, args.join(", "))
+            }
+            Query::Degree(q) => match q.t {
+                Some(t) => write!(f, "{}.degree({})", q.inner, Name(t)),
+                None => write!(f, "{}.degree()", q.inner),
+            },
+            Query::Project(q) => write!(f, "{}.project()", q.inner),
+        }
+    }
+}
+
+// Writes a name, quoting it if it can't be written as a bare word.
+struct Name(Identifier);
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = self.0.as_str();
+        if !s.is_empty() && s.chars().all(is_word_char) {
+            write!(f, "{s}")
+        } else {
+            write!(f, "{}", serde_json::Value::String(s.to_string()))
+        }
+    }
+}
+
+// Writes a parameter placeholder.
+struct Param(Identifier);
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "${}", Name(self.0))
+    }
+}
+
+// Writes a filter value, or the placeholder of the parameter it's taken
+// from.
+struct Operand<'a>(&'a Json, Option<Identifier>);
+
+impl<'a> fmt::Display for Operand<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.1 {
+            Some(param) => write!(f, "{}", Param(param)),
+            None => write!(f, "{}", **self.0),
+        }
+    }
+}
+
+// Writes the bounds of a property range filter.
+struct Range<'a>(Identifier, &'a Bound<Json>, &'a Bound<Json>);
+
+impl<'a> fmt::Display for Range<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = Name(self.0);
+        let low = match self.1 {
+            Bound::Included(value) => Some(format!("{name} >= {}", **value)),
+            Bound::Excluded(value) => Some(format!("{name} > {}", **value)),
+            Bound::Unbounded => None,
+        };
+        let high = match self.2 {
+            Bound::Included(value) => Some(format!("{name} <= {}", **value)),
+            Bound::Excluded(value) => Some(format!("{name} < {}", **value)),
+            Bound::Unbounded => None,
+        };
+        let bounds: Vec<String> = low.into_iter().chain(high).collect();
+        write!(f, "{}", bounds.join(", "))
+    }
+}
+
+fn quoted(s: &str) -> serde_json::Value {
+    serde_json::Value::String(s.to_string())
+}
+
+fn json_type_word(t: JsonType) -> &'static str {
+    match t {
+        JsonType::Null => "null",
+        JsonType::Bool => "bool",
+        JsonType::Number => "number",
+        JsonType::String => "string",
+        JsonType::Array => "array",
+        JsonType::Object => "object",
+    }
+}
+
+fn direction_word(direction: EdgeDirection) -> &'static str {
+    match direction {
+        EdgeDirection::Outbound => "out",
+        EdgeDirection::Inbound => "in",
+        EdgeDirection::Both => "both",
+    }
+}
+
+fn path_args(source_id: Uuid, target_id: Uuid, direction: EdgeDirection, t: Option<Identifier>) -> Vec<String> {
+    let mut args = vec![source_id.to_string(), target_id.to_string()];
+    if direction != EdgeDirection::Outbound {
+        args.push(direction_word(direction).to_string());
+    }
+    if let Some(t) = t {
+        args.push(format!("type={}", Name(t)));
+    }
+    args
+}
+
+#[derive(Clone, Copy)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+// A condition on a property, e.g. `age > 30`.
+enum Predicate {
+    Presence(Identifier),
+    Compare(Identifier, Comparison, Json),
+    CompareParam(Identifier, Comparison, Identifier),
+    Matches(Identifier, PropertyPredicate),
+}
+
+// The property filter described by one or more predicates.
+enum Filter {
+    Presence(Identifier),
+    Value(Identifier, Json, bool, Option<Identifier>),
+    Range(Identifier, Bound<Json>, Bound<Json>),
+    Predicate(Identifier, PropertyPredicate),
+}
+
+// Combines predicates into a filter. Predicates can only be combined if
+// they're the lower and upper bounds of a range on the same property.
+fn filter(start: usize, predicates: Vec<(usize, Predicate)>) -> ParseResult<Filter> {
+    let mut predicates = predicates.into_iter();
+    let (name, mut low, mut high) = match predicates.next() {
+        None => return Err(ParseError::new(start, "expected a property filter")),
+        Some((_, Predicate::Presence(name))) if predicates.len() == 0 => return Ok(Filter::Presence(name)),
+        Some((_, Predicate::Compare(name, Comparison::Equal, value))) if predicates.len() == 0 => {
+            return Ok(Filter::Value(name, value, true, None))
+        }
+        Some((_, Predicate::Compare(name, Comparison::NotEqual, value))) if predicates.len() == 0 => {
+            return Ok(Filter::Value(name, value, false, None))
+        }
+        Some((_, Predicate::CompareParam(name, Comparison::Equal, param))) if predicates.len() == 0 => {
+            return Ok(Filter::Value(
+                name,
+                Json::new(serde_json::Value::Null),
+                true,
+                Some(param),
+            ))
+        }
+        Some((_, Predicate::CompareParam(name, Comparison::NotEqual, param))) if predicates.len() == 0 => {
+            return Ok(Filter::Value(
+                name,
+                Json::new(serde_json::Value::Null),
+                false,
+                Some(param),
+            ))
+        }
+        Some((_, Predicate::Matches(name, predicate))) if predicates.len() == 0 => {
+            return Ok(Filter::Predicate(name, predicate))
+        }
+        Some((pos, predicate)) => {
+            let name = match predicate {
+                Predicate::Compare(name, _, _)
+                | Predicate::CompareParam(name, _, _)
+                | Predicate::Presence(name)
+                | Predicate::Matches(name, _) => name,
+            };
+            let (low, high) = range_bound(pos, predicate_bound(pos, predicate)?)?;
+            (name, low, high)
+        }
+    };
+
+    for (pos, predicate) in predicates {
+        match predicate {
+            Predicate::Compare(other_name, _, _) if other_name == name => {}
+            _ => {
+                return Err(ParseError::new(
+                    pos,
+                    "filters can only be combined as the lower and upper bounds of a range on one property",
+                ))
+            }
+        }
+        match (range_bound(pos, predicate_bound(pos, predicate)?)?, &low, &high) {
+            ((low_bound, Bound::Unbounded), Bound::Unbounded, _) => low = low_bound,
+            ((Bound::Unbounded, high_bound), _, Bound::Unbounded) => high = high_bound,
+            _ => {
+                return Err(ParseError::new(
+                    pos,
+                    "a range can only have one lower and one upper bound",
+                ))
+            }
+        }
+    }
+
+    Ok(Filter::Range(name, low, high))
+}
+
+// Gets the comparison and value of a predicate, erroring if it isn't a
+// comparison.
+fn predicate_bound(pos: usize, predicate: Predicate) -> ParseResult<(Comparison, Json)> {
+    match predicate {
+        Predicate::Compare(_, comparison, value) => Ok((comparison, value)),
+        Predicate::CompareParam(..) => Err(ParseError::new(
+            pos,
+            "parameters can only be used in a single `==` or `!=` filter",
+        )),
+        Predicate::Presence(_) | Predicate::Matches(..) => Err(ParseError::new(
+            pos,
+            "filters can only be combined as the lower and upper bounds of a range on one property",
+        )),
+    }
+}
+
+// Converts a comparison into the lower and upper bounds of a range.
+fn range_bound(pos: usize, (comparison, value): (Comparison, Json)) -> ParseResult<(Bound<Json>, Bound<Json>)> {
+    match comparison {
+        Comparison::Greater => Ok((Bound::Excluded(value), Bound::Unbounded)),
+        Comparison::GreaterOrEqual => Ok((Bound::Included(value), Bound::Unbounded)),
+        Comparison::Less => Ok((Bound::Unbounded, Bound::Excluded(value))),
+        Comparison::LessOrEqual => Ok((Bound::Unbounded, Bound::Included(value))),
+        Comparison::Equal | Comparison::NotEqual => Err(ParseError::new(
+            pos,
+            "`==` and `!=` filters can't be combined with other filters",
+        )),
+    }
+}
+
+// Converts the result of building a query into a parse error, if it's
+// invalid.
+// Writes the argument for a filter that allows scanning.
+fn scan_arg(allow_scan: bool) -> &'static str {
+    if allow_scan {
+        ", scan=true"
+    } else {
+        ""
+    }
+}
+
+fn validated<Q: Into<Query>>(start: usize, name: &str, result: ValidationResult<Q>) -> ParseResult<Query> {
+    result
+        .map(Into::into)
+        .map_err(|err| ParseError::new(start, format!("invalid `{name}`: {err}")))
+}
+
+fn required<T>(start: usize, name: &str, value: Option<T>, description: &str) -> ParseResult<T> {
+    value.ok_or_else(|| ParseError::new(start, format!("`{name}` requires {description}")))
+}
+
+// An argument in a parenthesized argument list.
+#[derive(Clone, Copy)]
+enum Arg<'a> {
+    // A positional argument, with its index among the positional arguments.
+    Positional(usize),
+    // A keyword argument, with its position in the text.
+    Keyword(usize, &'a str),
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn error<S: Into<String>>(&self, message: S) -> ParseError {
+        ParseError::new(self.pos, message)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> ParseResult<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{token}`")))
+        }
+    }
+
+    fn word(&mut self, expected: &str) -> ParseResult<&'a str> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let len = rest.find(|c: char| !is_word_char(c)).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error(format!("expected {expected}")));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    // Parses a word, and converts it with `convert`.
+    fn parse_word<T>(&mut self, expected: &str, convert: impl FnOnce(&str) -> Option<T>) -> ParseResult<T> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let word = self.word(expected)?;
+        convert(word).ok_or_else(|| ParseError::new(start, format!("expected {expected}, found `{word}`")))
+    }
+
+    fn uuid(&mut self) -> ParseResult<Uuid> {
+        self.parse_word("a UUID", |word| Uuid::parse_str(word).ok())
+    }
+
+    fn number(&mut self) -> ParseResult<u32> {
+        self.parse_word("a number", |word| word.parse().ok())
+    }
+
+    // Parses a parameter placeholder, or returns `None` without consuming
+    // anything if there isn't one.
+    fn param(&mut self) -> ParseResult<Option<Identifier>> {
+        if self.eat("$") {
+            Ok(Some(self.name()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Parses a limit, which is either a number or a parameter placeholder.
+    fn limit(&mut self) -> ParseResult<(u32, Option<Identifier>)> {
+        match self.param()? {
+            Some(param) => Ok((u32::MAX, Some(param))),
+            None => Ok((self.number()?, None)),
+        }
+    }
+
+    fn boolean(&mut self) -> ParseResult<bool> {
+        self.parse_word("`true` or `false`", |word| word.parse().ok())
+    }
+
+    fn direction(&mut self) -> ParseResult<EdgeDirection> {
+        self.parse_word("`out`, `in` or `both`", |word| match word {
+            "out" => Some(EdgeDirection::Outbound),
+            "in" => Some(EdgeDirection::Inbound),
+            "both" => Some(EdgeDirection::Both),
+            _ => None,
+        })
+    }
+
+    fn name(&mut self) -> ParseResult<Identifier> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let name = if self.peek() == Some('"') {
+            let len = self.json_len();
+            let name: String = serde_json::from_str(&self.rest()[..len])
+                .map_err(|err| ParseError::new(start, format!("invalid quoted name: {err}")))?;
+            self.pos += len;
+            name
+        } else {
+            self.word("a name")?.to_string()
+        };
+        Identifier::new(name).map_err(|err| ParseError::new(start, format!("invalid name: {err}")))
+    }
+
+    // Gets the length of the JSON value at the current position, by finding
+    // where it ends without parsing it.
+    fn json_len(&self) -> usize {
+        let rest = self.rest();
+        let mut depth = 0;
+        let mut in_string = false;
+        let mut escaped = false;
+        for (i, c) in rest.char_indices() {
+            if in_string {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '"' {
+                    in_string = false;
+                    if depth == 0 {
+                        return i + 1;
+                    }
+                }
+                continue;
+            }
+            match c {
+                '"' => in_string = true,
+                '[' | '{' => depth += 1,
+                ']' | '}' if depth > 0 => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return i + 1;
+                    }
+                }
+                ',' | ')' | ']' | '}' if depth == 0 => return i,
+                c if c.is_whitespace() && depth == 0 => return i,
+                _ => {}
+            }
+        }
+        rest.len()
+    }
+
+    fn json(&mut self) -> ParseResult<Json> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let len = self.json_len();
+        if len == 0 {
+            return Err(self.error("expected a JSON value"));
+        }
+        let value = serde_json::from_str(&self.rest()[..len])
+            .map_err(|err| ParseError::new(start, format!("invalid JSON value: {err}")))?;
+        self.pos += len;
+        Ok(Json::new(value))
+    }
+
+    // Parses a property filter predicate, e.g. `age` or `age > 30`.
+    fn predicate(&mut self) -> ParseResult<(usize, Predicate)> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let name = self.name()?;
+        for (token, comparison) in COMPARISONS {
+            if self.eat(token) {
+                if let Some(param) = self.param()? {
+                    return Ok((start, Predicate::CompareParam(name, comparison, param)));
+                }
+                return Ok((start, Predicate::Compare(name, comparison, self.json()?)));
+            }
+        }
+        self.skip_whitespace();
+        let op_start = self.pos;
+        let op = match self.word("") {
+            Ok(op) => op,
+            Err(_) => return Ok((start, Predicate::Presence(name))),
+        };
+        let predicate = match op {
+            "startsWith" => PropertyPredicate::StartsWith(self.string()?),
+            "endsWith" => PropertyPredicate::EndsWith(self.string()?),
+            "contains" => PropertyPredicate::Contains(self.string()?),
+            "matches" => PropertyPredicate::Regex(self.string()?),
+            "in" => {
+                self.skip_whitespace();
+                let values_start = self.pos;
+                match &*self.json()? {
+                    serde_json::Value::Array(values) => {
+                        PropertyPredicate::In(values.iter().map(|v| Json::new(v.clone())).collect())
+                    }
+                    _ => return Err(ParseError::new(values_start, "expected a JSON array")),
+                }
+            }
+            "arrayContains" => PropertyPredicate::ArrayContains(self.json()?),
+            "isNull" => PropertyPredicate::IsNull,
+            "isType" => PropertyPredicate::IsType(self.parse_word("a JSON type", |word| match word {
+                "null" => Some(JsonType::Null),
+                "bool" => Some(JsonType::Bool),
+                "number" => Some(JsonType::Number),
+                "string" => Some(JsonType::String),
+                "array" => Some(JsonType::Array),
+                "object" => Some(JsonType::Object),
+                _ => None,
+            })?),
+            _ => return Err(ParseError::new(op_start, format!("unknown filter `{op}`"))),
+        };
+        Ok((start, Predicate::Matches(name, predicate)))
+    }
+
+    // Parses a JSON string.
+    fn string(&mut self) -> ParseResult<String> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match &*self.json()? {
+            serde_json::Value::String(s) => Ok(s.clone()),
+            _ => Err(ParseError::new(start, "expected a JSON string")),
+        }
+    }
+
+    // Parses the name of a keyword argument and the `=` after it, or
+    // returns `None` without consuming anything if the next argument is
+    // positional.
+    fn keyword(&mut self) -> Option<(usize, &'a str)> {
+        let start = self.pos;
+        self.skip_whitespace();
+        let pos = self.pos;
+        if let Ok(key) = self.word("") {
+            if self.eat("=") && !self.rest().starts_with('=') {
+                return Some((pos, key));
+            }
+        }
+        self.pos = start;
+        None
+    }
+
+    // Parses a parenthesized, comma-separated list of arguments, calling
+    // `arg` to parse each one.
+    fn args<F>(&mut self, mut arg: F) -> ParseResult<()>
+    where
+        F: FnMut(&mut Self, Arg<'a>) -> ParseResult<()>,
+    {
+        self.expect("(")?;
+        if self.eat(")") {
+            return Ok(());
+        }
+        let mut positional = 0;
+        loop {
+            match self.keyword() {
+                Some((pos, key)) => arg(self, Arg::Keyword(pos, key))?,
+                None => {
+                    arg(self, Arg::Positional(positional))?;
+                    positional += 1;
+                }
+            }
+            if self.eat(")") {
+                return Ok(());
+            }
+            if !self.eat(",") {
+                return Err(self.error("expected `,` or `)`"));
+            }
+        }
+    }
+
+    // Parses a comma-separated list of values, after the opening `[`.
+    fn list<T>(&mut self, mut value: impl FnMut(&mut Self) -> ParseResult<T>) -> ParseResult<Vec<T>> {
+        let mut values = Vec::new();
+        if self.eat("]") {
+            return Ok(values);
+        }
+        loop {
+            values.push(value(self)?);
+            if self.eat("]") {
+                return Ok(values);
+            }
+            if !self.eat(",") {
+                return Err(self.error("expected `,` or `]`"));
+            }
+        }
+    }
+
+    fn unexpected(&mut self, arg: Arg) -> ParseError {
+        self.skip_whitespace();
+        match arg {
+            Arg::Positional(_) => self.error("unexpected argument"),
+            Arg::Keyword(pos, key) => ParseError::new(pos, format!("unknown argument `{key}`")),
+        }
+    }
+
+    fn query(&mut self) -> ParseResult<Query> {
+        let mut q = self.source()?;
+        while self.eat(".") {
+            q = self.step(q)?;
+        }
+        Ok(q)
+    }
+
+    fn source(&mut self) -> ParseResult<Query> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.word("a query")? {
+            "V" => self.vertex_source(start),
+            "E" => self.edge_source(start),
+            "path" => self.path_source(start),
+            word => Err(ParseError::new(
+                start,
+                format!("unknown query `{word}`, expected `V`, `E` or `path`"),
+            )),
+        }
+    }
+
+    // Checks for sources that take their specific values from a parameter,
+    // e.g. `V($ids)`. Returns `None` without consuming anything otherwise.
+    fn source_param(&mut self) -> ParseResult<Option<Identifier>> {
+        let start = self.pos;
+        self.expect("(")?;
+        if let Some(param) = self.param()? {
+            self.expect(")")?;
+            return Ok(Some(param));
+        }
+        self.pos = start;
+        Ok(None)
+    }
+
+    // Checks for sources without arguments, or with a list of specific
+    // values. Returns `None` without consuming anything otherwise.
+    fn source_list<T>(&mut self, value: impl FnMut(&mut Self) -> ParseResult<T>) -> ParseResult<Option<Vec<T>>> {
+        let start = self.pos;
+        self.expect("(")?;
+        if self.eat(")") {
+            return Ok(Some(Vec::new()));
+        }
+        if self.eat("[") {
+            let values = self.list(value)?;
+            self.expect(")")?;
+            return Ok(Some(values));
+        }
+        self.pos = start;
+        Ok(None)
+    }
+
+    fn vertex_source(&mut self, start: usize) -> ParseResult<Query> {
+        if let Some(param) = self.source_param()? {
+            return Ok(SpecificVertexQuery::from_param(param).into());
+        }
+        let list_start = self.pos;
+        if let Some(ids) = self.source_list(|p| p.uuid())? {
+            if self.text[list_start..self.pos].contains('[') {
+                return Ok(SpecificVertexQuery::new(ids).into());
+            }
+            return Ok(Query::AllVertex);
+        }
+
+        let mut range: Option<RangeVertexQuery> = None;
+        let mut predicates = Vec::new();
+        self.args(|p, arg| {
+            match arg {
+                Arg::Keyword(_, "type") => range.get_or_insert_with(RangeVertexQuery::new).t = Some(p.name()?),
+                Arg::Keyword(_, "start") => range.get_or_insert_with(RangeVertexQuery::new).start_id = Some(p.uuid()?),
+                Arg::Keyword(_, "limit") => {
+                    let range = range.get_or_insert_with(RangeVertexQuery::new);
+                    (range.limit, range.limit_param) = p.limit()?;
+                }
+                Arg::Positional(_) => predicates.push(p.predicate()?),
+                arg => return Err(p.unexpected(arg)),
+            }
+            Ok(())
+        })?;
+
+        match range {
+            Some(_) if !predicates.is_empty() => Err(ParseError::new(
+                start,
+                "`type`, `start` and `limit` can't be combined with property filters",
+            )),
+            Some(range) => Ok(range.into()),
+            None => match filter(start, predicates)? {
+                Filter::Presence(name) => Ok(VertexWithPropertyPresenceQuery::new(name).into()),
+                Filter::Value(name, value, true, value_param) => Ok(VertexWithPropertyValueQuery {
+                    value_param,
+                    ..VertexWithPropertyValueQuery::new(name, value)
+                }
+                .into()),
+                Filter::Value(..) => Err(ParseError::new(start, "`!=` filters are only supported in `.has()`")),
+                Filter::Predicate(..) => Err(ParseError::new(
+                    start,
+                    "predicate filters are only supported in `.has()`",
+                )),
+                Filter::Range(name, low, high) => {
+                    validated(start, "V", VertexWithPropertyRangeQuery::new(name, low, high))
+                }
+            },
+        }
+    }
+
+    fn edge_source(&mut self, start: usize) -> ParseResult<Query> {
+        if let Some(param) = self.source_param()? {
+            return Ok(SpecificEdgeQuery::from_param(param).into());
+        }
+        let list_start = self.pos;
+        if let Some(edges) = self.source_list(|p| p.edge())? {
+            if self.text[list_start..self.pos].contains('[') {
+                return Ok(SpecificEdgeQuery::new(edges).into());
+            }
+            return Ok(Query::AllEdge);
+        }
+
+        let mut t = None;
+        let mut range_start = None;
+        let mut limit = None;
+        let mut predicates = Vec::new();
+        self.args(|p, arg| {
+            match arg {
+                Arg::Keyword(_, "type") => t = Some(p.name()?),
+                Arg::Keyword(_, "start") => range_start = Some(p.edge()?),
+                Arg::Keyword(_, "limit") => limit = Some(p.number()?),
+                Arg::Positional(_) => predicates.push(p.predicate()?),
+                arg => return Err(p.unexpected(arg)),
+            }
+            Ok(())
+        })?;
+
+        if t.is_some() || range_start.is_some() || limit.is_some() {
+            if !predicates.is_empty() {
+                return Err(ParseError::new(
+                    start,
+                    "`type`, `start` and `limit` can't be combined with property filters",
+                ));
+            }
+            let t = t.ok_or_else(|| ParseError::new(start, "`start` and `limit` on edges require a `type`"))?;
+            return Ok(EdgesByTypeQuery {
+                t,
+                limit: limit.unwrap_or(u32::MAX),
+                start: range_start,
+            }
+            .into());
+        }
+
+        match filter(start, predicates)? {
+            Filter::Presence(name) => Ok(EdgeWithPropertyPresenceQuery::new(name).into()),
+            Filter::Value(name, value, true, value_param) => Ok(EdgeWithPropertyValueQuery {
+                value_param,
+                ..EdgeWithPropertyValueQuery::new(name, value)
+            }
+            .into()),
+            Filter::Value(..) => Err(ParseError::new(start, "`!=` filters are only supported in `.has()`")),
+            Filter::Predicate(..) => Err(ParseError::new(
+                start,
+                "predicate filters are only supported in `.has()`",
+            )),
+            Filter::Range(name, low, high) => validated(start, "E", EdgeWithPropertyRangeQuery::new(name, low, high)),
+        }
+    }
+
+    // Parses an edge, written as `(outbound_id, t, inbound_id)`.
+    fn edge(&mut self) -> ParseResult<Edge> {
+        self.expect("(")?;
+        let outbound_id = self.uuid()?;
+        self.expect(",")?;
+        let t = self.name()?;
+        self.expect(",")?;
+        let inbound_id = self.uuid()?;
+        self.expect(")")?;
+        Ok(Edge::new(outbound_id, t, inbound_id))
+    }
+
+    fn path_source(&mut self, start: usize) -> ParseResult<Query> {
+        let mut source_id = None;
+        let mut target_id = None;
+        let mut direction = EdgeDirection::Outbound;
+        let mut t = None;
+        let mut weight = None;
+        let mut missing_weight = None;
+        self.args(|p, arg| {
+            match arg {
+                Arg::Positional(0) => source_id = Some(p.uuid()?),
+                Arg::Positional(1) => target_id = Some(p.uuid()?),
+                Arg::Positional(2) => direction = p.direction()?,
+                Arg::Keyword(_, "type") => t = Some(p.name()?),
+                Arg::Keyword(_, "weight") => weight = Some(p.name()?),
+                Arg::Keyword(pos, "missing") => missing_weight = Some((pos, p.missing_weight()?)),
+                arg => return Err(p.unexpected(arg)),
+            }
+            Ok(())
+        })?;
+
+        let source_id = required(start, "path", source_id, "a source vertex ID")?;
+        let target_id = required(start, "path", target_id, "a target vertex ID")?;
+        match (weight, missing_weight) {
+            (None, None) => Ok(ShortestPathQuery {
+                source_id,
+                target_id,
+                direction,
+                t,
+            }
+            .into()),
+            (None, Some((pos, _))) => Err(ParseError::new(pos, "`missing` requires a `weight`")),
+            (Some(weight), missing_weight) => {
+                let q = WeightedShortestPathQuery {
+                    direction,
+                    t,
+                    ..WeightedShortestPathQuery::new(source_id, target_id, weight)
+                };
+                match missing_weight {
+                    Some((_, missing_weight)) => validated(start, "path", q.missing_weight(missing_weight)),
+                    None => Ok(q.into()),
+                }
+            }
+        }
+    }
+
+    fn missing_weight(&mut self) -> ParseResult<MissingWeight> {
+        let start = self.pos;
+        match self.word("") {
+            Ok("skip") => Ok(MissingWeight::Skip),
+            Ok("error") => Ok(MissingWeight::Error),
+            _ => {
+                self.pos = start;
+                Ok(MissingWeight::Default(self.json()?))
+            }
+        }
+    }
+
+    fn step(&mut self, inner: Query) -> ParseResult<Query> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let step = self.word("a step")?;
+        let inner = Box::new(inner);
+        match step {
+            "out" | "in" | "both" => {
+                let mut t = None;
+                let mut limit = u32::MAX;
+                let mut limit_param = None;
+                let mut distinct = false;
+                self.args(|p, arg| {
+                    match arg {
+                        Arg::Positional(0) | Arg::Keyword(_, "type") => t = Some(p.name()?),
+                        Arg::Keyword(_, "limit") => (limit, limit_param) = p.limit()?,
+                        Arg::Keyword(_, "distinct") => distinct = p.boolean()?,
+                        arg => return Err(p.unexpected(arg)),
+                    }
+                    Ok(())
+                })?;
+                let direction = match step {
+                    "out" => EdgeDirection::Outbound,
+                    "in" => EdgeDirection::Inbound,
+                    _ => EdgeDirection::Both,
+                };
+                let result = PipeQuery::new(inner, direction).map(|q| PipeQuery {
+                    limit,
+                    limit_param,
+                    t,
+                    distinct,
+                    ..q
+                });
+                validated(start, step, result)
+            }
+            "props" => {
+                let mut name = None;
+                self.args(|p, arg| {
+                    match arg {
+                        Arg::Positional(0) => name = Some(p.name()?),
+                        arg => return Err(p.unexpected(arg)),
+                    }
+                    Ok(())
+                })?;
+                validated(
+                    start,
+                    step,
+                    PipePropertyQuery::new(inner).map(|q| PipePropertyQuery { name, ..q }),
+                )
+            }
+            "has" => {
+                let mut predicates = Vec::new();
+                let mut scan = false;
+                self.args(|p, arg| {
+                    match arg {
+                        Arg::Positional(_) => predicates.push(p.predicate()?),
+                        Arg::Keyword(_, "scan") => scan = p.boolean()?,
+                        arg => return Err(p.unexpected(arg)),
+                    }
+                    Ok(())
+                })?;
+                match filter(start, predicates)? {
+                    Filter::Presence(name) => {
+                        let result = PipeWithPropertyPresenceQuery::new(inner, name, true);
+                        validated(
+                            start,
+                            step,
+                            result.map(|q| PipeWithPropertyPresenceQuery { allow_scan: scan, ..q }),
+                        )
+                    }
+                    Filter::Value(name, value, equal, value_param) => {
+                        let result = PipeWithPropertyValueQuery::new(inner, name, value, equal);
+                        validated(
+                            start,
+                            step,
+                            result.map(|q| PipeWithPropertyValueQuery {
+                                allow_scan: scan,
+                                value_param,
+                                ..q
+                            }),
+                        )
+                    }
+                    _ if scan => Err(ParseError::new(
+                        start,
+                        "`scan` is only supported for presence and value filters",
+                    )),
+                    Filter::Range(name, low, high) => {
+                        validated(start, step, PipeWithPropertyRangeQuery::new(inner, name, low, high))
+                    }
+                    Filter::Predicate(name, predicate) => {
+                        validated(start, step, PipeWithPropertyPredicateQuery::new(inner, name, predicate))
+                    }
+                }
+            }
+            "hasNot" => {
+                let mut name = None;
+                let mut scan = false;
+                self.args(|p, arg| {
+                    match arg {
+                        Arg::Positional(0) => name = Some(p.name()?),
+                        Arg::Keyword(_, "scan") => scan = p.boolean()?,
+                        arg => return Err(p.unexpected(arg)),
+                    }
+                    Ok(())
+                })?;
+                let name = required(start, step, name, "a property name")?;
+                let result = PipeWithPropertyPresenceQuery::new(inner, name, false);
+                validated(
+                    start,
+                    step,
+                    result.map(|q| PipeWithPropertyPresenceQuery { allow_scan: scan, ..q }),
+                )
+            }
+            "traverse" => {
+                let mut direction = None;
+                let mut min_depth = None;
+                let mut max_depth = None;
+                let mut t = None;
+                let mut emit_frontiers = false;
+                self.args(|p, arg| {
+                    match arg {
+                        Arg::Positional(0) => direction = Some(p.direction()?),
+                        Arg::Positional(1) => min_depth = Some(p.number()?),
+                        Arg::Positional(2) => max_depth = Some(p.number()?),
+                        Arg::Keyword(_, "type") => t = Some(p.name()?),
+                        Arg::Keyword(_, "frontiers") => emit_frontiers = p.boolean()?,
+                        arg => return Err(p.unexpected(arg)),
+                    }
+                    Ok(())
+                })?;
+                let direction = required(start, step, direction, "a direction")?;
+                let min_depth = required(start, step, min_depth, "a minimum depth")?;
+                let max_depth = required(start, step, max_depth, "a maximum depth")?;
+                let result = TraversalQuery::new(inner, direction, min_depth, max_depth).map(|q| TraversalQuery {
+                    t,
+                    emit_frontiers,
+                    ..q
+                });
+                validated(start, step, result)
+            }
+            "subgraph" => {
+                let mut direction = None;
+                let mut depth = None;
+                let mut t = None;
+                let mut vertex_limit = u32::MAX;
+                let mut edge_limit = u32::MAX;
+                let mut properties = false;
+                self.args(|p, arg| {
+                    match arg {
+                        Arg::Positional(0) => direction = Some(p.direction()?),
+                        Arg::Positional(1) => depth = Some(p.number()?),
+                        Arg::Keyword(_, "type") => t = Some(p.name()?),
+                        Arg::Keyword(_, "vertexLimit") => vertex_limit = p.number()?,
+                        Arg::Keyword(_, "edgeLimit") => edge_limit = p.number()?,
+                        Arg::Keyword(_, "props") => properties = p.boolean()?,
+                        arg => return Err(p.unexpected(arg)),
+                    }
+                    Ok(())
+                })?;
+                let direction = required(start, step, direction, "a direction")?;
+                let depth = required(start, step, depth, "a depth")?;
+                let result = SubgraphQuery::new(inner, direction, depth).map(|q| SubgraphQuery {
+                    t,
+                    vertex_limit,
+                    edge_limit,
+                    properties,
+                    ..q
+                });
+                validated(start, step, result)
+            }
+            "resume" => {
+                let mut token = None;
+                self.args(|p, arg| {
+                    match arg {
+                        Arg::Positional(0) => {
+                            token =
+                                Some(p.parse_word("a continuation token", |word| {
+                                    ContinuationToken::from_hex(word).ok()
+                                })?)
+                        }
+                        arg => return Err(p.unexpected(arg)),
+                    }
+                    Ok(())
+                })?;
+                let token = required(start, step, token, "a continuation token")?;
+                validated(start, step, ResumeQuery::new(inner, token))
+            }
+            "union" | "intersect" | "except" => {
+                let mut other = None;
+                self.args(|p, arg| {
+                    match arg {
+                        Arg::Positional(0) => other = Some(Box::new(p.query()?)),
+                        arg => return Err(p.unexpected(arg)),
+                    }
+                    Ok(())
+                })?;
+                let other = required(start, step, other, "another query")?;
+                match step {
+                    "union" => validated(start, step, UnionQuery::new(inner, other)),
+                    "intersect" => validated(start, step, IntersectQuery::new(inner, other)),
+                    _ => validated(start, step, ExceptQuery::new(inner, other)),
+                }
+            }
+            "distinct" => {
+                self.args(|p, arg| Err(p.unexpected(arg)))?;
+                validated(start, step, DistinctQuery::new(inner))
+            }
+            "include" => {
+                self.args(|p, arg| Err(p.unexpected(arg)))?;
+                Ok(IncludeQuery::new(inner).into())
+            }
+            "count" => {
+                let mut distinct = false;
+                self.args(|p, arg| {
+                    match arg {
+                        Arg::Keyword(_, "distinct") => distinct = p.boolean()?,
+                        arg => return Err(p.unexpected(arg)),
+                    }
+                    Ok(())
+                })?;
+                let result = CountQuery::new(inner).map(|q| CountQuery { distinct, ..q });
+                validated(start, step, result)
+            }
+            "orderBy" => {
+                let mut name = None;
+                let mut direction = SortDirection::Ascending;
+                let mut limit = u32::MAX;
+                self.args(|p, arg| {
+                    match arg {
+                        Arg::Positional(0) => name = Some(p.name()?),
+                        Arg::Positional(1) => {
+                            direction = p.parse_word("`ascending` or `descending`", |word| word.parse().ok())?
+                        }
+                        Arg::Keyword(_, "limit") => limit = p.number()?,
+                        arg => return Err(p.unexpected(arg)),
+                    }
+                    Ok(())
+                })?;
+                let name = required(start, step, name, "a property name")?;
+                let result = OrderByQuery::new(inner, name).map(|q| OrderByQuery { direction, limit, ..q });
+                validated(start, step, result)
+            }
+            "sum" | "min" | "max" | "avg" | "histogram" => {
+                let mut name = None;
+                let mut width = None;
+                let mut group_by = None;
+                self.args(|p, arg| {
+                    match arg {
+                        Arg::Positional(0) => name = Some(p.name()?),
+                        Arg::Positional(1) if step == "histogram" => width = Some(p.json()?),
+                        Arg::Keyword(_, "by") => group_by = Some(GroupBy::Property(p.name()?)),
+                        Arg::Keyword(_, "byType") => {
+                            if p.boolean()? {
+                                group_by = Some(GroupBy::Type);
+                            }
+                        }
+                        arg => return Err(p.unexpected(arg)),
+                    }
+                    Ok(())
+                })?;
+                let name = required(start, step, name, "a property name")?;
+                let function = match step {
+                    "sum" => AggregateFunction::Sum,
+                    "min" => AggregateFunction::Min,
+                    "max" => AggregateFunction::Max,
+                    "avg" => AggregateFunction::Average,
+                    _ => AggregateFunction::Histogram(required(start, step, width, "a bucket width")?),
+                };
+                let result = AggregateQuery::new(inner, name, function).map(|q| AggregateQuery { group_by, ..q });
+                validated(start, step, result)
+            }
+            "degree" => {
+                let mut t = None;
+                self.args(|p, arg| {
+                    match arg {
+                        Arg::Positional(0) => t = Some(p.name()?),
+                        arg => return Err(p.unexpected(arg)),
+                    }
+                    Ok(())
+                })?;
+                validated(start, step, DegreeQuery::new(inner).map(|q| DegreeQuery { t, ..q }))
+            }
+            "project" => {
+                self.args(|p, arg| Err(p.unexpected(arg)))?;
+                validated(start, step, ProjectQuery::new(inner))
+            }
+            _ => Err(ParseError::new(start, format!("unknown step `{step}`"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ijson, AggregateFunction, AllEdgeQuery, AllVertexQuery, ContinuationToken, CountQueryExt, Edge, GroupBy,
+        Identifier, JsonType, ParseError, PropertyPredicate, Query, QueryExt, QueryOutputValue, RangeVertexQuery,
+        ShortestPathQuery, SpecificEdgeQuery, SpecificVertexQuery, TraversalQuery, Vertex,
+        VertexWithPropertyPresenceQuery, WeightedShortestPathQuery,
+    };
+    use std::ops::Bound;
+
+    fn round_trip(q: Query) {
+        let text = q.to_string();
+        assert_eq!(text.parse::<Query>(), Ok(q), "failed to round trip `{text}`");
+    }
+
+    fn parse_err(text: &str) -> ParseError {
+        text.parse::<Query>().unwrap_err()
+    }
+
+    #[test]
+    fn should_parse_example() {
+        let q: Query = "V(type=person).out(follows).has(age).props()".parse().unwrap();
+        let expected: Query = RangeVertexQuery::new()
+            .t(Identifier::new("person").unwrap())
+            .outbound()
+            .unwrap()
+            .t(Identifier::new("follows").unwrap())
+            .with_property(Identifier::new("age").unwrap())
+            .unwrap()
+            .properties()
+            .unwrap()
+            .into();
+        assert_eq!(q, expected);
+    }
+
+    #[test]
+    fn should_round_trip_sources() {
+        let name = Identifier::new("foo").unwrap();
+        let id = crate::util::generate_uuid_v1();
+        let edge = Edge::new(id, Identifier::new("bar").unwrap(), crate::util::generate_uuid_v1());
+        round_trip(AllVertexQuery.into());
+        round_trip(RangeVertexQuery::new().into());
+        round_trip(RangeVertexQuery::new().limit(5).t(name).start_id(id).into());
+        round_trip(SpecificVertexQuery::new(vec![id, id]).into());
+        round_trip(SpecificVertexQuery::new(Vec::new()).into());
+        round_trip(VertexWithPropertyPresenceQuery::new(name).into());
+        round_trip(crate::VertexWithPropertyValueQuery::new(name, ijson!({"a": [1, "b)"]})).into());
+        round_trip(
+            crate::VertexWithPropertyRangeQuery::new(name, Bound::Excluded(ijson!(1)), Bound::Unbounded)
+                .unwrap()
+                .into(),
+        );
+        round_trip(AllEdgeQuery.into());
+        round_trip(SpecificEdgeQuery::new(vec![edge.clone(), edge.clone()]).into());
+        round_trip(crate::EdgesByTypeQuery::new(name).into());
+        round_trip(crate::EdgesByTypeQuery::new(name).start(edge).limit(2).into());
+        round_trip(crate::EdgeWithPropertyPresenceQuery::new(name).into());
+        round_trip(crate::EdgeWithPropertyValueQuery::new(name, ijson!("x y")).into());
+        round_trip(
+            crate::EdgeWithPropertyRangeQuery::new(name, Bound::Included(ijson!(1.5)), Bound::Excluded(ijson!(3)))
+                .unwrap()
+                .into(),
+        );
+        round_trip(ShortestPathQuery::new(id, id).into());
+        round_trip(
+            ShortestPathQuery::new(id, id)
+                .direction(crate::EdgeDirection::Inbound)
+                .t(name)
+                .into(),
+        );
+        round_trip(WeightedShortestPathQuery::new(id, id, name).into());
+        round_trip(
+            WeightedShortestPathQuery::new(id, id, name)
+                .t(name)
+                .missing_weight(crate::MissingWeight::Default(ijson!(2)))
+                .unwrap()
+                .into(),
+        );
+        round_trip(
+            WeightedShortestPathQuery::new(id, id, name)
+                .missing_weight(crate::MissingWeight::Skip)
+                .unwrap()
+                .into(),
+        );
+    }
+
+    #[test]
+    fn should_round_trip_steps() {
+        let name = Identifier::new("foo").unwrap();
+        let quoted = Identifier::new("").unwrap();
+        let vertex = Vertex::new(name);
+        let token = ContinuationToken::after(&QueryOutputValue::Vertices(vec![vertex])).unwrap();
+        round_trip(AllVertexQuery.outbound().unwrap().into());
+        round_trip(AllEdgeQuery.inbound().unwrap().limit(2).t(quoted).distinct().into());
+        round_trip(AllVertexQuery.both().unwrap().limit(2).into());
+        round_trip(AllVertexQuery.properties().unwrap().into());
+        round_trip(AllVertexQuery.properties().unwrap().name(quoted).into());
+        round_trip(AllVertexQuery.with_property(name).unwrap().into());
+        round_trip(AllVertexQuery.without_property(name).unwrap().into());
+        round_trip(AllVertexQuery.with_property(name).unwrap().allow_scan().into());
+        round_trip(AllEdgeQuery.without_property(name).unwrap().allow_scan().into());
+        round_trip(
+            AllVertexQuery
+                .with_property_equal_to(name, ijson!(1))
+                .unwrap()
+                .allow_scan()
+                .into(),
+        );
+        round_trip(
+            AllVertexQuery
+                .with_property_equal_to(name, ijson!(null))
+                .unwrap()
+                .into(),
+        );
+        round_trip(
+            AllVertexQuery
+                .with_property_not_equal_to(name, ijson!([]))
+                .unwrap()
+                .into(),
+        );
+        round_trip(AllEdgeQuery.with_property_less_than(name, ijson!("z")).unwrap().into());
+        round_trip(
+            AllEdgeQuery
+                .with_property_between(name, ijson!(-1), ijson!(1e10))
+                .unwrap()
+                .into(),
+        );
+        let predicates = vec![
+            PropertyPredicate::StartsWith("a \"b\"".to_string()),
+            PropertyPredicate::EndsWith("c".to_string()),
+            PropertyPredicate::Contains(String::new()),
+            PropertyPredicate::Regex("^[a-z]+(,|$)".to_string()),
+            PropertyPredicate::In(vec![ijson!(1), ijson!({"a": [null]})]),
+            PropertyPredicate::In(Vec::new()),
+            PropertyPredicate::ArrayContains(ijson!("x")),
+            PropertyPredicate::IsNull,
+            PropertyPredicate::IsType(JsonType::Bool),
+        ];
+        for predicate in predicates {
+            round_trip(AllVertexQuery.with_property_matching(name, predicate).unwrap().into());
+        }
+        round_trip(
+            TraversalQuery::new(Box::new(AllVertexQuery.into()), crate::EdgeDirection::Inbound, 0, 3)
+                .unwrap()
+                .t(name)
+                .emit_frontiers()
+                .into(),
+        );
+        round_trip(
+            AllVertexQuery
+                .subgraph(crate::EdgeDirection::Outbound, 2)
+                .unwrap()
+                .into(),
+        );
+        round_trip(AllVertexQuery.subgraph(crate::EdgeDirection::Both, 1).unwrap().into());
+        round_trip(
+            AllVertexQuery
+                .subgraph(crate::EdgeDirection::Inbound, 1)
+                .unwrap()
+                .t(quoted)
+                .vertex_limit(10)
+                .edge_limit(0)
+                .with_properties()
+                .into(),
+        );
+        round_trip(AllVertexQuery.resume(token).unwrap().into());
+        round_trip(AllVertexQuery.union(AllEdgeQuery.outbound().unwrap()).unwrap().into());
+        round_trip(AllVertexQuery.intersect(AllVertexQuery.include()).unwrap().into());
+        round_trip(AllEdgeQuery.except(AllEdgeQuery).unwrap().into());
+        round_trip(AllVertexQuery.distinct().unwrap().into());
+        round_trip(AllVertexQuery.order_by(name).unwrap().into());
+        round_trip(AllVertexQuery.order_by(name).unwrap().descending().limit(3).into());
+        round_trip(AllVertexQuery.include().count().unwrap().into());
+        round_trip(AllVertexQuery.count_distinct().unwrap().into());
+        round_trip(AllVertexQuery.aggregate(name, AggregateFunction::Sum).unwrap().into());
+        round_trip(
+            AllVertexQuery
+                .aggregate(name, AggregateFunction::Average)
+                .unwrap()
+                .group_by(GroupBy::Type)
+                .into(),
+        );
+        round_trip(
+            AllEdgeQuery
+                .aggregate(name, AggregateFunction::Histogram(ijson!(0.5)))
+                .unwrap()
+                .group_by(GroupBy::Property(quoted))
+                .into(),
+        );
+        round_trip(AllVertexQuery.degree().unwrap().into());
+        round_trip(AllEdgeQuery.inbound().unwrap().degree().unwrap().t(quoted).into());
+        round_trip(
+            AllVertexQuery
+                .outbound()
+                .unwrap()
+                .inbound()
+                .unwrap()
+                .with_property(name)
+                .unwrap()
+                .properties()
+                .unwrap()
+                .project()
+                .unwrap()
+                .into(),
+        );
+    }
+
+    #[test]
+    fn should_round_trip_params() {
+        let name = Identifier::new("foo").unwrap();
+        let param = Identifier::new("p").unwrap();
+        let quoted = Identifier::new("").unwrap();
+        round_trip(SpecificVertexQuery::from_param(param).into());
+        round_trip(SpecificEdgeQuery::from_param(quoted).into());
+        round_trip(RangeVertexQuery::new().limit_param(param).into());
+        round_trip(RangeVertexQuery::new().t(name).limit_param(param).into());
+        round_trip(
+            crate::VertexWithPropertyValueQuery::new(name, ijson!(null))
+                .value_param(param)
+                .into(),
+        );
+        round_trip(
+            crate::EdgeWithPropertyValueQuery::new(name, ijson!(null))
+                .value_param(param)
+                .into(),
+        );
+        round_trip(AllVertexQuery.outbound().unwrap().limit_param(param).into());
+        round_trip(
+            AllVertexQuery
+                .with_property_not_equal_to(name, ijson!(null))
+                .unwrap()
+                .value_param(quoted)
+                .into(),
+        );
+
+        let q: Query = "V($ids).out(limit=$n).has(foo == $v)".parse().unwrap();
+        let expected: Query = SpecificVertexQuery::from_param(Identifier::new("ids").unwrap())
+            .outbound()
+            .unwrap()
+            .limit_param(Identifier::new("n").unwrap())
+            .with_property_equal_to(name, ijson!(null))
+            .unwrap()
+            .value_param(Identifier::new("v").unwrap())
+            .into();
+        assert_eq!(q, expected);
+    }
+
+    #[test]
+    fn should_parse_whitespace_and_keywords() {
+        let q: Query = " V ( ) . out ( limit = 3 , type = \"a-b\" ) . has ( x >= 1 , x < 2 ) "
+            .parse()
+            .unwrap();
+        let expected: Query = AllVertexQuery
+            .outbound()
+            .unwrap()
+            .limit(3)
+            .t(Identifier::new("a-b").unwrap())
+            .with_property_in_range(
+                Identifier::new("x").unwrap(),
+                Bound::Included(ijson!(1)),
+                Bound::Excluded(ijson!(2)),
+            )
+            .unwrap()
+            .into();
+        assert_eq!(q, expected);
+
+        // Properties can share names with keywords
+        let q: Query = "V(type == 1)".parse().unwrap();
+        let expected: Query =
+            crate::VertexWithPropertyValueQuery::new(Identifier::new("type").unwrap(), ijson!(1)).into();
+        assert_eq!(q, expected);
+    }
+
+    #[test]
+    fn should_not_parse_invalid_queries() {
+        assert_eq!(parse_err(""), ParseError::new(0, "expected a query"));
+        assert_eq!(
+            parse_err("X()"),
+            ParseError::new(0, "unknown query `X`, expected `V`, `E` or `path`")
+        );
+        assert_eq!(parse_err("V().foo()"), ParseError::new(4, "unknown step `foo`"));
+        assert_eq!(
+            parse_err("V().out(limit=x)"),
+            ParseError::new(14, "expected a number, found `x`")
+        );
+        assert_eq!(
+            parse_err("V().out(foo=1)"),
+            ParseError::new(8, "unknown argument `foo`")
+        );
+        assert_eq!(parse_err("V().out(a, b)"), ParseError::new(11, "unexpected argument"));
+        assert_eq!(parse_err("V().out("), ParseError::new(8, "expected a name"));
+        assert_eq!(parse_err("V().out(a"), ParseError::new(9, "expected `,` or `)`"));
+        assert_eq!(
+            parse_err("V([abc])"),
+            ParseError::new(3, "expected a UUID, found `abc`")
+        );
+        assert_eq!(
+            parse_err("V() x"),
+            ParseError::new(4, "expected a `.` step or the end of the query")
+        );
+        assert_eq!(parse_err("V(a == {)").position, 7);
+        assert_eq!(
+            parse_err("V().count().out()"),
+            ParseError::new(12, "invalid `out`: the given query combination cannot be nested")
+        );
+        assert_eq!(
+            parse_err("V(a > 1, b < 2)"),
+            ParseError::new(
+                9,
+                "filters can only be combined as the lower and upper bounds of a range on one property"
+            )
+        );
+        assert_eq!(
+            parse_err("V(a > $x)"),
+            ParseError::new(2, "parameters can only be used in a single `==` or `!=` filter")
+        );
+        assert_eq!(
+            parse_err("V(a > 1, a > 2)"),
+            ParseError::new(9, "a range can only have one lower and one upper bound")
+        );
+        assert_eq!(
+            parse_err("V().has(a > 1, scan=true)"),
+            ParseError::new(4, "`scan` is only supported for presence and value filters")
+        );
+        assert_eq!(
+            parse_err("V(type=a, b)"),
+            ParseError::new(0, "`type`, `start` and `limit` can't be combined with property filters")
+        );
+        assert_eq!(
+            parse_err("E(type=a, b)"),
+            ParseError::new(0, "`type`, `start` and `limit` can't be combined with property filters")
+        );
+        assert_eq!(
+            parse_err("E(limit=1)"),
+            ParseError::new(0, "`start` and `limit` on edges require a `type`")
+        );
+        assert_eq!(
+            parse_err("V().histogram(a)"),
+            ParseError::new(4, "`histogram` requires a bucket width")
+        );
+        assert_eq!(
+            parse_err("E().degree()"),
+            ParseError::new(4, "invalid `degree`: the given query combination cannot be nested")
+        );
+        assert_eq!(
+            parse_err("V().include().project()"),
+            ParseError::new(14, "invalid `project`: the given query combination cannot be nested")
+        );
+        assert_eq!(
+            parse_err("V().subgraph(out, 0)"),
+            ParseError::new(4, "invalid `subgraph`: invalid value")
+        );
+        assert_eq!(
+            parse_err("V().subgraph(out)"),
+            ParseError::new(4, "`subgraph` requires a depth")
+        );
+        assert_eq!(parse_err("V().resume(zz)").position, 11);
+        assert_eq!(
+            parse_err("V().has(a foo 1)"),
+            ParseError::new(10, "unknown filter `foo`")
+        );
+        assert_eq!(
+            parse_err("V().has(a startsWith 1)"),
+            ParseError::new(21, "expected a JSON string")
+        );
+        assert_eq!(
+            parse_err("V().has(a in 1)"),
+            ParseError::new(13, "expected a JSON array")
+        );
+        assert_eq!(
+            parse_err("V().has(a matches \"(\")"),
+            ParseError::new(4, "invalid `has`: invalid value")
+        );
+        assert_eq!(
+            parse_err("V(a isNull)"),
+            ParseError::new(0, "predicate filters are only supported in `.has()`")
+        );
+    }
+}
@@ -1,15 +1,37 @@
+mod aggregations;
+mod budgets;
 mod bulk_insert;
+mod continuations;
+mod degrees;
 mod edges;
 mod identifiers;
 mod json;
+mod language;
+mod params;
+mod paths;
+mod plans;
+mod predicates;
 mod properties;
 mod queries;
+mod rows;
+mod subgraphs;
 mod vertices;
 
+pub use self::aggregations::{AggregationGroup, AggregationValue, HistogramBucket};
+pub use self::budgets::QueryBudget;
 pub use self::bulk_insert::BulkInsertItem;
+pub(crate) use self::continuations::ContinuationPosition;
+pub use self::continuations::ContinuationToken;
+pub use self::degrees::VertexDegree;
 pub use self::edges::Edge;
 pub use self::identifiers::Identifier;
 pub use self::json::Json;
+pub use self::params::{ParamValue, Params, PreparedQuery};
+pub use self::paths::Path;
+pub use self::plans::QueryPlan;
+pub use self::predicates::{JsonType, PropertyPredicate};
 pub use self::properties::{EdgeProperties, EdgeProperty, NamedProperty, VertexProperties, VertexProperty};
 pub use self::queries::*;
+pub use self::rows::{Row, RowValue};
+pub use self::subgraphs::Subgraph;
 pub use self::vertices::Vertex;
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+
+use crate::errors::{ValidationError, ValidationResult};
+use crate::optimizer::map_children;
+use crate::{Edge, Identifier, Json, Query};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A value given for a parameter of a prepared query.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParamValue {
+    /// Vertex IDs, for `SpecificVertexQuery::from_param`.
+    Ids(Vec<Uuid>),
+    /// Edges, for `SpecificEdgeQuery::from_param`.
+    Edges(Vec<Edge>),
+    /// A property value, for `value_param`.
+    Value(Json),
+    /// A limit, for `limit_param`.
+    Limit(u32),
+}
+
+/// The values given for the parameters of a prepared query, by name.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Params(HashMap<Identifier, ParamValue>);
+
+impl Params {
+    /// Creates a new set of parameters without any values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value of a parameter.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the parameter.
+    /// * `value`: The value.
+    pub fn set<T: Into<Identifier>>(mut self, name: T, value: ParamValue) -> Self {
+        self.0.insert(name.into(), value);
+        self
+    }
+
+    /// Sets a parameter to vertex IDs.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the parameter.
+    /// * `ids`: The vertex IDs.
+    pub fn ids<T: Into<Identifier>>(self, name: T, ids: Vec<Uuid>) -> Self {
+        self.set(name, ParamValue::Ids(ids))
+    }
+
+    /// Sets a parameter to edges.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the parameter.
+    /// * `edges`: The edges.
+    pub fn edges<T: Into<Identifier>>(self, name: T, edges: Vec<Edge>) -> Self {
+        self.set(name, ParamValue::Edges(edges))
+    }
+
+    /// Sets a parameter to a property value.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the parameter.
+    /// * `value`: The property value.
+    pub fn value<T: Into<Identifier>>(self, name: T, value: Json) -> Self {
+        self.set(name, ParamValue::Value(value))
+    }
+
+    /// Sets a parameter to a limit.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the parameter.
+    /// * `limit`: The limit.
+    pub fn limit<T: Into<Identifier>>(self, name: T, limit: u32) -> Self {
+        self.set(name, ParamValue::Limit(limit))
+    }
+
+    /// Gets the value of a parameter.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the parameter.
+    pub fn get(&self, name: &Identifier) -> Option<&ParamValue> {
+        self.0.get(name)
+    }
+
+    /// Iterates over the parameters and their values.
+    pub fn iter(&self) -> impl Iterator<Item = (&Identifier, &ParamValue)> {
+        self.0.iter()
+    }
+}
+
+/// A query that was validated ahead of time, so that it can be run many
+/// times with different parameters. Created with `Database::prepare`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreparedQuery {
+    query: Query,
+}
+
+impl PreparedQuery {
+    pub(crate) fn new(query: Query) -> Self {
+        Self { query }
+    }
+
+    /// The prepared query, with its parameter placeholders.
+    pub fn query(&self) -> &Query {
+        &self.query
+    }
+
+    /// Fills in the parameter placeholders of the prepared query.
+    ///
+    /// # Arguments
+    /// * `params`: The values of the parameters.
+    pub fn bind(&self, params: &Params) -> ValidationResult<Query> {
+        self.query.clone().bind(params)
+    }
+}
+
+impl Query {
+    /// Fills in the parameter placeholders of the query, i.e. the ones set
+    /// with `from_param`, `value_param` or `limit_param`. Fails with
+    /// `ValidationError::MissingParameter` if a placeholder doesn't have a
+    /// value, or `ValidationError::InvalidValue` if a value is of the wrong
+    /// kind, e.g. a limit given for vertex IDs. Parameters without
+    /// placeholders are ignored.
+    ///
+    /// # Arguments
+    /// * `params`: The values of the parameters.
+    pub fn bind(self, params: &Params) -> ValidationResult<Query> {
+        let mut err = None;
+        let q = bind(self, params, &mut err);
+        match err {
+            Some(err) => Err(err),
+            None => Ok(q),
+        }
+    }
+}
+
+// Fills in the placeholders of `q` and the queries nested in it. Rather than
+// stopping at the first error, this records it in `err`, so that it can
+// reuse `map_children`.
+fn bind(q: Query, params: &Params, err: &mut Option<ValidationError>) -> Query {
+    let q = map_children(q, |q| bind(q, params, err));
+    match q {
+        Query::SpecificVertex(mut q) => {
+            if let Some(ids) = take(&mut q.ids_param, params, err, |value| match value {
+                ParamValue::Ids(ids) => Some(ids.clone()),
+                _ => None,
+            }) {
+                q.ids = ids;
+            }
+            Query::SpecificVertex(q)
+        }
+        Query::SpecificEdge(mut q) => {
+            if let Some(edges) = take(&mut q.edges_param, params, err, |value| match value {
+                ParamValue::Edges(edges) => Some(edges.clone()),
+                _ => None,
+            }) {
+                q.edges = edges;
+            }
+            Query::SpecificEdge(q)
+        }
+        Query::RangeVertex(mut q) => {
+            if let Some(limit) = take(&mut q.limit_param, params, err, limit) {
+                q.limit = limit;
+            }
+            Query::RangeVertex(q)
+        }
+        Query::Pipe(mut q) => {
+            if let Some(limit) = take(&mut q.limit_param, params, err, limit) {
+                q.limit = limit;
+            }
+            Query::Pipe(q)
+        }
+        Query::VertexWithPropertyValue(mut q) => {
+            if let Some(value) = take(&mut q.value_param, params, err, value) {
+                q.value = value;
+            }
+            Query::VertexWithPropertyValue(q)
+        }
+        Query::EdgeWithPropertyValue(mut q) => {
+            if let Some(value) = take(&mut q.value_param, params, err, value) {
+                q.value = value;
+            }
+            Query::EdgeWithPropertyValue(q)
+        }
+        Query::PipeWithPropertyValue(mut q) => {
+            if let Some(value) = take(&mut q.value_param, params, err, value) {
+                q.value = value;
+            }
+            Query::PipeWithPropertyValue(q)
+        }
+        q => q,
+    }
+}
+
+// Gets the value for a placeholder, clearing the placeholder.
+fn take<T>(
+    param: &mut Option<Identifier>,
+    params: &Params,
+    err: &mut Option<ValidationError>,
+    f: impl Fn(&ParamValue) -> Option<T>,
+) -> Option<T> {
+    let name = param.take()?;
+    let result = match params.get(&name) {
+        Some(value) => f(value).ok_or(ValidationError::InvalidValue),
+        None => Err(ValidationError::MissingParameter),
+    };
+    match result {
+        Ok(value) => Some(value),
+        Err(e) => {
+            err.get_or_insert(e);
+            None
+        }
+    }
+}
+
+fn limit(value: &ParamValue) -> Option<u32> {
+    match value {
+        ParamValue::Limit(limit) => Some(*limit),
+        _ => None,
+    }
+}
+
+fn value(value: &ParamValue) -> Option<Json> {
+    match value {
+        ParamValue::Value(value) => Some(value.clone()),
+        _ => None,
+    }
+}
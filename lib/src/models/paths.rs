@@ -0,0 +1,27 @@
+use crate::{Edge, Vertex};
+
+use serde::{Deserialize, Serialize};
+
+/// A path through the graph.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Path {
+    /// The vertices along the path, starting from the source vertex and
+    /// ending at the target vertex.
+    pub vertices: Vec<Vertex>,
+
+    /// The edges along the path. There is always one fewer edge than there
+    /// are vertices. Edges keep their original orientation, so an edge may
+    /// point against the direction of the path.
+    pub edges: Vec<Edge>,
+}
+
+impl Path {
+    /// Creates a new path.
+    ///
+    /// # Arguments
+    /// * `vertices`: The vertices along the path.
+    /// * `edges`: The edges along the path.
+    pub fn new(vertices: Vec<Vertex>, edges: Vec<Edge>) -> Self {
+        Self { vertices, edges }
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+use std::time::Duration;
+
+/// A stage in the plan for running a query, as returned by
+/// `Database::explain` and `Database::analyze`.
+///
+/// Each stage corresponds to a query, and has the stages for the queries it
+/// builds off of as children.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryPlan {
+    /// The kind of query run by the stage, e.g. `Pipe`.
+    pub stage: String,
+
+    /// The `Transaction` methods called by the stage, not including those
+    /// called by its children.
+    pub calls: Vec<String>,
+
+    /// Whether the property index read by the stage exists, or `None` if the
+    /// stage doesn't read from an index. If this is `Some(false)`, running
+    /// the query will fail with `Error::NotIndexed`, unless `scan` is set.
+    pub indexed: Option<bool>,
+
+    /// Whether the stage reads the property of each value, because the
+    /// property isn't indexed and the query allows scanning.
+    pub scan: bool,
+
+    /// An upper bound on the number of values output by the stage,
+    /// estimated from vertex and edge counts and limits. `None` if there's
+    /// no estimate.
+    pub estimated_rows: Option<u64>,
+
+    /// The number of values actually output by the stage. Only set by
+    /// `Database::analyze`.
+    pub actual_rows: Option<u64>,
+
+    /// How long the stage took to run, including its children. Only set by
+    /// `Database::analyze`.
+    pub elapsed: Option<Duration>,
+
+    /// The stages for the queries this stage builds off of.
+    pub children: Vec<QueryPlan>,
+}
+
+impl QueryPlan {
+    /// Creates a new query plan stage, without any estimates or children.
+    ///
+    /// # Arguments
+    /// * `stage`: The kind of query run by the stage.
+    /// * `calls`: The `Transaction` methods called by the stage.
+    pub fn new<S: Into<String>>(stage: S, calls: Vec<String>) -> Self {
+        Self {
+            stage: stage.into(),
+            calls,
+            indexed: None,
+            scan: false,
+            estimated_rows: None,
+            actual_rows: None,
+            elapsed: None,
+            children: Vec::new(),
+        }
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+        write!(f, "{:indent$}{}", "", self.stage, indent = depth * 2)?;
+        if !self.calls.is_empty() {
+            write!(f, " [{}]", self.calls.join(", "))?;
+        }
+
+        let mut details = Vec::new();
+        match self.indexed {
+            Some(true) => details.push("indexed".to_string()),
+            Some(false) => details.push("not indexed".to_string()),
+            None => {}
+        }
+        if self.scan {
+            details.push("scan".to_string());
+        }
+        if let Some(rows) = self.estimated_rows {
+            details.push(format!("estimated rows: {rows}"));
+        }
+        if let Some(rows) = self.actual_rows {
+            details.push(format!("actual rows: {rows}"));
+        }
+        if let Some(elapsed) = self.elapsed {
+            details.push(format!("elapsed: {elapsed:?}"));
+        }
+        if !details.is_empty() {
+            write!(f, " ({})", details.join(", "))?;
+        }
+        writeln!(f)?;
+
+        for child in &self.children {
+            child.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for QueryPlan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QueryPlan;
+    use std::time::Duration;
+
+    #[test]
+    fn should_display_plan_tree() {
+        let mut plan = QueryPlan::new("Count", Vec::new());
+        plan.estimated_rows = Some(1);
+        let mut child = QueryPlan::new(
+            "VertexWithPropertyPresence",
+            vec!["vertex_ids_with_property".to_string()],
+        );
+        child.indexed = Some(false);
+        child.actual_rows = Some(0);
+        child.elapsed = Some(Duration::from_millis(2));
+        plan.children.push(child);
+        assert_eq!(
+            plan.to_string(),
+            "Count (estimated rows: 1)\n  VertexWithPropertyPresence [vertex_ids_with_property] (not indexed, actual \
+             rows: 0, elapsed: 2ms)\n"
+        );
+    }
+}
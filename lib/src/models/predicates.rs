@@ -0,0 +1,148 @@
+use crate::{errors, Json};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// A JSON value type.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonType {
+    /// `null`.
+    Null,
+    /// `true` or `false`.
+    Bool,
+    /// A number.
+    Number,
+    /// A string.
+    String,
+    /// An array.
+    Array,
+    /// An object.
+    Object,
+}
+
+impl JsonType {
+    /// Gets the type of a JSON value.
+    ///
+    /// # Arguments
+    /// * `value`: The JSON value.
+    pub fn of(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => JsonType::Null,
+            serde_json::Value::Bool(_) => JsonType::Bool,
+            serde_json::Value::Number(_) => JsonType::Number,
+            serde_json::Value::String(_) => JsonType::String,
+            serde_json::Value::Array(_) => JsonType::Array,
+            serde_json::Value::Object(_) => JsonType::Object,
+        }
+    }
+}
+
+/// A condition that a property value must satisfy.
+#[derive(Eq, PartialEq, Clone, Debug, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PropertyPredicate {
+    /// The value is a string starting with the given prefix.
+    StartsWith(String),
+    /// The value is a string ending with the given suffix.
+    EndsWith(String),
+    /// The value is a string containing the given substring.
+    Contains(String),
+    /// The value is a string matching the given regular expression. The
+    /// expression can match anywhere in the string, unless it's anchored
+    /// with `^` or `$`.
+    Regex(String),
+    /// The value is equal to one of the given values.
+    In(Vec<Json>),
+    /// The value is an array containing the given value.
+    ArrayContains(Json),
+    /// The value is `null`.
+    IsNull,
+    /// The value is of the given type.
+    IsType(JsonType),
+}
+
+impl PropertyPredicate {
+    /// Prepares the predicate for checking values.
+    ///
+    /// # Errors
+    /// Returns a `ValidationError` if the predicate is a regular expression
+    /// that fails to compile.
+    pub(crate) fn matcher(&self) -> errors::ValidationResult<PredicateMatcher<'_>> {
+        let regex = match self {
+            PropertyPredicate::Regex(pattern) => {
+                Some(Regex::new(pattern).map_err(|_| errors::ValidationError::InvalidValue)?)
+            }
+            _ => None,
+        };
+        Ok(PredicateMatcher { predicate: self, regex })
+    }
+}
+
+/// A predicate prepared for checking values, so that regular expressions are
+/// only compiled once.
+pub(crate) struct PredicateMatcher<'a> {
+    predicate: &'a PropertyPredicate,
+    regex: Option<Regex>,
+}
+
+impl<'a> PredicateMatcher<'a> {
+    /// Checks whether a property value satisfies the predicate.
+    pub(crate) fn matches(&self, value: &Json) -> bool {
+        match (self.predicate, &**value) {
+            (PropertyPredicate::StartsWith(prefix), serde_json::Value::String(s)) => s.starts_with(prefix.as_str()),
+            (PropertyPredicate::EndsWith(suffix), serde_json::Value::String(s)) => s.ends_with(suffix.as_str()),
+            (PropertyPredicate::Contains(substring), serde_json::Value::String(s)) => s.contains(substring.as_str()),
+            (PropertyPredicate::Regex(_), serde_json::Value::String(s)) => {
+                self.regex.as_ref().is_some_and(|regex| regex.is_match(s))
+            }
+            (PropertyPredicate::In(values), _) => values.contains(value),
+            (PropertyPredicate::ArrayContains(item), serde_json::Value::Array(items)) => {
+                items.iter().any(|i| i == &**item)
+            }
+            (PropertyPredicate::IsNull, serde_json::Value::Null) => true,
+            (PropertyPredicate::IsType(t), value) => JsonType::of(value) == *t,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JsonType, PropertyPredicate};
+    use crate::ijson;
+
+    #[test]
+    fn should_match_values() {
+        let cases = vec![
+            (PropertyPredicate::StartsWith("ab".to_string()), ijson!("abc"), true),
+            (PropertyPredicate::StartsWith("ab".to_string()), ijson!("cab"), false),
+            (PropertyPredicate::EndsWith("bc".to_string()), ijson!("abc"), true),
+            (PropertyPredicate::Contains("b".to_string()), ijson!("abc"), true),
+            (PropertyPredicate::Contains("b".to_string()), ijson!(["b"]), false),
+            (PropertyPredicate::Regex("^a.c$".to_string()), ijson!("abc"), true),
+            (PropertyPredicate::Regex("^a.c$".to_string()), ijson!("abcd"), false),
+            (PropertyPredicate::In(vec![ijson!(1), ijson!("a")]), ijson!("a"), true),
+            (PropertyPredicate::In(vec![ijson!(1), ijson!("a")]), ijson!(2), false),
+            (PropertyPredicate::ArrayContains(ijson!(2)), ijson!([1, 2]), true),
+            (PropertyPredicate::ArrayContains(ijson!(3)), ijson!([1, 2]), false),
+            (PropertyPredicate::ArrayContains(ijson!(1)), ijson!(1), false),
+            (PropertyPredicate::IsNull, ijson!(null), true),
+            (PropertyPredicate::IsNull, ijson!(false), false),
+            (PropertyPredicate::IsType(JsonType::Object), ijson!({}), true),
+            (PropertyPredicate::IsType(JsonType::Number), ijson!("1"), false),
+        ];
+        for (predicate, value, expected) in cases {
+            assert_eq!(
+                predicate.matcher().unwrap().matches(&value),
+                expected,
+                "{predicate:?} on {value:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn should_not_match_with_invalid_regex() {
+        assert!(PropertyPredicate::Regex("(".to_string()).matcher().is_err());
+    }
+}
@@ -1,8 +1,11 @@
+use std::ops::Bound;
 use std::str::FromStr;
 use std::u32;
 
-use crate::{errors, Edge, Identifier, Json};
+use crate::models::ContinuationPosition;
+use crate::{errors, ContinuationToken, Edge, Identifier, Json, PropertyPredicate};
 
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 macro_rules! into_query {
@@ -31,14 +34,17 @@ macro_rules! nestable_query {
 /// Edge and vertex queries can build off of one another via pipes - e.g. you
 /// can get the outbound edges of a set of vertices by piping from a vertex
 /// query to an edge query. `EdgeDirection`s are used to specify which
-/// end of things you want to pipe - either the outbound items or the inbound
-/// items.
-#[derive(Eq, PartialEq, Clone, Debug, Hash, Copy)]
+/// end of things you want to pipe - the outbound items, the inbound items,
+/// or both.
+#[derive(Eq, PartialEq, Clone, Debug, Hash, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum EdgeDirection {
     /// Outbound direction.
     Outbound,
     /// Inbound direction.
     Inbound,
+    /// Both directions.
+    Both,
 }
 
 impl FromStr for EdgeDirection {
@@ -48,6 +54,7 @@ impl FromStr for EdgeDirection {
         match s {
             "outbound" => Ok(EdgeDirection::Outbound),
             "inbound" => Ok(EdgeDirection::Inbound),
+            "both" => Ok(EdgeDirection::Both),
             _ => Err(errors::ValidationError::InvalidValue),
         }
     }
@@ -58,12 +65,84 @@ impl From<EdgeDirection> for String {
         match d {
             EdgeDirection::Outbound => "outbound".to_string(),
             EdgeDirection::Inbound => "inbound".to_string(),
+            EdgeDirection::Both => "both".to_string(),
         }
     }
 }
 
+/// Specifies which direction values should be sorted in.
+#[derive(Eq, PartialEq, Clone, Debug, Hash, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    /// Smallest values first.
+    Ascending,
+    /// Largest values first.
+    Descending,
+}
+
+impl FromStr for SortDirection {
+    type Err = errors::ValidationError;
+
+    fn from_str(s: &str) -> Result<SortDirection, Self::Err> {
+        match s {
+            "ascending" => Ok(SortDirection::Ascending),
+            "descending" => Ok(SortDirection::Descending),
+            _ => Err(errors::ValidationError::InvalidValue),
+        }
+    }
+}
+
+impl From<SortDirection> for String {
+    fn from(d: SortDirection) -> Self {
+        match d {
+            SortDirection::Ascending => "ascending".to_string(),
+            SortDirection::Descending => "descending".to_string(),
+        }
+    }
+}
+
+/// Specifies how property values are aggregated.
+#[derive(Eq, PartialEq, Clone, Debug, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregateFunction {
+    /// The sum of the values.
+    Sum,
+    /// The smallest value.
+    Min,
+    /// The largest value.
+    Max,
+    /// The mean of the values.
+    Average,
+    /// Counts how many values fall into buckets of the given width.
+    Histogram(Json),
+}
+
+/// Specifies how vertices or edges are grouped before their property values
+/// are aggregated.
+#[derive(Eq, PartialEq, Clone, Debug, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    /// Groups by vertex or edge type.
+    Type,
+    /// Groups by the value of a property.
+    Property(Identifier),
+}
+
 /// A query to get a set of values from the database.
-#[derive(Eq, PartialEq, Clone, Debug)]
+///
+/// Queries can also be written as text, e.g.
+/// `V(type=person).out(follows).props()`, and parsed with `str::parse`. See
+/// the `FromStr` implementation for the syntax.
+///
+/// With serde, queries are represented as externally tagged enums, with
+/// variants named like the protobuf query fields. For example, in JSON,
+/// `AllVertexQuery.outbound()` is
+/// `{"pipe": {"inner": "all_vertex", "direction": "outbound", "limit":
+/// 4294967295, "t": null, "distinct": false}}`. Unlike queries built with
+/// constructors, deserialized queries aren't validated up front; invalid
+/// combinations instead fail when the query is run.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Query {
     /// Gets all vertices.
     AllVertex,
@@ -75,6 +154,8 @@ pub enum Query {
     VertexWithPropertyPresence(VertexWithPropertyPresenceQuery),
     /// Gets vertices with a property equal to a given value.
     VertexWithPropertyValue(VertexWithPropertyValueQuery),
+    /// Gets vertices with a property value within a given range.
+    VertexWithPropertyRange(VertexWithPropertyRangeQuery),
 
     /// Gets all edges.
     AllEdge,
@@ -84,6 +165,10 @@ pub enum Query {
     EdgeWithPropertyPresence(EdgeWithPropertyPresenceQuery),
     /// Gets edges with a property equal to a given value.
     EdgeWithPropertyValue(EdgeWithPropertyValueQuery),
+    /// Gets edges with a property value within a given range.
+    EdgeWithPropertyRange(EdgeWithPropertyRangeQuery),
+    /// Gets edges of a given type.
+    EdgesByType(EdgesByTypeQuery),
 
     /// Gets the vertices associated with edges, or edges associated with
     /// vertices.
@@ -94,11 +179,45 @@ pub enum Query {
     PipeWithPropertyPresence(PipeWithPropertyPresenceQuery),
     /// Gets vertices or edges with a property equal to a given value.
     PipeWithPropertyValue(PipeWithPropertyValueQuery),
+    /// Gets vertices or edges with a property value within a given range.
+    PipeWithPropertyRange(PipeWithPropertyRangeQuery),
+    /// Gets vertices or edges with a property value satisfying a predicate.
+    PipeWithPropertyPredicate(PipeWithPropertyPredicateQuery),
+    /// Walks edges from a set of vertices for a variable number of hops.
+    Traversal(TraversalQuery),
+    /// Finds the shortest path between two vertices.
+    ShortestPath(ShortestPathQuery),
+    /// Finds the shortest path between two vertices, weighted by an edge
+    /// property.
+    WeightedShortestPath(WeightedShortestPathQuery),
+    /// Gets the vertices and edges within a number of hops of a set of
+    /// vertices.
+    Subgraph(SubgraphQuery),
+    /// Resumes a query after the position in a continuation token.
+    Resume(ResumeQuery),
+    /// Gets the values output by either of two queries.
+    Union(UnionQuery),
+    /// Gets the values output by both of two queries.
+    Intersect(IntersectQuery),
+    /// Gets the values output by one query but not another.
+    Except(ExceptQuery),
+    /// Removes duplicate values output by a query.
+    Distinct(DistinctQuery),
+    /// Sorts vertices or edges by a property.
+    OrderBy(OrderByQuery),
 
     /// Includes the results of a query in output.
     Include(IncludeQuery),
     /// Counts the number of items returned from a query.
     Count(CountQuery),
+    /// Aggregates a property over the vertices or edges returned from a
+    /// query.
+    Aggregate(AggregateQuery),
+    /// Gets the number of edges connected to the vertices returned from a
+    /// query.
+    Degree(DegreeQuery),
+    /// Gets rows binding the value output by each stage of a query.
+    Project(ProjectQuery),
 }
 
 impl Query {
@@ -112,15 +231,35 @@ impl Query {
             | Query::SpecificVertex(_)
             | Query::VertexWithPropertyPresence(_)
             | Query::VertexWithPropertyValue(_)
+            | Query::VertexWithPropertyRange(_)
             | Query::AllEdge
             | Query::SpecificEdge(_)
             | Query::EdgeWithPropertyPresence(_)
             | Query::EdgeWithPropertyValue(_)
-            | Query::Count(_) => 1,
+            | Query::EdgeWithPropertyRange(_)
+            | Query::EdgesByType(_)
+            | Query::ShortestPath(_)
+            | Query::WeightedShortestPath(_)
+            | Query::Subgraph(_)
+            | Query::Count(_)
+            | Query::Aggregate(_)
+            | Query::Degree(_)
+            | Query::Project(_) => 1,
             Query::Pipe(q) => q.inner.output_len(),
             Query::PipeProperty(q) => q.inner.output_len(),
             Query::PipeWithPropertyPresence(q) => q.inner.output_len(),
             Query::PipeWithPropertyValue(q) => q.inner.output_len(),
+            Query::PipeWithPropertyRange(q) => q.inner.output_len(),
+            Query::PipeWithPropertyPredicate(q) => q.inner.output_len(),
+            // Frontiers stop being output once no new vertices are reached,
+            // so this only accounts for the first one.
+            Query::Traversal(q) => q.inner.output_len() + q.emit_frontiers as usize,
+            Query::Resume(q) => q.inner.output_len(),
+            Query::Union(q) => q.left.output_len() + q.right.output_len() - 1,
+            Query::Intersect(q) => q.left.output_len() + q.right.output_len() - 1,
+            Query::Except(q) => q.left.output_len() + q.right.output_len() - 1,
+            Query::Distinct(q) => q.inner.output_len(),
+            Query::OrderBy(q) => q.inner.output_len(),
             Query::Include(q) => 1 + q.inner.output_len(),
         }
     }
@@ -134,13 +273,23 @@ impl Query {
             | Query::RangeVertex(_)
             | Query::SpecificVertex(_)
             | Query::VertexWithPropertyPresence(_)
-            | Query::VertexWithPropertyValue(_) => Ok(QueryOutputValue::Vertices(Vec::default())),
+            | Query::VertexWithPropertyValue(_)
+            | Query::VertexWithPropertyRange(_) => Ok(QueryOutputValue::Vertices(Vec::default())),
             Query::AllEdge
             | Query::SpecificEdge(_)
             | Query::EdgeWithPropertyPresence(_)
-            | Query::EdgeWithPropertyValue(_) => Ok(QueryOutputValue::Edges(Vec::default())),
+            | Query::EdgeWithPropertyValue(_)
+            | Query::EdgeWithPropertyRange(_)
+            | Query::EdgesByType(_) => Ok(QueryOutputValue::Edges(Vec::default())),
             Query::Count(_) => Ok(QueryOutputValue::Count(0)),
-            Query::Pipe(q) => q.inner.output_type(),
+            Query::Aggregate(_) => Ok(QueryOutputValue::Aggregation(Vec::default())),
+            Query::Degree(_) => Ok(QueryOutputValue::Degrees(Vec::default())),
+            Query::Project(_) => Ok(QueryOutputValue::Rows(Vec::default())),
+            Query::Pipe(q) => match q.inner.output_type()? {
+                QueryOutputValue::Vertices(_) => Ok(QueryOutputValue::Edges(Vec::default())),
+                QueryOutputValue::Edges(_) => Ok(QueryOutputValue::Vertices(Vec::default())),
+                _ => Err(errors::ValidationError::InnerQuery),
+            },
             Query::PipeProperty(q) => match q.inner.output_type()? {
                 QueryOutputValue::Vertices(_) => Ok(QueryOutputValue::VertexProperties(Vec::default())),
                 QueryOutputValue::Edges(_) => Ok(QueryOutputValue::EdgeProperties(Vec::default())),
@@ -148,9 +297,65 @@ impl Query {
             },
             Query::PipeWithPropertyPresence(q) => q.inner.output_type(),
             Query::PipeWithPropertyValue(q) => q.inner.output_type(),
+            Query::PipeWithPropertyRange(q) => q.inner.output_type(),
+            Query::PipeWithPropertyPredicate(q) => q.inner.output_type(),
+            Query::Traversal(_) => Ok(QueryOutputValue::Vertices(Vec::default())),
+            Query::ShortestPath(_) | Query::WeightedShortestPath(_) => Ok(QueryOutputValue::Paths(Vec::default())),
+            Query::Subgraph(_) => Ok(QueryOutputValue::Subgraph(crate::Subgraph::default())),
+            Query::Resume(q) => q.inner.output_type(),
+            Query::Union(q) => q.left.output_type(),
+            Query::Intersect(q) => q.left.output_type(),
+            Query::Except(q) => q.left.output_type(),
+            Query::Distinct(q) => q.inner.output_type(),
+            Query::OrderBy(q) => q.inner.output_type(),
             Query::Include(q) => q.inner.output_type(),
         }
     }
+
+    /// Determines whether the query yields its results in a stable order
+    /// that can be resumed from a continuation token.
+    pub(crate) fn is_resumable(&self) -> bool {
+        match self {
+            Query::AllVertex
+            | Query::RangeVertex(_)
+            | Query::VertexWithPropertyPresence(_)
+            | Query::VertexWithPropertyValue(_)
+            | Query::VertexWithPropertyRange(_)
+            | Query::AllEdge
+            | Query::EdgeWithPropertyPresence(_)
+            | Query::EdgeWithPropertyValue(_)
+            | Query::EdgeWithPropertyRange(_)
+            | Query::EdgesByType(_) => true,
+            // Edges piped in both directions don't say which direction they
+            // were found in, so the pipe can't tell where to resume from
+            Query::Pipe(q) => {
+                q.direction != EdgeDirection::Both && matches!(q.inner.output_type(), Ok(QueryOutputValue::Vertices(_)))
+            }
+            _ => false,
+        }
+    }
+
+    /// Determines whether each stage of the query can bind a value in the
+    /// rows of a projection, i.e. whether it's a chain of pipes, property
+    /// pipes and property filters starting from a query that outputs
+    /// vertices or edges.
+    pub(crate) fn is_projectable(&self) -> bool {
+        match self {
+            Query::Pipe(q) => q.inner.is_projectable(),
+            Query::PipeProperty(q) => q.inner.is_projectable(),
+            Query::PipeWithPropertyPresence(q) => q.inner.is_projectable(),
+            Query::PipeWithPropertyValue(q) => q.inner.is_projectable(),
+            Query::PipeWithPropertyRange(q) => q.inner.is_projectable(),
+            Query::PipeWithPropertyPredicate(q) => q.inner.is_projectable(),
+            // Included values would be lost, since a projection only
+            // outputs its rows
+            Query::Include(_) => false,
+            q => matches!(
+                q.output_type(),
+                Ok(QueryOutputValue::Vertices(_) | QueryOutputValue::Edges(_))
+            ),
+        }
+    }
 }
 
 /// Extension trait containing common functions for all query structs.
@@ -165,6 +370,12 @@ pub trait QueryExt: Into<Query> {
         PipeQuery::new(Box::new(self.into()), EdgeDirection::Inbound)
     }
 
+    /// Gets the vertices or edges associated with this query in either
+    /// direction.
+    fn both(self) -> errors::ValidationResult<PipeQuery> {
+        PipeQuery::new(Box::new(self.into()), EdgeDirection::Both)
+    }
+
     /// Gets values with a property.
     ///
     /// # Arguments
@@ -207,11 +418,178 @@ pub trait QueryExt: Into<Query> {
         PipeWithPropertyValueQuery::new(Box::new(self.into()), name, value, false)
     }
 
+    /// Gets values with a property value within a given range.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property.
+    /// * `low`: The lower bound of the range.
+    /// * `high`: The upper bound of the range.
+    fn with_property_in_range<T: Into<Identifier>>(
+        self,
+        name: T,
+        low: Bound<Json>,
+        high: Bound<Json>,
+    ) -> errors::ValidationResult<PipeWithPropertyRangeQuery> {
+        PipeWithPropertyRangeQuery::new(Box::new(self.into()), name, low, high)
+    }
+
+    /// Gets values with a property greater than a given value.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property.
+    /// * `value`: The exclusive lower bound.
+    fn with_property_greater_than<T: Into<Identifier>>(
+        self,
+        name: T,
+        value: Json,
+    ) -> errors::ValidationResult<PipeWithPropertyRangeQuery> {
+        self.with_property_in_range(name, Bound::Excluded(value), Bound::Unbounded)
+    }
+
+    /// Gets values with a property less than a given value.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property.
+    /// * `value`: The exclusive upper bound.
+    fn with_property_less_than<T: Into<Identifier>>(
+        self,
+        name: T,
+        value: Json,
+    ) -> errors::ValidationResult<PipeWithPropertyRangeQuery> {
+        self.with_property_in_range(name, Bound::Unbounded, Bound::Excluded(value))
+    }
+
+    /// Gets values with a property between two given values, inclusive.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property.
+    /// * `low`: The inclusive lower bound.
+    /// * `high`: The inclusive upper bound.
+    fn with_property_between<T: Into<Identifier>>(
+        self,
+        name: T,
+        low: Json,
+        high: Json,
+    ) -> errors::ValidationResult<PipeWithPropertyRangeQuery> {
+        self.with_property_in_range(name, Bound::Included(low), Bound::Included(high))
+    }
+
+    /// Gets values with a property value satisfying a predicate.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property.
+    /// * `predicate`: The predicate the property value must satisfy.
+    fn with_property_matching<T: Into<Identifier>>(
+        self,
+        name: T,
+        predicate: PropertyPredicate,
+    ) -> errors::ValidationResult<PipeWithPropertyPredicateQuery> {
+        PipeWithPropertyPredicateQuery::new(Box::new(self.into()), name, predicate)
+    }
+
+    /// Walks edges from the vertices returned by this query for a variable
+    /// number of hops.
+    ///
+    /// # Arguments
+    /// * `direction`: Which direction to walk edges in.
+    /// * `min_depth`: The minimum number of hops.
+    /// * `max_depth`: The maximum number of hops.
+    fn traverse(
+        self,
+        direction: EdgeDirection,
+        min_depth: u32,
+        max_depth: u32,
+    ) -> errors::ValidationResult<TraversalQuery> {
+        TraversalQuery::new(Box::new(self.into()), direction, min_depth, max_depth)
+    }
+
+    /// Gets the subgraph within a number of hops of the vertices returned by
+    /// this query.
+    ///
+    /// # Arguments
+    /// * `direction`: Which direction to walk edges in.
+    /// * `depth`: The maximum number of hops.
+    fn subgraph(self, direction: EdgeDirection, depth: u32) -> errors::ValidationResult<SubgraphQuery> {
+        SubgraphQuery::new(Box::new(self.into()), direction, depth)
+    }
+
     /// Gets the properties associated with the query results.
     fn properties(self) -> errors::ValidationResult<PipePropertyQuery> {
         PipePropertyQuery::new(Box::new(self.into()))
     }
 
+    /// Resumes this query after the position in a continuation token.
+    ///
+    /// # Arguments
+    /// * `continuation`: The continuation token returned with an earlier
+    ///   result of this query.
+    fn resume(self, continuation: ContinuationToken) -> errors::ValidationResult<ResumeQuery> {
+        ResumeQuery::new(Box::new(self.into()), continuation)
+    }
+
+    /// Gets the values output by either this query or another one.
+    ///
+    /// # Arguments
+    /// * `other`: The other query.
+    fn union<Q: Into<Query>>(self, other: Q) -> errors::ValidationResult<UnionQuery> {
+        UnionQuery::new(Box::new(self.into()), Box::new(other.into()))
+    }
+
+    /// Gets the values output by both this query and another one.
+    ///
+    /// # Arguments
+    /// * `other`: The other query.
+    fn intersect<Q: Into<Query>>(self, other: Q) -> errors::ValidationResult<IntersectQuery> {
+        IntersectQuery::new(Box::new(self.into()), Box::new(other.into()))
+    }
+
+    /// Gets the values output by this query but not another one.
+    ///
+    /// # Arguments
+    /// * `other`: The other query, whose values are excluded.
+    fn except<Q: Into<Query>>(self, other: Q) -> errors::ValidationResult<ExceptQuery> {
+        ExceptQuery::new(Box::new(self.into()), Box::new(other.into()))
+    }
+
+    /// Sorts the values output by this query by a property, in ascending
+    /// order.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to sort by.
+    fn order_by<T: Into<Identifier>>(self, name: T) -> errors::ValidationResult<OrderByQuery> {
+        OrderByQuery::new(Box::new(self.into()), name)
+    }
+
+    /// Aggregates a numeric property over the values output by this query.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the property to aggregate.
+    /// * `function`: How to aggregate the property values.
+    fn aggregate<T: Into<Identifier>>(
+        self,
+        name: T,
+        function: AggregateFunction,
+    ) -> errors::ValidationResult<AggregateQuery> {
+        AggregateQuery::new(Box::new(self.into()), name, function)
+    }
+
+    /// Gets the number of edges connected to the vertices output by this
+    /// query.
+    fn degree(self) -> errors::ValidationResult<DegreeQuery> {
+        DegreeQuery::new(Box::new(self.into()))
+    }
+
+    /// Gets rows binding the value output by each stage of this query, so
+    /// that values can be correlated across pipes.
+    fn project(self) -> errors::ValidationResult<ProjectQuery> {
+        ProjectQuery::new(Box::new(self.into()))
+    }
+
+    /// Removes duplicate values from this query's output.
+    fn distinct(self) -> errors::ValidationResult<DistinctQuery> {
+        DistinctQuery::new(Box::new(self.into()))
+    }
+
     /// Include this query's output, even if it is an intermediate result.
     fn include(self) -> IncludeQuery {
         IncludeQuery::new(Box::new(self.into()))
@@ -223,10 +601,15 @@ pub trait CountQueryExt: Into<Query> {
     fn count(self) -> errors::ValidationResult<CountQuery> {
         CountQuery::new(Box::new(self.into()))
     }
+
+    /// Gets the count of distinct values from this query.
+    fn count_distinct(self) -> errors::ValidationResult<CountQuery> {
+        Ok(CountQuery::new(Box::new(self.into()))?.distinct())
+    }
 }
 
 /// Gets all vertices.
-#[derive(Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct AllVertexQuery;
 
 impl QueryExt for AllVertexQuery {}
@@ -241,7 +624,7 @@ impl Into<Query> for AllVertexQuery {
 }
 
 /// Gets a range of vertices.
-#[derive(Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct RangeVertexQuery {
     /// Limits the number of vertices to get.
     pub limit: u32,
@@ -251,6 +634,10 @@ pub struct RangeVertexQuery {
 
     /// Sets the lowest vertex ID to return.
     pub start_id: Option<Uuid>,
+
+    /// Takes the limit from a parameter of a prepared query, if set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit_param: Option<Identifier>,
 }
 
 nestable_query!(RangeVertexQuery, RangeVertex);
@@ -268,6 +655,7 @@ impl RangeVertexQuery {
             limit: u32::max_value(),
             t: None,
             start_id: None,
+            limit_param: None,
         }
     }
 
@@ -280,6 +668,7 @@ impl RangeVertexQuery {
             limit,
             t: self.t,
             start_id: self.start_id,
+            limit_param: self.limit_param,
         }
     }
 
@@ -292,6 +681,7 @@ impl RangeVertexQuery {
             limit: self.limit,
             t: Some(t),
             start_id: self.start_id,
+            limit_param: self.limit_param,
         }
     }
 
@@ -304,15 +694,33 @@ impl RangeVertexQuery {
             limit: self.limit,
             t: self.t,
             start_id: Some(start_id),
+            limit_param: self.limit_param,
+        }
+    }
+
+    /// Takes the limit from a parameter of a prepared query.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the parameter.
+    pub fn limit_param<T: Into<Identifier>>(self, name: T) -> Self {
+        Self {
+            limit: self.limit,
+            t: self.t,
+            start_id: self.start_id,
+            limit_param: Some(name.into()),
         }
     }
 }
 
 /// Gets a specific set of vertices.
-#[derive(Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct SpecificVertexQuery {
     /// The IDs of the vertices to get.
     pub ids: Vec<Uuid>,
+
+    /// Takes the IDs from a parameter of a prepared query, if set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ids_param: Option<Identifier>,
 }
 
 nestable_query!(SpecificVertexQuery, SpecificVertex);
@@ -324,7 +732,7 @@ impl SpecificVertexQuery {
     /// Arguments
     /// * `ids`: The IDs of the vertices to get.
     pub fn new(ids: Vec<Uuid>) -> Self {
-        Self { ids }
+        Self { ids, ids_param: None }
     }
 
     /// Creates a new vertex query for getting a single vertex.
@@ -332,12 +740,24 @@ impl SpecificVertexQuery {
     /// Arguments
     /// * `id`: The ID of the vertex to get.
     pub fn single(id: Uuid) -> Self {
-        Self { ids: vec![id] }
+        Self::new(vec![id])
+    }
+
+    /// Creates a new vertex query for getting the vertices whose IDs are
+    /// given by a parameter of a prepared query.
+    ///
+    /// Arguments
+    /// * `name`: The name of the parameter.
+    pub fn from_param<T: Into<Identifier>>(name: T) -> Self {
+        Self {
+            ids: Vec::new(),
+            ids_param: Some(name.into()),
+        }
     }
 }
 
 /// Gets vertices with or without a given property.
-#[derive(Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct VertexWithPropertyPresenceQuery {
     /// The name of the property.
     pub name: Identifier,
@@ -356,12 +776,15 @@ impl VertexWithPropertyPresenceQuery {
 }
 
 /// Gets vertices with a property equal to a given value.
-#[derive(Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct VertexWithPropertyValueQuery {
     /// The name of the property.
     pub name: Identifier,
     /// The value of the property.
     pub value: Json,
+    /// Takes the value from a parameter of a prepared query, if set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_param: Option<Identifier>,
 }
 
 nestable_query!(VertexWithPropertyValueQuery, VertexWithPropertyValue);
@@ -376,12 +799,72 @@ impl VertexWithPropertyValueQuery {
         Self {
             name: name.into(),
             value,
+            value_param: None,
+        }
+    }
+
+    /// Takes the value from a parameter of a prepared query.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the parameter.
+    pub fn value_param<T: Into<Identifier>>(self, name: T) -> Self {
+        Self {
+            value_param: Some(name.into()),
+            ..self
+        }
+    }
+}
+
+/// Checks that a range of property values is valid. A range must be bounded
+/// on at least one end, and if bounded on both ends, the bounds must be of the
+/// same JSON type, since only values of the same type are comparable.
+fn validate_range(low: &Bound<Json>, high: &Bound<Json>) -> errors::ValidationResult<()> {
+    match (low, high) {
+        (Bound::Unbounded, Bound::Unbounded) => Err(errors::ValidationError::InvalidValue),
+        (Bound::Included(low) | Bound::Excluded(low), Bound::Included(high) | Bound::Excluded(high))
+            if !low.is_same_type(high) =>
+        {
+            Err(errors::ValidationError::InvalidValue)
         }
+        _ => Ok(()),
+    }
+}
+
+/// Gets vertices with a property value within a given range.
+///
+/// Only values of the same JSON type as the range's bounds will match - e.g.
+/// a range over numbers will never return vertices with a string value.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct VertexWithPropertyRangeQuery {
+    /// The name of the property.
+    pub name: Identifier,
+    /// The lower bound of the range.
+    pub low: Bound<Json>,
+    /// The upper bound of the range.
+    pub high: Bound<Json>,
+}
+
+nestable_query!(VertexWithPropertyRangeQuery, VertexWithPropertyRange);
+
+impl VertexWithPropertyRangeQuery {
+    /// Creates a new vertex with property range query.
+    ///
+    /// # Arguments
+    /// * `name`: The property name.
+    /// * `low`: The lower bound of the range.
+    /// * `high`: The upper bound of the range.
+    pub fn new<T: Into<Identifier>>(name: T, low: Bound<Json>, high: Bound<Json>) -> errors::ValidationResult<Self> {
+        validate_range(&low, &high)?;
+        Ok(Self {
+            name: name.into(),
+            low,
+            high,
+        })
     }
 }
 
 /// Gets all edges.
-#[derive(Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct AllEdgeQuery;
 
 impl QueryExt for AllEdgeQuery {}
@@ -396,10 +879,14 @@ impl Into<Query> for AllEdgeQuery {
 }
 
 /// Gets a specific set of edges.
-#[derive(Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct SpecificEdgeQuery {
     /// The edges to get.
     pub edges: Vec<Edge>,
+
+    /// Takes the edges from a parameter of a prepared query, if set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edges_param: Option<Identifier>,
 }
 
 nestable_query!(SpecificEdgeQuery, SpecificEdge);
@@ -410,7 +897,10 @@ impl SpecificEdgeQuery {
     /// Arguments
     /// * `edges`: The edges to get.
     pub fn new(edges: Vec<Edge>) -> Self {
-        Self { edges }
+        Self {
+            edges,
+            edges_param: None,
+        }
     }
 
     /// Creates a new edge query for getting a single edge.
@@ -418,12 +908,24 @@ impl SpecificEdgeQuery {
     /// Arguments
     /// * `edge`: The edge to get.
     pub fn single(edge: Edge) -> Self {
-        Self { edges: vec![edge] }
+        Self::new(vec![edge])
+    }
+
+    /// Creates a new edge query for getting the edges given by a parameter
+    /// of a prepared query.
+    ///
+    /// Arguments
+    /// * `name`: The name of the parameter.
+    pub fn from_param<T: Into<Identifier>>(name: T) -> Self {
+        Self {
+            edges: Vec::new(),
+            edges_param: Some(name.into()),
+        }
     }
 }
 
 /// Gets edges with or without a given property.
-#[derive(Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct EdgeWithPropertyPresenceQuery {
     /// The name of the property.
     pub name: Identifier,
@@ -442,12 +944,15 @@ impl EdgeWithPropertyPresenceQuery {
 }
 
 /// Gets edges with a property equal to a given value.
-#[derive(Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct EdgeWithPropertyValueQuery {
     /// The name of the property.
     pub name: Identifier,
     /// The value of the property.
     pub value: Json,
+    /// Takes the value from a parameter of a prepared query, if set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_param: Option<Identifier>,
 }
 
 nestable_query!(EdgeWithPropertyValueQuery, EdgeWithPropertyValue);
@@ -462,6 +967,103 @@ impl EdgeWithPropertyValueQuery {
         Self {
             name: name.into(),
             value,
+            value_param: None,
+        }
+    }
+
+    /// Takes the value from a parameter of a prepared query.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the parameter.
+    pub fn value_param<T: Into<Identifier>>(self, name: T) -> Self {
+        Self {
+            value_param: Some(name.into()),
+            ..self
+        }
+    }
+}
+
+/// Gets edges with a property value within a given range.
+///
+/// Only values of the same JSON type as the range's bounds will match - e.g.
+/// a range over numbers will never return edges with a string value.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct EdgeWithPropertyRangeQuery {
+    /// The name of the property.
+    pub name: Identifier,
+    /// The lower bound of the range.
+    pub low: Bound<Json>,
+    /// The upper bound of the range.
+    pub high: Bound<Json>,
+}
+
+nestable_query!(EdgeWithPropertyRangeQuery, EdgeWithPropertyRange);
+
+impl EdgeWithPropertyRangeQuery {
+    /// Creates a new edge with property range query.
+    ///
+    /// # Arguments
+    /// * `name`: The property name.
+    /// * `low`: The lower bound of the range.
+    /// * `high`: The upper bound of the range.
+    pub fn new<T: Into<Identifier>>(name: T, low: Bound<Json>, high: Bound<Json>) -> errors::ValidationResult<Self> {
+        validate_range(&low, &high)?;
+        Ok(Self {
+            name: name.into(),
+            low,
+            high,
+        })
+    }
+}
+
+/// Gets edges of a given type, ordered by outbound ID and then inbound ID.
+/// This reads from an index of edges by type, so it doesn't need to iterate
+/// over edges of other types.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct EdgesByTypeQuery {
+    /// The type of edges to get.
+    pub t: Identifier,
+
+    /// Limits the number of edges to get.
+    pub limit: u32,
+
+    /// Sets the lowest edge to return. Only the outbound and inbound IDs of
+    /// the edge are used, so its type doesn't need to match `t`.
+    pub start: Option<Edge>,
+}
+
+nestable_query!(EdgesByTypeQuery, EdgesByType);
+
+impl EdgesByTypeQuery {
+    /// Creates a new edges by type query.
+    ///
+    /// # Arguments
+    /// * `t`: The type of edges to get.
+    pub fn new<T: Into<Identifier>>(t: T) -> Self {
+        Self {
+            t: t.into(),
+            limit: u32::MAX,
+            start: None,
+        }
+    }
+
+    /// Sets the limit.
+    ///
+    /// # Arguments
+    /// * `limit`: Limits the number of returned results.
+    pub fn limit(self, limit: u32) -> Self {
+        Self { limit, ..self }
+    }
+
+    /// Sets the lowest edge to return, e.g. to get the next page of results
+    /// after the last edge of the previous page.
+    ///
+    /// # Arguments
+    /// * `start`: The lowest edge to return.
+    pub fn start(self, start: Edge) -> Self {
+        Self {
+            start: Some(start),
+            ..self
         }
     }
 }
@@ -471,7 +1073,7 @@ impl EdgeWithPropertyValueQuery {
 ///
 /// Generally, you shouldn't need to construct this directly, but rather call
 /// `.outbound()` or `.inbound()`.
-#[derive(Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct PipeQuery {
     /// The edge query to build off of.
     pub inner: Box<Query>,
@@ -484,6 +1086,13 @@ pub struct PipeQuery {
 
     /// Filters the type of values returned.
     pub t: Option<Identifier>,
+
+    /// Whether to skip values that were already returned.
+    pub distinct: bool,
+
+    /// Takes the limit from a parameter of a prepared query, if set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit_param: Option<Identifier>,
 }
 
 nestable_query!(PipeQuery, Pipe);
@@ -505,6 +1114,8 @@ impl PipeQuery {
             direction,
             limit: u32::max_value(),
             t: None,
+            distinct: false,
+            limit_param: None,
         })
     }
 
@@ -518,6 +1129,8 @@ impl PipeQuery {
             direction: self.direction,
             limit,
             t: self.t,
+            distinct: self.distinct,
+            limit_param: self.limit_param,
         }
     }
 
@@ -531,120 +1144,921 @@ impl PipeQuery {
             direction: self.direction,
             limit: self.limit,
             t: Some(t),
+            distinct: self.distinct,
+            limit_param: self.limit_param,
         }
     }
-}
-
-/// Returns the properties associated with a vertex or edge.
-#[derive(Eq, PartialEq, Clone, Debug)]
-pub struct PipePropertyQuery {
-    /// The inner query.
-    pub inner: Box<Query>,
-    /// The property name to get. If `None`, all properties will be fetched.
-    pub name: Option<Identifier>,
-}
-
-into_query!(PipePropertyQuery, PipeProperty);
-impl CountQueryExt for PipePropertyQuery {}
 
-impl PipePropertyQuery {
-    /// Creates a new pipe property query.
-    ///
-    /// # Arguments
-    /// * `inner`: The query to pipe.
-    pub fn new(inner: Box<Query>) -> errors::ValidationResult<Self> {
-        match inner.output_type()? {
-            QueryOutputValue::Vertices(_) | QueryOutputValue::Edges(_) => {}
-            _ => return Err(errors::ValidationError::InnerQuery),
+    /// Skips values that were already returned, e.g. a vertex reached
+    /// through multiple edges. Duplicates don't count towards the limit.
+    pub fn distinct(self) -> Self {
+        Self {
+            inner: self.inner,
+            direction: self.direction,
+            limit: self.limit,
+            t: self.t,
+            distinct: true,
+            limit_param: self.limit_param,
         }
-        Ok(Self { inner, name: None })
     }
 
-    /// Only include properties with a given name.
+    /// Takes the limit from a parameter of a prepared query.
     ///
     /// # Arguments
-    /// * `name`: The name filter.
-    pub fn name(self, name: Identifier) -> Self {
+    /// * `name`: The name of the parameter.
+    pub fn limit_param<T: Into<Identifier>>(self, name: T) -> Self {
         Self {
             inner: self.inner,
-            name: Some(name),
-        }
+            direction: self.direction,
+            limit: self.limit,
+            t: self.t,
+            distinct: self.distinct,
+            limit_param: Some(name.into()),
+        }
+    }
+}
+
+/// Walks edges from a set of vertices for a variable number of hops.
+///
+/// Each vertex is visited at most once, at the shallowest depth it is
+/// reached, so cycles do not cause vertices to be returned more than once.
+/// Vertices reached at a depth between `min_depth` and `max_depth`
+/// (inclusive) are returned. A `min_depth` of 0 includes the starting
+/// vertices themselves.
+///
+/// Generally, you shouldn't need to construct this directly, but rather call
+/// `.traverse()`.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct TraversalQuery {
+    /// The vertex query to start from.
+    pub inner: Box<Query>,
+
+    /// Whether to walk outbound or inbound edges.
+    pub direction: EdgeDirection,
+
+    /// Filters the type of edges walked.
+    pub t: Option<Identifier>,
+
+    /// The minimum number of hops a vertex must be from the start to be
+    /// returned.
+    pub min_depth: u32,
+
+    /// The maximum number of hops to walk.
+    pub max_depth: u32,
+
+    /// Whether to also output the vertices first reached at each depth, as
+    /// separate values preceding the final result.
+    pub emit_frontiers: bool,
+}
+
+nestable_query!(TraversalQuery, Traversal);
+
+impl TraversalQuery {
+    /// Constructs a new traversal query.
+    ///
+    /// # Arguments
+    /// * `inner`: The inner query, which must output vertices.
+    /// * `direction`: Which direction to walk edges in.
+    /// * `min_depth`: The minimum number of hops.
+    /// * `max_depth`: The maximum number of hops.
+    pub fn new(
+        inner: Box<Query>,
+        direction: EdgeDirection,
+        min_depth: u32,
+        max_depth: u32,
+    ) -> errors::ValidationResult<Self> {
+        match inner.output_type()? {
+            QueryOutputValue::Vertices(_) => {}
+            _ => return Err(errors::ValidationError::InnerQuery),
+        }
+        if max_depth == 0 || min_depth > max_depth {
+            return Err(errors::ValidationError::InvalidValue);
+        }
+
+        Ok(Self {
+            inner,
+            direction,
+            t: None,
+            min_depth,
+            max_depth,
+            emit_frontiers: false,
+        })
+    }
+
+    /// Filter the type of edges walked.
+    ///
+    /// # Arguments
+    /// * `t`: Sets the type filter.
+    pub fn t(self, t: Identifier) -> Self {
+        Self { t: Some(t), ..self }
+    }
+
+    /// Output the vertices first reached at each depth, in addition to the
+    /// final result.
+    pub fn emit_frontiers(self) -> Self {
+        Self {
+            emit_frontiers: true,
+            ..self
+        }
+    }
+}
+
+/// Finds the shortest path between two vertices, by number of hops.
+///
+/// The output will contain the path if one exists, or be empty otherwise.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct ShortestPathQuery {
+    /// The ID of the vertex the path starts from.
+    pub source_id: Uuid,
+
+    /// The ID of the vertex the path ends at.
+    pub target_id: Uuid,
+
+    /// Whether to walk outbound or inbound edges from the source vertex.
+    pub direction: EdgeDirection,
+
+    /// Filters the type of edges walked.
+    pub t: Option<Identifier>,
+}
+
+into_query!(ShortestPathQuery, ShortestPath);
+impl CountQueryExt for ShortestPathQuery {}
+
+impl ShortestPathQuery {
+    /// Constructs a new shortest path query, which walks outbound edges.
+    ///
+    /// # Arguments
+    /// * `source_id`: The ID of the vertex the path starts from.
+    /// * `target_id`: The ID of the vertex the path ends at.
+    pub fn new(source_id: Uuid, target_id: Uuid) -> Self {
+        Self {
+            source_id,
+            target_id,
+            direction: EdgeDirection::Outbound,
+            t: None,
+        }
+    }
+
+    /// Sets the direction to walk edges in.
+    ///
+    /// # Arguments
+    /// * `direction`: Which direction to walk edges in.
+    pub fn direction(self, direction: EdgeDirection) -> Self {
+        Self { direction, ..self }
+    }
+
+    /// Filter the type of edges walked.
+    ///
+    /// # Arguments
+    /// * `t`: Sets the type filter.
+    pub fn t(self, t: Identifier) -> Self {
+        Self { t: Some(t), ..self }
+    }
+}
+
+/// Specifies how to handle edges without a usable weight when finding
+/// weighted shortest paths. A weight is unusable if the property is missing,
+/// or is not a non-negative number.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingWeight {
+    /// Don't walk the edge.
+    Skip,
+    /// Use the given weight for the edge.
+    Default(Json),
+    /// Fail the query with `Error::InvalidWeight`.
+    Error,
+}
+
+/// Finds the shortest path between two vertices, where the cost of walking
+/// each edge is read from a numeric edge property.
+///
+/// The output will contain the path if one exists, or be empty otherwise.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct WeightedShortestPathQuery {
+    /// The ID of the vertex the path starts from.
+    pub source_id: Uuid,
+
+    /// The ID of the vertex the path ends at.
+    pub target_id: Uuid,
+
+    /// Whether to walk outbound or inbound edges from the source vertex.
+    pub direction: EdgeDirection,
+
+    /// Filters the type of edges walked.
+    pub t: Option<Identifier>,
+
+    /// The name of the edge property containing the weight.
+    pub weight: Identifier,
+
+    /// How to handle edges without a usable weight.
+    pub missing_weight: MissingWeight,
+}
+
+into_query!(WeightedShortestPathQuery, WeightedShortestPath);
+impl CountQueryExt for WeightedShortestPathQuery {}
+
+impl WeightedShortestPathQuery {
+    /// Constructs a new weighted shortest path query, which walks outbound
+    /// edges and fails on edges without a usable weight.
+    ///
+    /// # Arguments
+    /// * `source_id`: The ID of the vertex the path starts from.
+    /// * `target_id`: The ID of the vertex the path ends at.
+    /// * `weight`: The name of the edge property containing the weight.
+    pub fn new<T: Into<Identifier>>(source_id: Uuid, target_id: Uuid, weight: T) -> Self {
+        Self {
+            source_id,
+            target_id,
+            direction: EdgeDirection::Outbound,
+            t: None,
+            weight: weight.into(),
+            missing_weight: MissingWeight::Error,
+        }
+    }
+
+    /// Sets the direction to walk edges in.
+    ///
+    /// # Arguments
+    /// * `direction`: Which direction to walk edges in.
+    pub fn direction(self, direction: EdgeDirection) -> Self {
+        Self { direction, ..self }
+    }
+
+    /// Filter the type of edges walked.
+    ///
+    /// # Arguments
+    /// * `t`: Sets the type filter.
+    pub fn t(self, t: Identifier) -> Self {
+        Self { t: Some(t), ..self }
+    }
+
+    /// Sets how to handle edges without a usable weight.
+    ///
+    /// # Arguments
+    /// * `missing_weight`: The behavior. Default weights must be
+    ///   non-negative numbers.
+    pub fn missing_weight(self, missing_weight: MissingWeight) -> errors::ValidationResult<Self> {
+        if let MissingWeight::Default(ref value) = missing_weight {
+            if !matches!(value.as_f64(), Some(weight) if weight >= 0.0) {
+                return Err(errors::ValidationError::InvalidValue);
+            }
+        }
+        Ok(Self { missing_weight, ..self })
+    }
+}
+
+/// Gets the subgraph around a set of vertices: the vertices within a number
+/// of hops of them, and the edges walked to reach those vertices.
+///
+/// Vertices are output in the order they're reached, starting with the
+/// vertices of the inner query. Once `vertex_limit` vertices have been
+/// reached, only edges between them are added. Once `edge_limit` edges have
+/// been added, walking stops. Either way, both ends of every edge in the
+/// output are in the output too.
+///
+/// # Examples
+/// ```
+/// use indradb::{EdgeDirection, Identifier, QueryExt, SpecificVertexQuery};
+/// use uuid::Uuid;
+///
+/// let q = SpecificVertexQuery::single(Uuid::default())
+///     .subgraph(EdgeDirection::Outbound, 2)
+///     .unwrap()
+///     .t(Identifier::new("follows").unwrap())
+///     .vertex_limit(100)
+///     .with_properties();
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct SubgraphQuery {
+    /// The vertex query to start from.
+    pub inner: Box<Query>,
+
+    /// Whether to walk outbound or inbound edges.
+    pub direction: EdgeDirection,
+
+    /// Filters the type of edges walked.
+    pub t: Option<Identifier>,
+
+    /// The maximum number of hops to walk.
+    pub depth: u32,
+
+    /// The maximum number of vertices to output.
+    pub vertex_limit: u32,
+
+    /// The maximum number of edges to output.
+    pub edge_limit: u32,
+
+    /// Whether to also output the properties of the vertices and edges.
+    pub properties: bool,
+}
+
+into_query!(SubgraphQuery, Subgraph);
+
+impl SubgraphQuery {
+    /// Constructs a new subgraph query, without size limits.
+    ///
+    /// # Arguments
+    /// * `inner`: The inner query, which must output vertices.
+    /// * `direction`: Which direction to walk edges in.
+    /// * `depth`: The maximum number of hops.
+    ///
+    /// # Errors
+    /// Returns a `ValidationError` if the inner query doesn't output
+    /// vertices, or the depth is 0.
+    pub fn new(inner: Box<Query>, direction: EdgeDirection, depth: u32) -> errors::ValidationResult<Self> {
+        match inner.output_type()? {
+            QueryOutputValue::Vertices(_) => {}
+            _ => return Err(errors::ValidationError::InnerQuery),
+        }
+        if depth == 0 {
+            return Err(errors::ValidationError::InvalidValue);
+        }
+
+        Ok(Self {
+            inner,
+            direction,
+            t: None,
+            depth,
+            vertex_limit: u32::MAX,
+            edge_limit: u32::MAX,
+            properties: false,
+        })
+    }
+
+    /// Filter the type of edges walked.
+    ///
+    /// # Arguments
+    /// * `t`: Sets the type filter.
+    pub fn t(self, t: Identifier) -> Self {
+        Self { t: Some(t), ..self }
+    }
+
+    /// Sets the maximum number of vertices to output.
+    ///
+    /// # Arguments
+    /// * `vertex_limit`: The limit.
+    pub fn vertex_limit(self, vertex_limit: u32) -> Self {
+        Self { vertex_limit, ..self }
+    }
+
+    /// Sets the maximum number of edges to output.
+    ///
+    /// # Arguments
+    /// * `edge_limit`: The limit.
+    pub fn edge_limit(self, edge_limit: u32) -> Self {
+        Self { edge_limit, ..self }
+    }
+
+    /// Also output the properties of the vertices and edges.
+    pub fn with_properties(self) -> Self {
+        Self {
+            properties: true,
+            ..self
+        }
+    }
+}
+
+/// Returns the properties associated with a vertex or edge.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct PipePropertyQuery {
+    /// The inner query.
+    pub inner: Box<Query>,
+    /// The property name to get. If `None`, all properties will be fetched.
+    pub name: Option<Identifier>,
+}
+
+into_query!(PipePropertyQuery, PipeProperty);
+impl CountQueryExt for PipePropertyQuery {}
+
+impl PipePropertyQuery {
+    /// Creates a new pipe property query.
+    ///
+    /// # Arguments
+    /// * `inner`: The query to pipe.
+    pub fn new(inner: Box<Query>) -> errors::ValidationResult<Self> {
+        match inner.output_type()? {
+            QueryOutputValue::Vertices(_) | QueryOutputValue::Edges(_) => {}
+            _ => return Err(errors::ValidationError::InnerQuery),
+        }
+        Ok(Self { inner, name: None })
+    }
+
+    /// Only include properties with a given name.
+    ///
+    /// # Arguments
+    /// * `name`: The name filter.
+    pub fn name(self, name: Identifier) -> Self {
+        Self {
+            inner: self.inner,
+            name: Some(name),
+        }
+    }
+
+    /// Gets rows binding the value output by each stage of this query,
+    /// ending with each property.
+    pub fn project(self) -> errors::ValidationResult<ProjectQuery> {
+        ProjectQuery::new(Box::new(self.into()))
+    }
+}
+
+/// Gets vertices or edges with or without a property.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct PipeWithPropertyPresenceQuery {
+    /// The query to filter.
+    pub inner: Box<Query>,
+    /// The name of the property.
+    pub name: Identifier,
+    /// Whether we should look for property presence or lack thereof.
+    pub exists: bool,
+    /// Whether to read the property of each value if the property isn't
+    /// indexed, rather than failing with `Error::NotIndexed`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_scan: bool,
+}
+
+nestable_query!(PipeWithPropertyPresenceQuery, PipeWithPropertyPresence);
+
+impl PipeWithPropertyPresenceQuery {
+    /// Gets vertices with a property.
+    ///
+    /// Arguments
+    /// * `inner`: The query to filter.
+    /// * `name`: The name of the property.
+    /// * `exists`: Whether we should look for property presence or lack thereof.
+    pub fn new<T: Into<Identifier>>(inner: Box<Query>, name: T, exists: bool) -> errors::ValidationResult<Self> {
+        match inner.output_type()? {
+            QueryOutputValue::Vertices(_) | QueryOutputValue::Edges(_) => {}
+            _ => return Err(errors::ValidationError::InnerQuery),
+        }
+        Ok(Self {
+            inner,
+            name: name.into(),
+            exists,
+            allow_scan: false,
+        })
+    }
+
+    /// Reads the property of each value if the property isn't indexed,
+    /// rather than failing with `Error::NotIndexed`. This is cheap when
+    /// filtering a few values, but slow when filtering many.
+    pub fn allow_scan(self) -> Self {
+        Self {
+            allow_scan: true,
+            ..self
+        }
+    }
+}
+
+/// Gets vertices or edges with a property equal to a given value.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct PipeWithPropertyValueQuery {
+    /// The query to filter.
+    pub inner: Box<Query>,
+    /// The name of the property.
+    pub name: Identifier,
+    /// The value of the property.
+    pub value: Json,
+    /// Whether we should look for property equality or non-equality.
+    pub equal: bool,
+    /// Whether to read the property of each value if the property isn't
+    /// indexed, rather than failing with `Error::NotIndexed`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_scan: bool,
+    /// Takes the value from a parameter of a prepared query, if set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_param: Option<Identifier>,
+}
+
+nestable_query!(PipeWithPropertyValueQuery, PipeWithPropertyValue);
+
+impl PipeWithPropertyValueQuery {
+    /// Constructs a new pipe with property value query.
+    ///
+    /// # Arguments
+    /// * `inner`: The inner query.
+    /// * `name`: The property name to filter.
+    /// * `value`: The property value to filter.
+    /// * `equal`: Whether the value should be equal, or not equal.
+    pub fn new<T: Into<Identifier>>(
+        inner: Box<Query>,
+        name: T,
+        value: Json,
+        equal: bool,
+    ) -> errors::ValidationResult<Self> {
+        match inner.output_type()? {
+            QueryOutputValue::Vertices(_) | QueryOutputValue::Edges(_) => {}
+            _ => return Err(errors::ValidationError::InnerQuery),
+        }
+        Ok(Self {
+            inner,
+            name: name.into(),
+            value,
+            equal,
+            allow_scan: false,
+            value_param: None,
+        })
+    }
+
+    /// Reads the property of each value if the property isn't indexed,
+    /// rather than failing with `Error::NotIndexed`. This is cheap when
+    /// filtering a few values, but slow when filtering many.
+    pub fn allow_scan(self) -> Self {
+        Self {
+            allow_scan: true,
+            ..self
+        }
+    }
+
+    /// Takes the value from a parameter of a prepared query.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the parameter.
+    pub fn value_param<T: Into<Identifier>>(self, name: T) -> Self {
+        Self {
+            value_param: Some(name.into()),
+            ..self
+        }
+    }
+}
+
+/// Gets vertices or edges with a property value within a given range.
+///
+/// Only values of the same JSON type as the range's bounds will match - e.g.
+/// a range over numbers will never match a string value.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct PipeWithPropertyRangeQuery {
+    /// The query to filter.
+    pub inner: Box<Query>,
+    /// The name of the property.
+    pub name: Identifier,
+    /// The lower bound of the range.
+    pub low: Bound<Json>,
+    /// The upper bound of the range.
+    pub high: Bound<Json>,
+}
+
+nestable_query!(PipeWithPropertyRangeQuery, PipeWithPropertyRange);
+
+impl PipeWithPropertyRangeQuery {
+    /// Constructs a new pipe with property range query.
+    ///
+    /// # Arguments
+    /// * `inner`: The inner query.
+    /// * `name`: The property name to filter.
+    /// * `low`: The lower bound of the range.
+    /// * `high`: The upper bound of the range.
+    pub fn new<T: Into<Identifier>>(
+        inner: Box<Query>,
+        name: T,
+        low: Bound<Json>,
+        high: Bound<Json>,
+    ) -> errors::ValidationResult<Self> {
+        match inner.output_type()? {
+            QueryOutputValue::Vertices(_) | QueryOutputValue::Edges(_) => {}
+            _ => return Err(errors::ValidationError::InnerQuery),
+        }
+        validate_range(&low, &high)?;
+        Ok(Self {
+            inner,
+            name: name.into(),
+            low,
+            high,
+        })
+    }
+}
+
+/// Gets vertices or edges with a property value satisfying a predicate.
+///
+/// Unlike the other property filters, this doesn't use property indexes, but
+/// instead reads the property of each piped vertex or edge. Vertices and
+/// edges without the property never match.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct PipeWithPropertyPredicateQuery {
+    /// The query to filter.
+    pub inner: Box<Query>,
+    /// The name of the property.
+    pub name: Identifier,
+    /// The predicate the property value must satisfy.
+    pub predicate: PropertyPredicate,
+}
+
+nestable_query!(PipeWithPropertyPredicateQuery, PipeWithPropertyPredicate);
+
+impl PipeWithPropertyPredicateQuery {
+    /// Constructs a new pipe with property predicate query.
+    ///
+    /// # Arguments
+    /// * `inner`: The inner query.
+    /// * `name`: The property name to filter.
+    /// * `predicate`: The predicate the property value must satisfy.
+    ///
+    /// # Errors
+    /// Returns a `ValidationError` if the inner query doesn't output
+    /// vertices or edges, or if the predicate is an invalid regular
+    /// expression.
+    pub fn new<T: Into<Identifier>>(
+        inner: Box<Query>,
+        name: T,
+        predicate: PropertyPredicate,
+    ) -> errors::ValidationResult<Self> {
+        match inner.output_type()? {
+            QueryOutputValue::Vertices(_) | QueryOutputValue::Edges(_) => {}
+            _ => return Err(errors::ValidationError::InnerQuery),
+        }
+        predicate.matcher()?;
+        Ok(Self {
+            inner,
+            name: name.into(),
+            predicate,
+        })
+    }
+}
+
+/// Resumes a query after the position in a continuation token.
+///
+/// Only queries that yield results in a stable order can be resumed: vertex
+/// and edge queries that read all items or read from a property index, and
+/// pipes from vertices to edges. Resuming a pipe continues from the inner
+/// vertex whose edge the token was created from; if that vertex is no longer
+/// part of the inner query's output, nothing more is returned.
+///
+/// Generally, you shouldn't need to construct this directly, but rather call
+/// `.resume()`.
+///
+/// # Examples
+/// ```
+/// use indradb::{AllEdgeQuery, ContinuationToken, Edge, Identifier, QueryExt, QueryOutputValue};
+/// # let edge = Edge::new(uuid::Uuid::nil(), Identifier::new("foo").unwrap(), uuid::Uuid::nil());
+/// # let output = QueryOutputValue::Edges(vec![edge]);
+/// // A query to return the edges after the last one in a previous output.
+/// let continuation = ContinuationToken::after(&output).unwrap();
+/// let q = AllEdgeQuery.resume(continuation);
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct ResumeQuery {
+    /// The query to resume.
+    pub inner: Box<Query>,
+
+    /// The position to resume after.
+    pub continuation: ContinuationToken,
+}
+
+nestable_query!(ResumeQuery, Resume);
+
+impl ResumeQuery {
+    /// Constructs a new resume query.
+    ///
+    /// # Arguments
+    /// * `inner`: The query to resume.
+    /// * `continuation`: The position to resume after.
+    pub fn new(inner: Box<Query>, continuation: ContinuationToken) -> errors::ValidationResult<Self> {
+        if !inner.is_resumable() {
+            return Err(errors::ValidationError::InnerQuery);
+        }
+        match (inner.output_type()?, &continuation.0) {
+            (QueryOutputValue::Vertices(_), ContinuationPosition::Vertex(_))
+            | (QueryOutputValue::Edges(_), ContinuationPosition::Edge(_)) => {}
+            _ => return Err(errors::ValidationError::InvalidValue),
+        }
+        Ok(Self { inner, continuation })
+    }
+}
+
+/// Gets the values output by either of two queries.
+///
+/// Both queries must output the same type of values - either vertices or
+/// edges. Values are returned at most once, in the order they're first
+/// output by the left query and then the right one.
+///
+/// Generally, you shouldn't need to construct this directly, but rather call
+/// `.union()`.
+///
+/// # Examples
+/// ```
+/// use indradb::{AllEdgeQuery, QueryExt};
+/// // A query to return all vertices with either outbound or inbound edges.
+/// let q = AllEdgeQuery.outbound().unwrap().union(AllEdgeQuery.inbound().unwrap());
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct UnionQuery {
+    /// The left query.
+    pub left: Box<Query>,
+
+    /// The right query.
+    pub right: Box<Query>,
+}
+
+nestable_query!(UnionQuery, Union);
+
+impl UnionQuery {
+    /// Constructs a new union query.
+    ///
+    /// # Arguments
+    /// * `left`: The left query.
+    /// * `right`: The right query.
+    pub fn new(left: Box<Query>, right: Box<Query>) -> errors::ValidationResult<Self> {
+        validate_set_operands(&left, &right)?;
+        Ok(Self { left, right })
     }
 }
 
-/// Gets vertices or edges with or without a property.
-#[derive(Eq, PartialEq, Clone, Debug)]
-pub struct PipeWithPropertyPresenceQuery {
-    /// The query to filter.
+/// Gets the values output by both of two queries.
+///
+/// Both queries must output the same type of values - either vertices or
+/// edges. Values are returned at most once, in the order they're output by
+/// the left query.
+///
+/// Generally, you shouldn't need to construct this directly, but rather call
+/// `.intersect()`.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct IntersectQuery {
+    /// The left query.
+    pub left: Box<Query>,
+
+    /// The right query.
+    pub right: Box<Query>,
+}
+
+nestable_query!(IntersectQuery, Intersect);
+
+impl IntersectQuery {
+    /// Constructs a new intersect query.
+    ///
+    /// # Arguments
+    /// * `left`: The left query.
+    /// * `right`: The right query.
+    pub fn new(left: Box<Query>, right: Box<Query>) -> errors::ValidationResult<Self> {
+        validate_set_operands(&left, &right)?;
+        Ok(Self { left, right })
+    }
+}
+
+/// Gets the values output by one query but not another.
+///
+/// Both queries must output the same type of values - either vertices or
+/// edges. Values are returned at most once, in the order they're output by
+/// the left query.
+///
+/// Generally, you shouldn't need to construct this directly, but rather call
+/// `.except()`.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct ExceptQuery {
+    /// The query to get values from.
+    pub left: Box<Query>,
+
+    /// The query whose values are excluded.
+    pub right: Box<Query>,
+}
+
+nestable_query!(ExceptQuery, Except);
+
+impl ExceptQuery {
+    /// Constructs a new except query.
+    ///
+    /// # Arguments
+    /// * `left`: The query to get values from.
+    /// * `right`: The query whose values are excluded.
+    pub fn new(left: Box<Query>, right: Box<Query>) -> errors::ValidationResult<Self> {
+        validate_set_operands(&left, &right)?;
+        Ok(Self { left, right })
+    }
+}
+
+/// Validates that the operands of a set operation output the same type of
+/// values, which must be vertices or edges.
+fn validate_set_operands(left: &Query, right: &Query) -> errors::ValidationResult<()> {
+    match (left.output_type()?, right.output_type()?) {
+        (QueryOutputValue::Vertices(_), QueryOutputValue::Vertices(_))
+        | (QueryOutputValue::Edges(_), QueryOutputValue::Edges(_)) => Ok(()),
+        _ => Err(errors::ValidationError::InnerQuery),
+    }
+}
+
+/// Removes duplicate values output by a query, keeping the first occurrence
+/// of each.
+///
+/// Vertices are compared by ID, and properties by the vertex or edge they
+/// belong to.
+///
+/// # Examples
+/// ```
+/// use indradb::{AllEdgeQuery, DistinctQuery, QueryExt};
+/// // A query to return each vertex with outbound edges once. This could
+/// // also be done with `PipeQuery::distinct`, which is cheaper.
+/// let q = DistinctQuery::new(Box::new(AllEdgeQuery.outbound().unwrap().into()));
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct DistinctQuery {
+    /// The query to remove duplicates from.
     pub inner: Box<Query>,
-    /// The name of the property.
-    pub name: Identifier,
-    /// Whether we should look for property presence or lack thereof.
-    pub exists: bool,
 }
 
-nestable_query!(PipeWithPropertyPresenceQuery, PipeWithPropertyPresence);
+nestable_query!(DistinctQuery, Distinct);
 
-impl PipeWithPropertyPresenceQuery {
-    /// Gets vertices with a property.
+impl DistinctQuery {
+    /// Constructs a new distinct query.
     ///
-    /// Arguments
-    /// * `inner`: The query to filter.
-    /// * `name`: The name of the property.
-    /// * `exists`: Whether we should look for property presence or lack thereof.
-    pub fn new<T: Into<Identifier>>(inner: Box<Query>, name: T, exists: bool) -> errors::ValidationResult<Self> {
+    /// # Arguments
+    /// * `inner`: The query to remove duplicates from.
+    pub fn new(inner: Box<Query>) -> errors::ValidationResult<Self> {
         match inner.output_type()? {
-            QueryOutputValue::Vertices(_) | QueryOutputValue::Edges(_) => {}
+            QueryOutputValue::Vertices(_)
+            | QueryOutputValue::Edges(_)
+            | QueryOutputValue::VertexProperties(_)
+            | QueryOutputValue::EdgeProperties(_)
+            | QueryOutputValue::Paths(_) => {}
             _ => return Err(errors::ValidationError::InnerQuery),
         }
-        Ok(Self {
-            inner,
-            name: name.into(),
-            exists,
-        })
+        Ok(Self { inner })
     }
 }
 
-/// Gets vertices or edges with a property equal to a given value.
-#[derive(Eq, PartialEq, Clone, Debug)]
-pub struct PipeWithPropertyValueQuery {
-    /// The query to filter.
+/// Sorts vertices or edges by a property.
+///
+/// Property values are compared with `Json::total_cmp`, so values of the
+/// same type are ordered as with `Json`'s `PartialOrd`. Values that don't
+/// have the property are always sorted last, and values that compare equal
+/// keep the order they were output by the inner query in.
+///
+/// Generally, you shouldn't need to construct this directly, but rather call
+/// `.order_by()`.
+///
+/// # Examples
+/// ```
+/// use indradb::{Identifier, QueryExt, SpecificVertexQuery};
+/// # let user_id = uuid::Uuid::nil();
+/// // A query to return the 10 most recent posts of a user.
+/// let q = SpecificVertexQuery::single(user_id)
+///     .outbound()
+///     .unwrap()
+///     .t(Identifier::new("posted").unwrap())
+///     .inbound()
+///     .unwrap()
+///     .order_by(Identifier::new("created_at").unwrap())
+///     .unwrap()
+///     .descending()
+///     .limit(10);
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct OrderByQuery {
+    /// The query to sort the output of.
     pub inner: Box<Query>,
-    /// The name of the property.
+
+    /// The name of the property to sort by.
     pub name: Identifier,
-    /// The value of the property.
-    pub value: Json,
-    /// Whether we should look for property equality or non-equality.
-    pub equal: bool,
+
+    /// Whether to sort in ascending or descending order.
+    pub direction: SortDirection,
+
+    /// Limits the number of values to get, after sorting.
+    pub limit: u32,
 }
 
-nestable_query!(PipeWithPropertyValueQuery, PipeWithPropertyValue);
+nestable_query!(OrderByQuery, OrderBy);
 
-impl PipeWithPropertyValueQuery {
-    /// Constructs a new pipe with property value query.
+impl OrderByQuery {
+    /// Constructs a new order by query, which sorts in ascending order.
     ///
     /// # Arguments
-    /// * `inner`: The inner query.
-    /// * `name`: The property name to filter.
-    /// * `value`: The property value to filter.
-    /// * `equal`: Whether the value should be equal, or not equal.
-    pub fn new<T: Into<Identifier>>(
-        inner: Box<Query>,
-        name: T,
-        value: Json,
-        equal: bool,
-    ) -> errors::ValidationResult<Self> {
+    /// * `inner`: The query to sort the output of.
+    /// * `name`: The name of the property to sort by.
+    pub fn new<T: Into<Identifier>>(inner: Box<Query>, name: T) -> errors::ValidationResult<Self> {
         match inner.output_type()? {
             QueryOutputValue::Vertices(_) | QueryOutputValue::Edges(_) => {}
             _ => return Err(errors::ValidationError::InnerQuery),
         }
+
         Ok(Self {
             inner,
             name: name.into(),
-            value,
-            equal,
+            direction: SortDirection::Ascending,
+            limit: u32::MAX,
         })
     }
+
+    /// Sorts in descending order.
+    pub fn descending(self) -> Self {
+        Self {
+            inner: self.inner,
+            name: self.name,
+            direction: SortDirection::Descending,
+            limit: self.limit,
+        }
+    }
+
+    /// Sets the limit, so that only the top values are returned.
+    ///
+    /// # Arguments
+    /// * `limit`: Limits the number of returned results.
+    pub fn limit(self, limit: u32) -> Self {
+        Self {
+            inner: self.inner,
+            name: self.name,
+            direction: self.direction,
+            limit,
+        }
+    }
 }
 
 /// Includes the results of a query in output.
@@ -662,7 +2076,7 @@ impl PipeWithPropertyValueQuery {
 /// // vertices are explicitly included as intermediate results.
 /// let q = AllVertexQuery.include().outbound();
 /// ```
-#[derive(Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct IncludeQuery {
     /// The query to export.
     pub inner: Box<Query>,
@@ -688,10 +2102,14 @@ impl IncludeQuery {
 /// // A query to return the total number of vertices in the database.
 /// let q = AllVertexQuery.count();
 /// ```
-#[derive(Eq, PartialEq, Clone, Debug)]
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct CountQuery {
     /// The query to export.
     pub inner: Box<Query>,
+
+    /// Whether to only count distinct values, as compared by
+    /// `DistinctQuery`.
+    pub distinct: bool,
 }
 
 into_query!(CountQuery, Count);
@@ -706,15 +2124,214 @@ impl CountQuery {
             QueryOutputValue::Vertices(_)
             | QueryOutputValue::Edges(_)
             | QueryOutputValue::VertexProperties(_)
-            | QueryOutputValue::EdgeProperties(_) => {}
+            | QueryOutputValue::EdgeProperties(_)
+            | QueryOutputValue::Paths(_) => {}
+            _ => return Err(errors::ValidationError::InnerQuery),
+        }
+        Ok(Self { inner, distinct: false })
+    }
+
+    /// Only counts distinct values.
+    pub fn distinct(self) -> Self {
+        Self {
+            inner: self.inner,
+            distinct: true,
+        }
+    }
+}
+
+/// Aggregates a numeric property over the vertices or edges returned from a
+/// query, optionally grouping them first. Property values that aren't
+/// numbers are skipped.
+///
+/// # Examples
+/// ```
+/// use indradb::{AggregateFunction, AllVertexQuery, GroupBy, Identifier, QueryExt};
+/// // A query to return the average age of each type of vertex.
+/// let q = AllVertexQuery
+///     .aggregate(Identifier::new("age").unwrap(), AggregateFunction::Average)
+///     .unwrap()
+///     .group_by(GroupBy::Type);
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct AggregateQuery {
+    /// The query to aggregate the output of.
+    pub inner: Box<Query>,
+
+    /// The name of the property to aggregate.
+    pub name: Identifier,
+
+    /// How to aggregate the property values.
+    pub function: AggregateFunction,
+
+    /// How to group values before aggregating them, if at all.
+    pub group_by: Option<GroupBy>,
+}
+
+into_query!(AggregateQuery, Aggregate);
+
+impl AggregateQuery {
+    /// Constructs a new aggregate query, which doesn't group values.
+    ///
+    /// # Arguments
+    /// * `inner`: The query to aggregate the output of.
+    /// * `name`: The name of the property to aggregate.
+    /// * `function`: How to aggregate the property values.
+    ///
+    /// # Errors
+    /// Returns a `ValidationError` if the inner query doesn't output vertices
+    /// or edges, or if a histogram's bucket width isn't a positive number.
+    pub fn new<T: Into<Identifier>>(
+        inner: Box<Query>,
+        name: T,
+        function: AggregateFunction,
+    ) -> errors::ValidationResult<Self> {
+        match inner.output_type()? {
+            QueryOutputValue::Vertices(_) | QueryOutputValue::Edges(_) => {}
+            _ => return Err(errors::ValidationError::InnerQuery),
+        }
+
+        if let AggregateFunction::Histogram(ref width) = function {
+            match width.as_f64() {
+                Some(width) if width > 0.0 => {}
+                _ => return Err(errors::ValidationError::InvalidValue),
+            }
+        }
+
+        Ok(Self {
+            inner,
+            name: name.into(),
+            function,
+            group_by: None,
+        })
+    }
+
+    /// Groups values before aggregating them.
+    ///
+    /// # Arguments
+    /// * `group_by`: How to group the values.
+    pub fn group_by(self, group_by: GroupBy) -> Self {
+        Self {
+            inner: self.inner,
+            name: self.name,
+            function: self.function,
+            group_by: Some(group_by),
+        }
+    }
+}
+
+/// Gets the number of edges going out of and coming into each vertex
+/// returned from a query. Edges are counted without being read, so this is
+/// cheaper than piping to the edges and counting them.
+///
+/// # Examples
+/// ```
+/// use indradb::{AllVertexQuery, Identifier, QueryExt};
+///
+/// let q = AllVertexQuery
+///     .degree()
+///     .unwrap()
+///     .t(Identifier::new("follows").unwrap());
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct DegreeQuery {
+    /// The query to get the vertices from.
+    pub inner: Box<Query>,
+
+    /// Only counts edges of this type, if set.
+    pub t: Option<Identifier>,
+}
+
+into_query!(DegreeQuery, Degree);
+
+impl DegreeQuery {
+    /// Constructs a new degree query, which counts edges of any type.
+    ///
+    /// # Arguments
+    /// * `inner`: The query to get the vertices from.
+    ///
+    /// # Errors
+    /// Returns a `ValidationError` if the inner query doesn't output
+    /// vertices.
+    pub fn new(inner: Box<Query>) -> errors::ValidationResult<Self> {
+        match inner.output_type()? {
+            QueryOutputValue::Vertices(_) => {}
             _ => return Err(errors::ValidationError::InnerQuery),
         }
+        Ok(Self { inner, t: None })
+    }
+
+    /// Only counts edges of a given type.
+    ///
+    /// # Arguments
+    /// * `t`: The edge type.
+    pub fn t(self, t: Identifier) -> Self {
+        Self {
+            inner: self.inner,
+            t: Some(t),
+        }
+    }
+}
+
+/// Gets rows binding the value output by each stage of a query, e.g. a
+/// user, the edge to a user they follow, that user, and its name. Unlike
+/// including the output of each stage, this keeps track of which values
+/// were piped from which.
+///
+/// The query that the projected chain of pipes starts from outputs a row for
+/// each of its values. Each pipe then adds the values piped to from the last
+/// value of a row, with a row for each of them; a pipe's limit applies to
+/// the number of rows it outputs, and a distinct pipe skips rows that
+/// duplicate an earlier one. Property pipes add each property as a value in
+/// the same way, and property filters keep the rows whose last value passes
+/// the filter.
+///
+/// # Examples
+/// ```
+/// use indradb::{AllVertexQuery, Identifier, QueryExt};
+///
+/// let follows = Identifier::new("follows").unwrap();
+/// let q = AllVertexQuery
+///     .outbound()
+///     .unwrap()
+///     .t(follows)
+///     .inbound()
+///     .unwrap()
+///     .properties()
+///     .unwrap()
+///     .name(Identifier::new("name").unwrap())
+///     .project()
+///     .unwrap();
+/// ```
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct ProjectQuery {
+    /// The query to bind the values of.
+    pub inner: Box<Query>,
+}
+
+into_query!(ProjectQuery, Project);
+
+impl ProjectQuery {
+    /// Constructs a new projection.
+    ///
+    /// # Arguments
+    /// * `inner`: The query to bind the values of.
+    ///
+    /// # Errors
+    /// Returns a `ValidationError` if the inner query isn't a chain of
+    /// pipes, property pipes and property filters starting from a query
+    /// that outputs vertices or edges.
+    pub fn new(inner: Box<Query>) -> errors::ValidationResult<Self> {
+        if !inner.is_projectable() {
+            return Err(errors::ValidationError::InnerQuery);
+        }
         Ok(Self { inner })
     }
 }
 
 /// Value(s) returned from a query.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum QueryOutputValue {
     /// Vertices.
     Vertices(Vec<crate::Vertex>),
@@ -726,14 +2343,75 @@ pub enum QueryOutputValue {
     VertexProperties(Vec<crate::VertexProperties>),
     /// Edge properties.
     EdgeProperties(Vec<crate::EdgeProperties>),
+    /// Paths.
+    Paths(Vec<crate::Path>),
+    /// Aggregated property values, one per group.
+    Aggregation(Vec<crate::AggregationGroup>),
+    /// Vertex degrees.
+    Degrees(Vec<crate::VertexDegree>),
+    /// A subgraph.
+    Subgraph(crate::Subgraph),
+    /// Rows output by a projection.
+    Rows(Vec<crate::Row>),
+}
+
+/// A piece of a query output value, as yielded when streaming query results.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QueryOutputChunk {
+    /// The items in this chunk.
+    pub value: QueryOutputValue,
+    /// Whether more chunks for the same output value follow. Consecutive
+    /// chunks should be concatenated until one without `more` set is reached.
+    pub more: bool,
+    /// A token for resuming the query after the last item in this chunk.
+    /// This is only set on non-empty chunks of the final output value of a
+    /// resumable query.
+    pub continuation: Option<ContinuationToken>,
+}
+
+impl QueryOutputChunk {
+    /// Creates a new query output chunk.
+    ///
+    /// # Arguments
+    /// * `value`: The items in this chunk.
+    /// * `more`: Whether more chunks for the same output value follow.
+    pub fn new(value: QueryOutputValue, more: bool) -> Self {
+        Self {
+            value,
+            more,
+            continuation: None,
+        }
+    }
+
+    /// Sets the continuation token to resume after the last item in this
+    /// chunk.
+    pub(crate) fn with_continuation(self) -> Self {
+        let continuation = ContinuationToken::after(&self.value);
+        Self {
+            value: self.value,
+            more: self.more,
+            continuation,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        ijson, AllVertexQuery, CountQuery, CountQueryExt, EdgeDirection, Identifier, PipePropertyQuery, PipeQuery,
-        PipeWithPropertyPresenceQuery, PipeWithPropertyValueQuery, Query, ValidationError,
+        ijson, AggregateFunction, AggregationGroup, AggregationValue, AllEdgeQuery, AllVertexQuery, ContinuationToken,
+        CountQuery, CountQueryExt, Edge, EdgeDirection, EdgeProperties, EdgeWithPropertyPresenceQuery,
+        EdgeWithPropertyRangeQuery, EdgeWithPropertyValueQuery, EdgesByTypeQuery, GroupBy, HistogramBucket, Identifier,
+        JsonType, MissingWeight, NamedProperty, Path, PipePropertyQuery, PipeQuery, PipeWithPropertyPresenceQuery,
+        PipeWithPropertyRangeQuery, PipeWithPropertyValueQuery, ProjectQuery, PropertyPredicate, Query, QueryExt,
+        QueryOutputValue, RangeVertexQuery, Row, RowValue, ShortestPathQuery, SpecificEdgeQuery, SpecificVertexQuery,
+        Subgraph, TraversalQuery, ValidationError, Vertex, VertexDegree, VertexProperties,
+        VertexWithPropertyPresenceQuery, VertexWithPropertyRangeQuery, VertexWithPropertyValueQuery,
+        WeightedShortestPathQuery,
     };
+    use serde::de::DeserializeOwned;
+    use serde::Serialize;
+    use std::fmt::Debug;
+    use std::ops::Bound;
     use std::str::FromStr;
 
     fn expect_inner_query_err<T: core::fmt::Debug>(result: Result<T, ValidationError>) {
@@ -743,6 +2421,13 @@ mod tests {
         }
     }
 
+    fn expect_invalid_value_err<T: core::fmt::Debug>(result: Result<T, ValidationError>) {
+        match result {
+            Err(ValidationError::InvalidValue) => (),
+            _ => panic!("unexpected result: {:?}", result),
+        }
+    }
+
     #[test]
     fn should_convert_str_to_edge_direction() {
         assert_eq!(EdgeDirection::from_str("outbound").unwrap(), EdgeDirection::Outbound);
@@ -764,6 +2449,7 @@ mod tests {
         expect_inner_query_err(CountQuery::new(Box::new(q.clone())));
         expect_inner_query_err(PipeQuery::new(Box::new(q.clone()), EdgeDirection::Outbound));
         expect_inner_query_err(PipePropertyQuery::new(Box::new(q.clone())));
+        expect_inner_query_err(ProjectQuery::new(Box::new(q.clone())));
         expect_inner_query_err(PipeWithPropertyPresenceQuery::new(
             Box::new(q.clone()),
             Identifier::new("foo").unwrap(),
@@ -775,5 +2461,223 @@ mod tests {
             ijson!("bar"),
             true,
         ));
+        expect_inner_query_err(PipeWithPropertyRangeQuery::new(
+            Box::new(q.clone()),
+            Identifier::new("foo").unwrap(),
+            Bound::Included(ijson!(1)),
+            Bound::Unbounded,
+        ));
+    }
+
+    fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: T) {
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(
+            serde_json::from_str::<T>(&json).unwrap(),
+            value,
+            "failed to round trip `{json}`"
+        );
+    }
+
+    #[test]
+    fn should_deserialize_property_filters_without_allow_scan() {
+        // Property filters serialized before they could scan don't have
+        // `allow_scan`, and ones that don't scan are still serialized that way
+        let name = Identifier::new("foo").unwrap();
+        let json = serde_json::json!({
+            "pipe_with_property_presence": {"inner": "all_vertex", "name": "foo", "exists": true}
+        });
+        let q: Query = AllVertexQuery.with_property(name).unwrap().into();
+        assert_eq!(serde_json::from_value::<Query>(json.clone()).unwrap(), q);
+        assert_eq!(serde_json::to_value(&q).unwrap(), json);
+
+        let json = serde_json::json!({
+            "pipe_with_property_value": {"inner": "all_edge", "name": "foo", "value": "bar", "equal": false}
+        });
+        let q: Query = AllEdgeQuery
+            .with_property_not_equal_to(name, ijson!("bar"))
+            .unwrap()
+            .into();
+        assert_eq!(serde_json::from_value::<Query>(json.clone()).unwrap(), q);
+        assert_eq!(serde_json::to_value(&q).unwrap(), json);
+    }
+
+    #[test]
+    fn should_serialize_queries() {
+        let q: Query = AllVertexQuery.outbound().unwrap().into();
+        assert_eq!(
+            serde_json::to_value(&q).unwrap(),
+            serde_json::json!({
+                "pipe": {"inner": "all_vertex", "direction": "outbound", "limit": u32::MAX, "t": null, "distinct": false}
+            })
+        );
+
+        let name = Identifier::new("foo").unwrap();
+        let id = crate::util::generate_uuid_v1();
+        let edge = Edge::new(id, name, id);
+        let token = ContinuationToken::after(&QueryOutputValue::Edges(vec![edge.clone()])).unwrap();
+        let queries: Vec<Query> = vec![
+            AllVertexQuery.into(),
+            RangeVertexQuery::new().limit(1).t(name).start_id(id).into(),
+            SpecificVertexQuery::single(id).into(),
+            VertexWithPropertyPresenceQuery::new(name).into(),
+            VertexWithPropertyValueQuery::new(name, ijson!({"a": [1, 2.5]})).into(),
+            VertexWithPropertyRangeQuery::new(name, Bound::Included(ijson!(1)), Bound::Unbounded)
+                .unwrap()
+                .into(),
+            AllEdgeQuery.into(),
+            SpecificEdgeQuery::single(edge.clone()).into(),
+            EdgesByTypeQuery::new(name).start(edge).limit(1).into(),
+            EdgeWithPropertyPresenceQuery::new(name).into(),
+            EdgeWithPropertyValueQuery::new(name, ijson!("bar")).into(),
+            EdgeWithPropertyRangeQuery::new(name, Bound::Unbounded, Bound::Excluded(ijson!("b")))
+                .unwrap()
+                .into(),
+            AllEdgeQuery.inbound().unwrap().limit(2).t(name).distinct().into(),
+            AllVertexQuery.properties().unwrap().name(name).into(),
+            AllVertexQuery.without_property(name).unwrap().into(),
+            AllVertexQuery
+                .with_property_not_equal_to(name, ijson!(null))
+                .unwrap()
+                .into(),
+            AllEdgeQuery
+                .with_property_between(name, ijson!(1), ijson!(2))
+                .unwrap()
+                .into(),
+            AllVertexQuery
+                .with_property_matching(name, PropertyPredicate::In(vec![ijson!(1), ijson!("a")]))
+                .unwrap()
+                .into(),
+            AllEdgeQuery
+                .with_property_matching(name, PropertyPredicate::IsType(JsonType::Array))
+                .unwrap()
+                .into(),
+            TraversalQuery::new(Box::new(AllVertexQuery.into()), EdgeDirection::Inbound, 1, 3)
+                .unwrap()
+                .t(name)
+                .emit_frontiers()
+                .into(),
+            ShortestPathQuery::new(id, id)
+                .direction(EdgeDirection::Inbound)
+                .t(name)
+                .into(),
+            WeightedShortestPathQuery::new(id, id, name)
+                .missing_weight(MissingWeight::Default(ijson!(1)))
+                .unwrap()
+                .into(),
+            AllEdgeQuery.resume(token).unwrap().into(),
+            AllVertexQuery.union(AllVertexQuery).unwrap().into(),
+            AllVertexQuery.intersect(AllVertexQuery).unwrap().into(),
+            AllVertexQuery.except(AllVertexQuery).unwrap().into(),
+            AllVertexQuery.distinct().unwrap().into(),
+            AllVertexQuery.order_by(name).unwrap().descending().limit(1).into(),
+            AllVertexQuery.include().into(),
+            AllVertexQuery.count_distinct().unwrap().into(),
+            AllVertexQuery
+                .aggregate(name, AggregateFunction::Histogram(ijson!(0.5)))
+                .unwrap()
+                .group_by(GroupBy::Property(name))
+                .into(),
+            AllEdgeQuery
+                .aggregate(name, AggregateFunction::Average)
+                .unwrap()
+                .group_by(GroupBy::Type)
+                .into(),
+            AllVertexQuery.degree().unwrap().t(name).into(),
+            AllVertexQuery
+                .subgraph(EdgeDirection::Outbound, 2)
+                .unwrap()
+                .t(name)
+                .edge_limit(10)
+                .with_properties()
+                .into(),
+            SpecificVertexQuery::from_param(name)
+                .outbound()
+                .unwrap()
+                .limit_param(name)
+                .with_property_equal_to(name, ijson!(null))
+                .unwrap()
+                .value_param(name)
+                .into(),
+            SpecificEdgeQuery::from_param(name).into(),
+            AllVertexQuery
+                .outbound()
+                .unwrap()
+                .inbound()
+                .unwrap()
+                .with_property(name)
+                .unwrap()
+                .properties()
+                .unwrap()
+                .project()
+                .unwrap()
+                .into(),
+            RangeVertexQuery::new().limit_param(name).into(),
+        ];
+        for q in queries {
+            round_trip(q);
+        }
+    }
+
+    #[test]
+    fn should_serialize_query_output_values() {
+        let name = Identifier::new("foo").unwrap();
+        let vertex = Vertex::new(name);
+        let edge = Edge::new(vertex.id, name, vertex.id);
+        let values = vec![
+            QueryOutputValue::Vertices(vec![vertex.clone()]),
+            QueryOutputValue::Edges(vec![edge.clone()]),
+            QueryOutputValue::Count(3),
+            QueryOutputValue::VertexProperties(vec![VertexProperties::new(
+                vertex.clone(),
+                vec![NamedProperty::new(name, ijson!(true))],
+            )]),
+            QueryOutputValue::EdgeProperties(vec![EdgeProperties::new(
+                edge.clone(),
+                vec![NamedProperty::new(name, ijson!([1, "a"]))],
+            )]),
+            QueryOutputValue::Paths(vec![Path::new(
+                vec![vertex.clone(), vertex.clone()],
+                vec![edge.clone()],
+            )]),
+            QueryOutputValue::Subgraph(Subgraph::new(vec![vertex.clone()], vec![edge.clone()])),
+            QueryOutputValue::Degrees(vec![VertexDegree::new(vertex.clone(), 2, 0)]),
+            QueryOutputValue::Rows(vec![Row::new(vec![
+                RowValue::Vertex(vertex),
+                RowValue::Edge(edge),
+                RowValue::Property(NamedProperty::new(name, ijson!(1))),
+            ])]),
+            QueryOutputValue::Aggregation(vec![
+                AggregationGroup::new(None, 2, AggregationValue::Number(Some(1.5))),
+                AggregationGroup::new(Some(ijson!("a")), 0, AggregationValue::Number(None)),
+                AggregationGroup::new(
+                    Some(ijson!(null)),
+                    1,
+                    AggregationValue::Histogram(vec![HistogramBucket::new(0.5, 1)]),
+                ),
+            ]),
+        ];
+        for value in values {
+            round_trip(value);
+        }
+    }
+
+    #[test]
+    fn should_fail_for_invalid_ranges() {
+        let name = Identifier::new("foo").unwrap();
+        expect_invalid_value_err(VertexWithPropertyRangeQuery::new(
+            name,
+            Bound::Unbounded,
+            Bound::Unbounded,
+        ));
+        expect_invalid_value_err(EdgeWithPropertyRangeQuery::new(
+            name,
+            Bound::Included(ijson!(1)),
+            Bound::Excluded(ijson!("2")),
+        ));
+        expect_invalid_value_err(AllVertexQuery.with_property_between(name, ijson!(null), ijson!(false)));
+        assert!(AllVertexQuery
+            .with_property_between(name, ijson!(1), ijson!(2.5))
+            .is_ok());
+        assert!(AllVertexQuery.with_property_greater_than(name, ijson!("a")).is_ok());
     }
 }
@@ -0,0 +1,33 @@
+use crate::{Edge, NamedProperty, Vertex};
+
+use serde::{Deserialize, Serialize};
+
+/// A value bound by one stage of a projection.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowValue {
+    /// A vertex.
+    Vertex(Vertex),
+    /// An edge.
+    Edge(Edge),
+    /// A property of the value bound before it.
+    Property(NamedProperty),
+}
+
+/// A row output by a projection, binding the value output by each stage of
+/// the projected query.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Row {
+    /// The values, with the one bound by the first stage first.
+    pub values: Vec<RowValue>,
+}
+
+impl Row {
+    /// Creates a new row.
+    ///
+    /// # Arguments
+    /// * `values`: The values, with the one bound by the first stage first.
+    pub fn new(values: Vec<RowValue>) -> Self {
+        Self { values }
+    }
+}
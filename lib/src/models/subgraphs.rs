@@ -0,0 +1,37 @@
+use crate::{Edge, EdgeProperties, Vertex, VertexProperties};
+
+use serde::{Deserialize, Serialize};
+
+/// A set of vertices, and edges between them.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Subgraph {
+    /// The vertices in the subgraph.
+    pub vertices: Vec<Vertex>,
+
+    /// The edges in the subgraph. Both ends of each edge are in `vertices`.
+    pub edges: Vec<Edge>,
+
+    /// The properties of the vertices, if they were requested. Vertices
+    /// without properties are left out.
+    pub vertex_properties: Vec<VertexProperties>,
+
+    /// The properties of the edges, if they were requested. Edges without
+    /// properties are left out.
+    pub edge_properties: Vec<EdgeProperties>,
+}
+
+impl Subgraph {
+    /// Creates a new subgraph without properties.
+    ///
+    /// # Arguments
+    /// * `vertices`: The vertices in the subgraph.
+    /// * `edges`: The edges in the subgraph.
+    pub fn new(vertices: Vec<Vertex>, edges: Vec<Edge>) -> Self {
+        Self {
+            vertices,
+            edges,
+            vertex_properties: Vec::new(),
+            edge_properties: Vec::new(),
+        }
+    }
+}
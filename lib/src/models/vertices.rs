@@ -1,4 +1,5 @@
 use crate::{util::generate_uuid_v1, Identifier};
+use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
 use uuid::Uuid;
 
@@ -6,7 +7,7 @@ use uuid::Uuid;
 ///
 /// Vertices are how you would represent nouns in the datastore. An example
 /// might be a user, or a movie. All vertices have a unique ID and a type.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Vertex {
     /// The id of the vertex.
     pub id: Uuid,
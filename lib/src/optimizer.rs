@@ -0,0 +1,286 @@
+use crate::models::{
+    EdgeDirection, EdgeWithPropertyPresenceQuery, EdgeWithPropertyRangeQuery, EdgeWithPropertyValueQuery,
+    EdgesByTypeQuery, PipeWithPropertyPresenceQuery, PipeWithPropertyRangeQuery, PipeWithPropertyValueQuery, Query,
+    VertexWithPropertyPresenceQuery, VertexWithPropertyRangeQuery, VertexWithPropertyValueQuery,
+};
+
+impl Query {
+    /// Rewrites the query into an equivalent one that's cheaper to run. The
+    /// optimized query outputs the same values, in the same order, as the
+    /// original. `Database` optimizes queries before running them, so this
+    /// generally doesn't need to be called directly.
+    ///
+    /// The rewrites are:
+    /// * Property filters over all vertices or edges become lookups of the
+    ///   property index, unless they allow scanning, since `Database` only
+    ///   allows that when the index doesn't exist.
+    /// * Range scans without a limit, type filter or start ID become full
+    ///   scans, which can be counted without iterating.
+    /// * Type filters on the outbound edges of all vertices are pushed down
+    ///   into range scans of the edge type index, outside of projections.
+    /// * Removing duplicates is skipped for queries that can't output any.
+    /// * Nested includes are merged.
+    pub fn optimize(self) -> Query {
+        optimize(self, false)
+    }
+}
+
+// Optimizes `q`. `projected` is whether `q` is nested in a projection.
+fn optimize(q: Query, projected: bool) -> Query {
+    rewrite(optimize_children(q, projected), projected)
+}
+
+// Optimizes the queries nested in `q`, leaving `q` itself as-is.
+fn optimize_children(q: Query, projected: bool) -> Query {
+    match q {
+        Query::Resume(mut q) => {
+            // The resumed query isn't rewritten itself, since that could
+            // change whether it's resumable.
+            q.inner = Box::new(optimize_children(*q.inner, projected));
+            Query::Resume(q)
+        }
+        Query::Project(mut q) => {
+            q.inner = Box::new(optimize(*q.inner, true));
+            Query::Project(q)
+        }
+        q => map_children(q, |q| optimize(q, projected)),
+    }
+}
+
+// Replaces each query nested directly in `q` with the result of `f`.
+pub(crate) fn map_children(q: Query, mut f: impl FnMut(Query) -> Query) -> Query {
+    match q {
+        Query::Pipe(mut q) => {
+            q.inner = Box::new(f(*q.inner));
+            Query::Pipe(q)
+        }
+        Query::PipeProperty(mut q) => {
+            q.inner = Box::new(f(*q.inner));
+            Query::PipeProperty(q)
+        }
+        Query::PipeWithPropertyPresence(mut q) => {
+            q.inner = Box::new(f(*q.inner));
+            Query::PipeWithPropertyPresence(q)
+        }
+        Query::PipeWithPropertyValue(mut q) => {
+            q.inner = Box::new(f(*q.inner));
+            Query::PipeWithPropertyValue(q)
+        }
+        Query::PipeWithPropertyRange(mut q) => {
+            q.inner = Box::new(f(*q.inner));
+            Query::PipeWithPropertyRange(q)
+        }
+        Query::PipeWithPropertyPredicate(mut q) => {
+            q.inner = Box::new(f(*q.inner));
+            Query::PipeWithPropertyPredicate(q)
+        }
+        Query::Traversal(mut q) => {
+            q.inner = Box::new(f(*q.inner));
+            Query::Traversal(q)
+        }
+        Query::Resume(mut q) => {
+            q.inner = Box::new(f(*q.inner));
+            Query::Resume(q)
+        }
+        Query::Union(mut q) => {
+            q.left = Box::new(f(*q.left));
+            q.right = Box::new(f(*q.right));
+            Query::Union(q)
+        }
+        Query::Intersect(mut q) => {
+            q.left = Box::new(f(*q.left));
+            q.right = Box::new(f(*q.right));
+            Query::Intersect(q)
+        }
+        Query::Except(mut q) => {
+            q.left = Box::new(f(*q.left));
+            q.right = Box::new(f(*q.right));
+            Query::Except(q)
+        }
+        Query::Distinct(mut q) => {
+            q.inner = Box::new(f(*q.inner));
+            Query::Distinct(q)
+        }
+        Query::OrderBy(mut q) => {
+            q.inner = Box::new(f(*q.inner));
+            Query::OrderBy(q)
+        }
+        Query::Include(mut q) => {
+            q.inner = Box::new(f(*q.inner));
+            Query::Include(q)
+        }
+        Query::Count(mut q) => {
+            q.inner = Box::new(f(*q.inner));
+            Query::Count(q)
+        }
+        Query::Aggregate(mut q) => {
+            q.inner = Box::new(f(*q.inner));
+            Query::Aggregate(q)
+        }
+        Query::Degree(mut q) => {
+            q.inner = Box::new(f(*q.inner));
+            Query::Degree(q)
+        }
+        Query::Project(mut q) => {
+            q.inner = Box::new(f(*q.inner));
+            Query::Project(q)
+        }
+        Query::Subgraph(mut q) => {
+            q.inner = Box::new(f(*q.inner));
+            Query::Subgraph(q)
+        }
+        q => q,
+    }
+}
+
+// Applies the rewrite rules to `q`, assuming the queries nested in it have
+// already been optimized.
+fn rewrite(q: Query, projected: bool) -> Query {
+    match q {
+        Query::RangeVertex(q)
+            if q.limit == u32::MAX && q.t.is_none() && q.start_id.is_none() && q.limit_param.is_none() =>
+        {
+            Query::AllVertex
+        }
+        // The type index is sorted by outbound ID and then inbound ID, like
+        // the outbound edges of all vertices are. Projections output a
+        // column per piped query, so the vertices can't be skipped there.
+        Query::Pipe(q)
+            if !projected
+                && matches!(*q.inner, Query::AllVertex)
+                && q.direction == EdgeDirection::Outbound
+                && q.t.is_some()
+                && q.limit_param.is_none() =>
+        {
+            EdgesByTypeQuery {
+                t: q.t.unwrap(),
+                limit: q.limit,
+                start: None,
+            }
+            .into()
+        }
+        // Property indexes return results sorted the same way as full
+        // scans, so these rewrites don't change the order of results.
+        Query::PipeWithPropertyPresence(q) if q.exists && !q.allow_scan => match *q.inner {
+            Query::AllVertex => VertexWithPropertyPresenceQuery::new(q.name).into(),
+            Query::AllEdge => EdgeWithPropertyPresenceQuery::new(q.name).into(),
+            inner => PipeWithPropertyPresenceQuery {
+                inner: Box::new(inner),
+                ..q
+            }
+            .into(),
+        },
+        Query::PipeWithPropertyValue(q) if q.equal && !q.allow_scan => match *q.inner {
+            Query::AllVertex => VertexWithPropertyValueQuery {
+                name: q.name,
+                value: q.value,
+                value_param: q.value_param,
+            }
+            .into(),
+            Query::AllEdge => EdgeWithPropertyValueQuery {
+                name: q.name,
+                value: q.value,
+                value_param: q.value_param,
+            }
+            .into(),
+            inner => PipeWithPropertyValueQuery {
+                inner: Box::new(inner),
+                ..q
+            }
+            .into(),
+        },
+        Query::PipeWithPropertyRange(q) => match *q.inner {
+            Query::AllVertex => VertexWithPropertyRangeQuery {
+                name: q.name,
+                low: q.low,
+                high: q.high,
+            }
+            .into(),
+            Query::AllEdge => EdgeWithPropertyRangeQuery {
+                name: q.name,
+                low: q.low,
+                high: q.high,
+            }
+            .into(),
+            inner => PipeWithPropertyRangeQuery {
+                inner: Box::new(inner),
+                ..q
+            }
+            .into(),
+        },
+        Query::Distinct(q) if is_distinct(&q.inner) => *q.inner,
+        // Includes don't re-export the values of included queries, so
+        // including a query twice is the same as including it once.
+        Query::Include(q) if matches!(*q.inner, Query::Include(_)) => *q.inner,
+        q => q,
+    }
+}
+
+// Checks whether a query can never output duplicate values.
+fn is_distinct(q: &Query) -> bool {
+    matches!(
+        q,
+        Query::AllVertex
+            | Query::RangeVertex(_)
+            | Query::VertexWithPropertyPresence(_)
+            | Query::VertexWithPropertyValue(_)
+            | Query::VertexWithPropertyRange(_)
+            | Query::AllEdge
+            | Query::EdgeWithPropertyPresence(_)
+            | Query::EdgeWithPropertyValue(_)
+            | Query::EdgeWithPropertyRange(_)
+            | Query::EdgesByType(_)
+            | Query::Union(_)
+            | Query::Intersect(_)
+            | Query::Except(_)
+            | Query::Distinct(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ijson, AllEdgeQuery, AllVertexQuery, CountQueryExt, Identifier, Query, QueryExt, RangeVertexQuery,
+        SpecificVertexQuery, VertexWithPropertyValueQuery,
+    };
+
+    #[test]
+    fn should_rewrite_nested_queries() {
+        let name = Identifier::new("foo").unwrap();
+        let q: Query = RangeVertexQuery::new()
+            .with_property_equal_to(name, ijson!(1))
+            .unwrap()
+            .outbound()
+            .unwrap()
+            .count()
+            .unwrap()
+            .into();
+        let expected: Query = VertexWithPropertyValueQuery::new(name, ijson!(1))
+            .outbound()
+            .unwrap()
+            .count()
+            .unwrap()
+            .into();
+        assert_eq!(q.optimize(), expected);
+    }
+
+    #[test]
+    fn should_not_rewrite_unindexable_queries() {
+        let name = Identifier::new("foo").unwrap();
+        let queries: Vec<Query> = vec![
+            AllVertexQuery
+                .with_property_not_equal_to(name, ijson!(1))
+                .unwrap()
+                .into(),
+            AllEdgeQuery.without_property(name).unwrap().into(),
+            AllEdgeQuery.with_property(name).unwrap().allow_scan().into(),
+            AllVertexQuery.include().with_property(name).unwrap().into(),
+            RangeVertexQuery::new().limit(1).into(),
+            SpecificVertexQuery::new(Vec::new()).distinct().unwrap().into(),
+            AllVertexQuery.outbound().unwrap().t(name).project().unwrap().into(),
+        ];
+        for q in queries {
+            assert_eq!(q.clone().optimize(), q);
+        }
+    }
+}
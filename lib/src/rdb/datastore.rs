@@ -1,34 +1,116 @@
 use std::collections::HashSet;
 use std::i32;
+use std::ops::Bound;
 use std::path::Path;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 use std::u64;
 
 use super::managers::*;
+use super::store::Store;
 use crate::errors::Result;
-use crate::{BulkInsertItem, Database, Datastore, DynIter, Edge, Identifier, Json, Transaction, Vertex};
+use crate::{BulkInsertItem, Database, Datastore, DynIter, Edge, EdgeDirection, Identifier, Json, Transaction, Vertex};
 
-use rocksdb::{DBCompactionStyle, Options, WriteBatch, DB};
+use rocksdb::{DBCompactionStyle, Options, DB};
 use uuid::Uuid;
 
-const CF_NAMES: [&str; 8] = [
+const CF_NAMES: [&str; 12] = [
     "vertices:v2",
+    "vertex_types:v1",
     "edge_ranges:v2",
     "reversed_edge_ranges:v2",
+    "edge_types:v1",
+    "outbound_degrees:v1",
+    "inbound_degrees:v1",
     "vertex_properties:v2",
     "edge_properties:v2",
-    "vertex_property_values:v2",
-    "edge_property_values:v2",
+    "vertex_property_values:v3",
+    "edge_property_values:v3",
     "metadata:v2",
 ];
 
+// Column families from older versions of the datastore. Property value
+// indices used to be keyed on a hash of the value; when these are found, the
+// indices are rebuilt with an order-preserving key encoding.
+const LEGACY_CF_NAMES: [&str; 2] = ["vertex_property_values:v2", "edge_property_values:v2"];
+
+// Writes the property value index entries for all vertices and edges that have
+// a given property.
+fn build_property_value_index(db: &Store, name: Identifier) -> Result<()> {
+    let vertex_manager = VertexManager::new(db);
+    let edge_range_manager = EdgeRangeManager::new(db);
+    let vertex_property_manager = VertexPropertyManager::new(db);
+    let edge_property_manager = EdgePropertyManager::new(db);
+    let vertex_property_value_manager = VertexPropertyValueManager::new(db);
+    let edge_property_value_manager = EdgePropertyValueManager::new(db);
+
+    for item in vertex_manager.iterate_for_range(Uuid::default()) {
+        let vertex = item?;
+        if let Some(property_value) = vertex_property_manager.get(vertex.id, name)? {
+            vertex_property_value_manager.set(vertex.id, name, &property_value);
+        }
+    }
+
+    for item in edge_range_manager.iterate_for_all() {
+        let edge = item?;
+        if let Some(property_value) = edge_property_manager.get(&edge, name)? {
+            edge_property_value_manager.set(&edge, name, &property_value);
+        }
+    }
+
+    Ok(())
+}
+
+// Writes the vertex type index entries for all vertices. This is used to
+// build the index for databases created before it existed.
+fn build_vertex_type_index(db: &Store) -> Result<()> {
+    let vertex_manager = VertexManager::new(db);
+    let vertex_type_manager = VertexTypeManager::new(db);
+    for item in vertex_manager.iterate_for_range(Uuid::default()) {
+        vertex_type_manager.set(&item?);
+    }
+    Ok(())
+}
+
+// Writes the edge type index entries for all edges. This is used to build
+// the index for databases created before it existed.
+fn build_edge_type_index(db: &Store) -> Result<()> {
+    let edge_range_manager = EdgeRangeManager::new(db);
+    let edge_type_manager = EdgeTypeManager::new(db);
+    for item in edge_range_manager.iterate_for_all() {
+        edge_type_manager.set(&item?);
+    }
+    Ok(())
+}
+
+// Writes the degree counters for all edges. This is used when counters are
+// enabled on an existing database.
+fn build_degree_counters(db: &Store) -> Result<()> {
+    let edge_range_manager = EdgeRangeManager::new(db);
+    let outbound_degree_manager = DegreeManager::new(db);
+    let inbound_degree_manager = DegreeManager::new_inbound(db);
+    for item in edge_range_manager.iterate_for_all() {
+        let edge = item?;
+        outbound_degree_manager.add(edge.outbound_id, edge.t, 1);
+        inbound_degree_manager.add(edge.inbound_id, edge.t, 1);
+    }
+    Ok(())
+}
+
 pub struct RocksdbTransaction<'a> {
-    db: &'a DB,
+    db: Store<'a>,
     indexed_properties: Arc<RwLock<HashSet<Identifier>>>,
+    // Properties indexed in this transaction, which are only shared with
+    // other transactions once it's committed.
+    new_indexed_properties: HashSet<Identifier>,
+    degree_counters: bool,
     vertex_manager: VertexManager<'a>,
+    vertex_type_manager: VertexTypeManager<'a>,
     edge_manager: EdgeManager<'a>,
     edge_range_manager: EdgeRangeManager<'a>,
     reversed_edge_range_manager: EdgeRangeManager<'a>,
+    edge_type_manager: EdgeTypeManager<'a>,
+    outbound_degree_manager: DegreeManager<'a>,
+    inbound_degree_manager: DegreeManager<'a>,
     vertex_property_manager: VertexPropertyManager<'a>,
     edge_property_manager: EdgePropertyManager<'a>,
     vertex_property_value_manager: VertexPropertyValueManager<'a>,
@@ -37,6 +119,18 @@ pub struct RocksdbTransaction<'a> {
 }
 
 impl<'a> RocksdbTransaction<'a> {
+    fn is_indexed(&self, name: Identifier) -> bool {
+        self.new_indexed_properties.contains(&name) || self.indexed_properties.read().unwrap().contains(&name)
+    }
+
+    // Gets the indexed properties, including the ones indexed in this
+    // transaction.
+    fn indexed_properties(&self) -> HashSet<Identifier> {
+        let mut indexed_properties = self.indexed_properties.read().unwrap().clone();
+        indexed_properties.extend(&self.new_indexed_properties);
+        indexed_properties
+    }
+
     fn vertex_ids_from_property_value_iterator(
         &'a self,
         iter: impl Iterator<Item = Result<VertexPropertyValueKey>> + 'a,
@@ -50,6 +144,18 @@ impl<'a> RocksdbTransaction<'a> {
             Err(err) => Some(Err(err)),
         })
     }
+
+    // Adds `delta` to the degree counters of both ends of each edge. This
+    // is a no-op unless counters are enabled, so callers need to make sure
+    // each edge is only passed once per change.
+    fn update_degrees<'b>(&self, edges: impl IntoIterator<Item = &'b Edge>, delta: i64) {
+        if self.degree_counters {
+            for edge in edges {
+                self.outbound_degree_manager.add(edge.outbound_id, edge.t, delta);
+                self.inbound_degree_manager.add(edge.inbound_id, edge.t, delta);
+            }
+        }
+    }
 }
 
 impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
@@ -78,8 +184,30 @@ impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
         Ok(Box::new(iter))
     }
 
+    fn vertices_with_type(&'a self, t: Identifier) -> Result<DynIter<'a, Vertex>> {
+        self.range_vertices_with_type(t, Uuid::default())
+    }
+
+    fn range_vertices_with_type(&'a self, t: Identifier, offset: Uuid) -> Result<DynIter<'a, Vertex>> {
+        // Bulk inserts can overwrite a vertex with one of a different type
+        // without clearing the old index entry, so entries are checked
+        // against the vertex itself
+        let iter = self
+            .vertex_type_manager
+            .iterate_for_range(t, offset)
+            .filter_map(move |item| match item {
+                Ok(vertex) => match self.vertex_manager.get(vertex.id) {
+                    Ok(Some(t)) if t == vertex.t => Some(Ok(vertex)),
+                    Ok(_) => None,
+                    Err(err) => Some(Err(err)),
+                },
+                Err(err) => Some(Err(err)),
+            });
+        Ok(Box::new(iter))
+    }
+
     fn vertex_ids_with_property(&'a self, name: Identifier) -> Result<Option<DynIter<'a, Uuid>>> {
-        if self.indexed_properties.read().unwrap().contains(&name) {
+        if self.is_indexed(name) {
             let iter = self.vertex_property_value_manager.iterate_for_name(name);
             let iter = self.vertex_ids_from_property_value_iterator(iter);
             Ok(Some(Box::new(iter)))
@@ -89,7 +217,7 @@ impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
     }
 
     fn vertex_ids_with_property_value(&'a self, name: Identifier, value: &Json) -> Result<Option<DynIter<'a, Uuid>>> {
-        if self.indexed_properties.read().unwrap().contains(&name) {
+        if self.is_indexed(name) {
             let iter = self.vertex_property_value_manager.iterate_for_value(name, value);
             let iter = self.vertex_ids_from_property_value_iterator(iter);
             Ok(Some(Box::new(iter)))
@@ -98,6 +226,21 @@ impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
         }
     }
 
+    fn vertex_ids_with_property_range(
+        &'a self,
+        name: Identifier,
+        low: Bound<&Json>,
+        high: Bound<&Json>,
+    ) -> Result<Option<DynIter<'a, Uuid>>> {
+        if self.is_indexed(name) {
+            let iter = self.vertex_property_value_manager.iterate_for_range(name, low, high);
+            let iter = self.vertex_ids_from_property_value_iterator(iter);
+            Ok(Some(Box::new(iter)))
+        } else {
+            Ok(None)
+        }
+    }
+
     fn edge_count(&self) -> u64 {
         let iter = self.edge_range_manager.iterate_for_all();
         iter.count() as u64
@@ -134,8 +277,41 @@ impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
         Ok(Box::new(iter))
     }
 
+    fn vertex_degree(&'a self, id: Uuid, direction: EdgeDirection, t: Option<Identifier>) -> Result<u64> {
+        let (degree_manager, edge_range_manager) = match direction {
+            EdgeDirection::Outbound => (&self.outbound_degree_manager, &self.edge_range_manager),
+            EdgeDirection::Inbound => (&self.inbound_degree_manager, &self.reversed_edge_range_manager),
+            EdgeDirection::Both => {
+                return Ok(self.vertex_degree(id, EdgeDirection::Outbound, t)?
+                    + self.vertex_degree(id, EdgeDirection::Inbound, t)?)
+            }
+        };
+        if self.degree_counters {
+            return degree_manager.get(id, t);
+        }
+        let mut count = 0;
+        for item in edge_range_manager.iterate_for_root(id, t)? {
+            item?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn edges_with_type(&'a self, t: Identifier) -> Result<DynIter<'a, Edge>> {
+        self.range_edges_with_type(Edge::new(Uuid::default(), t, Uuid::default()))
+    }
+
+    fn range_edges_with_type(&'a self, offset: Edge) -> Result<DynIter<'a, Edge>> {
+        let iter = self.edge_type_manager.iterate_for_range(&offset);
+        Ok(Box::new(iter))
+    }
+
+    fn edge_count_with_type(&'a self, t: Identifier) -> Result<u64> {
+        self.edge_type_manager.count_for_type(t)
+    }
+
     fn edges_with_property(&'a self, name: Identifier) -> Result<Option<DynIter<'a, Edge>>> {
-        if self.indexed_properties.read().unwrap().contains(&name) {
+        if self.is_indexed(name) {
             let iter = self
                 .edge_property_value_manager
                 .iterate_for_name(name)
@@ -150,7 +326,7 @@ impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
     }
 
     fn edges_with_property_value(&'a self, name: Identifier, value: &Json) -> Result<Option<DynIter<'a, Edge>>> {
-        if self.indexed_properties.read().unwrap().contains(&name) {
+        if self.is_indexed(name) {
             let iter = self
                 .edge_property_value_manager
                 .iterate_for_value(name, value)
@@ -164,6 +340,26 @@ impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
         }
     }
 
+    fn edges_with_property_range(
+        &'a self,
+        name: Identifier,
+        low: Bound<&Json>,
+        high: Bound<&Json>,
+    ) -> Result<Option<DynIter<'a, Edge>>> {
+        if self.is_indexed(name) {
+            let iter = self
+                .edge_property_value_manager
+                .iterate_for_range(name, low, high)
+                .map(|r| match r {
+                    Ok((_, _, e)) => Ok(e),
+                    Err(err) => Err(err),
+                });
+            Ok(Some(Box::new(iter)))
+        } else {
+            Ok(None)
+        }
+    }
+
     fn vertex_property(&self, vertex: &Vertex, name: Identifier) -> Result<Option<Json>> {
         match self.vertex_property_manager.get(vertex.id, name)? {
             None => Ok(None),
@@ -193,61 +389,69 @@ impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
     }
 
     fn delete_vertices(&mut self, vertices: Vec<Vertex>) -> Result<()> {
-        let indexed_properties = self.indexed_properties.read().unwrap();
-        let mut batch = WriteBatch::default();
+        let indexed_properties = self.indexed_properties();
+
+        if self.degree_counters {
+            // Edges between deleted vertices are found from both ends, so
+            // they're collected first to only be counted once
+            let mut edges = HashSet::new();
+            for vertex in &vertices {
+                for item in self.edge_range_manager.iterate_for_root(vertex.id, None)? {
+                    edges.insert(item?);
+                }
+                for item in self.reversed_edge_range_manager.iterate_for_root(vertex.id, None)? {
+                    edges.insert(item?.reversed());
+                }
+            }
+            self.update_degrees(&edges, -1);
+        }
 
         for vertex in vertices.into_iter() {
-            self.vertex_manager.delete(&mut batch, &indexed_properties, vertex.id)?;
+            self.vertex_manager.delete(&indexed_properties, vertex.id)?;
         }
-
-        self.db.write(batch)?;
         Ok(())
     }
 
     fn delete_edges(&mut self, edges: Vec<Edge>) -> Result<()> {
-        let indexed_properties = self.indexed_properties.read().unwrap();
-        let mut batch = WriteBatch::default();
+        let indexed_properties = self.indexed_properties();
 
         for edge in edges.into_iter() {
             if self.vertex_manager.get(edge.outbound_id)?.is_some() {
-                self.edge_manager.delete(&mut batch, &indexed_properties, &edge)?;
+                if self.degree_counters && self.edge_range_manager.contains(&edge)? {
+                    self.update_degrees([&edge], -1);
+                }
+                self.edge_manager.delete(&indexed_properties, &edge)?;
             };
         }
-
-        self.db.write(batch)?;
         Ok(())
     }
 
     fn delete_vertex_properties(&mut self, props: Vec<(Uuid, Identifier)>) -> Result<()> {
-        let indexed_properties = self.indexed_properties.read().unwrap();
-        let mut batch = WriteBatch::default();
+        let indexed_properties = self.indexed_properties();
 
         for (id, name) in props.into_iter() {
-            self.vertex_property_manager
-                .delete(&mut batch, &indexed_properties, id, name)?;
+            self.vertex_property_manager.delete(&indexed_properties, id, name)?;
         }
-
-        self.db.write(batch)?;
         Ok(())
     }
 
     fn delete_edge_properties(&mut self, props: Vec<(Edge, Identifier)>) -> Result<()> {
-        let indexed_properties = self.indexed_properties.read().unwrap();
-        let mut batch = WriteBatch::default();
+        let indexed_properties = self.indexed_properties();
 
         for (edge, name) in props.into_iter() {
-            self.edge_property_manager
-                .delete(&mut batch, &indexed_properties, &edge, name)?;
+            self.edge_property_manager.delete(&indexed_properties, &edge, name)?;
         }
-
-        self.db.write(batch)?;
         Ok(())
     }
 
     fn sync(&self) -> Result<()> {
         self.vertex_manager.compact();
+        self.vertex_type_manager.compact();
         self.edge_range_manager.compact();
         self.edge_range_manager.compact();
+        self.edge_type_manager.compact();
+        self.outbound_degree_manager.compact();
+        self.inbound_degree_manager.compact();
         self.vertex_property_manager.compact();
         self.edge_property_manager.compact();
         self.vertex_property_value_manager.compact();
@@ -257,13 +461,35 @@ impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
         Ok(())
     }
 
+    fn commit(&mut self) -> Result<()> {
+        if self.new_indexed_properties.is_empty() {
+            self.db.commit()?;
+            return Ok(());
+        }
+
+        // The indexed properties are locked until the commit is done, so
+        // that properties indexed by transactions committed at the same time
+        // are all recorded.
+        let mut indexed_properties = self.indexed_properties.write().unwrap();
+        let mut all_indexed_properties = indexed_properties.clone();
+        all_indexed_properties.extend(&self.new_indexed_properties);
+        self.metadata_manager.set_indexed_properties(&all_indexed_properties)?;
+        self.db.commit()?;
+        indexed_properties.extend(self.new_indexed_properties.drain());
+        Ok(())
+    }
+
+    fn rollback(&mut self) -> Result<()> {
+        self.db.rollback();
+        self.new_indexed_properties.clear();
+        Ok(())
+    }
+
     fn create_vertex(&mut self, vertex: &Vertex) -> Result<bool> {
         if self.vertex_manager.exists(vertex.id)? {
             Ok(false)
         } else {
-            let mut batch = WriteBatch::default();
-            self.vertex_manager.create(&mut batch, vertex)?;
-            self.db.write(batch)?;
+            self.vertex_manager.create(vertex)?;
             Ok(true)
         }
     }
@@ -272,91 +498,76 @@ impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
         if !self.vertex_manager.exists(edge.outbound_id)? || !self.vertex_manager.exists(edge.inbound_id)? {
             Ok(false)
         } else {
-            let mut batch = WriteBatch::default();
-            self.edge_manager.set(&mut batch, edge)?;
-            self.db.write(batch)?;
+            if self.degree_counters && !self.edge_range_manager.contains(edge)? {
+                self.update_degrees([edge], 1);
+            }
+            self.edge_manager.set(edge)?;
             Ok(true)
         }
     }
 
     // We override the default `bulk_insert` implementation because further
-    // optimization can be done by using `WriteBatch`s.
+    // optimization can be done by skipping the checks of whether vertices
+    // exist. Within a transaction, the writes are still held in memory until
+    // it's committed; `Database::bulk_insert` avoids that by writing to a
+    // `WriteBatch` directly.
     fn bulk_insert(&mut self, items: Vec<BulkInsertItem>) -> Result<()> {
-        let indexed_properties = self.indexed_properties.read().unwrap();
-        let mut batch = WriteBatch::default();
+        let indexed_properties = self.indexed_properties();
+        // Stores that write directly don't read their own writes, so edges
+        // inserted more than once are tracked here to only count them once
+        let mut counted_edges = HashSet::new();
 
         for item in items {
             match item {
                 BulkInsertItem::Vertex(ref vertex) => {
-                    self.vertex_manager.create(&mut batch, vertex)?;
+                    self.vertex_manager.create(vertex)?;
                 }
                 BulkInsertItem::Edge(ref edge) => {
-                    self.edge_manager.set(&mut batch, edge)?;
+                    if self.degree_counters
+                        && !counted_edges.contains(edge)
+                        && !self.edge_range_manager.contains(edge)?
+                    {
+                        self.update_degrees([edge], 1);
+                        counted_edges.insert(edge.clone());
+                    }
+                    self.edge_manager.set(edge)?;
                 }
                 BulkInsertItem::VertexProperty(id, ref name, ref value) => {
                     self.vertex_property_manager
-                        .set(&mut batch, &indexed_properties, id, *name, value)?;
+                        .set(&indexed_properties, id, *name, value)?;
                 }
                 BulkInsertItem::EdgeProperty(ref edge, ref name, ref value) => {
                     self.edge_property_manager
-                        .set(&mut batch, &indexed_properties, edge, *name, value)?;
+                        .set(&indexed_properties, edge, *name, value)?;
                 }
             }
         }
-
-        self.db.write(batch)?;
         Ok(())
     }
 
     fn index_property(&mut self, name: Identifier) -> Result<()> {
-        let mut indexed_properties = self.indexed_properties.write().unwrap();
-        if !indexed_properties.insert(name) {
+        if self.is_indexed(name) {
             return Ok(());
         }
-
-        let mut batch = WriteBatch::default();
-        self.metadata_manager
-            .set_indexed_properties(&mut batch, &indexed_properties)?;
-
-        for item in self.vertex_manager.iterate_for_range(Uuid::default()) {
-            let vertex = item?;
-            if let Some(property_value) = self.vertex_property_manager.get(vertex.id, name)? {
-                self.vertex_property_value_manager
-                    .set(&mut batch, vertex.id, name, &property_value);
-            }
-        }
-
-        for item in self.edge_range_manager.iterate_for_all() {
-            let edge = item?;
-            if let Some(property_value) = self.edge_property_manager.get(&edge, name)? {
-                self.edge_property_value_manager
-                    .set(&mut batch, &edge, name, &property_value);
-            }
-        }
-
-        self.db.write(batch)?;
+        build_property_value_index(&self.db, name)?;
+        self.new_indexed_properties.insert(name);
         Ok(())
     }
 
     fn set_vertex_properties(&mut self, vertices: Vec<Uuid>, name: Identifier, value: &Json) -> Result<()> {
-        let indexed_properties = self.indexed_properties.read().unwrap();
-        let mut batch = WriteBatch::default();
+        let indexed_properties = self.indexed_properties();
         for id in vertices.into_iter() {
-            self.vertex_property_manager
-                .set(&mut batch, &indexed_properties, id, name, value)?;
+            self.vertex_property_manager.set(&indexed_properties, id, name, value)?;
         }
-        self.db.write(batch)?;
         Ok(())
     }
 
     fn set_edge_properties(&mut self, edges: Vec<Edge>, name: Identifier, value: &Json) -> Result<()> {
-        let indexed_properties = self.indexed_properties.read().unwrap();
-        let mut batch = WriteBatch::default();
+        let indexed_properties = self.indexed_properties();
         for edge in edges.into_iter() {
             self.edge_property_manager
-                .set(&mut batch, &indexed_properties, &edge, name, value)?;
+                .set(&indexed_properties, &edge, name, value)?;
         }
-        self.db.write(batch)?;
         Ok(())
     }
 }
@@ -365,7 +576,10 @@ impl<'a> Transaction<'a> for RocksdbTransaction<'a> {
 #[derive(Debug)]
 pub struct RocksdbDatastore {
     db: Arc<DB>,
+    // Held while transactions are committed, see `Store`.
+    commit_lock: Arc<Mutex<()>>,
     indexed_properties: Arc<RwLock<HashSet<Identifier>>>,
+    degree_counters: bool,
 }
 
 impl RocksdbDatastore {
@@ -375,28 +589,7 @@ impl RocksdbDatastore {
     /// * `path`: The file path to the rocksdb database.
     pub fn new_db<P: AsRef<Path>>(path: P) -> Result<Database<RocksdbDatastore>> {
         let opts = RocksdbDatastore::get_options(None);
-        let path = path.as_ref();
-
-        let db = match DB::open_cf(&opts, path, CF_NAMES) {
-            Ok(db) => db,
-            Err(_) => {
-                let mut db = DB::open(&opts, path)?;
-
-                for cf_name in &CF_NAMES {
-                    db.create_cf(cf_name, &opts)?;
-                }
-
-                db
-            }
-        };
-
-        let metadata_manager = MetadataManager::new(&db);
-        let indexed_properties = metadata_manager.get_indexed_properties()?;
-
-        Ok(Database::new(RocksdbDatastore {
-            db: Arc::new(db),
-            indexed_properties: Arc::new(RwLock::new(indexed_properties)),
-        }))
+        RocksdbDatastore::open(path.as_ref(), &opts, false)
     }
 
     /// Creates a new rocksdb datastore with user-tuned rocksdb Option.
@@ -405,27 +598,105 @@ impl RocksdbDatastore {
     /// * `path`: The file path to the rocksdb database.
     /// * `opts`: The user-tuned rocksdb options.
     pub fn new_db_with_options<P: AsRef<Path>>(path: P, opts: &Options) -> Result<Database<RocksdbDatastore>> {
-        let path = path.as_ref();
+        RocksdbDatastore::open(path.as_ref(), opts, false)
+    }
+
+    /// Creates a new rocksdb datastore that keeps count of the edges going
+    /// out of and coming into each vertex, so that degree queries don't
+    /// need to iterate over edges. This costs extra writes whenever edges
+    /// are created or deleted. Once enabled on a database, the counters are
+    /// kept up to date however it's opened later.
+    ///
+    /// # Arguments
+    /// * `path`: The file path to the rocksdb database.
+    /// * `opts`: The user-tuned rocksdb options.
+    pub fn new_db_with_degree_counters<P: AsRef<Path>>(path: P, opts: &Options) -> Result<Database<RocksdbDatastore>> {
+        RocksdbDatastore::open(path.as_ref(), opts, true)
+    }
+
+    fn open(path: &Path, opts: &Options, degree_counters: bool) -> Result<Database<RocksdbDatastore>> {
+        // Degree counters are updated with merges, so the merge operator is
+        // needed whether or not counters are enabled.
+        let mut opts = opts.clone();
+        opts.set_merge_operator_associative("indradb_degrees", merge_degrees);
+        let opts = &opts;
+        let commit_lock = Mutex::new(());
 
         let db = match DB::open_cf(opts, path, CF_NAMES) {
             Ok(db) => db,
             Err(_) => {
-                let mut db = DB::open(opts, path)?;
+                // Either the database doesn't exist yet, or it was created
+                // with a different set of column families by an older
+                // version. Open it with whatever it has, and then create the
+                // missing column families.
+                let existing_cf_names = DB::list_cf(opts, path).unwrap_or_default();
+                let mut db = if existing_cf_names.is_empty() {
+                    DB::open(opts, path)?
+                } else {
+                    DB::open_cf(opts, path, &existing_cf_names)?
+                };
 
                 for cf_name in &CF_NAMES {
-                    db.create_cf(cf_name, opts)?;
+                    if !existing_cf_names.iter().any(|name| name == cf_name) {
+                        db.create_cf(cf_name, opts)?;
+                    }
+                }
+
+                // Rebuild the property value indices before dropping the
+                // legacy ones, so that an interrupted migration is retried
+                // the next time the database is opened.
+                let legacy_cf_names: Vec<&str> = LEGACY_CF_NAMES
+                    .into_iter()
+                    .filter(|cf_name| existing_cf_names.iter().any(|name| name == cf_name))
+                    .collect();
+                if !legacy_cf_names.is_empty() {
+                    let store = Store::new(&db, &commit_lock);
+                    let metadata_manager = MetadataManager::new(&store);
+                    for name in metadata_manager.get_indexed_properties()? {
+                        build_property_value_index(&store, name)?;
+                    }
+                    store.commit()?;
+                    for cf_name in legacy_cf_names {
+                        db.drop_cf(cf_name)?;
+                    }
                 }
 
                 db
             }
         };
 
-        let metadata_manager = MetadataManager::new(&db);
+        // Build the type indices for databases created before they existed.
+        // Each index and the marker that it's been built are written
+        // together, so an interrupted build is retried the next time the
+        // database is opened.
+        let store = Store::new(&db, &commit_lock);
+        let metadata_manager = MetadataManager::new(&store);
+        if !metadata_manager.has_vertex_type_index()? {
+            build_vertex_type_index(&store)?;
+            metadata_manager.set_has_vertex_type_index();
+            store.commit()?;
+        }
+        if !metadata_manager.has_edge_type_index()? {
+            build_edge_type_index(&store)?;
+            metadata_manager.set_has_edge_type_index();
+            store.commit()?;
+        }
+
+        // Degree counters are built in the same way, but only when asked to.
+        let has_degree_counters = metadata_manager.has_degree_counters()?;
+        if degree_counters && !has_degree_counters {
+            build_degree_counters(&store)?;
+            metadata_manager.set_has_degree_counters();
+            store.commit()?;
+        }
+
         let indexed_properties = metadata_manager.get_indexed_properties()?;
 
         Ok(Database::new(RocksdbDatastore {
             db: Arc::new(db),
+            commit_lock: Arc::new(commit_lock),
             indexed_properties: Arc::new(RwLock::new(indexed_properties)),
+            degree_counters: degree_counters || has_degree_counters,
         }))
     }
 
@@ -468,23 +739,46 @@ impl RocksdbDatastore {
 
         opts
     }
-}
 
-impl Datastore for RocksdbDatastore {
-    type Transaction<'a> = RocksdbTransaction<'a> where Self: 'a;
-    fn transaction(&'_ self) -> Self::Transaction<'_> {
+    fn transaction_with_store<'a>(&'a self, db: Store<'a>) -> RocksdbTransaction<'a> {
         RocksdbTransaction {
-            db: &self.db,
             indexed_properties: self.indexed_properties.clone(),
-            vertex_manager: VertexManager::new(&self.db),
-            edge_manager: EdgeManager::new(&self.db),
-            edge_range_manager: EdgeRangeManager::new(&self.db),
-            reversed_edge_range_manager: EdgeRangeManager::new_reversed(&self.db),
-            vertex_property_manager: VertexPropertyManager::new(&self.db),
-            edge_property_manager: EdgePropertyManager::new(&self.db),
-            vertex_property_value_manager: VertexPropertyValueManager::new(&self.db),
-            edge_property_value_manager: EdgePropertyValueManager::new(&self.db),
-            metadata_manager: MetadataManager::new(&self.db),
+            new_indexed_properties: HashSet::new(),
+            degree_counters: self.degree_counters,
+            vertex_manager: VertexManager::new(&db),
+            vertex_type_manager: VertexTypeManager::new(&db),
+            edge_manager: EdgeManager::new(&db),
+            edge_range_manager: EdgeRangeManager::new(&db),
+            reversed_edge_range_manager: EdgeRangeManager::new_reversed(&db),
+            edge_type_manager: EdgeTypeManager::new(&db),
+            outbound_degree_manager: DegreeManager::new(&db),
+            inbound_degree_manager: DegreeManager::new_inbound(&db),
+            vertex_property_manager: VertexPropertyManager::new(&db),
+            edge_property_manager: EdgePropertyManager::new(&db),
+            vertex_property_value_manager: VertexPropertyValueManager::new(&db),
+            edge_property_value_manager: EdgePropertyValueManager::new(&db),
+            metadata_manager: MetadataManager::new(&db),
+            db,
         }
     }
 }
+
+impl Datastore for RocksdbDatastore {
+    type Transaction<'a>
+        = RocksdbTransaction<'a>
+    where
+        Self: 'a;
+    fn transaction(&'_ self) -> Self::Transaction<'_> {
+        self.transaction_with_store(Store::new(&self.db, &self.commit_lock))
+    }
+
+    // Bulk inserts write to a `WriteBatch` directly, rather than holding
+    // every write in memory until they're committed. Other transactions
+    // can't be committed until it's done, so it can't conflict with them.
+    fn bulk_insert(&self, items: Vec<BulkInsertItem>) -> Result<()> {
+        let _lock = self.commit_lock.lock().unwrap();
+        let mut txn = self.transaction_with_store(Store::direct(&self.db, &self.commit_lock));
+        txn.bulk_insert(items)?;
+        txn.commit()
+    }
+}
@@ -1,6 +1,7 @@
+use std::cmp::Ordering;
 use std::collections::HashSet;
 use std::io::Cursor;
-use std::ops::Deref;
+use std::ops::{Bound, Deref};
 use std::result::Result as StdResult;
 use std::u8;
 
@@ -8,16 +9,18 @@ use crate::errors::Result;
 use crate::models;
 use crate::util;
 
-use rocksdb::{ColumnFamilyRef, DBIterator, Direction, IteratorMode, WriteBatch, DB};
+use super::store::{Store, StoreIterator};
+
+use rocksdb::MergeOperands;
 use uuid::Uuid;
 
 pub type OwnedPropertyItem = (Uuid, models::Identifier, models::Json);
 pub type EdgePropertyItem = (models::Edge, models::Identifier, models::Json);
-pub type VertexPropertyValueKey = (models::Identifier, u64, Uuid);
-pub type EdgePropertyValueKey = (models::Identifier, u64, models::Edge);
+pub type VertexPropertyValueKey = (models::Identifier, models::Json, Uuid);
+pub type EdgePropertyValueKey = (models::Identifier, models::Json, models::Edge);
 
 fn take_with_prefix(
-    iterator: DBIterator<'_>,
+    iterator: StoreIterator<'_>,
     prefix: Vec<u8>,
 ) -> impl Iterator<Item = StdResult<(Box<[u8]>, Box<[u8]>), rocksdb::Error>> + '_ {
     iterator.take_while(move |item| -> bool {
@@ -29,16 +32,58 @@ fn take_with_prefix(
     })
 }
 
+// Builds the keys needed to iterate over a range of property values. Returns
+// the prefix that all keys in the range share (the property name, and the
+// JSON type tag of the range's bounds), and the key to start iterating from.
+fn range_keys(
+    property_name: models::Identifier,
+    low: Bound<&models::Json>,
+    high: Bound<&models::Json>,
+) -> (Vec<u8>, Vec<u8>) {
+    let name_prefix = util::build(&[util::Component::Identifier(property_name)]);
+    let prefix = match (low, high) {
+        (Bound::Included(v) | Bound::Excluded(v), _) | (_, Bound::Included(v) | Bound::Excluded(v)) => {
+            let mut prefix = util::build(&[util::Component::Identifier(property_name), util::Component::Json(v)]);
+            prefix.truncate(name_prefix.len() + 1);
+            prefix
+        }
+        _ => name_prefix,
+    };
+    let low_key = match low {
+        Bound::Included(v) | Bound::Excluded(v) => {
+            util::build(&[util::Component::Identifier(property_name), util::Component::Json(v)])
+        }
+        Bound::Unbounded => prefix.clone(),
+    };
+    (prefix, low_key)
+}
+
+fn is_below_range(value: &models::Json, low: &Bound<models::Json>) -> bool {
+    match low {
+        Bound::Included(low) => value.total_cmp(low) == Ordering::Less,
+        Bound::Excluded(low) => value.total_cmp(low) != Ordering::Greater,
+        Bound::Unbounded => false,
+    }
+}
+
+fn is_above_range(value: &models::Json, high: &Bound<models::Json>) -> bool {
+    match high {
+        Bound::Included(high) => value.total_cmp(high) == Ordering::Greater,
+        Bound::Excluded(high) => value.total_cmp(high) != Ordering::Less,
+        Bound::Unbounded => false,
+    }
+}
+
 pub(crate) struct VertexManager<'a> {
-    db: &'a DB,
-    cf: ColumnFamilyRef<'a>,
+    db: Store<'a>,
+    cf: &'static str,
 }
 
 impl<'a> VertexManager<'a> {
-    pub fn new(db: &'a DB) -> Self {
+    pub fn new(db: &Store<'a>) -> Self {
         VertexManager {
-            db,
-            cf: db.cf_handle("vertices:v2").unwrap(),
+            db: db.clone(),
+            cf: "vertices:v2",
         }
     }
 
@@ -47,11 +92,11 @@ impl<'a> VertexManager<'a> {
     }
 
     pub fn exists(&self, id: Uuid) -> Result<bool> {
-        Ok(self.db.get_cf(&self.cf, self.key(id))?.is_some())
+        Ok(self.db.get(self.cf, self.key(id))?.is_some())
     }
 
     pub fn get(&self, id: Uuid) -> Result<Option<models::Identifier>> {
-        match self.db.get_cf(&self.cf, self.key(id))? {
+        match self.db.get(self.cf, self.key(id))? {
             Some(value_bytes) => {
                 let mut cursor = Cursor::new(value_bytes.deref());
                 Ok(Some(util::read_identifier(&mut cursor)))
@@ -62,9 +107,7 @@ impl<'a> VertexManager<'a> {
 
     pub fn iterate_for_range(&'a self, id: Uuid) -> impl Iterator<Item = Result<models::Vertex>> + 'a {
         let low_key = util::build(&[util::Component::Uuid(id)]);
-        let iter = self
-            .db
-            .iterator_cf(&self.cf, IteratorMode::From(&low_key, Direction::Forward));
+        let iter = self.db.iterator(self.cf, &low_key);
         iter.map(|item| -> Result<models::Vertex> {
             let (k, v) = item?;
 
@@ -80,48 +123,43 @@ impl<'a> VertexManager<'a> {
         })
     }
 
-    pub fn create(&self, batch: &mut WriteBatch, vertex: &models::Vertex) -> Result<()> {
+    pub fn create(&self, vertex: &models::Vertex) -> Result<()> {
         let key = self.key(vertex.id);
-        batch.put_cf(&self.cf, &key, &util::build(&[util::Component::Identifier(vertex.t)]));
+        self.db
+            .put(self.cf, &key, util::build(&[util::Component::Identifier(vertex.t)]));
+        VertexTypeManager::new(&self.db).set(vertex);
         Ok(())
     }
 
-    pub fn delete(
-        &self,
-        batch: &mut WriteBatch,
-        indexed_properties: &HashSet<models::Identifier>,
-        id: Uuid,
-    ) -> Result<()> {
-        batch.delete_cf(&self.cf, self.key(id));
+    pub fn delete(&self, indexed_properties: &HashSet<models::Identifier>, id: Uuid) -> Result<()> {
+        if let Some(t) = self.get(id)? {
+            VertexTypeManager::new(&self.db).delete(&models::Vertex::with_id(id, t));
+        }
+        self.db.delete(self.cf, self.key(id));
 
-        let vertex_property_manager = VertexPropertyManager::new(self.db);
+        let vertex_property_manager = VertexPropertyManager::new(&self.db);
         for item in vertex_property_manager.iterate_for_owner(id)? {
             let (vertex_property_owner_id, vertex_property_name, _) = item?;
-            vertex_property_manager.delete(
-                batch,
-                indexed_properties,
-                vertex_property_owner_id,
-                vertex_property_name,
-            )?;
+            vertex_property_manager.delete(indexed_properties, vertex_property_owner_id, vertex_property_name)?;
         }
 
-        let edge_manager = EdgeManager::new(self.db);
+        let edge_manager = EdgeManager::new(&self.db);
 
         {
-            let edge_range_manager = EdgeRangeManager::new(self.db);
+            let edge_range_manager = EdgeRangeManager::new(&self.db);
             for item in edge_range_manager.iterate_for_root(id, None)? {
                 let edge = item?;
                 debug_assert_eq!(edge.outbound_id, id);
-                edge_manager.delete(batch, indexed_properties, &edge)?;
+                edge_manager.delete(indexed_properties, &edge)?;
             }
         }
 
         {
-            let reversed_edge_range_manager = EdgeRangeManager::new_reversed(self.db);
+            let reversed_edge_range_manager = EdgeRangeManager::new_reversed(&self.db);
             for item in reversed_edge_range_manager.iterate_for_root(id, None)? {
                 let edge = item?;
                 debug_assert_eq!(edge.outbound_id, id);
-                edge_manager.delete(batch, indexed_properties, &edge)?;
+                edge_manager.delete(indexed_properties, &edge.reversed())?;
             }
         }
 
@@ -129,74 +167,250 @@ impl<'a> VertexManager<'a> {
     }
 
     pub fn compact(&self) {
-        self.db
-            .compact_range_cf(&self.cf, Option::<&[u8]>::None, Option::<&[u8]>::None);
+        self.db.compact(self.cf);
+    }
+}
+
+pub(crate) struct VertexTypeManager<'a> {
+    db: Store<'a>,
+    cf: &'static str,
+}
+
+impl<'a> VertexTypeManager<'a> {
+    pub fn new(db: &Store<'a>) -> Self {
+        VertexTypeManager {
+            db: db.clone(),
+            cf: "vertex_types:v1",
+        }
+    }
+
+    fn key(&self, t: models::Identifier, id: Uuid) -> Vec<u8> {
+        util::build(&[util::Component::Identifier(t), util::Component::Uuid(id)])
+    }
+
+    pub fn iterate_for_range(
+        &'a self,
+        t: models::Identifier,
+        id: Uuid,
+    ) -> impl Iterator<Item = Result<models::Vertex>> + 'a {
+        let prefix = util::build(&[util::Component::Identifier(t)]);
+        let low_key = self.key(t, id);
+        let iter = self.db.iterator(self.cf, &low_key);
+        take_with_prefix(iter, prefix).map(|item| -> Result<models::Vertex> {
+            let (k, _) = item?;
+            let mut cursor = Cursor::new(k);
+            let t = util::read_identifier(&mut cursor);
+            let id = util::read_uuid(&mut cursor);
+            Ok(models::Vertex::with_id(id, t))
+        })
+    }
+
+    pub fn set(&self, vertex: &models::Vertex) {
+        self.db.put(self.cf, self.key(vertex.t, vertex.id), []);
+    }
+
+    pub fn delete(&self, vertex: &models::Vertex) {
+        self.db.delete(self.cf, self.key(vertex.t, vertex.id));
+    }
+
+    pub fn compact(&self) {
+        self.db.compact(self.cf);
     }
 }
 
 pub(crate) struct EdgeManager<'a> {
-    db: &'a DB,
+    db: Store<'a>,
 }
 
 impl<'a> EdgeManager<'a> {
-    pub fn new(db: &'a DB) -> Self {
-        EdgeManager { db }
+    pub fn new(db: &Store<'a>) -> Self {
+        EdgeManager { db: db.clone() }
     }
 
-    pub fn set(&self, batch: &mut WriteBatch, edge: &models::Edge) -> Result<()> {
-        let edge_range_manager = EdgeRangeManager::new(self.db);
-        let reversed_edge_range_manager = EdgeRangeManager::new_reversed(self.db);
+    pub fn set(&self, edge: &models::Edge) -> Result<()> {
+        let edge_range_manager = EdgeRangeManager::new(&self.db);
+        let reversed_edge_range_manager = EdgeRangeManager::new_reversed(&self.db);
         let reversed_edge = edge.reversed();
 
         if edge_range_manager.contains(edge)? {
-            edge_range_manager.delete(batch, edge)?;
-            reversed_edge_range_manager.delete(batch, &reversed_edge)?;
+            edge_range_manager.delete(edge)?;
+            reversed_edge_range_manager.delete(&reversed_edge)?;
         }
 
-        edge_range_manager.set(batch, edge)?;
-        reversed_edge_range_manager.set(batch, &reversed_edge)?;
+        edge_range_manager.set(edge)?;
+        reversed_edge_range_manager.set(&reversed_edge)?;
+        EdgeTypeManager::new(&self.db).set(edge);
         Ok(())
     }
 
-    pub fn delete(
-        &self,
-        batch: &mut WriteBatch,
-        indexed_properties: &HashSet<models::Identifier>,
-        edge: &models::Edge,
-    ) -> Result<()> {
-        let edge_range_manager = EdgeRangeManager::new(self.db);
-        edge_range_manager.delete(batch, edge)?;
+    pub fn delete(&self, indexed_properties: &HashSet<models::Identifier>, edge: &models::Edge) -> Result<()> {
+        let edge_range_manager = EdgeRangeManager::new(&self.db);
+        edge_range_manager.delete(edge)?;
 
-        let reversed_edge_range_manager = EdgeRangeManager::new_reversed(self.db);
-        reversed_edge_range_manager.delete(batch, &edge.reversed())?;
+        let reversed_edge_range_manager = EdgeRangeManager::new_reversed(&self.db);
+        reversed_edge_range_manager.delete(&edge.reversed())?;
 
-        let edge_property_manager = EdgePropertyManager::new(self.db);
+        EdgeTypeManager::new(&self.db).delete(edge);
+
+        let edge_property_manager = EdgePropertyManager::new(&self.db);
         for item in edge_property_manager.iterate_for_owner(edge)? {
             let (edge_property_edge, edge_property_name, _) = item?;
-            edge_property_manager.delete(batch, indexed_properties, &edge_property_edge, edge_property_name)?;
+            edge_property_manager.delete(indexed_properties, &edge_property_edge, edge_property_name)?;
         }
 
         Ok(())
     }
 }
 
+pub(crate) struct EdgeTypeManager<'a> {
+    db: Store<'a>,
+    cf: &'static str,
+}
+
+impl<'a> EdgeTypeManager<'a> {
+    pub fn new(db: &Store<'a>) -> Self {
+        EdgeTypeManager {
+            db: db.clone(),
+            cf: "edge_types:v1",
+        }
+    }
+
+    fn key(&self, edge: &models::Edge) -> Vec<u8> {
+        util::build(&[
+            util::Component::Identifier(edge.t),
+            util::Component::Uuid(edge.outbound_id),
+            util::Component::Uuid(edge.inbound_id),
+        ])
+    }
+
+    fn iterate_for_prefix(&'a self, t: models::Identifier) -> impl Iterator<Item = Result<Box<[u8]>>> + 'a {
+        let prefix = util::build(&[util::Component::Identifier(t)]);
+        let iter = self.db.iterator(self.cf, &prefix);
+        take_with_prefix(iter, prefix).map(|item| Ok(item?.0))
+    }
+
+    pub fn count_for_type(&self, t: models::Identifier) -> Result<u64> {
+        let mut count = 0;
+        for item in self.iterate_for_prefix(t) {
+            item?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    pub fn iterate_for_range(&'a self, offset: &models::Edge) -> impl Iterator<Item = Result<models::Edge>> + 'a {
+        let prefix = util::build(&[util::Component::Identifier(offset.t)]);
+        let low_key = self.key(offset);
+        let iter = self.db.iterator(self.cf, &low_key);
+        take_with_prefix(iter, prefix).map(|item| -> Result<models::Edge> {
+            let (k, _) = item?;
+            let mut cursor = Cursor::new(k);
+            let t = util::read_identifier(&mut cursor);
+            let outbound_id = util::read_uuid(&mut cursor);
+            let inbound_id = util::read_uuid(&mut cursor);
+            Ok(models::Edge::new(outbound_id, t, inbound_id))
+        })
+    }
+
+    pub fn set(&self, edge: &models::Edge) {
+        self.db.put(self.cf, self.key(edge), []);
+    }
+
+    pub fn delete(&self, edge: &models::Edge) {
+        self.db.delete(self.cf, self.key(edge));
+    }
+
+    pub fn compact(&self) {
+        self.db.compact(self.cf);
+    }
+}
+
+// Combines degree counter deltas. Counters are stored as big-endian `i64`s,
+// so that deltas can be merged without reading the current count first.
+pub fn merge_degrees(_key: &[u8], existing: Option<&[u8]>, operands: &MergeOperands) -> Option<Vec<u8>> {
+    Some(sum_degrees(existing, operands))
+}
+
+// Adds degree counter deltas to an existing count.
+pub(crate) fn sum_degrees<'b>(existing: Option<&[u8]>, operands: impl IntoIterator<Item = &'b [u8]>) -> Vec<u8> {
+    let mut count = existing.map_or(0, read_degree);
+    for operand in operands {
+        count += read_degree(operand);
+    }
+    count.to_be_bytes().to_vec()
+}
+
+fn read_degree(bytes: &[u8]) -> i64 {
+    bytes.try_into().map_or(0, i64::from_be_bytes)
+}
+
+pub(crate) struct DegreeManager<'a> {
+    db: Store<'a>,
+    cf: &'static str,
+}
+
+impl<'a> DegreeManager<'a> {
+    pub fn new(db: &Store<'a>) -> Self {
+        DegreeManager {
+            db: db.clone(),
+            cf: "outbound_degrees:v1",
+        }
+    }
+
+    pub fn new_inbound(db: &Store<'a>) -> Self {
+        DegreeManager {
+            db: db.clone(),
+            cf: "inbound_degrees:v1",
+        }
+    }
+
+    fn key(&self, id: Uuid, t: models::Identifier) -> Vec<u8> {
+        util::build(&[util::Component::Uuid(id), util::Component::Identifier(t)])
+    }
+
+    pub fn get(&self, id: Uuid, t: Option<models::Identifier>) -> Result<u64> {
+        let count = match t {
+            Some(t) => self.db.get(self.cf, self.key(id, t))?.map_or(0, |v| read_degree(&v)),
+            None => {
+                let prefix = util::build(&[util::Component::Uuid(id)]);
+                let iter = self.db.iterator(self.cf, &prefix);
+                let mut count = 0;
+                for item in take_with_prefix(iter, prefix) {
+                    let (_, v) = item?;
+                    count += read_degree(&v);
+                }
+                count
+            }
+        };
+        Ok(count.max(0) as u64)
+    }
+
+    pub fn add(&self, id: Uuid, t: models::Identifier, delta: i64) {
+        self.db.merge(self.cf, self.key(id, t), delta.to_be_bytes());
+    }
+
+    pub fn compact(&self) {
+        self.db.compact(self.cf);
+    }
+}
+
 pub(crate) struct EdgeRangeManager<'a> {
-    db: &'a DB,
-    cf: ColumnFamilyRef<'a>,
+    db: Store<'a>,
+    cf: &'static str,
 }
 
 impl<'a> EdgeRangeManager<'a> {
-    pub fn new(db: &'a DB) -> Self {
+    pub fn new(db: &Store<'a>) -> Self {
         EdgeRangeManager {
-            db,
-            cf: db.cf_handle("edge_ranges:v2").unwrap(),
+            db: db.clone(),
+            cf: "edge_ranges:v2",
         }
     }
 
-    pub fn new_reversed(db: &'a DB) -> Self {
+    pub fn new_reversed(db: &Store<'a>) -> Self {
         EdgeRangeManager {
-            db,
-            cf: db.cf_handle("reversed_edge_ranges:v2").unwrap(),
+            db: db.clone(),
+            cf: "reversed_edge_ranges:v2",
         }
     }
 
@@ -223,7 +437,7 @@ impl<'a> EdgeRangeManager<'a> {
     }
 
     pub fn contains(&self, edge: &models::Edge) -> Result<bool> {
-        Ok(self.db.get_cf(&self.cf, self.key(edge))?.is_some())
+        Ok(self.db.get(self.cf, self.key(edge))?.is_some())
     }
 
     pub fn iterate_for_root(
@@ -235,16 +449,12 @@ impl<'a> EdgeRangeManager<'a> {
             Some(t) => {
                 let prefix = util::build(&[util::Component::Uuid(id), util::Component::Identifier(t)]);
                 let low_key = util::build(&[util::Component::Uuid(id), util::Component::Identifier(t)]);
-                let iter = self
-                    .db
-                    .iterator_cf(&self.cf, IteratorMode::From(&low_key, Direction::Forward));
+                let iter = self.db.iterator(self.cf, &low_key);
                 (prefix, iter)
             }
             None => {
                 let prefix = util::build(&[util::Component::Uuid(id)]);
-                let iter = self
-                    .db
-                    .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
+                let iter = self.db.iterator(self.cf, &prefix);
                 (prefix, iter)
             }
         };
@@ -264,44 +474,41 @@ impl<'a> EdgeRangeManager<'a> {
             util::Component::Identifier(t),
             util::Component::Uuid(second_id),
         ]);
-        let iter = self
-            .db
-            .iterator_cf(&self.cf, IteratorMode::From(&low_key, Direction::Forward));
+        let iter = self.db.iterator(self.cf, &low_key);
         Ok(Box::new(self.iterate(iter)))
     }
 
     pub fn iterate_for_all(&'a self) -> impl Iterator<Item = Result<models::Edge>> + 'a {
-        let iterator = self.db.iterator_cf(&self.cf, IteratorMode::Start);
+        let iterator = self.db.iterator(self.cf, &[]);
         self.iterate(iterator)
     }
 
-    pub fn set(&self, batch: &mut WriteBatch, edge: &models::Edge) -> Result<()> {
+    pub fn set(&self, edge: &models::Edge) -> Result<()> {
         let key = self.key(edge);
-        batch.put_cf(&self.cf, &key, []);
+        self.db.put(self.cf, &key, []);
         Ok(())
     }
 
-    pub fn delete(&self, batch: &mut WriteBatch, edge: &models::Edge) -> Result<()> {
-        batch.delete_cf(&self.cf, self.key(edge));
+    pub fn delete(&self, edge: &models::Edge) -> Result<()> {
+        self.db.delete(self.cf, self.key(edge));
         Ok(())
     }
 
     pub fn compact(&self) {
-        self.db
-            .compact_range_cf(&self.cf, Option::<&[u8]>::None, Option::<&[u8]>::None);
+        self.db.compact(self.cf);
     }
 }
 
 pub(crate) struct VertexPropertyManager<'a> {
-    db: &'a DB,
-    cf: ColumnFamilyRef<'a>,
+    db: Store<'a>,
+    cf: &'static str,
 }
 
 impl<'a> VertexPropertyManager<'a> {
-    pub fn new(db: &'a DB) -> Self {
+    pub fn new(db: &Store<'a>) -> Self {
         VertexPropertyManager {
-            db,
-            cf: db.cf_handle("vertex_properties:v2").unwrap(),
+            db: db.clone(),
+            cf: "vertex_properties:v2",
         }
     }
 
@@ -318,9 +525,7 @@ impl<'a> VertexPropertyManager<'a> {
     ) -> Result<impl Iterator<Item = Result<OwnedPropertyItem>> + 'a> {
         let prefix = util::build(&[util::Component::Uuid(vertex_id)]);
 
-        let iterator = self
-            .db
-            .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
+        let iterator = self.db.iterator(self.cf, &prefix);
 
         let filtered = take_with_prefix(iterator, prefix);
 
@@ -337,7 +542,7 @@ impl<'a> VertexPropertyManager<'a> {
     }
 
     pub fn get(&self, vertex_id: Uuid, name: models::Identifier) -> Result<Option<models::Json>> {
-        match self.db.get_cf(&self.cf, self.key(vertex_id, name))? {
+        match self.db.get(self.cf, self.key(vertex_id, name))? {
             Some(value_bytes) => Ok(Some(serde_json::from_slice(&value_bytes)?)),
             None => Ok(None),
         }
@@ -345,7 +550,6 @@ impl<'a> VertexPropertyManager<'a> {
 
     pub fn set(
         &self,
-        batch: &mut WriteBatch,
         indexed_properties: &HashSet<models::Identifier>,
         vertex_id: Uuid,
         name: models::Identifier,
@@ -354,50 +558,48 @@ impl<'a> VertexPropertyManager<'a> {
         let is_indexed = indexed_properties.contains(&name);
         let key = self.key(vertex_id, name);
         if is_indexed {
-            self.delete(batch, indexed_properties, vertex_id, name)?;
+            self.delete(indexed_properties, vertex_id, name)?;
         }
         let value_json = serde_json::to_vec(value)?;
-        batch.put_cf(&self.cf, &key, &value_json);
+        self.db.put(self.cf, &key, &value_json);
         if is_indexed {
-            let vertex_property_value_manager = VertexPropertyValueManager::new(self.db);
-            vertex_property_value_manager.set(batch, vertex_id, name, value);
+            let vertex_property_value_manager = VertexPropertyValueManager::new(&self.db);
+            vertex_property_value_manager.set(vertex_id, name, value);
         }
         Ok(())
     }
 
     pub fn delete(
         &self,
-        batch: &mut WriteBatch,
         indexed_properties: &HashSet<models::Identifier>,
         vertex_id: Uuid,
         name: models::Identifier,
     ) -> Result<()> {
         if indexed_properties.contains(&name) {
             if let Some(value) = self.get(vertex_id, name)? {
-                let vertex_property_value_manager = VertexPropertyValueManager::new(self.db);
-                vertex_property_value_manager.delete(batch, vertex_id, name, &value);
+                let vertex_property_value_manager = VertexPropertyValueManager::new(&self.db);
+                vertex_property_value_manager.delete(vertex_id, name, &value);
             }
         }
-        batch.delete_cf(&self.cf, self.key(vertex_id, name));
+        self.db.delete(self.cf, self.key(vertex_id, name));
         Ok(())
     }
 
     pub fn compact(&self) {
-        self.db
-            .compact_range_cf(&self.cf, Option::<&[u8]>::None, Option::<&[u8]>::None);
+        self.db.compact(self.cf);
     }
 }
 
 pub(crate) struct EdgePropertyManager<'a> {
-    db: &'a DB,
-    cf: ColumnFamilyRef<'a>,
+    db: Store<'a>,
+    cf: &'static str,
 }
 
 impl<'a> EdgePropertyManager<'a> {
-    pub fn new(db: &'a DB) -> Self {
+    pub fn new(db: &Store<'a>) -> Self {
         EdgePropertyManager {
-            db,
-            cf: db.cf_handle("edge_properties:v2").unwrap(),
+            db: db.clone(),
+            cf: "edge_properties:v2",
         }
     }
 
@@ -420,9 +622,7 @@ impl<'a> EdgePropertyManager<'a> {
             util::Component::Uuid(edge.inbound_id),
         ]);
 
-        let iterator = self
-            .db
-            .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
+        let iterator = self.db.iterator(self.cf, &prefix);
 
         let filtered = take_with_prefix(iterator, prefix);
 
@@ -451,7 +651,7 @@ impl<'a> EdgePropertyManager<'a> {
     }
 
     pub fn get(&self, edge: &models::Edge, name: models::Identifier) -> Result<Option<models::Json>> {
-        match self.db.get_cf(&self.cf, self.key(edge, name))? {
+        match self.db.get(self.cf, self.key(edge, name))? {
             Some(value_bytes) => Ok(Some(serde_json::from_slice(&value_bytes)?)),
             None => Ok(None),
         }
@@ -459,7 +659,6 @@ impl<'a> EdgePropertyManager<'a> {
 
     pub fn set(
         &self,
-        batch: &mut WriteBatch,
         indexed_properties: &HashSet<models::Identifier>,
         edge: &models::Edge,
         name: models::Identifier,
@@ -468,50 +667,48 @@ impl<'a> EdgePropertyManager<'a> {
         let is_indexed = indexed_properties.contains(&name);
         let key = self.key(edge, name);
         if is_indexed {
-            self.delete(batch, indexed_properties, edge, name)?;
+            self.delete(indexed_properties, edge, name)?;
         }
         let value_json = serde_json::to_vec(value)?;
-        batch.put_cf(&self.cf, &key, &value_json);
+        self.db.put(self.cf, &key, &value_json);
         if is_indexed {
-            let edge_property_value_manager = EdgePropertyValueManager::new(self.db);
-            edge_property_value_manager.set(batch, edge, name, value);
+            let edge_property_value_manager = EdgePropertyValueManager::new(&self.db);
+            edge_property_value_manager.set(edge, name, value);
         }
         Ok(())
     }
 
     pub fn delete(
         &self,
-        batch: &mut WriteBatch,
         indexed_properties: &HashSet<models::Identifier>,
         edge: &models::Edge,
         name: models::Identifier,
     ) -> Result<()> {
         if indexed_properties.contains(&name) {
             if let Some(value) = self.get(edge, name)? {
-                let edge_property_value_manager = EdgePropertyValueManager::new(self.db);
-                edge_property_value_manager.delete(batch, edge, name, &value);
+                let edge_property_value_manager = EdgePropertyValueManager::new(&self.db);
+                edge_property_value_manager.delete(edge, name, &value);
             }
         }
-        batch.delete_cf(&self.cf, self.key(edge, name));
+        self.db.delete(self.cf, self.key(edge, name));
         Ok(())
     }
 
     pub fn compact(&self) {
-        self.db
-            .compact_range_cf(&self.cf, Option::<&[u8]>::None, Option::<&[u8]>::None);
+        self.db.compact(self.cf);
     }
 }
 
 pub(crate) struct VertexPropertyValueManager<'a> {
-    db: &'a DB,
-    cf: ColumnFamilyRef<'a>,
+    db: Store<'a>,
+    cf: &'static str,
 }
 
 impl<'a> VertexPropertyValueManager<'a> {
-    pub fn new(db: &'a DB) -> Self {
+    pub fn new(db: &Store<'a>) -> Self {
         VertexPropertyValueManager {
-            db,
-            cf: db.cf_handle("vertex_property_values:v2").unwrap(),
+            db: db.clone(),
+            cf: "vertex_property_values:v3",
         }
     }
 
@@ -525,7 +722,7 @@ impl<'a> VertexPropertyValueManager<'a> {
 
     fn iterate(
         &'a self,
-        iterator: DBIterator<'a>,
+        iterator: StoreIterator<'a>,
         prefix: Vec<u8>,
     ) -> impl Iterator<Item = Result<VertexPropertyValueKey>> + 'a {
         let filtered = take_with_prefix(iterator, prefix);
@@ -534,9 +731,9 @@ impl<'a> VertexPropertyValueManager<'a> {
             let (k, _) = item?;
             let mut cursor = Cursor::new(k);
             let name = util::read_identifier(&mut cursor);
-            let value_hash = util::read_u64(&mut cursor);
+            let value = util::read_json(&mut cursor);
             let vertex_id = util::read_uuid(&mut cursor);
-            Ok((name, value_hash, vertex_id))
+            Ok((name, value, vertex_id))
         })
     }
 
@@ -545,9 +742,7 @@ impl<'a> VertexPropertyValueManager<'a> {
         property_name: models::Identifier,
     ) -> impl Iterator<Item = Result<VertexPropertyValueKey>> + 'a {
         let prefix = util::build(&[util::Component::Identifier(property_name)]);
-        let iter = self
-            .db
-            .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
+        let iter = self.db.iterator(self.cf, &prefix);
         self.iterate(iter, prefix)
     }
 
@@ -560,50 +755,55 @@ impl<'a> VertexPropertyValueManager<'a> {
             util::Component::Identifier(property_name),
             util::Component::Json(property_value),
         ]);
-        let iter = self
-            .db
-            .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
+        let iter = self.db.iterator(self.cf, &prefix);
         self.iterate(iter, prefix)
     }
 
-    pub fn set(
-        &self,
-        batch: &mut WriteBatch,
-        vertex_id: Uuid,
+    pub fn iterate_for_range(
+        &'a self,
         property_name: models::Identifier,
-        property_value: &models::Json,
-    ) {
+        low: Bound<&models::Json>,
+        high: Bound<&models::Json>,
+    ) -> impl Iterator<Item = Result<VertexPropertyValueKey>> + 'a {
+        let (prefix, low_key) = range_keys(property_name, low, high);
+        let (low, high) = (low.cloned(), high.cloned());
+        let iter = self.db.iterator(self.cf, &low_key);
+        self.iterate(iter, prefix)
+            .skip_while(move |item| match item {
+                Ok((_, value, _)) => is_below_range(value, &low),
+                Err(_) => false,
+            })
+            .take_while(move |item| match item {
+                Ok((_, value, _)) => !is_above_range(value, &high),
+                Err(_) => true,
+            })
+    }
+
+    pub fn set(&self, vertex_id: Uuid, property_name: models::Identifier, property_value: &models::Json) {
         let key = self.key(property_name, property_value, vertex_id);
-        batch.put_cf(&self.cf, key, []);
+        self.db.put(self.cf, key, []);
     }
 
-    pub fn delete(
-        &self,
-        batch: &mut WriteBatch,
-        vertex_id: Uuid,
-        property_name: models::Identifier,
-        property_value: &models::Json,
-    ) {
+    pub fn delete(&self, vertex_id: Uuid, property_name: models::Identifier, property_value: &models::Json) {
         let key = self.key(property_name, property_value, vertex_id);
-        batch.delete_cf(&self.cf, key);
+        self.db.delete(self.cf, key);
     }
 
     pub fn compact(&self) {
-        self.db
-            .compact_range_cf(&self.cf, Option::<&[u8]>::None, Option::<&[u8]>::None);
+        self.db.compact(self.cf);
     }
 }
 
 pub(crate) struct EdgePropertyValueManager<'a> {
-    db: &'a DB,
-    cf: ColumnFamilyRef<'a>,
+    db: Store<'a>,
+    cf: &'static str,
 }
 
 impl<'a> EdgePropertyValueManager<'a> {
-    pub fn new(db: &'a DB) -> Self {
+    pub fn new(db: &Store<'a>) -> Self {
         EdgePropertyValueManager {
-            db,
-            cf: db.cf_handle("edge_property_values:v2").unwrap(),
+            db: db.clone(),
+            cf: "edge_property_values:v3",
         }
     }
 
@@ -619,7 +819,7 @@ impl<'a> EdgePropertyValueManager<'a> {
 
     fn iterate(
         &'a self,
-        iterator: DBIterator<'a>,
+        iterator: StoreIterator<'a>,
         prefix: Vec<u8>,
     ) -> impl Iterator<Item = Result<EdgePropertyValueKey>> + 'a {
         let filtered = take_with_prefix(iterator, prefix);
@@ -628,11 +828,11 @@ impl<'a> EdgePropertyValueManager<'a> {
             let (k, _) = item?;
             let mut cursor = Cursor::new(k);
             let name = util::read_identifier(&mut cursor);
-            let value_hash = util::read_u64(&mut cursor);
+            let value = util::read_json(&mut cursor);
             let out_id = util::read_uuid(&mut cursor);
             let t = util::read_identifier(&mut cursor);
             let in_id = util::read_uuid(&mut cursor);
-            Ok((name, value_hash, models::Edge::new(out_id, t, in_id)))
+            Ok((name, value, models::Edge::new(out_id, t, in_id)))
         })
     }
 
@@ -641,9 +841,7 @@ impl<'a> EdgePropertyValueManager<'a> {
         property_name: models::Identifier,
     ) -> impl Iterator<Item = Result<EdgePropertyValueKey>> + 'a {
         let prefix = util::build(&[util::Component::Identifier(property_name)]);
-        let iter = self
-            .db
-            .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
+        let iter = self.db.iterator(self.cf, &prefix);
         self.iterate(iter, prefix)
     }
 
@@ -656,68 +854,96 @@ impl<'a> EdgePropertyValueManager<'a> {
             util::Component::Identifier(property_name),
             util::Component::Json(property_value),
         ]);
-        let iter = self
-            .db
-            .iterator_cf(&self.cf, IteratorMode::From(&prefix, Direction::Forward));
+        let iter = self.db.iterator(self.cf, &prefix);
         self.iterate(iter, prefix)
     }
 
-    pub fn set(
-        &self,
-        batch: &mut WriteBatch,
-        edge: &models::Edge,
+    pub fn iterate_for_range(
+        &'a self,
         property_name: models::Identifier,
-        property_value: &models::Json,
-    ) {
+        low: Bound<&models::Json>,
+        high: Bound<&models::Json>,
+    ) -> impl Iterator<Item = Result<EdgePropertyValueKey>> + 'a {
+        let (prefix, low_key) = range_keys(property_name, low, high);
+        let (low, high) = (low.cloned(), high.cloned());
+        let iter = self.db.iterator(self.cf, &low_key);
+        self.iterate(iter, prefix)
+            .skip_while(move |item| match item {
+                Ok((_, value, _)) => is_below_range(value, &low),
+                Err(_) => false,
+            })
+            .take_while(move |item| match item {
+                Ok((_, value, _)) => !is_above_range(value, &high),
+                Err(_) => true,
+            })
+    }
+
+    pub fn set(&self, edge: &models::Edge, property_name: models::Identifier, property_value: &models::Json) {
         let key = self.key(property_name, property_value, edge);
-        batch.put_cf(&self.cf, key, []);
+        self.db.put(self.cf, key, []);
     }
 
-    pub fn delete(
-        &self,
-        batch: &mut WriteBatch,
-        edge: &models::Edge,
-        property_name: models::Identifier,
-        property_value: &models::Json,
-    ) {
+    pub fn delete(&self, edge: &models::Edge, property_name: models::Identifier, property_value: &models::Json) {
         let key = self.key(property_name, property_value, edge);
-        batch.delete_cf(&self.cf, key);
+        self.db.delete(self.cf, key);
     }
 
     pub fn compact(&self) {
-        self.db
-            .compact_range_cf(&self.cf, Option::<&[u8]>::None, Option::<&[u8]>::None);
+        self.db.compact(self.cf);
     }
 }
 
 pub(crate) struct MetadataManager<'a> {
-    db: &'a DB,
-    cf: ColumnFamilyRef<'a>,
+    db: Store<'a>,
+    cf: &'static str,
 }
 
 impl<'a> MetadataManager<'a> {
-    pub fn new(db: &'a DB) -> Self {
+    pub fn new(db: &Store<'a>) -> Self {
         MetadataManager {
-            db,
-            cf: db.cf_handle("metadata:v2").unwrap(),
+            db: db.clone(),
+            cf: "metadata:v2",
         }
     }
 
     pub fn get_indexed_properties(&self) -> Result<HashSet<models::Identifier>> {
-        match self.db.get_cf(&self.cf, "indexed_properties")? {
+        match self.db.get(self.cf, "indexed_properties")? {
             Some(value_bytes) => Ok(bincode::deserialize(&value_bytes)?),
             None => Ok(HashSet::default()),
         }
     }
 
-    pub fn set_indexed_properties(&self, batch: &mut WriteBatch, indices: &HashSet<models::Identifier>) -> Result<()> {
+    pub fn set_indexed_properties(&self, indices: &HashSet<models::Identifier>) -> Result<()> {
         let value_bytes = bincode::serialize(&indices)?;
-        batch.put_cf(&self.cf, "indexed_properties", &value_bytes);
+        self.db.put(self.cf, "indexed_properties", &value_bytes);
         Ok(())
     }
 
+    pub fn has_vertex_type_index(&self) -> Result<bool> {
+        Ok(self.db.get(self.cf, "vertex_type_index")?.is_some())
+    }
+
+    pub fn set_has_vertex_type_index(&self) {
+        self.db.put(self.cf, "vertex_type_index", []);
+    }
+
+    pub fn has_edge_type_index(&self) -> Result<bool> {
+        Ok(self.db.get(self.cf, "edge_type_index")?.is_some())
+    }
+
+    pub fn set_has_edge_type_index(&self) {
+        self.db.put(self.cf, "edge_type_index", []);
+    }
+
+    pub fn has_degree_counters(&self) -> Result<bool> {
+        Ok(self.db.get(self.cf, "degree_counters")?.is_some())
+    }
+
+    pub fn set_has_degree_counters(&self) {
+        self.db.put(self.cf, "degree_counters", []);
+    }
+
     pub fn compact(&self) {
-        self.db
-            .compact_range_cf(&self.cf, Option::<&[u8]>::None, Option::<&[u8]>::None);
+        self.db.compact(self.cf);
     }
 }
@@ -2,6 +2,7 @@
 
 mod datastore;
 mod managers;
+mod store;
 
 pub use self::datastore::RocksdbDatastore;
 
@@ -42,6 +43,101 @@ mod tests {
         RocksdbDatastore::repair(dir.path(), &RocksdbDatastore::get_options(Some(1))).unwrap();
     }
 
+    #[test]
+    fn should_get_vertex_degrees_with_counters() {
+        use super::RocksdbDatastore;
+
+        let path = tempdir().unwrap().into_path();
+        let opts = RocksdbDatastore::get_options(Some(1));
+        let db = RocksdbDatastore::new_db_with_degree_counters(&path, &opts).unwrap();
+        crate::tests::should_get_vertex_degrees(&db).unwrap();
+        crate::tests::should_get_degrees_of_deleted_vertices(&db).unwrap();
+        crate::tests::should_get_degrees_of_bulk_inserted_edges(&db).unwrap();
+    }
+
+    #[test]
+    fn should_not_commit_conflicting_transactions() {
+        use super::RocksdbDatastore;
+        use crate::util::{extract_count, extract_edges};
+        use crate::{AllEdgeQuery, AllVertexQuery, CountQueryExt, Edge, Error, Identifier, SpecificVertexQuery};
+
+        let path = tempdir().unwrap().into_path();
+        let db = RocksdbDatastore::new_db(&path).unwrap();
+        let t = Identifier::new("test_vertex_type").unwrap();
+        let edge_t = Identifier::new("test_edge_type").unwrap();
+        let outbound_id = db.create_vertex_from_type(t).unwrap();
+        let inbound_id = db.create_vertex_from_type(t).unwrap();
+        let edge = Edge::new(outbound_id, edge_t, inbound_id);
+
+        // An edge is created to a vertex deleted by a transaction that's
+        // committed first
+        let mut deleting_txn = db.begin();
+        let mut creating_txn = db.begin();
+        deleting_txn.delete(SpecificVertexQuery::single(inbound_id)).unwrap();
+        assert!(creating_txn.create_edge(&edge).unwrap());
+        deleting_txn.commit().unwrap();
+        let result = creating_txn.commit();
+        crate::expect_err!(result, Error::Conflict);
+        assert!(extract_edges(db.get(AllEdgeQuery).unwrap()).unwrap().is_empty());
+
+        // The same, but with the edge created first
+        let inbound_id = db.create_vertex_from_type(t).unwrap();
+        let edge = Edge::new(outbound_id, edge_t, inbound_id);
+        let mut deleting_txn = db.begin();
+        let mut creating_txn = db.begin();
+        deleting_txn.delete(SpecificVertexQuery::single(inbound_id)).unwrap();
+        assert!(creating_txn.create_edge(&edge).unwrap());
+        creating_txn.commit().unwrap();
+        let result = deleting_txn.commit();
+        crate::expect_err!(result, Error::Conflict);
+        assert_eq!(extract_edges(db.get(AllEdgeQuery).unwrap()).unwrap(), vec![edge]);
+
+        // Transactions that don't read anything the other changed are both
+        // committed
+        let mut first_txn = db.begin();
+        let mut second_txn = db.begin();
+        first_txn.create_vertex_from_type(t).unwrap();
+        second_txn.create_vertex_from_type(t).unwrap();
+        first_txn.commit().unwrap();
+        second_txn.commit().unwrap();
+        let count = extract_count(db.get(AllVertexQuery.count().unwrap()).unwrap()).unwrap();
+        assert_eq!(count, 4);
+    }
+
+    #[test]
+    fn should_retry_conflicting_writes() {
+        use super::RocksdbDatastore;
+        use crate::util::extract_count;
+        use crate::{ijson, AllEdgeQuery, CountQueryExt, Edge, Identifier, SpecificVertexQuery};
+        use std::thread;
+
+        let path = tempdir().unwrap().into_path();
+        let db = RocksdbDatastore::new_db(&path).unwrap();
+        let t = Identifier::new("test_vertex_type").unwrap();
+        let edge_t = Identifier::new("test_edge_type").unwrap();
+        let name = Identifier::new("foo").unwrap();
+        let outbound_id = db.create_vertex_from_type(t).unwrap();
+
+        // Every write reads the outbound vertex, so writes made at the same
+        // time conflict, and have to be retried for all of them to succeed
+        thread::scope(|scope| {
+            for i in 0..4 {
+                let db = &db;
+                scope.spawn(move || {
+                    for _ in 0..10 {
+                        let inbound_id = db.create_vertex_from_type(t).unwrap();
+                        assert!(db.create_edge(&Edge::new(outbound_id, edge_t, inbound_id)).unwrap());
+                        db.set_properties(SpecificVertexQuery::single(outbound_id), name, &ijson!(i))
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let count = extract_count(db.get(AllEdgeQuery.count().unwrap()).unwrap()).unwrap();
+        assert_eq!(count, 40);
+    }
+
     // Tests for a regression where reversed range queries were incorrect.
     // See https://github.com/indradb/indradb/issues/280
     #[test]
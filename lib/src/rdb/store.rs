@@ -0,0 +1,546 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::btree_map::Entry;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::iter::Peekable;
+use std::ops::Bound;
+use std::rc::Rc;
+use std::result::Result as StdResult;
+use std::sync::Mutex;
+
+use super::managers::sum_degrees;
+use crate::errors::{Error, Result};
+
+use rocksdb::{ColumnFamilyRef, Direction, IteratorMode, WriteBatch, DB};
+
+type KeyValue = (Box<[u8]>, Box<[u8]>);
+type CommittedIterator<'a> = Box<dyn Iterator<Item = StdResult<KeyValue, rocksdb::Error>> + 'a>;
+type PendingWrites = HashMap<&'static str, BTreeMap<Vec<u8>, PendingWrite>>;
+
+// A write to a key that hasn't been committed yet.
+#[derive(Clone, Debug)]
+enum PendingWrite {
+    Put(Vec<u8>),
+    Delete,
+    // Degree counter deltas, which are added to whatever count has been
+    // committed by the time they are.
+    Merge(Vec<Vec<u8>>),
+}
+
+impl PendingWrite {
+    // Gets the value the key will have once this write is committed, given
+    // the value it currently has.
+    fn apply(&self, committed: Option<&[u8]>) -> Option<Vec<u8>> {
+        match self {
+            PendingWrite::Put(value) => Some(value.clone()),
+            PendingWrite::Delete => None,
+            PendingWrite::Merge(operands) => Some(sum_degrees(committed, operands.iter().map(Vec::as_slice))),
+        }
+    }
+}
+
+// The committed data read by a transaction, which is checked when the
+// transaction is committed to make sure it hasn't been changed since. Values
+// are recorded as hashes.
+#[derive(Default)]
+struct ReadSet {
+    // The values of the keys read, or `None` for keys that didn't exist.
+    keys: HashMap<(&'static str, Vec<u8>), Option<u64>>,
+    ranges: Vec<RangeRead>,
+}
+
+// A range of keys read by an iterator.
+struct RangeRead {
+    cf: &'static str,
+    from: Vec<u8>,
+    to: Bound<Vec<u8>>,
+    // The hash of the committed keys and values in the range.
+    hash: u64,
+}
+
+fn hash_value(value: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn is_before(key: &[u8], to: &Bound<Vec<u8>>) -> bool {
+    match to {
+        Bound::Included(to) => key <= &to[..],
+        Bound::Excluded(to) => key < &to[..],
+        Bound::Unbounded => true,
+    }
+}
+
+// The database as seen by a transaction. Writes are held in memory, sorted
+// by column family and key, until they're committed all at once with a
+// `WriteBatch`. Reads see them as though they had already been written.
+// Clones share the same pending writes, so that every manager of a
+// transaction sees the writes made through the others.
+//
+// Transactions are optimistic: the committed data they read is recorded, and
+// if any of it has changed by the time they're committed, the commit fails
+// with `Error::Conflict`. Commits are serialized by `commit_lock`, so that
+// nothing can be committed between the check and the write.
+#[derive(Clone)]
+pub(crate) struct Store<'a> {
+    db: &'a DB,
+    commit_lock: &'a Mutex<()>,
+    pending: Rc<RefCell<PendingWrites>>,
+    reads: Rc<RefCell<ReadSet>>,
+    // The writes of a store created with `Store::direct`.
+    direct: Option<Rc<RefCell<WriteBatch>>>,
+}
+
+impl<'a> Store<'a> {
+    pub fn new(db: &'a DB, commit_lock: &'a Mutex<()>) -> Self {
+        Store {
+            db,
+            commit_lock,
+            pending: Rc::default(),
+            reads: Rc::default(),
+            direct: None,
+        }
+    }
+
+    // Creates a store that adds writes straight to a `WriteBatch`, rather
+    // than holding them as pending writes, for bulk inserts that would be too
+    // large to hold that way. Reads don't see the writes until they're
+    // committed, and committing doesn't check for conflicts, so the caller
+    // has to hold the commit lock for as long as the store is used.
+    pub fn direct(db: &'a DB, commit_lock: &'a Mutex<()>) -> Self {
+        Store {
+            direct: Some(Rc::default()),
+            ..Store::new(db, commit_lock)
+        }
+    }
+
+    fn cf_handle(&self, cf: &str) -> ColumnFamilyRef<'a> {
+        self.db.cf_handle(cf).unwrap()
+    }
+
+    pub fn get<K: AsRef<[u8]>>(&self, cf: &'static str, key: K) -> StdResult<Option<Vec<u8>>, rocksdb::Error> {
+        let key = key.as_ref();
+        let pending = self
+            .pending
+            .borrow()
+            .get(cf)
+            .and_then(|writes| writes.get(key))
+            .cloned();
+        match pending {
+            None => self.get_committed(cf, key),
+            Some(write @ PendingWrite::Merge(_)) => {
+                let committed = self.get_committed(cf, key)?;
+                Ok(write.apply(committed.as_deref()))
+            }
+            Some(write) => Ok(write.apply(None)),
+        }
+    }
+
+    // Gets the committed value of a key, recording that it was read.
+    fn get_committed(&self, cf: &'static str, key: &[u8]) -> StdResult<Option<Vec<u8>>, rocksdb::Error> {
+        let value = self.db.get_cf(&self.cf_handle(cf), key)?;
+        if self.direct.is_some() {
+            // Nothing is checked when committing
+            return Ok(value);
+        }
+        let mut reads = self.reads.borrow_mut();
+        reads
+            .keys
+            .entry((cf, key.to_vec()))
+            .or_insert_with(|| value.as_deref().map(hash_value));
+        Ok(value)
+    }
+
+    // Iterates over the keys of a column family, starting from `from`.
+    pub fn iterator(&self, cf: &'static str, from: &[u8]) -> StoreIterator<'a> {
+        let committed = self.committed_iterator(cf, from);
+        StoreIterator::new(committed, self.pending.clone(), self.reads.clone(), cf, from)
+    }
+
+    fn committed_iterator(&self, cf: &'static str, from: &[u8]) -> CommittedIterator<'a> {
+        let iter = self
+            .db
+            .iterator_cf(&self.cf_handle(cf), IteratorMode::From(from, Direction::Forward));
+        Box::new(iter)
+    }
+
+    pub fn put<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, cf: &'static str, key: K, value: V) {
+        if let Some(direct) = &self.direct {
+            direct.borrow_mut().put_cf(&self.cf_handle(cf), key, value);
+            return;
+        }
+        let write = PendingWrite::Put(value.as_ref().to_vec());
+        let mut pending = self.pending.borrow_mut();
+        pending.entry(cf).or_default().insert(key.as_ref().to_vec(), write);
+    }
+
+    pub fn delete<K: AsRef<[u8]>>(&self, cf: &'static str, key: K) {
+        if let Some(direct) = &self.direct {
+            direct.borrow_mut().delete_cf(&self.cf_handle(cf), key);
+            return;
+        }
+        let mut pending = self.pending.borrow_mut();
+        pending
+            .entry(cf)
+            .or_default()
+            .insert(key.as_ref().to_vec(), PendingWrite::Delete);
+    }
+
+    // Adds a degree counter delta to a key. Deltas are kept as merges where
+    // possible, so that they're added to the committed count rather than
+    // overwriting it.
+    pub fn merge<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, cf: &'static str, key: K, value: V) {
+        if let Some(direct) = &self.direct {
+            direct.borrow_mut().merge_cf(&self.cf_handle(cf), key, value);
+            return;
+        }
+        let value = value.as_ref().to_vec();
+        let mut pending = self.pending.borrow_mut();
+        match pending.entry(cf).or_default().entry(key.as_ref().to_vec()) {
+            Entry::Vacant(entry) => {
+                entry.insert(PendingWrite::Merge(vec![value]));
+            }
+            Entry::Occupied(mut entry) => match entry.get_mut() {
+                PendingWrite::Merge(operands) => operands.push(value),
+                write => {
+                    let base = write.apply(None);
+                    *write = PendingWrite::Put(sum_degrees(base.as_deref(), [value.as_slice()]));
+                }
+            },
+        }
+    }
+
+    // Writes the pending writes to the database, unless the committed data
+    // read has changed. If this fails, they're kept, so that the commit can
+    // be retried or rolled back.
+    pub fn commit(&self) -> Result<()> {
+        if let Some(direct) = &self.direct {
+            let batch = std::mem::take(&mut *direct.borrow_mut());
+            self.db.write(batch)?;
+            return Ok(());
+        }
+        if self.pending.borrow().is_empty() {
+            self.rollback();
+            return Ok(());
+        }
+
+        let _lock = self.commit_lock.lock().unwrap();
+        if !self.is_unchanged()? {
+            return Err(Error::Conflict);
+        }
+        let mut batch = WriteBatch::default();
+        for (cf, writes) in self.pending.borrow().iter() {
+            let cf = self.cf_handle(cf);
+            for (key, write) in writes {
+                match write {
+                    PendingWrite::Put(value) => batch.put_cf(&cf, key, value),
+                    PendingWrite::Delete => batch.delete_cf(&cf, key),
+                    PendingWrite::Merge(operands) => {
+                        for operand in operands {
+                            batch.merge_cf(&cf, key, operand);
+                        }
+                    }
+                }
+            }
+        }
+        self.db.write(batch)?;
+        self.rollback();
+        Ok(())
+    }
+
+    // Discards the pending writes, and the record of what was read.
+    pub fn rollback(&self) {
+        if let Some(direct) = &self.direct {
+            *direct.borrow_mut() = WriteBatch::default();
+        }
+        self.pending.borrow_mut().clear();
+        *self.reads.borrow_mut() = ReadSet::default();
+    }
+
+    // Checks whether the committed data read is still the same.
+    fn is_unchanged(&self) -> StdResult<bool, rocksdb::Error> {
+        let reads = self.reads.borrow();
+        for ((cf, key), hash) in &reads.keys {
+            let value = self.db.get_cf(&self.cf_handle(cf), key)?;
+            if value.as_deref().map(hash_value) != *hash {
+                return Ok(false);
+            }
+        }
+        for range in &reads.ranges {
+            let mut hasher = DefaultHasher::new();
+            for item in self.committed_iterator(range.cf, &range.from) {
+                let (key, value) = item?;
+                if !is_before(&key, &range.to) {
+                    break;
+                }
+                (key, value).hash(&mut hasher);
+            }
+            if hasher.finish() != range.hash {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn compact(&self, cf: &'static str) {
+        self.db
+            .compact_range_cf(&self.cf_handle(cf), Option::<&[u8]>::None, Option::<&[u8]>::None);
+    }
+
+    pub fn flush(&self) -> StdResult<(), rocksdb::Error> {
+        self.db.flush()
+    }
+}
+
+// Iterates over the keys of a column family as seen by a transaction, by
+// merging the committed keys with the pending writes. Pending writes are
+// looked up again for every key, so writes made while iterating are seen if
+// they're ahead of the iterator. The range of keys iterated over is recorded
+// as read when the iterator is dropped.
+pub(crate) struct StoreIterator<'a> {
+    committed: Peekable<CommittedIterator<'a>>,
+    pending: Rc<RefCell<PendingWrites>>,
+    reads: Rc<RefCell<ReadSet>>,
+    cf: &'static str,
+    from: Vec<u8>,
+    // Only pending writes to keys within this bound are left to be merged.
+    position: Bound<Vec<u8>>,
+    done: bool,
+    // Hashes the committed keys and values iterated over.
+    hasher: DefaultHasher,
+}
+
+impl<'a> StoreIterator<'a> {
+    fn new(
+        committed: CommittedIterator<'a>,
+        pending: Rc<RefCell<PendingWrites>>,
+        reads: Rc<RefCell<ReadSet>>,
+        cf: &'static str,
+        from: &[u8],
+    ) -> Self {
+        StoreIterator {
+            committed: committed.peekable(),
+            pending,
+            reads,
+            cf,
+            from: from.to_vec(),
+            position: Bound::Included(from.to_vec()),
+            done: false,
+            hasher: DefaultHasher::new(),
+        }
+    }
+
+    fn next_committed(&mut self) -> Option<StdResult<KeyValue, rocksdb::Error>> {
+        let item = self.committed.next();
+        if let Some(Ok(ref entry)) = item {
+            entry.hash(&mut self.hasher);
+        }
+        item
+    }
+
+    fn next_pending(&self) -> Option<(Vec<u8>, PendingWrite)> {
+        let pending = self.pending.borrow();
+        let writes = pending.get(self.cf)?;
+        let low = match &self.position {
+            Bound::Included(key) => Bound::Included(key.as_slice()),
+            Bound::Excluded(key) => Bound::Excluded(key.as_slice()),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        writes
+            .range::<[u8], _>((low, Bound::Unbounded))
+            .next()
+            .map(|(key, write)| (key.clone(), write.clone()))
+    }
+}
+
+impl<'a> Iterator for StoreIterator<'a> {
+    type Item = StdResult<KeyValue, rocksdb::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let next_pending = self.next_pending();
+            let order = match (self.committed.peek(), &next_pending) {
+                (Some(Err(_)), _) => return self.committed.next(),
+                (Some(Ok(_)), None) => Ordering::Less,
+                (None, None) => {
+                    self.done = true;
+                    return None;
+                }
+                (None, Some(_)) => Ordering::Greater,
+                (Some(Ok((key, _))), Some((pending_key, _))) => key[..].cmp(pending_key),
+            };
+
+            if order == Ordering::Less {
+                let item = self.next_committed();
+                if let Some(Ok((ref key, _))) = item {
+                    self.position = Bound::Excluded(key.to_vec());
+                }
+                return item;
+            }
+
+            let (key, write) = next_pending.unwrap();
+            let committed = match order {
+                Ordering::Equal => match self.next_committed() {
+                    Some(Ok((_, value))) => Some(value),
+                    _ => None,
+                },
+                _ => None,
+            };
+            self.position = Bound::Excluded(key.clone());
+            if let Some(value) = write.apply(committed.as_deref()) {
+                return Some(Ok((key.into_boxed_slice(), value.into_boxed_slice())));
+            }
+        }
+    }
+}
+
+impl<'a> Drop for StoreIterator<'a> {
+    fn drop(&mut self) {
+        // Everything before the position has been iterated over
+        let to = match std::mem::replace(&mut self.position, Bound::Unbounded) {
+            _ if self.done => Bound::Unbounded,
+            Bound::Included(key) => Bound::Excluded(key),
+            Bound::Excluded(key) => Bound::Included(key),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        self.reads.borrow_mut().ranges.push(RangeRead {
+            cf: self.cf,
+            from: std::mem::take(&mut self.from),
+            to,
+            hash: self.hasher.finish(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PendingWrite, PendingWrites, ReadSet, StoreIterator};
+    use std::cell::RefCell;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    use std::ops::Bound;
+    use std::rc::Rc;
+
+    fn iterator<'a>(
+        committed: &[(&[u8], &[u8])],
+        pending: &[(&[u8], PendingWrite)],
+        reads: &Rc<RefCell<ReadSet>>,
+        from: &[u8],
+    ) -> StoreIterator<'a> {
+        let committed: Vec<_> = committed
+            .iter()
+            .map(|(k, v)| Ok((k.to_vec().into_boxed_slice(), v.to_vec().into_boxed_slice())))
+            .collect();
+        let mut writes = PendingWrites::default();
+        for (key, write) in pending {
+            writes.entry("cf").or_default().insert(key.to_vec(), write.clone());
+        }
+        StoreIterator::new(
+            Box::new(committed.into_iter()),
+            Rc::new(RefCell::new(writes)),
+            reads.clone(),
+            "cf",
+            from,
+        )
+    }
+
+    fn collect(
+        committed: &[(&[u8], &[u8])],
+        pending: &[(&[u8], PendingWrite)],
+        from: &[u8],
+    ) -> Vec<(Vec<u8>, Vec<u8>)> {
+        iterator(committed, pending, &Rc::default(), from)
+            .map(|item| {
+                let (k, v) = item.unwrap();
+                (k.to_vec(), v.to_vec())
+            })
+            .collect()
+    }
+
+    fn hash(entries: &[(&[u8], &[u8])]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for entry in entries {
+            entry.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    #[test]
+    fn should_merge_pending_writes() {
+        let committed: [(&[u8], &[u8]); 3] = [(b"a", b"1"), (b"c", b"2"), (b"e", b"3")];
+        let pending = [
+            (&b"b"[..], PendingWrite::Put(b"4".to_vec())),
+            (b"c", PendingWrite::Delete),
+            (b"d", PendingWrite::Delete),
+            (b"e", PendingWrite::Put(b"5".to_vec())),
+            (b"f", PendingWrite::Put(b"6".to_vec())),
+        ];
+        assert_eq!(
+            collect(&committed, &pending, b""),
+            vec![
+                (b"a".to_vec(), b"1".to_vec()),
+                (b"b".to_vec(), b"4".to_vec()),
+                (b"e".to_vec(), b"5".to_vec()),
+                (b"f".to_vec(), b"6".to_vec()),
+            ]
+        );
+        // Pending writes before where iteration starts are skipped, like
+        // the committed keys are
+        assert_eq!(
+            collect(&committed[2..], &pending, b"d"),
+            vec![(b"e".to_vec(), b"5".to_vec()), (b"f".to_vec(), b"6".to_vec())]
+        );
+    }
+
+    #[test]
+    fn should_merge_pending_degree_deltas() {
+        let count = |n: i64| n.to_be_bytes().to_vec();
+        let committed: [(&[u8], &[u8]); 1] = [(b"a", &count(2))];
+        let pending = [
+            (&b"a"[..], PendingWrite::Merge(vec![count(1), count(-4)])),
+            (b"b", PendingWrite::Merge(vec![count(3)])),
+        ];
+        assert_eq!(
+            collect(&committed, &pending, b""),
+            vec![(b"a".to_vec(), count(-1)), (b"b".to_vec(), count(3))]
+        );
+    }
+
+    #[test]
+    fn should_record_range_reads() {
+        let committed: [(&[u8], &[u8]); 3] = [(b"a", b"1"), (b"c", b"2"), (b"e", b"3")];
+        let pending = [(&b"b"[..], PendingWrite::Put(b"4".to_vec()))];
+        let reads = Rc::default();
+
+        // Keys up to the last one reached are read, whether it's committed
+        // or not
+        let mut iter = iterator(&committed, &pending, &reads, b"");
+        iter.next();
+        iter.next();
+        drop(iter);
+        let mut iter = iterator(&committed[1..], &pending, &reads, b"c");
+        iter.next();
+        drop(iter);
+        // An iterator that's run to the end reads everything after where it
+        // started
+        iterator(&committed, &pending, &reads, b"").for_each(drop);
+
+        let reads = reads.borrow();
+        let ranges: Vec<_> = reads
+            .ranges
+            .iter()
+            .map(|r| (r.from.clone(), r.to.clone(), r.hash))
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (Vec::new(), Bound::Included(b"b".to_vec()), hash(&committed[..1])),
+                (b"c".to_vec(), Bound::Included(b"c".to_vec()), hash(&committed[1..2])),
+                (Vec::new(), Bound::Unbounded, hash(&committed)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use super::util;
+use crate::{
+    errors, expect_err, ijson, AggregateFunction, AggregateQuery, AggregationGroup, AggregationValue, AllEdgeQuery,
+    AllVertexQuery, CountQueryExt, Database, Datastore, Edge, Error, GroupBy, HistogramBucket, Identifier, Json,
+    QueryExt, QueryOutputValue, SpecificEdgeQuery, SpecificVertexQuery,
+};
+
+use uuid::Uuid;
+
+pub fn should_aggregate_vertex_property<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    create_aged_vertices(db)?;
+    let age = Identifier::new("age")?;
+
+    let expected = [
+        (AggregateFunction::Sum, 75.0),
+        (AggregateFunction::Min, 10.0),
+        (AggregateFunction::Max, 40.0),
+        (AggregateFunction::Average, 25.0),
+    ];
+    for (function, number) in expected {
+        let groups = util::get_aggregation(db, AllVertexQuery.aggregate(age, function)?)?;
+        assert_eq!(
+            groups,
+            vec![AggregationGroup::new(None, 3, AggregationValue::Number(Some(number)))]
+        );
+    }
+    Ok(())
+}
+
+pub fn should_get_histogram<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    create_aged_vertices(db)?;
+    let q = AllVertexQuery.aggregate(Identifier::new("age")?, AggregateFunction::Histogram(ijson!(30)))?;
+    let groups = util::get_aggregation(db, q)?;
+    assert_eq!(groups.len(), 1);
+    assert_eq!(
+        groups[0].value,
+        AggregationValue::Histogram(vec![HistogramBucket::new(0.0, 2), HistogramBucket::new(30.0, 1)])
+    );
+    Ok(())
+}
+
+pub fn should_aggregate_nothing<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let age = Identifier::new("age")?;
+    let groups = util::get_aggregation(db, AllVertexQuery.aggregate(age, AggregateFunction::Average)?)?;
+    assert_eq!(
+        groups,
+        vec![AggregationGroup::new(None, 0, AggregationValue::Number(None))]
+    );
+    let groups = util::get_aggregation(db, AllVertexQuery.aggregate(age, AggregateFunction::Sum)?)?;
+    assert_eq!(
+        groups,
+        vec![AggregationGroup::new(None, 0, AggregationValue::Number(Some(0.0)))]
+    );
+    let groups = util::get_aggregation(
+        db,
+        AllVertexQuery
+            .aggregate(age, AggregateFunction::Sum)?
+            .group_by(GroupBy::Type),
+    )?;
+    assert!(groups.is_empty());
+    Ok(())
+}
+
+pub fn should_group_by_type<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, inbound_ids) = util::create_edges(db)?;
+    let weight = Identifier::new("weight")?;
+    let other_t = Identifier::new("other_edge_type")?;
+    db.create_edge(&Edge::new(outbound_id, other_t, inbound_ids[0]))?;
+    db.set_properties(AllEdgeQuery, weight, &ijson!(2))?;
+
+    let q = AllEdgeQuery
+        .aggregate(weight, AggregateFunction::Sum)?
+        .group_by(GroupBy::Type);
+    assert_eq!(
+        util::get_aggregation(db, q)?,
+        vec![
+            AggregationGroup::new(Some(ijson!("other_edge_type")), 1, AggregationValue::Number(Some(2.0))),
+            AggregationGroup::new(Some(ijson!("test_edge_type")), 5, AggregationValue::Number(Some(10.0))),
+        ]
+    );
+    Ok(())
+}
+
+pub fn should_group_by_property<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = create_aged_vertices(db)?;
+    let team = Identifier::new("team")?;
+    db.set_properties(SpecificVertexQuery::new(ids[..2].to_vec()), team, &ijson!("red"))?;
+    db.set_properties(SpecificVertexQuery::single(ids[2]), team, &ijson!("blue"))?;
+
+    let q = AllVertexQuery
+        .aggregate(Identifier::new("age")?, AggregateFunction::Max)?
+        .group_by(GroupBy::Property(team));
+    assert_eq!(
+        util::get_aggregation(db, q)?,
+        vec![
+            AggregationGroup::new(
+                Some(Json::new(serde_json::Value::Null)),
+                0,
+                AggregationValue::Number(None)
+            ),
+            AggregationGroup::new(Some(ijson!("blue")), 1, AggregationValue::Number(Some(40.0))),
+            AggregationGroup::new(Some(ijson!("red")), 2, AggregationValue::Number(Some(25.0))),
+        ]
+    );
+    Ok(())
+}
+
+pub fn should_include_aggregate_inner_query<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, _) = util::create_edges(db)?;
+    let edges = util::get_edges(db, SpecificVertexQuery::single(outbound_id).outbound()?)?;
+    let q = SpecificEdgeQuery::new(edges.clone())
+        .include()
+        .aggregate(Identifier::new("weight")?, AggregateFunction::Max)?;
+    let output = db.get(q)?;
+    assert_eq!(output.len(), 2);
+    assert_eq!(output[0], QueryOutputValue::Edges(edges));
+    Ok(())
+}
+
+pub fn should_not_aggregate_invalid_queries<D: Datastore>(_db: &Database<D>) -> Result<(), Error> {
+    let age = Identifier::new("age")?;
+    let result = AggregateQuery::new(Box::new(AllVertexQuery.count()?.into()), age, AggregateFunction::Sum);
+    expect_err!(result, errors::ValidationError::InnerQuery);
+    let result = AllVertexQuery.aggregate(age, AggregateFunction::Histogram(ijson!(0)));
+    expect_err!(result, errors::ValidationError::InvalidValue);
+    let result = AllVertexQuery.aggregate(age, AggregateFunction::Histogram(ijson!("10")));
+    expect_err!(result, errors::ValidationError::InvalidValue);
+    Ok(())
+}
+
+// Creates four vertices: three with ages of 10, 25 and 40, and one whose age
+// isn't a number. Returns the ids of the first three.
+fn create_aged_vertices<D: Datastore>(db: &Database<D>) -> Result<[Uuid; 3], Error> {
+    let t = Identifier::new("test_vertex_type")?;
+    let age = Identifier::new("age")?;
+    let mut ids = [Uuid::default(); 3];
+    for (id, value) in ids.iter_mut().zip([10, 25, 40]) {
+        *id = db.create_vertex_from_type(t)?;
+        db.set_properties(SpecificVertexQuery::single(*id), age, &ijson!(value))?;
+    }
+    let other_id = db.create_vertex_from_type(t)?;
+    db.set_properties(SpecificVertexQuery::single(other_id), age, &ijson!("unknown"))?;
+    Ok(ids)
+}
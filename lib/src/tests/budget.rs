@@ -0,0 +1,122 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::util;
+use crate::util::extract_paths;
+use crate::{
+    errors, expect_err, ijson, AllVertexQuery, BulkInsertItem, Database, Datastore, EdgeDirection, Error, Identifier,
+    MissingWeight, QueryBudget, QueryExt, ShortestPathQuery, SpecificVertexQuery, Vertex, WeightedShortestPathQuery,
+};
+
+pub fn should_limit_stage_rows<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, _) = util::create_edges(db)?;
+    let q = SpecificVertexQuery::single(outbound_id).outbound()?;
+
+    let result = db.get_with_budget(q.clone(), &QueryBudget::new().max_stage_rows(4));
+    expect_err!(result, Error::BudgetExceeded(errors::BudgetLimit::StageRows));
+    let output = db.get_with_budget(q, &QueryBudget::new().max_stage_rows(5))?;
+    assert_eq!(output.len(), 1);
+
+    let result = db.get_with_budget(AllVertexQuery, &QueryBudget::new().max_stage_rows(5));
+    expect_err!(result, Error::BudgetExceeded(errors::BudgetLimit::StageRows));
+    Ok(())
+}
+
+pub fn should_limit_output_rows<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, _) = util::create_edges(db)?;
+    // Outputs the vertex, and then its 5 edges
+    let q = SpecificVertexQuery::single(outbound_id).include().outbound()?;
+
+    let result = db.get_with_budget(q.clone(), &QueryBudget::new().max_output_rows(5));
+    expect_err!(result, Error::BudgetExceeded(errors::BudgetLimit::OutputRows));
+    let output = db.get_with_budget(q, &QueryBudget::new().max_output_rows(6))?;
+    assert_eq!(output.len(), 2);
+    Ok(())
+}
+
+pub fn should_time_out_query<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, _) = util::create_edges(db)?;
+    let q = SpecificVertexQuery::single(outbound_id).outbound()?;
+
+    let result = db.get_with_budget(q.clone(), &QueryBudget::new().timeout(Duration::ZERO));
+    expect_err!(result, Error::BudgetExceeded(errors::BudgetLimit::Timeout));
+    let output = db.get_with_budget(q, &QueryBudget::new().timeout(Duration::from_secs(60)))?;
+    assert_eq!(output.len(), 1);
+    Ok(())
+}
+
+pub fn should_cancel_query<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, _) = util::create_edges(db)?;
+    let q = SpecificVertexQuery::single(outbound_id).outbound()?;
+
+    let cancelled = Arc::new(AtomicBool::new(true));
+    let result = db.get_with_budget(q.clone(), &QueryBudget::new().cancelled_by(cancelled));
+    expect_err!(result, Error::Cancelled);
+
+    // Streamed queries are cancelled too
+    let budget = QueryBudget::new().cancelled_by(Arc::new(AtomicBool::new(true)));
+    let result: Result<Vec<_>, Error> = db.get_iter_with_budget(AllVertexQuery, &budget)?.collect();
+    expect_err!(result, Error::Cancelled);
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let output = db.get_with_budget(q, &QueryBudget::new().cancelled_by(cancelled))?;
+    assert_eq!(output.len(), 1);
+    Ok(())
+}
+
+pub fn should_limit_path_searches<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, inbound_ids) = util::create_edges(db)?;
+    // The path goes through the outbound vertex, so its other neighbors are
+    // all visited along the way
+    let q = ShortestPathQuery::new(inbound_ids[0], inbound_ids[4]).direction(EdgeDirection::Both);
+    let weighted_q = WeightedShortestPathQuery::new(inbound_ids[0], inbound_ids[4], Identifier::new("weight")?)
+        .direction(EdgeDirection::Both)
+        .missing_weight(MissingWeight::Default(ijson!(1)))?;
+
+    let budget = QueryBudget::new().max_stage_rows(4);
+    expect_err!(
+        db.get_with_budget(q.clone(), &budget),
+        Error::BudgetExceeded(errors::BudgetLimit::StageRows)
+    );
+    expect_err!(
+        db.get_with_budget(weighted_q.clone(), &budget),
+        Error::BudgetExceeded(errors::BudgetLimit::StageRows)
+    );
+
+    let budget = QueryBudget::new().cancelled_by(Arc::new(AtomicBool::new(true)));
+    expect_err!(db.get_with_budget(q.clone(), &budget), Error::Cancelled);
+    expect_err!(db.get_with_budget(weighted_q.clone(), &budget), Error::Cancelled);
+
+    let budget = QueryBudget::new().max_stage_rows(7);
+    let path = extract_paths(db.get_with_budget(q, &budget)?).unwrap().pop();
+    assert_eq!(path.unwrap().vertices[1].id, outbound_id);
+    let path = extract_paths(db.get_with_budget(weighted_q, &budget)?).unwrap().pop();
+    assert_eq!(path.unwrap().vertices[1].id, outbound_id);
+    Ok(())
+}
+
+pub fn should_enforce_budget_when_streaming<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let t = Identifier::new("test_vertex_type")?;
+    // Enough vertices to span multiple chunks
+    let items = (0..2500).map(|_| BulkInsertItem::Vertex(Vertex::new(t))).collect();
+    db.bulk_insert(items)?;
+
+    let mut iter = db.get_iter_with_budget(AllVertexQuery, &QueryBudget::new().max_output_rows(1500))?;
+    assert!(iter.next().unwrap().is_ok());
+    expect_err!(
+        iter.next().unwrap(),
+        Error::BudgetExceeded(errors::BudgetLimit::OutputRows)
+    );
+    assert!(iter.next().is_none());
+    // The iterator holds its transaction open until it's dropped
+    drop(iter);
+
+    let iter = db.get_iter_with_budget(AllVertexQuery, &QueryBudget::new().timeout(Duration::ZERO))?;
+    let result: Result<Vec<_>, Error> = iter.collect();
+    expect_err!(result, Error::BudgetExceeded(errors::BudgetLimit::Timeout));
+
+    let iter = db.get_iter_with_budget(AllVertexQuery, &QueryBudget::new().max_output_rows(2500))?;
+    assert_eq!(iter.collect::<Result<Vec<_>, Error>>()?.len(), 3);
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use super::util;
+use crate::{
+    errors, expect_err, ijson, AllEdgeQuery, AllVertexQuery, ContinuationToken, Database, Datastore, Edge, Error,
+    Identifier, Query, QueryExt, QueryOutputValue, RangeVertexQuery, ResumeQuery, SpecificVertexQuery, Vertex,
+    VertexWithPropertyPresenceQuery,
+};
+
+use uuid::Uuid;
+
+pub fn should_resume_all_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let t = Identifier::new("test_vertex_type")?;
+    for _ in 0..5 {
+        db.create_vertex_from_type(t)?;
+    }
+
+    let vertices = util::get_vertices(db, AllVertexQuery)?;
+    let token = ContinuationToken::after(&QueryOutputValue::Vertices(vertices[..2].to_vec())).unwrap();
+    let resumed = util::get_vertices(db, AllVertexQuery.resume(token.clone())?)?;
+    assert_eq!(resumed, vertices[2..]);
+
+    let resumed = util::get_vertices(db, RangeVertexQuery::new().limit(2).resume(token)?)?;
+    assert_eq!(resumed, vertices[2..4]);
+    Ok(())
+}
+
+pub fn should_resume_all_edges<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    util::create_edges(db)?;
+    let edges = util::get_edges(db, AllEdgeQuery)?;
+    let token = ContinuationToken::after(&QueryOutputValue::Edges(edges[..3].to_vec())).unwrap();
+    let resumed = util::get_edges(db, AllEdgeQuery.resume(token)?)?;
+    assert_eq!(resumed, edges[3..]);
+    Ok(())
+}
+
+pub fn should_page_through_outbound_pipe<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (hub_id, _) = util::create_edges(db)?;
+    let (other_id, _) = util::create_edges(db)?;
+    let q = SpecificVertexQuery::new(vec![hub_id, other_id]).outbound()?;
+    check_pages(db, q.clone().limit(3).into(), util::get_edges(db, q)?)
+}
+
+pub fn should_page_through_inbound_pipe<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let t = Identifier::new("test_vertex_type")?;
+    let edge_t = Identifier::new("test_edge_type")?;
+    let hub_id = db.create_vertex_from_type(t)?;
+    for _ in 0..5 {
+        let outbound_id = db.create_vertex_from_type(t)?;
+        db.create_edge(&Edge::new(outbound_id, edge_t, hub_id))?;
+    }
+
+    let q = SpecificVertexQuery::single(hub_id).inbound()?;
+    check_pages(db, q.clone().limit(2).into(), util::get_edges(db, q)?)
+}
+
+pub fn should_page_through_property_query<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let t = Identifier::new("test_vertex_type")?;
+    let property_name = Identifier::new("foo")?;
+    db.index_property(property_name)?;
+    let mut ids = Vec::new();
+    for _ in 0..5 {
+        ids.push(db.create_vertex_from_type(t)?);
+    }
+    db.set_properties(SpecificVertexQuery::new(ids), property_name, &ijson!(true))?;
+
+    let q = VertexWithPropertyPresenceQuery::new(property_name);
+    let vertices = util::get_vertices(db, q.clone())?;
+    assert_eq!(vertices.len(), 5);
+
+    let mut resumed = vertices[..1].to_vec();
+    while let Some(token) = ContinuationToken::after(&QueryOutputValue::Vertices(resumed.clone())) {
+        let page = util::get_vertices(db, q.clone().resume(token)?)?;
+        if page.is_empty() {
+            break;
+        }
+        resumed.push(page[0].clone());
+    }
+    assert_eq!(resumed, vertices);
+    Ok(())
+}
+
+pub fn should_get_continuation_with_chunks<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, _) = util::create_edges(db)?;
+    let q = SpecificVertexQuery::single(outbound_id).include().outbound()?;
+    let chunks = db.get_iter(q.clone())?.collect::<Result<Vec<_>, Error>>()?;
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[0].continuation, None);
+    assert_eq!(chunks[1].continuation, ContinuationToken::after(&chunks[1].value));
+    assert!(chunks[1].continuation.is_some());
+
+    let resumed = util::get_edges(db, q.resume(chunks[1].continuation.clone().unwrap())?)?;
+    assert!(resumed.is_empty());
+
+    let chunks = db
+        .get_iter(SpecificVertexQuery::single(outbound_id))?
+        .collect::<Result<Vec<_>, Error>>()?;
+    assert_eq!(chunks[0].continuation, None);
+    Ok(())
+}
+
+pub fn should_not_resume_invalid_queries<D: Datastore>(_db: &Database<D>) -> Result<(), Error> {
+    let vertex = Vertex::new(Identifier::new("test_vertex_type")?);
+    let token = ContinuationToken::after(&QueryOutputValue::Vertices(vec![vertex])).unwrap();
+    let result = SpecificVertexQuery::single(Uuid::default()).resume(token.clone());
+    expect_err!(result, errors::ValidationError::InnerQuery);
+    let result = AllEdgeQuery.outbound()?.resume(token.clone());
+    expect_err!(result, errors::ValidationError::InnerQuery);
+    let result = AllVertexQuery.both()?.resume(token.clone());
+    expect_err!(result, errors::ValidationError::InnerQuery);
+    let result = AllEdgeQuery.resume(token);
+    expect_err!(result, errors::ValidationError::InvalidValue);
+    Ok(())
+}
+
+// Pages through a query, resuming after the last result of each page, and
+// checks that the pages add up to the expected output.
+fn check_pages<D: Datastore>(db: &Database<D>, q: Query, expected: Vec<Edge>) -> Result<(), Error> {
+    let mut edges = util::get_edges(db, q.clone())?;
+    let mut pages = 1;
+    while let Some(token) = ContinuationToken::after(&QueryOutputValue::Edges(edges.clone())) {
+        let page = util::get_edges(db, ResumeQuery::new(Box::new(q.clone()), token)?)?;
+        if page.is_empty() {
+            break;
+        }
+        edges.extend(page);
+        pages += 1;
+    }
+    assert!(pages > 1);
+    assert_eq!(edges, expected);
+    Ok(())
+}
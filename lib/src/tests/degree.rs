@@ -0,0 +1,91 @@
+use super::util;
+use crate::{
+    errors, expect_err, AllEdgeQuery, BulkInsertItem, CountQueryExt, Database, Datastore, DegreeQuery, Edge, Error,
+    Identifier, QueryExt, QueryOutputValue, SpecificEdgeQuery, SpecificVertexQuery, Vertex,
+};
+
+pub fn should_get_vertex_degrees<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, inbound_ids) = util::create_edges(db)?;
+    let t = Identifier::new("test_edge_type")?;
+    let other_t = Identifier::new("other_edge_type")?;
+    db.create_edge(&Edge::new(inbound_ids[0], other_t, outbound_id))?;
+    db.create_edge(&Edge::new(outbound_id, other_t, outbound_id))?;
+
+    let q = SpecificVertexQuery::new(vec![outbound_id, inbound_ids[0], inbound_ids[1]]);
+    let degrees = util::get_degrees(db, q.clone().degree()?)?;
+    let counts: Vec<(u64, u64)> = degrees.iter().map(|d| (d.outbound, d.inbound)).collect();
+    assert_eq!(counts, vec![(6, 2), (1, 1), (0, 1)]);
+    assert_eq!(degrees[0].vertex.id, outbound_id);
+    assert_eq!(degrees[1].vertex.id, inbound_ids[0]);
+
+    let degrees = util::get_degrees(db, q.clone().degree()?.t(t))?;
+    let counts: Vec<(u64, u64)> = degrees.iter().map(|d| (d.outbound, d.inbound)).collect();
+    assert_eq!(counts, vec![(5, 0), (0, 1), (0, 1)]);
+
+    // Degrees should match the number of piped edges
+    let outbound_count = util::get_count(db, SpecificVertexQuery::single(outbound_id).outbound()?.count()?)?;
+    assert_eq!(outbound_count, 6);
+
+    let plan = db.explain(q.clone().degree()?)?;
+    assert_eq!(plan.calls, vec!["vertex_degree".to_string()]);
+
+    // Deleted edges should no longer be counted
+    db.delete(SpecificEdgeQuery::single(Edge::new(outbound_id, t, inbound_ids[1])))?;
+    let degrees = util::get_degrees(db, q.degree()?.t(t))?;
+    let counts: Vec<(u64, u64)> = degrees.iter().map(|d| (d.outbound, d.inbound)).collect();
+    assert_eq!(counts, vec![(4, 0), (0, 1), (0, 0)]);
+    Ok(())
+}
+
+pub fn should_get_degrees_of_deleted_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, inbound_ids) = util::create_edges(db)?;
+    db.delete(SpecificVertexQuery::single(inbound_ids[0]))?;
+    let degrees = util::get_degrees(db, SpecificVertexQuery::single(outbound_id).degree()?)?;
+    assert_eq!(degrees.len(), 1);
+    assert_eq!((degrees[0].outbound, degrees[0].inbound), (4, 0));
+
+    db.delete(SpecificVertexQuery::single(outbound_id))?;
+    let degrees = util::get_degrees(db, SpecificVertexQuery::new(inbound_ids[1..].to_vec()).degree()?)?;
+    assert!(degrees.iter().all(|d| d.outbound == 0 && d.inbound == 0));
+    Ok(())
+}
+
+pub fn should_get_degrees_of_bulk_inserted_edges<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let t = Identifier::new("test_vertex_type")?;
+    let edge_t = Identifier::new("test_edge_type")?;
+    let outbound = Vertex::new(t);
+    let inbound = Vertex::new(t);
+    let edge = Edge::new(outbound.id, edge_t, inbound.id);
+    db.bulk_insert(vec![
+        BulkInsertItem::Vertex(outbound.clone()),
+        BulkInsertItem::Vertex(inbound.clone()),
+        BulkInsertItem::Edge(edge.clone()),
+        BulkInsertItem::Edge(edge.clone()),
+    ])?;
+    // Inserting the same edge again shouldn't count it twice
+    db.bulk_insert(vec![BulkInsertItem::Edge(edge)])?;
+
+    let degrees = util::get_degrees(db, SpecificVertexQuery::new(vec![outbound.id, inbound.id]).degree()?)?;
+    let mut counts: Vec<(u64, u64)> = degrees.iter().map(|d| (d.outbound, d.inbound)).collect();
+    counts.sort();
+    assert_eq!(counts, vec![(0, 1), (1, 0)]);
+    Ok(())
+}
+
+pub fn should_include_degree_inner_query<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, _) = util::create_edges(db)?;
+    let vertices = util::get_vertices(db, SpecificVertexQuery::single(outbound_id))?;
+    let q = SpecificVertexQuery::single(outbound_id).include().degree()?;
+    let output = db.get(q)?;
+    assert_eq!(output.len(), 2);
+    assert_eq!(output[0], QueryOutputValue::Vertices(vertices));
+    Ok(())
+}
+
+pub fn should_not_get_degree_of_invalid_queries<D: Datastore>(_db: &Database<D>) -> Result<(), Error> {
+    let result = AllEdgeQuery.degree();
+    expect_err!(result, errors::ValidationError::InnerQuery);
+    let result = DegreeQuery::new(Box::new(AllEdgeQuery.outbound()?.count()?.into()));
+    expect_err!(result, errors::ValidationError::InnerQuery);
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use super::util;
+use crate::{
+    errors, expect_err, ijson, AllEdgeQuery, AllVertexQuery, CountQueryExt, Database, Datastore, DistinctQuery, Edge,
+    Error, Identifier, QueryExt, SpecificVertexQuery,
+};
+
+use uuid::Uuid;
+
+pub fn should_pipe_distinct_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (y, z) = create_duplicate_edges(db)?;
+
+    let vertices = util::get_vertices(db, AllEdgeQuery.inbound()?)?;
+    assert_eq!(vertices.len(), 4);
+
+    let vertices = util::get_vertices(db, AllEdgeQuery.inbound()?.distinct())?;
+    let mut ids: Vec<Uuid> = vertices.into_iter().map(|v| v.id).collect();
+    ids.sort();
+    let mut expected_ids = vec![y, z];
+    expected_ids.sort();
+    assert_eq!(ids, expected_ids);
+
+    // Duplicates shouldn't count towards the limit
+    let vertices = util::get_vertices(db, AllEdgeQuery.inbound()?.distinct().limit(2))?;
+    assert_eq!(vertices.len(), 2);
+    assert_ne!(vertices[0].id, vertices[1].id);
+    Ok(())
+}
+
+pub fn should_pipe_distinct_edges<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, _) = util::create_edges(db)?;
+    let q = SpecificVertexQuery::new(vec![outbound_id, outbound_id]).outbound()?;
+    assert_eq!(util::get_edges(db, q.clone())?.len(), 10);
+    assert_eq!(
+        util::get_edges(db, q.distinct())?,
+        util::get_edges(db, SpecificVertexQuery::single(outbound_id).outbound()?)?
+    );
+    Ok(())
+}
+
+pub fn should_get_distinct_values<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    create_duplicate_edges(db)?;
+    let vertices = util::get_vertices(db, AllEdgeQuery.inbound()?)?;
+    let q = DistinctQuery::new(Box::new(AllEdgeQuery.inbound()?.into()))?;
+    let distinct_vertices = util::get_vertices(db, q)?;
+    assert_eq!(distinct_vertices.len(), 2);
+    // The first occurrence of each vertex should be kept, in order
+    assert_eq!(distinct_vertices[0], vertices[0]);
+    assert_eq!(
+        distinct_vertices[1],
+        vertices.into_iter().find(|v| v.id != distinct_vertices[0].id).unwrap()
+    );
+
+    db.set_properties(AllVertexQuery, Identifier::new("foo")?, &ijson!(true))?;
+    let q = DistinctQuery::new(Box::new(AllEdgeQuery.inbound()?.properties()?.into()))?;
+    let properties = db.get(q)?;
+    assert_eq!(properties.len(), 1);
+    match properties[0] {
+        crate::QueryOutputValue::VertexProperties(ref properties) => assert_eq!(properties.len(), 2),
+        ref value => panic!("unexpected value: {value:?}"),
+    }
+    Ok(())
+}
+
+pub fn should_count_distinct<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    create_duplicate_edges(db)?;
+    assert_eq!(util::get_count(db, AllEdgeQuery.inbound()?.count()?)?, 4);
+    assert_eq!(util::get_count(db, AllEdgeQuery.inbound()?.count_distinct()?)?, 2);
+    assert_eq!(util::get_count(db, AllVertexQuery.count_distinct()?)?, 3);
+    Ok(())
+}
+
+pub fn should_not_get_distinct_count<D: Datastore>(_db: &Database<D>) -> Result<(), Error> {
+    let result = DistinctQuery::new(Box::new(AllVertexQuery.count()?.into()));
+    expect_err!(result, errors::ValidationError::InnerQuery);
+    Ok(())
+}
+
+// Creates a vertex with three edges to `y` and one to `z`.
+fn create_duplicate_edges<D: Datastore>(db: &Database<D>) -> Result<(Uuid, Uuid), Error> {
+    let t = Identifier::new("test_vertex_type")?;
+    let x = db.create_vertex_from_type(t)?;
+    let y = db.create_vertex_from_type(t)?;
+    let z = db.create_vertex_from_type(t)?;
+    for edge_t in ["follows", "likes", "trusts"] {
+        db.create_edge(&Edge::new(x, Identifier::new(edge_t)?, y))?;
+    }
+    db.create_edge(&Edge::new(x, Identifier::new("follows")?, z))?;
+    Ok((y, z))
+}
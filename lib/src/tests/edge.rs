@@ -2,8 +2,9 @@ use std::collections::HashSet;
 
 use super::util;
 use crate::{
-    ijson, models, AllEdgeQuery, Database, Datastore, Edge, EdgeDirection, EdgeWithPropertyValueQuery, Error,
-    Identifier, QueryExt, SpecificEdgeQuery, SpecificVertexQuery,
+    ijson, models, AllEdgeQuery, ContinuationToken, CountQueryExt, Database, Datastore, Edge, EdgeDirection,
+    EdgeWithPropertyValueQuery, EdgesByTypeQuery, Error, Identifier, QueryExt, QueryOutputValue, SpecificEdgeQuery,
+    SpecificVertexQuery,
 };
 
 use uuid::Uuid;
@@ -221,6 +222,83 @@ pub fn should_get_edges_piped<D: Datastore>(db: &Database<D>) -> Result<(), Erro
     Ok(())
 }
 
+pub fn should_get_edges_piped_in_both_directions<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let vertex_t = models::Identifier::new("test_vertex_type")?;
+    let t = models::Identifier::new("test_edge_type")?;
+    let other_t = models::Identifier::new("other_edge_type")?;
+    let id = db.create_vertex_from_type(vertex_t)?;
+    let outbound_id = util::create_edge_from(db, id)?;
+    let inbound_id = db.create_vertex_from_type(vertex_t)?;
+    let other_id = db.create_vertex_from_type(vertex_t)?;
+    let edges = [
+        Edge::new(id, t, outbound_id),
+        Edge::new(inbound_id, t, id),
+        Edge::new(other_id, other_t, id),
+        Edge::new(id, t, id),
+    ];
+    for edge in &edges[1..] {
+        db.create_edge(edge)?;
+    }
+
+    // Edges keep their orientation, and loops are only returned once
+    let q = SpecificVertexQuery::single(id).both()?;
+    let range = util::get_edges(db, q.clone())?;
+    assert_eq!(range.len(), 4);
+    assert_eq!(
+        range.iter().cloned().collect::<HashSet<Edge>>(),
+        HashSet::from(edges.clone())
+    );
+    let range = util::get_edges(db, q.clone().t(t))?;
+    assert_eq!(range.len(), 3);
+    assert!(range.iter().all(|e| e.t == t));
+    assert_eq!(util::get_edges(db, q.limit(2))?.len(), 2);
+    assert_eq!(util::get_edge_count(db, id, Some(t), EdgeDirection::Both)?, 3);
+
+    // Edges are piped to both of their ends
+    let q = SpecificEdgeQuery::single(edges[1].clone()).both()?;
+    let ids: Vec<Uuid> = util::get_vertices(db, q.clone())?.into_iter().map(|v| v.id).collect();
+    assert_eq!(ids, vec![inbound_id, id]);
+    assert_eq!(util::get_vertices(db, q.limit(1))?.len(), 1);
+    let q = SpecificEdgeQuery::new(edges[..2].to_vec()).both()?.distinct();
+    assert_eq!(util::get_vertices(db, q)?.len(), 3);
+    Ok(())
+}
+
+pub fn should_get_edges_by_type<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, inbound_ids) = util::create_edges(db)?;
+    let t = models::Identifier::new("test_edge_type")?;
+    let other_t = models::Identifier::new("other_edge_type")?;
+    db.create_edge(&Edge::new(inbound_ids[0], other_t, outbound_id))?;
+    let mut edges: Vec<Edge> = inbound_ids.iter().map(|id| Edge::new(outbound_id, t, *id)).collect();
+    edges.sort();
+
+    assert_eq!(util::get_edges(db, EdgesByTypeQuery::new(t))?, edges);
+    assert_eq!(util::get_edges(db, EdgesByTypeQuery::new(t).limit(2))?, edges[..2]);
+    let plan = db.explain(EdgesByTypeQuery::new(t))?;
+    assert_eq!(plan.calls, vec!["edges_with_type".to_string()]);
+
+    // Only the vertex IDs of the start edge are used
+    let start = Edge::new(outbound_id, other_t, edges[1].inbound_id);
+    let range = util::get_edges(db, EdgesByTypeQuery::new(t).start(start).limit(2))?;
+    assert_eq!(range, edges[1..3]);
+
+    let token = ContinuationToken::after(&QueryOutputValue::Edges(edges[..2].to_vec())).unwrap();
+    let resumed = util::get_edges(db, EdgesByTypeQuery::new(t).resume(token)?)?;
+    assert_eq!(resumed, edges[2..]);
+
+    assert_eq!(util::get_count(db, EdgesByTypeQuery::new(t).count()?)?, 5);
+    assert_eq!(util::get_count(db, EdgesByTypeQuery::new(t).limit(3).count()?)?, 3);
+    assert_eq!(util::get_count(db, EdgesByTypeQuery::new(other_t).count()?)?, 1);
+    let plan = db.explain(EdgesByTypeQuery::new(t).count()?)?;
+    assert_eq!(plan.calls, vec!["edge_count_with_type".to_string()]);
+
+    // Deleted edges should be removed from the index
+    db.delete(SpecificEdgeQuery::single(edges[0].clone()))?;
+    assert_eq!(util::get_edges(db, EdgesByTypeQuery::new(t))?, edges[1..]);
+    assert_eq!(util::get_count(db, EdgesByTypeQuery::new(t).count()?)?, 4);
+    Ok(())
+}
+
 /// Test for a regression, see
 /// https://github.com/indradb/indradb/issues/278#issuecomment-1515797381
 pub fn should_delete_indexed_edge_with_property_value<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
@@ -0,0 +1,122 @@
+use super::util;
+use crate::{
+    ijson, AllVertexQuery, CountQueryExt, Database, Datastore, Error, Identifier, QueryExt, QueryPlan,
+    SpecificVertexQuery, VertexWithPropertyPresenceQuery,
+};
+
+pub fn should_explain_query<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, _) = util::create_edges(db)?;
+    let q = SpecificVertexQuery::single(outbound_id)
+        .outbound()?
+        .limit(3)
+        .inbound()?
+        .count()?;
+    let plan = db.explain(q)?;
+
+    assert_eq!(stages(&plan), vec!["Count", "Pipe", "Pipe", "SpecificVertex"]);
+    assert_eq!(plan.estimated_rows, Some(1));
+    let inbound = &plan.children[0];
+    assert_eq!(inbound.calls, vec!["specific_vertices".to_string()]);
+    assert_eq!(inbound.estimated_rows, Some(3));
+    let outbound = &inbound.children[0];
+    assert_eq!(outbound.calls, vec!["range_edges".to_string()]);
+    assert_eq!(outbound.estimated_rows, Some(3));
+    assert_eq!(outbound.children[0].estimated_rows, Some(1));
+
+    // Nothing should have been run
+    assert_eq!(plan.actual_rows, None);
+    assert_eq!(plan.elapsed, None);
+    Ok(())
+}
+
+pub fn should_explain_optimized_count<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let plan = db.explain(AllVertexQuery.count()?)?;
+    assert_eq!(stages(&plan), vec!["Count"]);
+    assert_eq!(plan.calls, vec!["vertex_count".to_string()]);
+    Ok(())
+}
+
+pub fn should_explain_missing_index<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let name = Identifier::new("foo")?;
+    let q = VertexWithPropertyPresenceQuery::new(name);
+
+    let plan = db.explain(q.clone())?;
+    assert_eq!(plan.indexed, Some(false));
+    let result = db.analyze(q.clone());
+    assert!(matches!(result, Err(Error::NotIndexed)));
+
+    db.index_property(name)?;
+    assert_eq!(db.explain(q.clone())?.indexed, Some(true));
+    assert_eq!(db.explain(AllVertexQuery.with_property(name)?)?.indexed, Some(true));
+    assert_eq!(db.analyze(q)?.actual_rows, Some(0));
+    Ok(())
+}
+
+pub fn should_explain_scan_fallback<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let name = Identifier::new("foo")?;
+    let (outbound_id, _) = util::create_edges(db)?;
+    let q = SpecificVertexQuery::single(outbound_id).outbound()?;
+
+    let plan = db.explain(q.clone().with_property(name)?)?;
+    assert_eq!(plan.indexed, Some(false));
+    assert!(!plan.scan);
+
+    let plan = db.analyze(q.with_property_equal_to(name, ijson!(1))?.allow_scan())?;
+    assert_eq!(plan.indexed, Some(false));
+    assert!(plan.scan);
+    assert_eq!(
+        plan.calls,
+        vec!["edges_with_property_value".to_string(), "edge_property".to_string()]
+    );
+    assert_eq!(plan.actual_rows, Some(0));
+    assert!(plan.to_string().contains("not indexed, scan"));
+    Ok(())
+}
+
+pub fn should_analyze_query<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, _) = util::create_edges(db)?;
+    let q = SpecificVertexQuery::single(outbound_id)
+        .include()
+        .outbound()?
+        .inbound()?;
+    let plan = db.analyze(q.clone())?;
+
+    assert_eq!(stages(&plan), vec!["Pipe", "Pipe", "Include", "SpecificVertex"]);
+    assert_eq!(actual_rows(&plan), vec![5, 5, 1, 1]);
+    assert!(plan.elapsed.is_some());
+    assert!(plan.children[0].elapsed.unwrap() <= plan.elapsed.unwrap());
+
+    // The plan from analyzing should match the explained one, plus actuals
+    let mut explained = db.explain(q)?;
+    assert_eq!(explained.estimated_rows, plan.estimated_rows);
+    strip_actuals(&mut explained);
+    let mut analyzed = plan;
+    strip_actuals(&mut analyzed);
+    assert_eq!(analyzed, explained);
+    Ok(())
+}
+
+// Flattens the stages of a plan in depth-first order.
+fn stages(plan: &QueryPlan) -> Vec<String> {
+    let mut stages = vec![plan.stage.clone()];
+    for child in &plan.children {
+        stages.extend(self::stages(child));
+    }
+    stages
+}
+
+fn actual_rows(plan: &QueryPlan) -> Vec<u64> {
+    let mut rows = vec![plan.actual_rows.unwrap()];
+    for child in &plan.children {
+        rows.extend(actual_rows(child));
+    }
+    rows
+}
+
+fn strip_actuals(plan: &mut QueryPlan) {
+    plan.actual_rows = None;
+    plan.elapsed = None;
+    for child in &mut plan.children {
+        strip_actuals(child);
+    }
+}
@@ -1,3 +1,6 @@
+use std::collections::HashSet;
+use std::ops::Bound;
+
 use super::util;
 use crate::{expect_err, ijson, models, Database, Datastore, Error, QueryExt};
 use uuid::Uuid;
@@ -274,3 +277,204 @@ pub fn should_pipe_not_indexed_errors<D: Datastore>(db: &Database<D>) -> Result<
 
     Ok(())
 }
+
+pub fn should_query_vertex_property_range<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let property_name = models::Identifier::new("rankable-vertex-property")?;
+
+    // Ensure errors happen when attempting to query before the property is indexed
+    let result = util::get_vertices(
+        db,
+        models::VertexWithPropertyRangeQuery::new(property_name, Bound::Included(ijson!(1)), Bound::Unbounded)?,
+    );
+    expect_err!(result, Error::NotIndexed);
+
+    db.index_property(property_name)?;
+    let vertex_t = models::Identifier::new("test_vertex_type")?;
+    let mut ids = Vec::new();
+    for value in [
+        ijson!(-2.5),
+        ijson!(1),
+        ijson!(3),
+        ijson!(10),
+        ijson!("4"),
+        ijson!(null),
+    ] {
+        let id = db.create_vertex_from_type(vertex_t)?;
+        db.set_properties(models::SpecificVertexQuery::single(id), property_name, &value)?;
+        ids.push(id);
+    }
+
+    let get_ids = |low: Bound<models::Json>, high: Bound<models::Json>| -> Result<HashSet<Uuid>, Error> {
+        let q = models::VertexWithPropertyRangeQuery::new(property_name, low, high)?;
+        Ok(util::get_vertices(db, q)?.into_iter().map(|v| v.id).collect())
+    };
+
+    // Inclusive and exclusive bounds
+    assert_eq!(
+        get_ids(Bound::Included(ijson!(1)), Bound::Included(ijson!(10)))?,
+        HashSet::from([ids[1], ids[2], ids[3]])
+    );
+    assert_eq!(
+        get_ids(Bound::Excluded(ijson!(1)), Bound::Excluded(ijson!(10)))?,
+        HashSet::from([ids[2]])
+    );
+
+    // Half-open ranges only match values of the same type as the bound
+    assert_eq!(
+        get_ids(Bound::Unbounded, Bound::Excluded(ijson!(3.0)))?,
+        HashSet::from([ids[0], ids[1]])
+    );
+    assert_eq!(
+        get_ids(Bound::Excluded(ijson!(1)), Bound::Unbounded)?,
+        HashSet::from([ids[2], ids[3]])
+    );
+    assert_eq!(
+        get_ids(Bound::Included(ijson!("")), Bound::Unbounded)?,
+        HashSet::from([ids[4]])
+    );
+
+    // Inverted ranges are empty
+    assert!(get_ids(Bound::Included(ijson!(10)), Bound::Included(ijson!(1)))?.is_empty());
+    assert!(get_ids(Bound::Excluded(ijson!(3)), Bound::Excluded(ijson!(3)))?.is_empty());
+    Ok(())
+}
+
+pub fn should_query_edge_property_range<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let property_name = models::Identifier::new("rankable-edge-property")?;
+
+    let result = util::get_edges(
+        db,
+        models::EdgeWithPropertyRangeQuery::new(property_name, Bound::Unbounded, Bound::Included(ijson!("m")))?,
+    );
+    expect_err!(result, Error::NotIndexed);
+
+    db.index_property(property_name)?;
+    let vertex_t = models::Identifier::new("test_vertex_type")?;
+    let edge_t = models::Identifier::new("test_edge_type")?;
+    let outbound_id = db.create_vertex_from_type(vertex_t)?;
+    let mut edges = Vec::new();
+    for value in [ijson!("apple"), ijson!("banana"), ijson!("cherry"), ijson!(5)] {
+        let inbound_id = db.create_vertex_from_type(vertex_t)?;
+        let edge = models::Edge::new(outbound_id, edge_t, inbound_id);
+        db.create_edge(&edge)?;
+        db.set_properties(models::SpecificEdgeQuery::single(edge.clone()), property_name, &value)?;
+        edges.push(edge);
+    }
+
+    let get_edges = |low: Bound<models::Json>, high: Bound<models::Json>| -> Result<HashSet<models::Edge>, Error> {
+        let q = models::EdgeWithPropertyRangeQuery::new(property_name, low, high)?;
+        Ok(util::get_edges(db, q)?.into_iter().collect())
+    };
+
+    assert_eq!(
+        get_edges(Bound::Included(ijson!("banana")), Bound::Unbounded)?,
+        HashSet::from([edges[1].clone(), edges[2].clone()])
+    );
+    assert_eq!(
+        get_edges(Bound::Excluded(ijson!("apple")), Bound::Excluded(ijson!("cherry")))?,
+        HashSet::from([edges[1].clone()])
+    );
+    assert_eq!(
+        get_edges(Bound::Unbounded, Bound::Included(ijson!(100)))?,
+        HashSet::from([edges[3].clone()])
+    );
+
+    // Deleted edges should drop out of the range
+    db.delete(models::SpecificEdgeQuery::single(edges[1].clone()))?;
+    assert_eq!(
+        get_edges(Bound::Included(ijson!("banana")), Bound::Unbounded)?,
+        HashSet::from([edges[2].clone()])
+    );
+    Ok(())
+}
+
+pub fn should_pipe_property_range<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let property_name = models::Identifier::new("pipeable-range-property")?;
+    let (outbound_id, inbound_ids) = util::create_edges(db)?;
+    let q = models::SpecificVertexQuery::single(outbound_id).outbound()?.inbound()?;
+
+    let result = util::get_vertices(db, q.clone().with_property_greater_than(property_name, ijson!(0))?);
+    expect_err!(result, Error::NotIndexed);
+
+    db.index_property(property_name)?;
+    for (i, id) in inbound_ids.iter().enumerate() {
+        db.set_properties(models::SpecificVertexQuery::single(*id), property_name, &ijson!(i))?;
+    }
+    // A vertex outside of the piped query should not be included
+    let other_id = db.create_vertex_from_type(models::Identifier::new("test_vertex_type")?)?;
+    db.set_properties(models::SpecificVertexQuery::single(other_id), property_name, &ijson!(2))?;
+
+    let get_ids = |q: models::PipeWithPropertyRangeQuery| -> Result<HashSet<Uuid>, Error> {
+        Ok(util::get_vertices(db, q)?.into_iter().map(|v| v.id).collect())
+    };
+
+    assert_eq!(
+        get_ids(q.clone().with_property_greater_than(property_name, ijson!(2))?)?,
+        HashSet::from([inbound_ids[3], inbound_ids[4]])
+    );
+    assert_eq!(
+        get_ids(q.clone().with_property_less_than(property_name, ijson!(2))?)?,
+        HashSet::from([inbound_ids[0], inbound_ids[1]])
+    );
+    assert_eq!(
+        get_ids(q.with_property_between(property_name, ijson!(1), ijson!(3))?)?,
+        HashSet::from([inbound_ids[1], inbound_ids[2], inbound_ids[3]])
+    );
+    Ok(())
+}
+
+pub fn should_scan_unindexed_vertex_property<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let name = models::Identifier::new("foo")?;
+    let (outbound_id, inbound_ids) = util::create_edges(db)?;
+    db.set_properties(models::SpecificVertexQuery::single(inbound_ids[0]), name, &ijson!(1))?;
+    db.set_properties(models::SpecificVertexQuery::single(inbound_ids[1]), name, &ijson!(2))?;
+    let q = models::SpecificVertexQuery::single(outbound_id).outbound()?.inbound()?;
+
+    let result = util::get_vertices(db, q.clone().with_property(name)?);
+    expect_err!(result, Error::NotIndexed);
+
+    let vertices = util::get_vertices(db, q.clone().with_property(name)?.allow_scan())?;
+    let ids: HashSet<Uuid> = vertices.into_iter().map(|v| v.id).collect();
+    assert_eq!(ids, HashSet::from([inbound_ids[0], inbound_ids[1]]));
+    let vertices = util::get_vertices(db, q.clone().without_property(name)?.allow_scan())?;
+    assert_eq!(vertices.len(), 3);
+    let vertices = util::get_vertices(db, q.clone().with_property_equal_to(name, ijson!(2))?.allow_scan())?;
+    assert_eq!(vertices.len(), 1);
+    assert_eq!(vertices[0].id, inbound_ids[1]);
+    let vertices = util::get_vertices(db, q.with_property_not_equal_to(name, ijson!(2))?.allow_scan())?;
+    assert_eq!(vertices.len(), 4);
+    Ok(())
+}
+
+pub fn should_scan_unindexed_edge_property<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let name = models::Identifier::new("foo")?;
+    let (outbound_id, _) = util::create_edges(db)?;
+    let q = models::SpecificVertexQuery::single(outbound_id).outbound()?;
+    let edges = util::get_edges(db, q.clone())?;
+    db.set_properties(models::SpecificEdgeQuery::single(edges[0].clone()), name, &ijson!(true))?;
+
+    let result = util::get_edges(db, q.clone().with_property_equal_to(name, ijson!(true))?);
+    expect_err!(result, Error::NotIndexed);
+
+    let scanned = util::get_edges(db, q.clone().with_property(name)?.allow_scan())?;
+    assert_eq!(scanned, vec![edges[0].clone()]);
+    let scanned = util::get_edges(db, q.clone().with_property_equal_to(name, ijson!(true))?.allow_scan())?;
+    assert_eq!(scanned, vec![edges[0].clone()]);
+    let scanned = util::get_edges(db, q.without_property(name)?.allow_scan())?;
+    assert_eq!(scanned, edges[1..].to_vec());
+    Ok(())
+}
+
+pub fn should_prefer_index_over_scan<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let name = models::Identifier::new("foo")?;
+    let id = setup_vertex_with_indexed_property(db, name)?;
+    let q = models::AllVertexQuery.with_property(name)?.allow_scan();
+    let vertices = util::get_vertices(db, q.clone())?;
+    assert_eq!(vertices.len(), 1);
+    assert_eq!(vertices[0].id, id);
+    // Filters of indexed properties are still rewritten into index lookups
+    let plan = db.explain(q)?;
+    assert!(!plan.scan);
+    assert_eq!(plan.stage, "VertexWithPropertyPresence");
+    Ok(())
+}
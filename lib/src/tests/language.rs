@@ -0,0 +1,23 @@
+use super::util;
+use crate::{ijson, CountQueryExt, Database, Datastore, Error, Identifier, Query, QueryExt, SpecificVertexQuery};
+
+pub fn should_get_with_text_query<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, inbound_ids) = util::create_edges(db)?;
+    let name = Identifier::new("foo")?;
+    db.index_property(name)?;
+    db.set_properties(SpecificVertexQuery::new(inbound_ids[..2].to_vec()), name, &ijson!(true))?;
+
+    let text = format!("V([{outbound_id}]).out(test_edge_type).in().has(foo == true).count()");
+    let q: Query = text.parse().unwrap();
+    let expected: Query = SpecificVertexQuery::single(outbound_id)
+        .outbound()?
+        .t(Identifier::new("test_edge_type")?)
+        .inbound()?
+        .with_property_equal_to(name, ijson!(true))?
+        .count()?
+        .into();
+    assert_eq!(q, expected);
+    assert_eq!(q.to_string(), text);
+    assert_eq!(util::get_count(db, q)?, 2);
+    Ok(())
+}
@@ -40,6 +40,7 @@ macro_rules! full_test_impl {
         define_test!(should_get_no_vertices_with_zero_limit, $code);
         define_test!(should_get_range_vertices_out_of_range, $code);
         define_test!(should_get_no_vertices_with_type_filter, $code);
+        define_test!(should_get_vertices_with_type_filter, $code);
         define_test!(should_get_single_vertex, $code);
         define_test!(should_get_single_vertex_nonexisting, $code);
         define_test!(should_get_vertices, $code);
@@ -67,6 +68,8 @@ macro_rules! full_test_impl {
         define_test!(should_get_edge_range, $code);
         define_test!(should_get_edges, $code);
         define_test!(should_get_edges_piped, $code);
+        define_test!(should_get_edges_piped_in_both_directions, $code);
+        define_test!(should_get_edges_by_type, $code);
         define_test!(should_delete_indexed_edge_with_property_value, $code);
 
         // Include queries
@@ -87,6 +90,12 @@ macro_rules! full_test_impl {
         define_test!(should_query_indexed_edge_property_empty, $code);
         define_test!(should_get_vertex_with_property_value_empty, $code);
         define_test!(should_pipe_not_indexed_errors, $code);
+        define_test!(should_query_vertex_property_range, $code);
+        define_test!(should_query_edge_property_range, $code);
+        define_test!(should_pipe_property_range, $code);
+        define_test!(should_scan_unindexed_vertex_property, $code);
+        define_test!(should_scan_unindexed_edge_property, $code);
+        define_test!(should_prefer_index_over_scan, $code);
 
         // Properties
         define_test!(should_handle_vertex_properties, $code);
@@ -102,5 +111,127 @@ macro_rules! full_test_impl {
         define_test!(should_not_set_properties_on_count, $code);
         define_test!(should_not_pipe_properties_on_vertex_count, $code);
         define_test!(should_not_pipe_property_presence_on_vertex_count, $code);
+
+        // Streaming
+        define_test!(should_stream_all_vertices, $code);
+        define_test!(should_stream_all_edges, $code);
+        define_test!(should_stream_empty_output, $code);
+        define_test!(should_stream_nested_query, $code);
+
+        // Continuations
+        define_test!(should_resume_all_vertices, $code);
+        define_test!(should_resume_all_edges, $code);
+        define_test!(should_page_through_outbound_pipe, $code);
+        define_test!(should_page_through_inbound_pipe, $code);
+        define_test!(should_page_through_property_query, $code);
+        define_test!(should_get_continuation_with_chunks, $code);
+        define_test!(should_not_resume_invalid_queries, $code);
+
+        // Set operations
+        define_test!(should_union_vertices, $code);
+        define_test!(should_intersect_vertices, $code);
+        define_test!(should_except_vertices, $code);
+        define_test!(should_combine_edges, $code);
+        define_test!(should_include_set_operation_operands, $code);
+        define_test!(should_not_combine_mismatched_queries, $code);
+
+        // Distinct
+        define_test!(should_pipe_distinct_vertices, $code);
+        define_test!(should_pipe_distinct_edges, $code);
+        define_test!(should_get_distinct_values, $code);
+        define_test!(should_count_distinct, $code);
+        define_test!(should_not_get_distinct_count, $code);
+
+        // Order by
+        define_test!(should_order_vertices_by_property, $code);
+        define_test!(should_get_top_k_vertices, $code);
+        define_test!(should_order_edges_by_property, $code);
+        define_test!(should_include_order_by_inner_query, $code);
+        define_test!(should_not_order_count, $code);
+
+        // Aggregations
+        define_test!(should_aggregate_vertex_property, $code);
+        define_test!(should_get_histogram, $code);
+        define_test!(should_aggregate_nothing, $code);
+        define_test!(should_group_by_type, $code);
+        define_test!(should_group_by_property, $code);
+        define_test!(should_include_aggregate_inner_query, $code);
+        define_test!(should_not_aggregate_invalid_queries, $code);
+
+        // Degrees
+        define_test!(should_get_vertex_degrees, $code);
+        define_test!(should_get_degrees_of_deleted_vertices, $code);
+        define_test!(should_get_degrees_of_bulk_inserted_edges, $code);
+        define_test!(should_include_degree_inner_query, $code);
+        define_test!(should_not_get_degree_of_invalid_queries, $code);
+
+        // Explain
+        define_test!(should_explain_query, $code);
+        define_test!(should_explain_optimized_count, $code);
+        define_test!(should_explain_missing_index, $code);
+        define_test!(should_explain_scan_fallback, $code);
+        define_test!(should_analyze_query, $code);
+
+        // Language
+        define_test!(should_get_with_text_query, $code);
+
+        // Optimizer
+        define_test!(should_optimize_vertex_property_filters, $code);
+        define_test!(should_optimize_edge_property_filters, $code);
+        define_test!(should_optimize_unindexed_property_filters, $code);
+        define_test!(should_optimize_range_scans, $code);
+        define_test!(should_optimize_type_filters, $code);
+        define_test!(should_optimize_redundant_distinct, $code);
+        define_test!(should_optimize_nested_includes, $code);
+
+        // Predicates
+        define_test!(should_filter_vertices_by_predicate, $code);
+        define_test!(should_filter_edges_by_predicate, $code);
+        define_test!(should_not_filter_by_invalid_predicate, $code);
+
+        // Traversals
+        define_test!(should_traverse_variable_hops, $code);
+        define_test!(should_traverse_cycles_once, $code);
+        define_test!(should_traverse_with_frontiers, $code);
+        define_test!(should_not_traverse_invalid_depths, $code);
+        define_test!(should_get_shortest_path, $code);
+        define_test!(should_get_shortest_of_many_paths, $code);
+        define_test!(should_not_get_missing_shortest_path, $code);
+        define_test!(should_get_weighted_shortest_path, $code);
+        define_test!(should_handle_missing_weights, $code);
+
+        // Subgraphs
+        define_test!(should_get_subgraph, $code);
+        define_test!(should_limit_subgraph_size, $code);
+        define_test!(should_get_subgraph_with_properties, $code);
+        define_test!(should_include_subgraph_inner_query, $code);
+        define_test!(should_not_get_subgraph_of_invalid_queries, $code);
+
+        // Budgets
+        define_test!(should_limit_stage_rows, $code);
+        define_test!(should_limit_output_rows, $code);
+        define_test!(should_time_out_query, $code);
+        define_test!(should_cancel_query, $code);
+        define_test!(should_limit_path_searches, $code);
+        define_test!(should_enforce_budget_when_streaming, $code);
+
+        // Prepared queries
+        define_test!(should_run_prepared_query, $code);
+        define_test!(should_bind_value_params, $code);
+        define_test!(should_not_run_query_with_missing_params, $code);
+        define_test!(should_not_prepare_invalid_queries, $code);
+
+        // Projections
+        define_test!(should_project_rows, $code);
+        define_test!(should_filter_projected_rows, $code);
+        define_test!(should_limit_projected_rows, $code);
+        define_test!(should_not_project_invalid_queries, $code);
+
+        // Transactions
+        define_test!(should_commit_transaction, $code);
+        define_test!(should_rollback_transaction, $code);
+        define_test!(should_rollback_deletes, $code);
+        define_test!(should_rollback_dropped_transaction, $code);
+        define_test!(should_not_rollback_committed_transaction, $code);
     };
 }
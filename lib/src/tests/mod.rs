@@ -4,22 +4,56 @@
 //! `indradb` crate can reuse them. Generally you can use the convenience macro
 //! `full_test_impl`.
 
+mod aggregation;
+mod budget;
 mod bulk_insert;
+mod continuation;
+mod degree;
+mod distinct;
 mod edge;
+mod explain;
 mod include_query;
 mod indexing;
+mod language;
 #[macro_use]
 mod macros;
+mod optimizer;
+mod order_by;
+mod predicate;
+mod prepared;
+mod projection;
 mod properties;
+mod set_operation;
+mod streaming;
+mod subgraph;
 mod sync;
+mod transaction;
+mod traversal;
 mod util;
 mod vertex;
 
+pub use self::aggregation::*;
+pub use self::budget::*;
 pub use self::bulk_insert::*;
+pub use self::continuation::*;
+pub use self::degree::*;
+pub use self::distinct::*;
 pub use self::edge::*;
+pub use self::explain::*;
 pub use self::include_query::*;
 pub use self::indexing::*;
+pub use self::language::*;
 pub use self::macros::*;
+pub use self::optimizer::*;
+pub use self::order_by::*;
+pub use self::predicate::*;
+pub use self::prepared::*;
+pub use self::projection::*;
 pub use self::properties::*;
+pub use self::set_operation::*;
+pub use self::streaming::*;
+pub use self::subgraph::*;
 pub use self::sync::*;
+pub use self::transaction::*;
+pub use self::traversal::*;
 pub use self::vertex::*;
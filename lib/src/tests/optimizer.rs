@@ -0,0 +1,150 @@
+use super::util;
+use crate::{
+    ijson, AllEdgeQuery, AllVertexQuery, CountQueryExt, Database, Datastore, DistinctQuery, Edge, EdgesByTypeQuery,
+    Error, Identifier, Query, QueryExt, RangeVertexQuery, SpecificEdgeQuery, SpecificVertexQuery,
+};
+
+pub fn should_optimize_vertex_property_filters<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let name = Identifier::new("foo")?;
+    db.index_property(name)?;
+    create_vertices_with_property(db, name)?;
+
+    check_optimized(db, AllVertexQuery.with_property(name)?)?;
+    check_optimized(db, AllVertexQuery.with_property_equal_to(name, ijson!(1))?)?;
+    check_optimized(db, AllVertexQuery.with_property_between(name, ijson!(1), ijson!(2))?)?;
+    check_optimized(db, RangeVertexQuery::new().with_property_greater_than(name, ijson!(0))?)?;
+    check_optimized(db, AllVertexQuery.with_property_equal_to(name, ijson!(2))?.outbound()?)?;
+    Ok(())
+}
+
+pub fn should_optimize_edge_property_filters<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let name = Identifier::new("foo")?;
+    db.index_property(name)?;
+    let (outbound_id, _) = util::create_edges(db)?;
+    let edges = util::get_edges(db, SpecificVertexQuery::single(outbound_id).outbound()?)?;
+    db.set_properties(SpecificEdgeQuery::new(edges[..2].to_vec()), name, &ijson!(1))?;
+    db.set_properties(SpecificEdgeQuery::new(edges[2..3].to_vec()), name, &ijson!(2))?;
+
+    check_optimized(db, AllEdgeQuery.with_property(name)?)?;
+    check_optimized(db, AllEdgeQuery.with_property_equal_to(name, ijson!(1))?)?;
+    check_optimized(db, AllEdgeQuery.with_property_less_than(name, ijson!(2))?.inbound()?)?;
+    Ok(())
+}
+
+pub fn should_optimize_unindexed_property_filters<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let name = Identifier::new("foo")?;
+    create_vertices_with_property(db, name)?;
+    let q = AllVertexQuery.with_property_equal_to(name, ijson!(1))?;
+    assert!(matches!(db.get_unoptimized(q.clone()), Err(Error::NotIndexed)));
+    assert!(matches!(db.get(q), Err(Error::NotIndexed)));
+    Ok(())
+}
+
+pub fn should_optimize_range_scans<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    util::create_edges(db)?;
+    let q: Query = RangeVertexQuery::new().count()?.into();
+    let expected: Query = AllVertexQuery.count()?.into();
+    assert_eq!(q.clone().optimize(), expected);
+    assert_eq!(db.get(q.clone())?, db.get_unoptimized(q)?);
+
+    // Range scans with a type filter or limit aren't full scans
+    let t = Identifier::new("test_inbound_vertex_type")?;
+    let q: Query = RangeVertexQuery::new().t(t).into();
+    assert_eq!(q.clone().optimize(), q);
+    let q: Query = RangeVertexQuery::new().limit(2).into();
+    assert_eq!(q.clone().optimize(), q);
+    Ok(())
+}
+
+pub fn should_optimize_type_filters<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, inbound_ids) = util::create_edges(db)?;
+    let (other_outbound_id, _) = util::create_edges(db)?;
+    let t = Identifier::new("test_edge_type")?;
+    let other_t = Identifier::new("other_edge_type")?;
+    for inbound_id in &inbound_ids[..2] {
+        db.create_edge(&Edge::new(other_outbound_id, other_t, *inbound_id))?;
+        db.create_edge(&Edge::new(*inbound_id, t, outbound_id))?;
+    }
+
+    let q: Query = AllVertexQuery.outbound()?.t(t).into();
+    let expected: Query = EdgesByTypeQuery::new(t).into();
+    assert_eq!(q.clone().optimize(), expected);
+    assert_eq!(util::get_edges(db, q.clone())?.len(), 12);
+    check_optimized(db, q)?;
+    check_optimized(db, AllVertexQuery.outbound()?.t(other_t))?;
+    check_optimized(db, AllVertexQuery.outbound()?.limit(7).t(t).count()?)?;
+
+    // Inbound edges are sorted by inbound ID, unlike the type index
+    let q: Query = AllVertexQuery.inbound()?.t(t).into();
+    assert_eq!(q.clone().optimize(), q);
+    Ok(())
+}
+
+pub fn should_optimize_redundant_distinct<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, _) = util::create_edges(db)?;
+    check_optimized(db, AllVertexQuery.distinct()?)?;
+    check_optimized(db, AllEdgeQuery.distinct()?.distinct()?)?;
+    check_optimized(
+        db,
+        AllVertexQuery
+            .union(SpecificVertexQuery::single(outbound_id))?
+            .distinct()?,
+    )?;
+
+    // Piped vertices can be duplicated, so they still need to be deduped
+    let q: Query = DistinctQuery::new(Box::new(AllEdgeQuery.outbound()?.into()))?.into();
+    assert_eq!(q.clone().optimize(), q);
+    Ok(())
+}
+
+pub fn should_optimize_nested_includes<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, _) = util::create_edges(db)?;
+    let q: Query = SpecificVertexQuery::single(outbound_id)
+        .include()
+        .include()
+        .outbound()?
+        .include()
+        .include()
+        .count()?
+        .into();
+    let expected: Query = SpecificVertexQuery::single(outbound_id)
+        .include()
+        .outbound()?
+        .include()
+        .count()?
+        .into();
+    assert_eq!(q.clone().optimize(), expected);
+    let output = db.get(q.clone())?;
+    assert_eq!(output.len(), 3);
+    assert_eq!(output, db.get_unoptimized(q)?);
+    Ok(())
+}
+
+// Checks that a query is rewritten by the optimizer, and that the rewritten
+// query outputs the same values as the original.
+fn check_optimized<D: Datastore, Q: Into<Query>>(db: &Database<D>, q: Q) -> Result<(), Error> {
+    let q = q.into();
+    assert_ne!(q.clone().optimize(), q);
+    let output = db.get(q.clone())?;
+    assert_eq!(output, db.get_unoptimized(q)?);
+    Ok(())
+}
+
+// Creates vertices with the property `name` set to 1, 2 and 3, and another
+// vertex without it. Each vertex has an edge to the next one.
+fn create_vertices_with_property<D: Datastore>(db: &Database<D>, name: Identifier) -> Result<(), Error> {
+    let t = Identifier::new("test_vertex_type")?;
+    let edge_t = Identifier::new("test_edge_type")?;
+    let mut previous_id = None;
+    for i in 0..4 {
+        let id = db.create_vertex_from_type(t)?;
+        if i < 3 {
+            db.set_properties(SpecificVertexQuery::single(id), name, &ijson!(i + 1))?;
+        }
+        if let Some(previous_id) = previous_id {
+            db.create_edge(&Edge::new(previous_id, edge_t, id))?;
+        }
+        previous_id = Some(id);
+    }
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use super::util;
+use crate::{
+    errors, ijson, AllEdgeQuery, AllVertexQuery, CountQueryExt, Database, Datastore, Edge, Error, Identifier,
+    OrderByQuery, QueryExt, SpecificEdgeQuery, SpecificVertexQuery,
+};
+
+use uuid::Uuid;
+
+pub fn should_order_vertices_by_property<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (ids, missing_id) = create_ranked_vertices(db)?;
+    let rank = Identifier::new("rank")?;
+
+    let q = SpecificVertexQuery::new(vec![ids[1], missing_id, ids[2], ids[0]]).order_by(rank)?;
+    let ordered: Vec<Uuid> = util::get_vertices(db, q.clone())?.into_iter().map(|v| v.id).collect();
+    assert_eq!(ordered, vec![ids[0], ids[1], ids[2], missing_id]);
+
+    // Vertices without the property should still go last
+    let ordered: Vec<Uuid> = util::get_vertices(db, q.descending())?
+        .into_iter()
+        .map(|v| v.id)
+        .collect();
+    assert_eq!(ordered, vec![ids[2], ids[1], ids[0], missing_id]);
+    Ok(())
+}
+
+pub fn should_get_top_k_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (ids, _) = create_ranked_vertices(db)?;
+    let rank = Identifier::new("rank")?;
+
+    let q = AllVertexQuery.order_by(rank)?.descending().limit(2);
+    let ordered: Vec<Uuid> = util::get_vertices(db, q)?.into_iter().map(|v| v.id).collect();
+    assert_eq!(ordered, vec![ids[2], ids[1]]);
+
+    assert!(util::get_vertices(db, AllVertexQuery.order_by(rank)?.limit(0))?.is_empty());
+    assert_eq!(
+        util::get_count(db, AllVertexQuery.order_by(rank)?.limit(3).count()?)?,
+        3
+    );
+    Ok(())
+}
+
+pub fn should_order_edges_by_property<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, inbound_ids) = util::create_edges(db)?;
+    let weight = Identifier::new("weight")?;
+    let edge_t = Identifier::new("test_edge_type")?;
+    let edges: Vec<Edge> = inbound_ids
+        .iter()
+        .map(|id| Edge::new(outbound_id, edge_t, *id))
+        .collect();
+    for (i, edge) in edges.iter().enumerate() {
+        db.set_properties(SpecificEdgeQuery::single(edge.clone()), weight, &ijson!(i % 3))?;
+    }
+
+    let ordered = util::get_edges(db, AllEdgeQuery.order_by(weight)?)?;
+    let weights: Vec<usize> = ordered
+        .iter()
+        .map(|e| edges.iter().position(|edge| edge == e).unwrap() % 3)
+        .collect();
+    assert_eq!(weights, vec![0, 0, 1, 1, 2]);
+    Ok(())
+}
+
+pub fn should_include_order_by_inner_query<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (ids, _) = create_ranked_vertices(db)?;
+    let q = SpecificVertexQuery::new(ids.to_vec())
+        .include()
+        .order_by(Identifier::new("rank")?)?
+        .descending();
+    let output = db.get(q)?;
+    assert_eq!(output.len(), 2);
+    assert_eq!(output[0], db.get(SpecificVertexQuery::new(ids.to_vec()))?[0]);
+    Ok(())
+}
+
+pub fn should_not_order_count<D: Datastore>(_db: &Database<D>) -> Result<(), Error> {
+    let result = OrderByQuery::new(Box::new(AllVertexQuery.count()?.into()), Identifier::new("rank")?);
+    expect_err!(result, errors::ValidationError::InnerQuery);
+    Ok(())
+}
+
+// Creates three vertices with ranks 1 through 3, and one vertex without a
+// rank.
+fn create_ranked_vertices<D: Datastore>(db: &Database<D>) -> Result<([Uuid; 3], Uuid), Error> {
+    let t = Identifier::new("test_vertex_type")?;
+    let rank = Identifier::new("rank")?;
+    let mut ids = [Uuid::default(); 3];
+    for (i, id) in ids.iter_mut().enumerate() {
+        *id = db.create_vertex_from_type(t)?;
+        db.set_properties(SpecificVertexQuery::single(*id), rank, &ijson!(i + 1))?;
+    }
+    let missing_id = db.create_vertex_from_type(t)?;
+    Ok((ids, missing_id))
+}
@@ -0,0 +1,104 @@
+use super::util;
+use crate::{
+    ijson, AllEdgeQuery, AllVertexQuery, CountQueryExt, Database, Datastore, Error, Identifier, JsonType,
+    PipeWithPropertyPredicateQuery, PropertyPredicate, QueryExt, SpecificEdgeQuery, SpecificVertexQuery,
+};
+
+use uuid::Uuid;
+
+pub fn should_filter_vertices_by_predicate<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let name = Identifier::new("foo")?;
+    let ids = create_vertices_with_values(db, name)?;
+
+    check_vertices(db, name, PropertyPredicate::StartsWith("ab".to_string()), &ids[..2])?;
+    check_vertices(db, name, PropertyPredicate::EndsWith("c".to_string()), &ids[..1])?;
+    check_vertices(db, name, PropertyPredicate::Contains("b".to_string()), &ids[..3])?;
+    check_vertices(db, name, PropertyPredicate::Regex("^ab(c|d)$".to_string()), &ids[..2])?;
+    check_vertices(
+        db,
+        name,
+        PropertyPredicate::In(vec![ijson!("abc"), ijson!(1), ijson!(null)]),
+        &[ids[0], ids[5]],
+    )?;
+    check_vertices(db, name, PropertyPredicate::ArrayContains(ijson!(2)), &ids[3..4])?;
+    check_vertices(db, name, PropertyPredicate::IsNull, &ids[5..6])?;
+    check_vertices(db, name, PropertyPredicate::IsType(JsonType::String), &ids[..3])?;
+    check_vertices(db, name, PropertyPredicate::IsType(JsonType::Object), &ids[4..5])?;
+    Ok(())
+}
+
+pub fn should_filter_edges_by_predicate<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let name = Identifier::new("foo")?;
+    let (outbound_id, _) = util::create_edges(db)?;
+    let edges = util::get_edges(db, SpecificVertexQuery::single(outbound_id).outbound()?)?;
+    db.set_properties(SpecificEdgeQuery::new(edges[..2].to_vec()), name, &ijson!([1, 2]))?;
+    db.set_properties(SpecificEdgeQuery::new(edges[2..3].to_vec()), name, &ijson!([3]))?;
+
+    let q = AllEdgeQuery.with_property_matching(name, PropertyPredicate::ArrayContains(ijson!(2)))?;
+    let mut result = util::get_edges(db, q.clone())?;
+    result.sort();
+    assert_eq!(result, edges[..2].to_vec());
+    assert_eq!(util::get_count(db, q.inbound()?.count()?)?, 2);
+
+    let q = SpecificVertexQuery::single(outbound_id)
+        .outbound()?
+        .with_property_matching(name, PropertyPredicate::IsType(JsonType::Array))?;
+    assert_eq!(util::get_edges(db, q)?.len(), 3);
+    Ok(())
+}
+
+pub fn should_not_filter_by_invalid_predicate<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let name = Identifier::new("foo")?;
+    assert!(AllVertexQuery
+        .with_property_matching(name, PropertyPredicate::Regex("[".to_string()))
+        .is_err());
+    assert!(PipeWithPropertyPredicateQuery::new(
+        Box::new(AllVertexQuery.count()?.into()),
+        name,
+        PropertyPredicate::IsNull
+    )
+    .is_err());
+
+    // Vertices without the property never match
+    db.create_vertex_from_type(Identifier::new("test_vertex_type")?)?;
+    let q = AllVertexQuery.with_property_matching(name, PropertyPredicate::IsType(JsonType::Null))?;
+    assert_eq!(util::get_vertices(db, q)?.len(), 0);
+    Ok(())
+}
+
+fn check_vertices<D: Datastore>(
+    db: &Database<D>,
+    name: Identifier,
+    predicate: PropertyPredicate,
+    expected: &[Uuid],
+) -> Result<(), Error> {
+    let q = AllVertexQuery.with_property_matching(name, predicate)?;
+    let mut ids: Vec<Uuid> = util::get_vertices(db, q)?.into_iter().map(|v| v.id).collect();
+    ids.sort();
+    let mut expected = expected.to_vec();
+    expected.sort();
+    assert_eq!(ids, expected);
+    Ok(())
+}
+
+// Creates vertices with the property `name` set to various values, and
+// another vertex without it.
+fn create_vertices_with_values<D: Datastore>(db: &Database<D>, name: Identifier) -> Result<Vec<Uuid>, Error> {
+    let t = Identifier::new("test_vertex_type")?;
+    let values = [
+        ijson!("abc"),
+        ijson!("abd"),
+        ijson!("b"),
+        ijson!([1, 2]),
+        ijson!({"a": 1}),
+        ijson!(null),
+    ];
+    let mut ids = Vec::new();
+    for value in values.iter() {
+        let id = db.create_vertex_from_type(t)?;
+        db.set_properties(SpecificVertexQuery::single(id), name, value)?;
+        ids.push(id);
+    }
+    db.create_vertex_from_type(t)?;
+    Ok(ids)
+}
@@ -0,0 +1,107 @@
+use super::util;
+use crate::{
+    errors, ijson, models, AllVertexQuery, CountQueryExt, Database, Datastore, Edge, EdgeWithPropertyValueQuery, Error,
+    Identifier, Params, QueryExt, SpecificEdgeQuery, SpecificVertexQuery, VertexWithPropertyValueQuery,
+};
+
+pub fn should_run_prepared_query<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, inbound_ids) = util::create_edges(db)?;
+    let ids = Identifier::new("ids")?;
+    let n = Identifier::new("n")?;
+    let prepared = db.prepare(SpecificVertexQuery::from_param(ids).outbound()?.limit_param(n))?;
+
+    let output = db.get_prepared(&prepared, &Params::new().ids(ids, vec![outbound_id]).limit(n, 2))?;
+    let edges = crate::util::extract_edges(output).unwrap();
+    assert_eq!(edges.len(), 2);
+    assert!(edges.iter().all(|e| e.outbound_id == outbound_id));
+
+    let edges = util::get_edges(
+        db,
+        prepared.bind(&Params::new().ids(ids, vec![outbound_id]).limit(n, 10))?,
+    )?;
+    assert_eq!(edges.len(), 5);
+    let edges = util::get_edges(
+        db,
+        prepared.bind(&Params::new().ids(ids, inbound_ids.to_vec()).limit(n, 10))?,
+    )?;
+    assert!(edges.is_empty());
+
+    // The prepared query itself keeps its placeholders
+    assert_eq!(prepared.query().to_string(), "V($ids).out(limit=$n)");
+    Ok(())
+}
+
+pub fn should_bind_value_params<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, inbound_ids) = util::create_edges(db)?;
+    let name = Identifier::new("color")?;
+    let value = Identifier::new("value")?;
+    db.index_property(name)?;
+    db.set_properties(SpecificVertexQuery::single(inbound_ids[0]), name, &ijson!("red"))?;
+    db.set_properties(SpecificVertexQuery::single(inbound_ids[1]), name, &ijson!("blue"))?;
+    let t = Identifier::new("test_edge_type")?;
+    let edge = Edge::new(outbound_id, t, inbound_ids[0]);
+    db.set_properties(SpecificEdgeQuery::single(edge.clone()), name, &ijson!("red"))?;
+
+    let prepared = db.prepare(VertexWithPropertyValueQuery::new(name, ijson!(null)).value_param(value))?;
+    let vertices = util::get_vertices(db, prepared.bind(&Params::new().value(value, ijson!("blue")))?)?;
+    assert_eq!(vertices.len(), 1);
+    assert_eq!(vertices[0].id, inbound_ids[1]);
+
+    let prepared = db.prepare(
+        SpecificVertexQuery::single(outbound_id)
+            .outbound()?
+            .inbound()?
+            .with_property_equal_to(name, ijson!(null))?
+            .value_param(value),
+    )?;
+    let vertices = util::get_vertices(db, prepared.bind(&Params::new().value(value, ijson!("red")))?)?;
+    assert_eq!(vertices.len(), 1);
+    assert_eq!(vertices[0].id, inbound_ids[0]);
+
+    let prepared = db.prepare(EdgeWithPropertyValueQuery::new(name, ijson!(null)).value_param(value))?;
+    let edges = util::get_edges(db, prepared.bind(&Params::new().value(value, ijson!("red")))?)?;
+    assert_eq!(edges, vec![edge.clone()]);
+
+    let edges_param = Identifier::new("edges")?;
+    let prepared = db.prepare(SpecificEdgeQuery::from_param(edges_param).inbound()?)?;
+    let vertices = util::get_vertices(db, prepared.bind(&Params::new().edges(edges_param, vec![edge]))?)?;
+    assert_eq!(vertices.len(), 1);
+    assert_eq!(vertices[0].id, inbound_ids[0]);
+    Ok(())
+}
+
+pub fn should_not_run_query_with_missing_params<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = Identifier::new("ids")?;
+    let n = Identifier::new("n")?;
+    let prepared = db.prepare(SpecificVertexQuery::from_param(ids).outbound()?.limit_param(n))?;
+
+    let result = prepared.bind(&Params::new().ids(ids, Vec::new()));
+    expect_err!(result, errors::ValidationError::MissingParameter);
+    let result = db.get_prepared(&prepared, &Params::new().ids(ids, Vec::new()));
+    expect_err!(result, Error::Invalid(errors::ValidationError::MissingParameter));
+
+    // Values of the wrong kind are rejected
+    let result = prepared.bind(&Params::new().limit(ids, 1).limit(n, 1));
+    expect_err!(result, errors::ValidationError::InvalidValue);
+
+    // Queries with placeholders can't be run without binding them first
+    let result = db.get(SpecificVertexQuery::from_param(ids));
+    expect_err!(result, Error::Invalid(errors::ValidationError::MissingParameter));
+    let result = db.get(AllVertexQuery.outbound()?.limit_param(n).count()?);
+    expect_err!(result, Error::Invalid(errors::ValidationError::MissingParameter));
+    Ok(())
+}
+
+pub fn should_not_prepare_invalid_queries<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let q = models::PipeQuery {
+        inner: Box::new(AllVertexQuery.count()?.into()),
+        direction: models::EdgeDirection::Outbound,
+        limit: 1,
+        t: None,
+        distinct: false,
+        limit_param: Some(Identifier::new("n")?),
+    };
+    let result = db.prepare(q);
+    expect_err!(result, Error::Invalid(errors::ValidationError::InnerQuery));
+    Ok(())
+}
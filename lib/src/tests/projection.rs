@@ -0,0 +1,155 @@
+use super::util;
+use crate::{
+    errors, ijson, AllVertexQuery, CountQueryExt, Database, Datastore, Edge, Error, Identifier, NamedProperty,
+    ProjectQuery, QueryExt, Row, RowValue, SpecificVertexQuery, Vertex,
+};
+
+use uuid::Uuid;
+
+// Creates three users, where the first follows the other two, and the second
+// follows the third. The second and third users have names. The users are
+// returned in ID order.
+fn create_follows<D: Datastore>(db: &Database<D>) -> Result<[Vertex; 3], Error> {
+    let t = Identifier::new("user")?;
+    let follows = Identifier::new("follows")?;
+    let name = Identifier::new("name")?;
+    let mut users = [Vertex::new(t), Vertex::new(t), Vertex::new(t)];
+    users.sort_by_key(|v| v.id);
+    for user in &users {
+        db.create_vertex(user)?;
+    }
+    db.create_edge(&Edge::new(users[0].id, follows, users[1].id))?;
+    db.create_edge(&Edge::new(users[0].id, follows, users[2].id))?;
+    db.create_edge(&Edge::new(users[1].id, follows, users[2].id))?;
+    db.set_properties(SpecificVertexQuery::single(users[1].id), name, &ijson!("b"))?;
+    db.set_properties(SpecificVertexQuery::single(users[2].id), name, &ijson!("c"))?;
+    Ok(users)
+}
+
+pub fn should_project_rows<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let [a, b, c] = create_follows(db)?;
+    let follows = Identifier::new("follows")?;
+    let name = Identifier::new("name")?;
+
+    let q = SpecificVertexQuery::new(vec![a.id, b.id])
+        .outbound()?
+        .t(follows)
+        .inbound()?
+        .properties()?
+        .name(name)
+        .project()?;
+    let rows = util::get_rows(db, q)?;
+    assert_eq!(
+        rows,
+        vec![
+            Row::new(vec![
+                RowValue::Vertex(a.clone()),
+                RowValue::Edge(Edge::new(a.id, follows, b.id)),
+                RowValue::Vertex(b.clone()),
+                RowValue::Property(NamedProperty::new(name, ijson!("b"))),
+            ]),
+            Row::new(vec![
+                RowValue::Vertex(a.clone()),
+                RowValue::Edge(Edge::new(a.id, follows, c.id)),
+                RowValue::Vertex(c.clone()),
+                RowValue::Property(NamedProperty::new(name, ijson!("c"))),
+            ]),
+            Row::new(vec![
+                RowValue::Vertex(b.clone()),
+                RowValue::Edge(Edge::new(b.id, follows, c.id)),
+                RowValue::Vertex(c.clone()),
+                RowValue::Property(NamedProperty::new(name, ijson!("c"))),
+            ]),
+        ]
+    );
+
+    // Piping in both directions binds both ends of each edge
+    let q = SpecificVertexQuery::single(b.id).both()?.t(follows).both()?.project()?;
+    let ends: Vec<(Uuid, Uuid)> = util::get_rows(db, q)?
+        .into_iter()
+        .map(|row| match &row.values[..] {
+            [RowValue::Vertex(_), RowValue::Edge(edge), RowValue::Vertex(vertex)] => (edge.outbound_id, vertex.id),
+            values => panic!("unexpected row values: {values:?}"),
+        })
+        .collect();
+    assert_eq!(ends, vec![(b.id, b.id), (b.id, c.id), (a.id, a.id), (a.id, b.id)]);
+    Ok(())
+}
+
+pub fn should_filter_projected_rows<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let [a, b, c] = create_follows(db)?;
+    let follows = Identifier::new("follows")?;
+    let name = Identifier::new("name")?;
+    db.index_property(name)?;
+
+    // Filters keep the rows whose last value passes them, without adding a
+    // value to the rows
+    let q = AllVertexQuery
+        .outbound()?
+        .t(follows)
+        .inbound()?
+        .with_property_equal_to(name, ijson!("c"))?
+        .project()?;
+    let rows = util::get_rows(db, q)?;
+    let pairs: Vec<(Uuid, Uuid)> = rows.iter().map(row_ends).collect();
+    assert_eq!(pairs, vec![(a.id, c.id), (b.id, c.id)]);
+    assert!(rows.iter().all(|row| row.values.len() == 3));
+
+    // Rows whose last value doesn't have the piped property are dropped
+    let q = AllVertexQuery.properties()?.name(name).project()?;
+    let rows = util::get_rows(db, q)?;
+    let ids: Vec<Uuid> = rows.iter().map(|row| row_ends(row).0).collect();
+    assert_eq!(ids, vec![b.id, c.id]);
+    Ok(())
+}
+
+pub fn should_limit_projected_rows<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let [a, b, c] = create_follows(db)?;
+
+    // The limit of a pipe applies to the rows it outputs
+    let q = AllVertexQuery.outbound()?.limit(2).inbound()?.project()?;
+    let pairs: Vec<(Uuid, Uuid)> = util::get_rows(db, q)?.iter().map(row_ends).collect();
+    assert_eq!(pairs, vec![(a.id, b.id), (a.id, c.id)]);
+
+    // Distinct pipes skip rows that duplicate an earlier one, but not rows
+    // that only end in the same value
+    let q = SpecificVertexQuery::new(vec![a.id, a.id, b.id])
+        .outbound()?
+        .distinct()
+        .inbound()?
+        .project()?;
+    let pairs: Vec<(Uuid, Uuid)> = util::get_rows(db, q)?.iter().map(row_ends).collect();
+    assert_eq!(pairs, vec![(a.id, b.id), (a.id, c.id), (b.id, c.id)]);
+    Ok(())
+}
+
+pub fn should_not_project_invalid_queries<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let result = ProjectQuery::new(Box::new(AllVertexQuery.count()?.into()));
+    expect_err!(result, errors::ValidationError::InnerQuery);
+    let result = ProjectQuery::new(Box::new(AllVertexQuery.degree()?.into()));
+    expect_err!(result, errors::ValidationError::InnerQuery);
+    // Included values would be lost
+    let result = AllVertexQuery.include().outbound()?.project();
+    expect_err!(result, errors::ValidationError::InnerQuery);
+
+    // Projections that weren't validated fail when they're run
+    let q = ProjectQuery {
+        inner: Box::new(AllVertexQuery.include().into()),
+    };
+    let result = db.get(q);
+    expect_err!(result, Error::Invalid(errors::ValidationError::InnerQuery));
+    Ok(())
+}
+
+// Gets the IDs of the first and last vertices in a row.
+fn row_ends(row: &Row) -> (Uuid, Uuid) {
+    let ids: Vec<Uuid> = row
+        .values
+        .iter()
+        .filter_map(|value| match value {
+            RowValue::Vertex(vertex) => Some(vertex.id),
+            _ => None,
+        })
+        .collect();
+    (ids[0], ids[ids.len() - 1])
+}
@@ -2,7 +2,7 @@ use super::util;
 use crate::util::extract_count;
 use crate::{
     errors, expect_err, ijson, AllVertexQuery, CountQueryExt, Database, Datastore, Edge, Error, Identifier,
-    PipePropertyQuery, PipeWithPropertyPresenceQuery, QueryExt, SpecificEdgeQuery, SpecificVertexQuery,
+    PipePropertyQuery, QueryExt, SpecificEdgeQuery, SpecificVertexQuery,
 };
 use uuid::Uuid;
 
@@ -236,13 +236,10 @@ pub fn should_not_pipe_properties_on_vertex_count<D: Datastore>(db: &Database<D>
 }
 
 pub fn should_not_pipe_property_presence_on_vertex_count<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
-    // We have to build the query without it's constructor because the
-    // constructor will catch this issue and trigger a `ValidationError`.
-    let q = PipeWithPropertyPresenceQuery {
-        inner: Box::new(AllVertexQuery.count()?.into()),
-        name: Identifier::new("foo")?,
-        exists: true,
-    };
+    // We have to swap in the inner query after building the query because
+    // the constructor will catch this issue and trigger a `ValidationError`.
+    let mut q = AllVertexQuery.with_property(Identifier::new("foo")?)?;
+    q.inner = Box::new(AllVertexQuery.count()?.into());
     let result = db.get(q);
     expect_err!(result, errors::Error::OperationOnQuery);
     Ok(())
@@ -0,0 +1,98 @@
+use super::util;
+use crate::{
+    errors, AllEdgeQuery, AllVertexQuery, CountQueryExt, Database, Datastore, Edge, Error, ExceptQuery, Identifier,
+    PipeQuery, QueryExt, SpecificVertexQuery,
+};
+
+use uuid::Uuid;
+
+pub fn should_union_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (a, b, [x, y, _]) = create_followers(db)?;
+    let q = followed_by(a)?.union(followed_by(b)?)?;
+    let ids: Vec<Uuid> = util::get_vertices(db, q)?.into_iter().map(|v| v.id).collect();
+    assert_eq!(ids, vec![x, y]);
+    Ok(())
+}
+
+pub fn should_intersect_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (a, b, [x, _, _]) = create_followers(db)?;
+    let q = followed_by(a)?.intersect(followed_by(b)?)?;
+    let ids: Vec<Uuid> = util::get_vertices(db, q)?.into_iter().map(|v| v.id).collect();
+    assert_eq!(ids, vec![x]);
+    Ok(())
+}
+
+pub fn should_except_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (a, b, [_, y, z]) = create_followers(db)?;
+    let q = followed_by(b)?.except(followed_by(a)?)?;
+    let ids: Vec<Uuid> = util::get_vertices(db, q)?.into_iter().map(|v| v.id).collect();
+    assert_eq!(ids, vec![y]);
+
+    let q = AllVertexQuery.except(followed_by(a)?.union(followed_by(b)?)?)?;
+    let ids: Vec<Uuid> = util::get_vertices(db, q)?.into_iter().map(|v| v.id).collect();
+    assert_eq!(ids.len(), 3);
+    assert!(ids.contains(&a) && ids.contains(&b) && ids.contains(&z));
+    Ok(())
+}
+
+pub fn should_combine_edges<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (a, b, _) = create_followers(db)?;
+    let a_edges = SpecificVertexQuery::single(a).outbound()?;
+    let b_edges = SpecificVertexQuery::single(b).outbound()?;
+
+    let mut edges = util::get_edges(db, a_edges.clone().union(b_edges.clone())?)?;
+    edges.sort();
+    assert_eq!(edges, util::get_edges(db, AllEdgeQuery)?);
+
+    let edges = util::get_edges(db, AllEdgeQuery.except(b_edges.clone())?)?;
+    assert_eq!(edges, util::get_edges(db, a_edges)?);
+
+    let edges = util::get_edges(db, AllEdgeQuery.intersect(b_edges.clone())?)?;
+    assert_eq!(edges, util::get_edges(db, b_edges)?);
+    Ok(())
+}
+
+pub fn should_include_set_operation_operands<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (a, b, _) = create_followers(db)?;
+    let q = followed_by(a)?.include().intersect(followed_by(b)?)?;
+    let output = db.get(q)?;
+    assert_eq!(output.len(), 2);
+    assert_eq!(output[0], db.get(followed_by(a)?)?[0]);
+
+    let count = util::get_count(db, followed_by(a)?.union(followed_by(b)?)?.count()?)?;
+    assert_eq!(count, 2);
+    Ok(())
+}
+
+pub fn should_not_combine_mismatched_queries<D: Datastore>(_db: &Database<D>) -> Result<(), Error> {
+    let result = AllVertexQuery.union(AllEdgeQuery);
+    expect_err!(result, errors::ValidationError::InnerQuery);
+    let result = AllEdgeQuery.intersect(AllVertexQuery.count()?);
+    expect_err!(result, errors::ValidationError::InnerQuery);
+    let result = ExceptQuery::new(
+        Box::new(AllVertexQuery.properties()?.into()),
+        Box::new(AllVertexQuery.properties()?.into()),
+    );
+    expect_err!(result, errors::ValidationError::InnerQuery);
+    Ok(())
+}
+
+// Creates two vertices `a` and `b`, where `a` follows `x`, and `b` follows
+// `x` and `y`. A third vertex `z` isn't followed by anyone.
+fn create_followers<D: Datastore>(db: &Database<D>) -> Result<(Uuid, Uuid, [Uuid; 3]), Error> {
+    let t = Identifier::new("test_vertex_type")?;
+    let edge_t = Identifier::new("follows")?;
+    let a = db.create_vertex_from_type(t)?;
+    let b = db.create_vertex_from_type(t)?;
+    let x = db.create_vertex_from_type(t)?;
+    let y = db.create_vertex_from_type(t)?;
+    let z = db.create_vertex_from_type(t)?;
+    db.create_edge(&Edge::new(a, edge_t, x))?;
+    db.create_edge(&Edge::new(b, edge_t, x))?;
+    db.create_edge(&Edge::new(b, edge_t, y))?;
+    Ok((a, b, [x, y, z]))
+}
+
+fn followed_by(id: Uuid) -> Result<PipeQuery, Error> {
+    Ok(SpecificVertexQuery::single(id).outbound()?.inbound()?)
+}
@@ -0,0 +1,101 @@
+use crate::{
+    AllEdgeQuery, AllVertexQuery, BulkInsertItem, Database, Datastore, Edge, Error, Identifier, Query, QueryExt,
+    QueryOutputChunk, QueryOutputValue, Vertex,
+};
+
+// Enough items to span multiple chunks.
+const STREAMED_ITEM_COUNT: usize = 2500;
+
+pub fn should_stream_all_vertices<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let t = Identifier::new("test_vertex_type")?;
+    let items = (0..STREAMED_ITEM_COUNT)
+        .map(|_| BulkInsertItem::Vertex(Vertex::new(t)))
+        .collect();
+    db.bulk_insert(items)?;
+
+    let chunks = get_chunks(db, AllVertexQuery)?;
+    assert!(chunks.len() > 1);
+    check_chunks(&chunks);
+    assert_eq!(merge_chunks(chunks), db.get(AllVertexQuery)?);
+    Ok(())
+}
+
+pub fn should_stream_all_edges<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let vertex_t = Identifier::new("test_vertex_type")?;
+    let edge_t = Identifier::new("test_edge_type")?;
+    let outbound_v = Vertex::new(vertex_t);
+    let mut items = vec![BulkInsertItem::Vertex(outbound_v.clone())];
+    for _ in 0..STREAMED_ITEM_COUNT {
+        let inbound_v = Vertex::new(vertex_t);
+        let edge = Edge::new(outbound_v.id, edge_t, inbound_v.id);
+        items.push(BulkInsertItem::Vertex(inbound_v));
+        items.push(BulkInsertItem::Edge(edge));
+    }
+    db.bulk_insert(items)?;
+
+    let chunks = get_chunks(db, AllEdgeQuery)?;
+    assert!(chunks.len() > 1);
+    check_chunks(&chunks);
+    assert_eq!(merge_chunks(chunks), db.get(AllEdgeQuery)?);
+    Ok(())
+}
+
+pub fn should_stream_empty_output<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let chunks = get_chunks(db, AllVertexQuery)?;
+    assert_eq!(
+        chunks,
+        vec![QueryOutputChunk::new(QueryOutputValue::Vertices(Vec::new()), false)]
+    );
+    Ok(())
+}
+
+pub fn should_stream_nested_query<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let vertex_t = Identifier::new("test_vertex_type")?;
+    let edge_t = Identifier::new("test_edge_type")?;
+    let outbound_id = db.create_vertex_from_type(vertex_t)?;
+    let inbound_id = db.create_vertex_from_type(vertex_t)?;
+    db.create_edge(&Edge::new(outbound_id, edge_t, inbound_id))?;
+
+    let q = AllVertexQuery.include().outbound()?;
+    let chunks = get_chunks(db, q.clone())?;
+    assert_eq!(chunks.len(), 2);
+    check_chunks(&chunks);
+    assert_eq!(merge_chunks(chunks), db.get(q)?);
+    Ok(())
+}
+
+fn get_chunks<D: Datastore, Q: Into<Query>>(db: &Database<D>, q: Q) -> Result<Vec<QueryOutputChunk>, Error> {
+    db.get_iter(q)?.collect()
+}
+
+fn check_chunks(chunks: &[QueryOutputChunk]) {
+    assert!(!chunks.last().unwrap().more);
+    for window in chunks.windows(2) {
+        if window[0].more {
+            assert_eq!(
+                std::mem::discriminant(&window[0].value),
+                std::mem::discriminant(&window[1].value)
+            );
+        }
+    }
+}
+
+fn merge_chunks(chunks: Vec<QueryOutputChunk>) -> Vec<QueryOutputValue> {
+    let mut output = Vec::new();
+    let mut more = false;
+    for chunk in chunks {
+        if more {
+            match (output.last_mut().unwrap(), chunk.value) {
+                (QueryOutputValue::Vertices(ref mut vertices), QueryOutputValue::Vertices(next)) => {
+                    vertices.extend(next)
+                }
+                (QueryOutputValue::Edges(ref mut edges), QueryOutputValue::Edges(next)) => edges.extend(next),
+                (value, next) => panic!("unexpected chunk: {:?} followed by {:?}", value, next),
+            }
+        } else {
+            output.push(chunk.value);
+        }
+        more = chunk.more;
+    }
+    output
+}
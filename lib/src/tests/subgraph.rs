@@ -0,0 +1,151 @@
+use std::collections::HashSet;
+
+use super::util;
+use crate::{
+    errors, ijson, AllEdgeQuery, Database, Datastore, Edge, EdgeDirection, Error, Identifier, QueryExt,
+    QueryOutputValue, SpecificEdgeQuery, SpecificVertexQuery, SubgraphQuery,
+};
+
+use uuid::Uuid;
+
+pub fn should_get_subgraph<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (ids, edges) = create_graph(db)?;
+    let q = SpecificVertexQuery::single(ids[0]).subgraph(EdgeDirection::Outbound, 2)?;
+    let subgraph = util::get_subgraph(db, q.clone())?;
+    assert_eq!(subgraph.vertices[0].id, ids[0]);
+    assert_eq!(
+        vertex_ids(&subgraph.vertices),
+        HashSet::from([ids[0], ids[1], ids[2], ids[4]])
+    );
+    assert_eq!(
+        subgraph.edges.iter().cloned().collect::<HashSet<Edge>>(),
+        HashSet::from([edges[0].clone(), edges[1].clone(), edges[3].clone()])
+    );
+    assert!(subgraph.vertex_properties.is_empty());
+
+    // Edges back to vertices already in the subgraph are kept
+    let subgraph = util::get_subgraph(
+        db,
+        SpecificVertexQuery::single(ids[0]).subgraph(EdgeDirection::Outbound, 4)?,
+    )?;
+    assert_eq!(subgraph.vertices.len(), 5);
+    assert_eq!(subgraph.edges.len(), 5);
+
+    let subgraph = util::get_subgraph(db, q.t(Identifier::new("next")?))?;
+    assert_eq!(vertex_ids(&subgraph.vertices), HashSet::from([ids[0], ids[1], ids[2]]));
+    assert_eq!(subgraph.edges.len(), 2);
+
+    let subgraph = util::get_subgraph(
+        db,
+        SpecificVertexQuery::single(ids[3]).subgraph(EdgeDirection::Inbound, 1)?,
+    )?;
+    assert_eq!(vertex_ids(&subgraph.vertices), HashSet::from([ids[3], ids[2]]));
+    assert_eq!(subgraph.edges, vec![edges[2].clone()]);
+
+    // Edges between vertices that are both expanded are only added once
+    let subgraph = util::get_subgraph(
+        db,
+        SpecificVertexQuery::single(ids[1]).subgraph(EdgeDirection::Both, 2)?,
+    )?;
+    assert_eq!(vertex_ids(&subgraph.vertices), ids.iter().copied().collect());
+    assert_eq!(subgraph.edges.len(), 5);
+    assert_eq!(
+        subgraph.edges.iter().cloned().collect::<HashSet<Edge>>(),
+        edges.iter().cloned().collect()
+    );
+    Ok(())
+}
+
+pub fn should_limit_subgraph_size<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, _) = util::create_edges(db)?;
+    let q = SpecificVertexQuery::single(outbound_id).subgraph(EdgeDirection::Outbound, 1)?;
+
+    let subgraph = util::get_subgraph(db, q.clone().vertex_limit(3))?;
+    assert_eq!(subgraph.vertices.len(), 3);
+    assert_eq!(subgraph.edges.len(), 2);
+    let ids = vertex_ids(&subgraph.vertices);
+    assert!(subgraph
+        .edges
+        .iter()
+        .all(|e| ids.contains(&e.outbound_id) && ids.contains(&e.inbound_id)));
+
+    let subgraph = util::get_subgraph(db, q.clone().edge_limit(1))?;
+    assert_eq!(subgraph.vertices.len(), 2);
+    assert_eq!(subgraph.edges.len(), 1);
+
+    let subgraph = util::get_subgraph(db, q.vertex_limit(0))?;
+    assert!(subgraph.vertices.is_empty());
+    assert!(subgraph.edges.is_empty());
+    Ok(())
+}
+
+pub fn should_get_subgraph_with_properties<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (ids, edges) = create_graph(db)?;
+    let name = Identifier::new("weight")?;
+    db.set_properties(SpecificVertexQuery::single(ids[1]), name, &ijson!(1))?;
+    db.set_properties(SpecificEdgeQuery::single(edges[0].clone()), name, &ijson!(2))?;
+    db.set_properties(SpecificEdgeQuery::single(edges[2].clone()), name, &ijson!(3))?;
+
+    let q = SpecificVertexQuery::single(ids[0])
+        .subgraph(EdgeDirection::Outbound, 1)?
+        .with_properties();
+    let subgraph = util::get_subgraph(db, q)?;
+    assert_eq!(subgraph.vertex_properties.len(), 1);
+    assert_eq!(subgraph.vertex_properties[0].vertex.id, ids[1]);
+    assert_eq!(subgraph.vertex_properties[0].props[0].value, ijson!(1));
+    assert_eq!(subgraph.edge_properties.len(), 1);
+    assert_eq!(subgraph.edge_properties[0].edge, edges[0]);
+    assert_eq!(subgraph.edge_properties[0].props[0].value, ijson!(2));
+    Ok(())
+}
+
+pub fn should_include_subgraph_inner_query<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let (outbound_id, _) = util::create_edges(db)?;
+    let vertices = util::get_vertices(db, SpecificVertexQuery::single(outbound_id))?;
+    let q = SpecificVertexQuery::single(outbound_id)
+        .include()
+        .subgraph(EdgeDirection::Outbound, 1)?;
+    let output = db.get(q)?;
+    assert_eq!(output.len(), 2);
+    assert_eq!(output[0], QueryOutputValue::Vertices(vertices));
+    Ok(())
+}
+
+pub fn should_not_get_subgraph_of_invalid_queries<D: Datastore>(_db: &Database<D>) -> Result<(), Error> {
+    let result = AllEdgeQuery.subgraph(EdgeDirection::Outbound, 1);
+    expect_err!(result, errors::ValidationError::InnerQuery);
+    let result = SubgraphQuery::new(
+        Box::new(SpecificVertexQuery::new(Vec::new()).into()),
+        EdgeDirection::Inbound,
+        0,
+    );
+    expect_err!(result, errors::ValidationError::InvalidValue);
+    Ok(())
+}
+
+// Creates the chain of vertices `0 -> 1 -> 2 -> 3` linked by `next` edges,
+// with `other` edges from 0 to 4, and from 3 back to 0.
+fn create_graph<D: Datastore>(db: &Database<D>) -> Result<(Vec<Uuid>, Vec<Edge>), Error> {
+    let t = Identifier::new("test_vertex_type")?;
+    let next_t = Identifier::new("next")?;
+    let other_t = Identifier::new("other")?;
+    let mut ids = Vec::new();
+    for _ in 0..5 {
+        ids.push(db.create_vertex_from_type(t)?);
+    }
+    let edges = vec![
+        Edge::new(ids[0], next_t, ids[1]),
+        Edge::new(ids[1], next_t, ids[2]),
+        Edge::new(ids[2], next_t, ids[3]),
+        Edge::new(ids[0], other_t, ids[4]),
+        Edge::new(ids[3], other_t, ids[0]),
+    ];
+    for edge in &edges {
+        db.create_edge(edge)?;
+    }
+    Ok((ids, edges))
+}
+
+fn vertex_ids(vertices: &[crate::Vertex]) -> HashSet<Uuid> {
+    vertices.iter().map(|v| v.id).collect()
+}
@@ -0,0 +1,187 @@
+use super::util;
+use crate::util::{extract_edges, extract_vertex_properties, extract_vertices};
+use crate::{
+    ijson, AllEdgeQuery, AllVertexQuery, CountQueryExt, Database, Datastore, Edge, EdgeDirection, Error, Identifier,
+    Json, QueryExt, SpecificEdgeQuery, SpecificVertexQuery, Vertex,
+};
+
+use uuid::Uuid;
+
+// Whether the datastore can roll back transactions. The ones that can't
+// write changes as soon as they're made, so the rollback tests are skipped
+// for them.
+fn can_roll_back<D: Datastore>(db: &Database<D>) -> Result<bool, Error> {
+    match db.begin().rollback() {
+        Ok(()) => Ok(true),
+        Err(Error::Unsupported) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+fn vertex_property<D: Datastore>(db: &Database<D>, id: Uuid, name: Identifier) -> Result<Option<Json>, Error> {
+    let q = SpecificVertexQuery::single(id).properties()?.name(name);
+    Ok(util::get_vertex_properties(db, q)?.pop().map(|prop| prop.value))
+}
+
+fn edge_property<D: Datastore>(db: &Database<D>, edge: Edge, name: Identifier) -> Result<Option<Json>, Error> {
+    let q = SpecificEdgeQuery::single(edge).properties()?.name(name);
+    Ok(util::get_edge_properties(db, q)?.pop().map(|prop| prop.value))
+}
+
+// Creates two users, where the first follows the second, and the first has a
+// name. The users are returned with the edge between them.
+fn create_follow<D: Datastore>(db: &Database<D>) -> Result<(Vertex, Vertex, Edge), Error> {
+    let t = Identifier::new("user")?;
+    let follows = Identifier::new("follows")?;
+    let name = Identifier::new("name")?;
+    let (a, b) = (Vertex::new(t), Vertex::new(t));
+    let edge = Edge::new(a.id, follows, b.id);
+    db.create_vertex(&a)?;
+    db.create_vertex(&b)?;
+    db.create_edge(&edge)?;
+    db.set_properties(SpecificVertexQuery::single(a.id), name, &ijson!("a"))?;
+    db.set_properties(SpecificEdgeQuery::single(edge.clone()), name, &ijson!("follows"))?;
+    Ok((a, b, edge))
+}
+
+pub fn should_commit_transaction<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let t = Identifier::new("user")?;
+    let follows = Identifier::new("follows")?;
+    let name = Identifier::new("name")?;
+    let (a, b) = (Vertex::new(t), Vertex::new(t));
+    let edge = Edge::new(a.id, follows, b.id);
+
+    let mut txn = db.begin();
+    assert!(txn.create_vertex(&a)?);
+    assert!(txn.create_vertex(&b)?);
+    // Vertices are seen by later changes in the same transaction
+    assert!(!txn.create_vertex(&a)?);
+    assert!(txn.create_edge(&edge)?);
+    txn.set_properties(SpecificVertexQuery::single(a.id), name, &ijson!("a"))?;
+    txn.set_properties(SpecificVertexQuery::single(a.id).outbound()?, name, &ijson!("follows"))?;
+
+    // Queries run through the transaction see its changes
+    let q = SpecificVertexQuery::single(a.id).outbound()?.inbound()?;
+    assert_eq!(extract_vertices(txn.get(q)?).unwrap(), vec![b.clone()]);
+    let q = SpecificVertexQuery::single(a.id).properties()?.name(name);
+    let props = extract_vertex_properties(txn.get(q)?).unwrap();
+    assert_eq!(props.len(), 1);
+    assert_eq!(props[0].props[0].value, ijson!("a"));
+    txn.commit()?;
+
+    let vertices = util::get_vertices(db, SpecificVertexQuery::new(vec![a.id, b.id]))?;
+    assert_eq!(vertices, vec![a.clone(), b]);
+    assert_eq!(
+        util::get_edges(db, SpecificEdgeQuery::single(edge.clone()))?,
+        vec![edge.clone()]
+    );
+    assert_eq!(vertex_property(db, a.id, name)?, Some(ijson!("a")));
+    assert_eq!(edge_property(db, edge, name)?, Some(ijson!("follows")));
+    Ok(())
+}
+
+pub fn should_rollback_transaction<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    if !can_roll_back(db)? {
+        return Ok(());
+    }
+
+    let t = Identifier::new("user")?;
+    let follows = Identifier::new("follows")?;
+    let name = Identifier::new("name")?;
+    let (a, b) = (Vertex::new(t), Vertex::new(t));
+    let edge = Edge::new(a.id, follows, b.id);
+
+    let mut txn = db.begin();
+    txn.create_vertex(&a)?;
+    txn.create_vertex(&b)?;
+    txn.create_edge(&edge)?;
+    txn.set_properties(SpecificVertexQuery::single(a.id), name, &ijson!("a"))?;
+    txn.index_property(name)?;
+    let q = AllVertexQuery.with_property_equal_to(name, ijson!("a"))?;
+    assert_eq!(extract_vertices(txn.get(q)?).unwrap(), vec![a.clone()]);
+    txn.rollback()?;
+
+    assert_eq!(util::get_vertex_count(db)?, 0);
+    assert_eq!(util::get_edges(db, AllEdgeQuery)?, Vec::new());
+    assert_eq!(vertex_property(db, a.id, name)?, None);
+    let result = db.get(AllVertexQuery.with_property(name)?);
+    expect_err!(result, Error::NotIndexed);
+    Ok(())
+}
+
+pub fn should_rollback_deletes<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    if !can_roll_back(db)? {
+        return Ok(());
+    }
+
+    let name = Identifier::new("name")?;
+    db.index_property(name)?;
+    let (a, b, edge) = create_follow(db)?;
+
+    // Deleting a vertex deletes its edges and properties, and all of it is
+    // rolled back together
+    let mut txn = db.begin();
+    txn.delete(SpecificVertexQuery::single(a.id))?;
+    txn.set_properties(SpecificVertexQuery::single(b.id), name, &ijson!("b"))?;
+    assert_eq!(extract_vertices(txn.get(AllVertexQuery)?).unwrap(), vec![b.clone()]);
+    assert_eq!(extract_edges(txn.get(AllEdgeQuery)?).unwrap(), Vec::new());
+    let q = AllVertexQuery.with_property_equal_to(name, ijson!("a"))?;
+    assert_eq!(extract_vertices(txn.get(q)?).unwrap(), Vec::new());
+    txn.rollback()?;
+
+    assert_eq!(util::get_vertex_count(db)?, 2);
+    assert_eq!(util::get_edges(db, AllEdgeQuery)?, vec![edge.clone()]);
+    assert_eq!(vertex_property(db, a.id, name)?, Some(ijson!("a")));
+    assert_eq!(vertex_property(db, b.id, name)?, None);
+    assert_eq!(edge_property(db, edge, name)?, Some(ijson!("follows")));
+    // The property value index is restored as well
+    let q = AllVertexQuery.with_property_equal_to(name, ijson!("a"))?;
+    assert_eq!(util::get_vertices(db, q)?, vec![a]);
+    assert_eq!(util::get_count(db, AllVertexQuery.with_property(name)?.count()?)?, 1);
+    Ok(())
+}
+
+pub fn should_rollback_dropped_transaction<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    if !can_roll_back(db)? {
+        return Ok(());
+    }
+
+    let (a, b, _) = create_follow(db)?;
+    let name = Identifier::new("name")?;
+
+    {
+        let mut txn = db.begin();
+        txn.delete(SpecificVertexQuery::single(b.id))?;
+        txn.set_properties(SpecificVertexQuery::single(a.id), name, &ijson!("changed"))?;
+        txn.create_vertex_from_type(Identifier::new("user")?)?;
+        // A failing change doesn't undo the earlier ones by itself
+        let result = txn.set_properties(AllVertexQuery.count()?, name, &ijson!("changed"));
+        expect_err!(result, Error::OperationOnQuery);
+        assert_eq!(extract_vertices(txn.get(AllVertexQuery)?).unwrap().len(), 2);
+    }
+
+    assert_eq!(util::get_vertex_count(db)?, 2);
+    assert_eq!(util::get_edge_count(db, a.id, None, EdgeDirection::Outbound)?, 1);
+    assert_eq!(vertex_property(db, a.id, name)?, Some(ijson!("a")));
+    Ok(())
+}
+
+pub fn should_not_rollback_committed_transaction<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let name = Identifier::new("name")?;
+    let (a, _, _) = create_follow(db)?;
+
+    let mut txn = db.begin();
+    txn.set_properties(SpecificVertexQuery::single(a.id), name, &ijson!("changed"))?;
+    txn.commit()?;
+
+    // Starting another transaction and rolling it back doesn't affect the
+    // committed changes
+    let mut txn = db.begin();
+    txn.delete(SpecificVertexQuery::single(a.id).properties()?)?;
+    let result = txn.rollback();
+    if !matches!(result, Err(Error::Unsupported)) {
+        result?;
+        assert_eq!(vertex_property(db, a.id, name)?, Some(ijson!("changed")));
+    }
+    Ok(())
+}
@@ -0,0 +1,320 @@
+use std::collections::HashSet;
+
+use super::util;
+use crate::{
+    ijson, CountQueryExt, Database, Datastore, Edge, EdgeDirection, Error, Identifier, MissingWeight, Path, QueryExt,
+    QueryOutputValue, ShortestPathQuery, SpecificEdgeQuery, SpecificVertexQuery, ValidationError, Vertex,
+    WeightedShortestPathQuery,
+};
+
+use uuid::Uuid;
+
+// Creates a chain of vertices `a -> b -> c -> d -> a` linked by `follows`
+// edges, with an additional `blocks` edge from `a` to `x`.
+fn create_cycle<D: Datastore>(db: &Database<D>) -> Result<[Uuid; 5], Error> {
+    let vertex_t = Identifier::new("test_vertex_type")?;
+    let follows_t = Identifier::new("follows")?;
+    let blocks_t = Identifier::new("blocks")?;
+    let ids = [
+        db.create_vertex_from_type(vertex_t)?,
+        db.create_vertex_from_type(vertex_t)?,
+        db.create_vertex_from_type(vertex_t)?,
+        db.create_vertex_from_type(vertex_t)?,
+        db.create_vertex_from_type(vertex_t)?,
+    ];
+    for i in 0..4 {
+        db.create_edge(&Edge::new(ids[i], follows_t, ids[(i + 1) % 4]))?;
+    }
+    db.create_edge(&Edge::new(ids[0], blocks_t, ids[4]))?;
+    Ok(ids)
+}
+
+fn traverse_ids<D: Datastore>(
+    db: &Database<D>,
+    start_id: Uuid,
+    direction: EdgeDirection,
+    min_depth: u32,
+    max_depth: u32,
+) -> Result<HashSet<Uuid>, Error> {
+    let q = SpecificVertexQuery::single(start_id)
+        .traverse(direction, min_depth, max_depth)?
+        .t(Identifier::new("follows")?);
+    Ok(util::get_vertices(db, q)?.into_iter().map(|v| v.id).collect())
+}
+
+pub fn should_traverse_variable_hops<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = create_cycle(db)?;
+    assert_eq!(
+        traverse_ids(db, ids[0], EdgeDirection::Outbound, 1, 2)?,
+        HashSet::from([ids[1], ids[2]])
+    );
+    assert_eq!(
+        traverse_ids(db, ids[0], EdgeDirection::Outbound, 2, 3)?,
+        HashSet::from([ids[2], ids[3]])
+    );
+    assert_eq!(
+        traverse_ids(db, ids[0], EdgeDirection::Outbound, 0, 1)?,
+        HashSet::from([ids[0], ids[1]])
+    );
+    assert_eq!(
+        traverse_ids(db, ids[0], EdgeDirection::Inbound, 1, 2)?,
+        HashSet::from([ids[3], ids[2]])
+    );
+    assert_eq!(
+        traverse_ids(db, ids[0], EdgeDirection::Both, 1, 1)?,
+        HashSet::from([ids[1], ids[3]])
+    );
+    assert_eq!(
+        traverse_ids(db, ids[0], EdgeDirection::Both, 2, 2)?,
+        HashSet::from([ids[2]])
+    );
+    Ok(())
+}
+
+pub fn should_traverse_cycles_once<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = create_cycle(db)?;
+    let q = SpecificVertexQuery::single(ids[0])
+        .traverse(EdgeDirection::Outbound, 1, 10)?
+        .t(Identifier::new("follows")?);
+    let mut result: Vec<Uuid> = util::get_vertices(db, q)?.into_iter().map(|v| v.id).collect();
+    assert_eq!(result, vec![ids[1], ids[2], ids[3]]);
+
+    // The starting vertex is only returned if the minimum depth is 0
+    let q = SpecificVertexQuery::new(vec![ids[0], ids[0]]).traverse(EdgeDirection::Outbound, 0, 10)?;
+    result = util::get_vertices(db, q)?.into_iter().map(|v| v.id).collect();
+    assert_eq!(result.len(), 5);
+    assert_eq!(result.iter().collect::<HashSet<_>>().len(), 5);
+    Ok(())
+}
+
+pub fn should_traverse_with_frontiers<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = create_cycle(db)?;
+    let vertex_t = Identifier::new("test_vertex_type")?;
+    let q = SpecificVertexQuery::single(ids[0])
+        .traverse(EdgeDirection::Outbound, 2, 10)?
+        .t(Identifier::new("follows")?)
+        .emit_frontiers();
+    let output = db.get(q)?;
+    assert_eq!(
+        output,
+        vec![
+            QueryOutputValue::Vertices(vec![Vertex::with_id(ids[1], vertex_t)]),
+            QueryOutputValue::Vertices(vec![Vertex::with_id(ids[2], vertex_t)]),
+            QueryOutputValue::Vertices(vec![Vertex::with_id(ids[3], vertex_t)]),
+            QueryOutputValue::Vertices(vec![
+                Vertex::with_id(ids[2], vertex_t),
+                Vertex::with_id(ids[3], vertex_t)
+            ]),
+        ]
+    );
+    Ok(())
+}
+
+pub fn should_not_traverse_invalid_depths<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let id = db.create_vertex_from_type(Identifier::new("test_vertex_type")?)?;
+    let q = SpecificVertexQuery::single(id);
+    assert!(matches!(
+        q.clone().traverse(EdgeDirection::Outbound, 2, 1),
+        Err(ValidationError::InvalidValue)
+    ));
+    assert!(matches!(
+        q.clone().traverse(EdgeDirection::Outbound, 0, 0),
+        Err(ValidationError::InvalidValue)
+    ));
+    assert!(matches!(
+        q.outbound()?.traverse(EdgeDirection::Outbound, 1, 2),
+        Err(ValidationError::InnerQuery)
+    ));
+    Ok(())
+}
+
+fn get_shortest_path<D: Datastore>(db: &Database<D>, q: ShortestPathQuery) -> Result<Option<Path>, Error> {
+    let mut paths = util::get_paths(db, q)?;
+    assert!(paths.len() <= 1);
+    Ok(paths.pop())
+}
+
+pub fn should_get_shortest_path<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = create_cycle(db)?;
+    let vertex_t = Identifier::new("test_vertex_type")?;
+    let follows_t = Identifier::new("follows")?;
+
+    let path = get_shortest_path(db, ShortestPathQuery::new(ids[0], ids[2]).t(follows_t))?.unwrap();
+    assert_eq!(
+        path,
+        Path::new(
+            vec![
+                Vertex::with_id(ids[0], vertex_t),
+                Vertex::with_id(ids[1], vertex_t),
+                Vertex::with_id(ids[2], vertex_t),
+            ],
+            vec![
+                Edge::new(ids[0], follows_t, ids[1]),
+                Edge::new(ids[1], follows_t, ids[2]),
+            ]
+        )
+    );
+
+    // Inbound paths walk edges backwards, but keep their orientation
+    let q = ShortestPathQuery::new(ids[0], ids[3])
+        .direction(EdgeDirection::Inbound)
+        .t(follows_t);
+    let path = get_shortest_path(db, q)?.unwrap();
+    assert_eq!(
+        path,
+        Path::new(
+            vec![Vertex::with_id(ids[0], vertex_t), Vertex::with_id(ids[3], vertex_t)],
+            vec![Edge::new(ids[3], follows_t, ids[0])]
+        )
+    );
+
+    // The edge type filter is respected
+    assert_eq!(
+        get_shortest_path(db, ShortestPathQuery::new(ids[0], ids[4]).t(follows_t))?,
+        None
+    );
+    let path = get_shortest_path(db, ShortestPathQuery::new(ids[0], ids[4]))?.unwrap();
+    assert_eq!(path.edges, vec![Edge::new(ids[0], Identifier::new("blocks")?, ids[4])]);
+
+    // A vertex has an empty path to itself
+    let path = get_shortest_path(db, ShortestPathQuery::new(ids[1], ids[1]))?.unwrap();
+    assert_eq!(path, Path::new(vec![Vertex::with_id(ids[1], vertex_t)], Vec::new()));
+    Ok(())
+}
+
+pub fn should_get_shortest_of_many_paths<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let vertex_t = Identifier::new("test_vertex_type")?;
+    let edge_t = Identifier::new("test_edge_type")?;
+    let mut ids = Vec::new();
+    for _ in 0..6 {
+        ids.push(db.create_vertex_from_type(vertex_t)?);
+    }
+    // A long path `0 -> 1 -> 2 -> 3 -> 4 -> 5`, and a shortcut `1 -> 4`
+    for i in 0..5 {
+        db.create_edge(&Edge::new(ids[i], edge_t, ids[i + 1]))?;
+    }
+    db.create_edge(&Edge::new(ids[1], edge_t, ids[4]))?;
+
+    let path = get_shortest_path(db, ShortestPathQuery::new(ids[0], ids[5]))?.unwrap();
+    assert_eq!(
+        path.vertices.into_iter().map(|v| v.id).collect::<Vec<Uuid>>(),
+        vec![ids[0], ids[1], ids[4], ids[5]]
+    );
+    assert_eq!(path.edges.len(), 3);
+
+    let count = util::get_count(db, ShortestPathQuery::new(ids[0], ids[5]).count()?)?;
+    assert_eq!(count, 1);
+    Ok(())
+}
+
+pub fn should_not_get_missing_shortest_path<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = create_cycle(db)?;
+    // `x` has no outbound edges
+    assert_eq!(get_shortest_path(db, ShortestPathQuery::new(ids[4], ids[0]))?, None);
+    // Vertices that don't exist have no paths
+    assert_eq!(
+        get_shortest_path(db, ShortestPathQuery::new(ids[0], Uuid::default()))?,
+        None
+    );
+    assert_eq!(
+        get_shortest_path(db, ShortestPathQuery::new(Uuid::default(), Uuid::default()))?,
+        None
+    );
+    Ok(())
+}
+
+// Creates a graph where the path with the fewest hops is not the cheapest:
+// `0 -> 1 -> 2 -> 3` with a cost of 1 per edge, and `0 -> 3` with a cost of
+// 5. The last vertex also has an edge `3 -> 4` with no cost.
+fn create_weighted_graph<D: Datastore>(db: &Database<D>) -> Result<Vec<Uuid>, Error> {
+    let vertex_t = Identifier::new("test_vertex_type")?;
+    let edge_t = Identifier::new("test_edge_type")?;
+    let cost = Identifier::new("cost")?;
+    let mut ids = Vec::new();
+    for _ in 0..5 {
+        ids.push(db.create_vertex_from_type(vertex_t)?);
+    }
+    for (i, j, weight) in [
+        (0, 1, ijson!(1)),
+        (1, 2, ijson!(1.0)),
+        (2, 3, ijson!(1)),
+        (0, 3, ijson!(5)),
+    ] {
+        let edge = Edge::new(ids[i], edge_t, ids[j]);
+        db.create_edge(&edge)?;
+        db.set_properties(SpecificEdgeQuery::single(edge), cost, &weight)?;
+    }
+    db.create_edge(&Edge::new(ids[3], edge_t, ids[4]))?;
+    Ok(ids)
+}
+
+fn path_ids(path: Path) -> Vec<Uuid> {
+    path.vertices.into_iter().map(|v| v.id).collect()
+}
+
+pub fn should_get_weighted_shortest_path<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = create_weighted_graph(db)?;
+    let edge_t = Identifier::new("test_edge_type")?;
+    let cost = Identifier::new("cost")?;
+
+    let q = WeightedShortestPathQuery::new(ids[0], ids[3], cost);
+    let path = util::get_paths(db, q)?.pop().unwrap();
+    assert_eq!(path_ids(path.clone()), vec![ids[0], ids[1], ids[2], ids[3]]);
+    assert_eq!(
+        path.edges,
+        vec![
+            Edge::new(ids[0], edge_t, ids[1]),
+            Edge::new(ids[1], edge_t, ids[2]),
+            Edge::new(ids[2], edge_t, ids[3]),
+        ]
+    );
+
+    // Making the long route more expensive should switch to the shortcut
+    db.set_properties(
+        SpecificEdgeQuery::single(Edge::new(ids[1], edge_t, ids[2])),
+        cost,
+        &ijson!(10),
+    )?;
+    let q = WeightedShortestPathQuery::new(ids[3], ids[0], cost).direction(EdgeDirection::Inbound);
+    let path = util::get_paths(db, q)?.pop().unwrap();
+    assert_eq!(path_ids(path), vec![ids[3], ids[0]]);
+
+    // There's no path against the direction of the edges
+    let q = WeightedShortestPathQuery::new(ids[3], ids[0], cost).missing_weight(MissingWeight::Skip)?;
+    assert!(util::get_paths(db, q)?.is_empty());
+    Ok(())
+}
+
+pub fn should_handle_missing_weights<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let ids = create_weighted_graph(db)?;
+    let cost = Identifier::new("cost")?;
+
+    // Edges without weights fail the query by default
+    let result = db.get(WeightedShortestPathQuery::new(ids[0], ids[4], cost));
+    expect_err!(result, Error::InvalidWeight);
+
+    // ...or can be skipped
+    let q = WeightedShortestPathQuery::new(ids[0], ids[4], cost).missing_weight(MissingWeight::Skip)?;
+    assert!(util::get_paths(db, q)?.is_empty());
+
+    // ...or can be given a default weight
+    let q = WeightedShortestPathQuery::new(ids[0], ids[4], cost).missing_weight(MissingWeight::Default(ijson!(2)))?;
+    let path = util::get_paths(db, q)?.pop().unwrap();
+    assert_eq!(path_ids(path), vec![ids[0], ids[1], ids[2], ids[3], ids[4]]);
+
+    // Non-numeric and negative weights are treated as missing
+    let edge = Edge::new(ids[0], Identifier::new("test_edge_type")?, ids[3]);
+    db.set_properties(SpecificEdgeQuery::single(edge), cost, &ijson!("cheap"))?;
+    let result = db.get(WeightedShortestPathQuery::new(ids[0], ids[3], cost));
+    expect_err!(result, Error::InvalidWeight);
+    let edge = Edge::new(ids[0], Identifier::new("test_edge_type")?, ids[1]);
+    db.set_properties(SpecificEdgeQuery::single(edge), cost, &ijson!(-1))?;
+    let q = WeightedShortestPathQuery::new(ids[0], ids[3], cost).missing_weight(MissingWeight::Skip)?;
+    assert!(util::get_paths(db, q)?.is_empty());
+
+    // Default weights must be usable themselves
+    let result =
+        WeightedShortestPathQuery::new(ids[0], ids[3], cost).missing_weight(MissingWeight::Default(ijson!(-1)));
+    assert!(matches!(result, Err(ValidationError::InvalidValue)));
+    Ok(())
+}
@@ -1,5 +1,8 @@
 use crate::errors::{Error, Result};
-use crate::util::{extract_count, extract_edge_properties, extract_edges, extract_vertex_properties, extract_vertices};
+use crate::util::{
+    extract_aggregation, extract_count, extract_degrees, extract_edge_properties, extract_edges, extract_paths,
+    extract_rows, extract_subgraph, extract_vertex_properties, extract_vertices,
+};
 use crate::{models, CountQueryExt, Database, Datastore, QueryExt};
 
 use uuid::Uuid;
@@ -44,6 +47,36 @@ pub(crate) fn get_edges<D: Datastore, Q: Into<models::Query>>(db: &Database<D>,
     Ok(extract_edges(db.get(q)?).unwrap())
 }
 
+pub(crate) fn get_count<D: Datastore, Q: Into<models::Query>>(db: &Database<D>, q: Q) -> Result<u64> {
+    Ok(extract_count(db.get(q)?).unwrap())
+}
+
+pub(crate) fn get_paths<D: Datastore, Q: Into<models::Query>>(db: &Database<D>, q: Q) -> Result<Vec<models::Path>> {
+    Ok(extract_paths(db.get(q)?).unwrap())
+}
+
+pub(crate) fn get_aggregation<D: Datastore, Q: Into<models::Query>>(
+    db: &Database<D>,
+    q: Q,
+) -> Result<Vec<models::AggregationGroup>> {
+    Ok(extract_aggregation(db.get(q)?).unwrap())
+}
+
+pub(crate) fn get_degrees<D: Datastore, Q: Into<models::Query>>(
+    db: &Database<D>,
+    q: Q,
+) -> Result<Vec<models::VertexDegree>> {
+    Ok(extract_degrees(db.get(q)?).unwrap())
+}
+
+pub(crate) fn get_rows<D: Datastore, Q: Into<models::Query>>(db: &Database<D>, q: Q) -> Result<Vec<models::Row>> {
+    Ok(extract_rows(db.get(q)?).unwrap())
+}
+
+pub(crate) fn get_subgraph<D: Datastore, Q: Into<models::Query>>(db: &Database<D>, q: Q) -> Result<models::Subgraph> {
+    Ok(extract_subgraph(db.get(q)?).unwrap())
+}
+
 pub(crate) fn get_edge_count<D: Datastore>(
     db: &Database<D>,
     id: Uuid,
@@ -55,6 +88,7 @@ pub(crate) fn get_edge_count<D: Datastore>(
     let q = match direction {
         models::EdgeDirection::Outbound => q.outbound().unwrap(),
         models::EdgeDirection::Inbound => q.inbound().unwrap(),
+        models::EdgeDirection::Both => q.both().unwrap(),
     };
 
     let q: models::Query = if let Some(t) = t {
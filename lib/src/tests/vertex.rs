@@ -55,6 +55,31 @@ pub fn should_get_no_vertices_with_type_filter<D: Datastore>(db: &Database<D>) -
     Ok(())
 }
 
+pub fn should_get_vertices_with_type_filter<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
+    let t = models::Identifier::new("movie")?;
+    create_vertices(db)?;
+    let mut ids = Vec::new();
+    for _ in 0..4 {
+        ids.push(db.create_vertex_from_type(t)?);
+    }
+    ids.sort();
+
+    let range = util::get_vertices(db, RangeVertexQuery::new().t(t))?;
+    assert_eq!(range.iter().map(|v| v.id).collect::<Vec<Uuid>>(), ids);
+    assert!(range.iter().all(|v| v.t == t));
+    let plan = db.explain(RangeVertexQuery::new().t(t))?;
+    assert_eq!(plan.calls, vec!["vertices_with_type".to_string()]);
+
+    let range = util::get_vertices(db, RangeVertexQuery::new().t(t).start_id(ids[1]).limit(2))?;
+    assert_eq!(range.iter().map(|v| v.id).collect::<Vec<Uuid>>(), ids[1..3].to_vec());
+
+    // Deleted vertices should be removed from the index
+    db.delete(SpecificVertexQuery::single(ids[0]))?;
+    let range = util::get_vertices(db, RangeVertexQuery::new().t(t))?;
+    assert_eq!(range.iter().map(|v| v.id).collect::<Vec<Uuid>>(), ids[1..].to_vec());
+    Ok(())
+}
+
 pub fn should_get_single_vertex<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
     let vertex_t = models::Identifier::new("test_vertex_type")?;
     let id = db.create_vertex_from_type(vertex_t)?;
@@ -194,14 +219,10 @@ pub fn should_not_delete_on_vertex_count<D: Datastore>(db: &Database<D>) -> Resu
 }
 
 pub fn should_not_pipe_on_vertex_count<D: Datastore>(db: &Database<D>) -> Result<(), Error> {
-    // We have to build the query without it's constructor because the
-    // constructor will catch this issue and trigger a `ValidationError`.
-    let q = models::PipeQuery {
-        inner: Box::new(AllVertexQuery.count()?.into()),
-        direction: models::EdgeDirection::Outbound,
-        limit: 1,
-        t: None,
-    };
+    // We have to swap in the inner query after building the query because
+    // the constructor will catch this issue and trigger a `ValidationError`.
+    let mut q = AllVertexQuery.outbound()?.limit(1);
+    q.inner = Box::new(AllVertexQuery.count()?.into());
     let result = db.get(q);
     expect_err!(result, errors::Error::OperationOnQuery);
     Ok(())
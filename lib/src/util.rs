@@ -1,9 +1,9 @@
 //! Utility functions. These are public because they may be useful for crates
 //! that implement Datastore.
 
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use std::convert::TryFrom;
 use std::io::{Cursor, Error as IoError, Read, Write};
+use std::sync::Arc;
 use std::{str, u8};
 
 use crate::errors::{ValidationError, ValidationResult};
@@ -16,6 +16,19 @@ use uuid::Uuid;
 
 const NODE_ID: [u8; 6] = [0, 0, 0, 0, 0, 0];
 
+// Type tags for the JSON component encoding. These are ordered the same way
+// as `Json::total_cmp` orders types.
+const JSON_NULL_TAG: u8 = 1;
+const JSON_BOOL_TAG: u8 = 2;
+const JSON_NUMBER_TAG: u8 = 3;
+const JSON_STRING_TAG: u8 = 4;
+const JSON_ARRAY_TAG: u8 = 5;
+const JSON_OBJECT_TAG: u8 = 6;
+
+// Markers used to delimit array elements and object entries.
+const JSON_END_MARKER: u8 = 0;
+const JSON_ITEM_MARKER: u8 = 1;
+
 static CONTEXT: Lazy<Context> = Lazy::new(|| Context::new(0));
 
 /// A byte-serializable value, frequently employed in the keys of key/value
@@ -27,7 +40,9 @@ pub enum Component<'a> {
     FixedLengthString(&'a str),
     /// An identifier.
     Identifier(models::Identifier),
-    /// A JSON value.
+    /// A JSON value. This is encoded such that the byte ordering of encoded
+    /// values matches `Json::total_cmp`, and such that no encoded value is a
+    /// prefix of another.
     Json(&'a models::Json),
 }
 
@@ -39,7 +54,7 @@ impl<'a> Component<'a> {
             Component::Uuid(_) => 16,
             Component::FixedLengthString(s) => s.len(),
             Component::Identifier(t) => t.0.len() + 1,
-            Component::Json(_) => 8,
+            Component::Json(json) => json_byte_len(json),
         }
     }
 
@@ -52,14 +67,149 @@ impl<'a> Component<'a> {
                 cursor.write_all(&[i.0.len() as u8])?;
                 cursor.write_all(i.0.as_bytes())
             }
-            Component::Json(json) => {
-                let mut hasher = DefaultHasher::new();
-                json.hash(&mut hasher);
-                let hash = hasher.finish();
-                cursor.write_u64::<BigEndian>(hash)
+            Component::Json(json) => write_json(cursor, json),
+        }
+    }
+}
+
+fn json_byte_len(value: &serde_json::Value) -> usize {
+    match value {
+        serde_json::Value::Null => 1,
+        serde_json::Value::Bool(_) => 2,
+        serde_json::Value::Number(_) => 25,
+        serde_json::Value::String(s) => string_byte_len(s) + 1,
+        serde_json::Value::Array(a) => a.iter().fold(2, |len, v| len + json_byte_len(v) + 1),
+        serde_json::Value::Object(o) => o
+            .iter()
+            .fold(2, |len, (k, v)| len + string_byte_len(k) + json_byte_len(v) + 1),
+    }
+}
+
+fn string_byte_len(s: &str) -> usize {
+    // Null bytes are escaped, and the string is terminated with two bytes
+    s.len() + s.bytes().filter(|b| *b == 0).count() + 2
+}
+
+fn write_json(cursor: &mut Cursor<Vec<u8>>, value: &serde_json::Value) -> Result<(), IoError> {
+    match value {
+        serde_json::Value::Null => cursor.write_all(&[JSON_NULL_TAG]),
+        serde_json::Value::Bool(b) => cursor.write_all(&[JSON_BOOL_TAG, *b as u8]),
+        serde_json::Value::Number(n) => {
+            cursor.write_all(&[JSON_NUMBER_TAG])?;
+            write_number(cursor, n)
+        }
+        serde_json::Value::String(s) => {
+            cursor.write_all(&[JSON_STRING_TAG])?;
+            write_string(cursor, s)
+        }
+        serde_json::Value::Array(a) => {
+            cursor.write_all(&[JSON_ARRAY_TAG])?;
+            for v in a {
+                cursor.write_all(&[JSON_ITEM_MARKER])?;
+                write_json(cursor, v)?;
+            }
+            cursor.write_all(&[JSON_END_MARKER])
+        }
+        serde_json::Value::Object(o) => {
+            cursor.write_all(&[JSON_OBJECT_TAG])?;
+            for (k, v) in o {
+                cursor.write_all(&[JSON_ITEM_MARKER])?;
+                write_string(cursor, k)?;
+                write_json(cursor, v)?;
             }
+            cursor.write_all(&[JSON_END_MARKER])
+        }
+    }
+}
+
+// Numbers are written as an order-preserving `f64`, followed by an
+// order-preserving `i128` of their integral value. The latter breaks ties
+// between large integers that can't be exactly represented as an `f64`.
+fn write_number(cursor: &mut Cursor<Vec<u8>>, n: &serde_json::Number) -> Result<(), IoError> {
+    let (approx, exact) = if let Some(i) = n.as_i64() {
+        (i as f64, i as i128)
+    } else if let Some(u) = n.as_u64() {
+        (u as f64, u as i128)
+    } else {
+        let f = n.as_f64().unwrap();
+        (f, f as i128)
+    };
+
+    // Normalize negative zero, since it's equal to positive zero
+    let approx = if approx == 0.0 { 0.0f64 } else { approx };
+    let bits = approx.to_bits();
+    let bits = if bits >> 63 == 1 { !bits } else { bits | (1 << 63) };
+    cursor.write_u64::<BigEndian>(bits)?;
+    cursor.write_u128::<BigEndian>((exact as u128) ^ (1 << 127))
+}
+
+// Strings are written with null bytes escaped, followed by a terminator that
+// sorts lower than any other byte sequence.
+fn write_string(cursor: &mut Cursor<Vec<u8>>, s: &str) -> Result<(), IoError> {
+    for b in s.bytes() {
+        if b == 0 {
+            cursor.write_all(&[0, 0xFF])?;
+        } else {
+            cursor.write_all(&[b])?;
         }
     }
+    cursor.write_all(&[0, 1])
+}
+
+fn read_json_value<T: AsRef<[u8]>>(cursor: &mut Cursor<T>) -> serde_json::Value {
+    match cursor.read_u8().unwrap() {
+        JSON_NULL_TAG => serde_json::Value::Null,
+        JSON_BOOL_TAG => serde_json::Value::Bool(cursor.read_u8().unwrap() != 0),
+        JSON_NUMBER_TAG => serde_json::Value::Number(read_number(cursor)),
+        JSON_STRING_TAG => serde_json::Value::String(read_string(cursor)),
+        JSON_ARRAY_TAG => {
+            let mut a = Vec::new();
+            while cursor.read_u8().unwrap() == JSON_ITEM_MARKER {
+                a.push(read_json_value(cursor));
+            }
+            serde_json::Value::Array(a)
+        }
+        JSON_OBJECT_TAG => {
+            let mut o = serde_json::Map::new();
+            while cursor.read_u8().unwrap() == JSON_ITEM_MARKER {
+                let k = read_string(cursor);
+                o.insert(k, read_json_value(cursor));
+            }
+            serde_json::Value::Object(o)
+        }
+        tag => panic!("unexpected json tag: {tag}"),
+    }
+}
+
+fn read_number<T: AsRef<[u8]>>(cursor: &mut Cursor<T>) -> serde_json::Number {
+    let bits = cursor.read_u64::<BigEndian>().unwrap();
+    let bits = if bits >> 63 == 1 { bits & !(1 << 63) } else { !bits };
+    let approx = f64::from_bits(bits);
+    let exact = (cursor.read_u128::<BigEndian>().unwrap() ^ (1 << 127)) as i128;
+
+    if approx.fract() == 0.0 && exact as f64 == approx {
+        if let Ok(i) = i64::try_from(exact) {
+            return serde_json::Number::from(i);
+        } else if let Ok(u) = u64::try_from(exact) {
+            return serde_json::Number::from(u);
+        }
+    }
+
+    serde_json::Number::from_f64(approx).unwrap()
+}
+
+fn read_string<T: AsRef<[u8]>>(cursor: &mut Cursor<T>) -> String {
+    let mut buf = Vec::new();
+    loop {
+        match cursor.read_u8().unwrap() {
+            0 => match cursor.read_u8().unwrap() {
+                0xFF => buf.push(0),
+                _ => break,
+            },
+            b => buf.push(b),
+        }
+    }
+    unsafe { String::from_utf8_unchecked(buf) }
 }
 
 // Serializes component(s) into bytes.
@@ -127,6 +277,14 @@ pub fn read_u64<T: AsRef<[u8]>>(cursor: &mut Cursor<T>) -> u64 {
     cursor.read_u64::<BigEndian>().unwrap()
 }
 
+/// Reads a JSON value from bytes.
+///
+/// # Arguments
+/// * `cursor`: The bytes to read from.
+pub fn read_json<T: AsRef<[u8]>>(cursor: &mut Cursor<T>) -> models::Json {
+    models::Json(Arc::new(read_json_value(cursor)))
+}
+
 /// Generates a UUID v1. This utility method uses a shared context and node ID
 /// to help ensure generated UUIDs are unique.
 pub fn generate_uuid_v1() -> Uuid {
@@ -216,15 +374,147 @@ pub fn extract_edge_properties(mut output: Vec<models::QueryOutputValue>) -> Opt
     }
 }
 
+/// Extracts paths from the last query output value, or `None`.
+///
+/// # Arguments
+/// * `output`: The query output.
+pub fn extract_paths(mut output: Vec<models::QueryOutputValue>) -> Option<Vec<models::Path>> {
+    if let Some(models::QueryOutputValue::Paths(paths)) = output.pop() {
+        Some(paths)
+    } else {
+        None
+    }
+}
+
+/// Extracts aggregation groups from the last query output value, or `None`.
+///
+/// # Arguments
+/// * `output`: The query output.
+pub fn extract_aggregation(mut output: Vec<models::QueryOutputValue>) -> Option<Vec<models::AggregationGroup>> {
+    if let Some(models::QueryOutputValue::Aggregation(groups)) = output.pop() {
+        Some(groups)
+    } else {
+        None
+    }
+}
+
+/// Extracts vertex degrees from the last query output value, or `None`.
+///
+/// # Arguments
+/// * `output`: The query output.
+pub fn extract_degrees(mut output: Vec<models::QueryOutputValue>) -> Option<Vec<models::VertexDegree>> {
+    if let Some(models::QueryOutputValue::Degrees(degrees)) = output.pop() {
+        Some(degrees)
+    } else {
+        None
+    }
+}
+
+/// Extracts projected rows from the last query output value, or `None`.
+///
+/// # Arguments
+/// * `output`: The query output.
+pub fn extract_rows(mut output: Vec<models::QueryOutputValue>) -> Option<Vec<models::Row>> {
+    if let Some(models::QueryOutputValue::Rows(rows)) = output.pop() {
+        Some(rows)
+    } else {
+        None
+    }
+}
+
+/// Extracts a subgraph from the last query output value, or `None`.
+///
+/// # Arguments
+/// * `output`: The query output.
+pub fn extract_subgraph(mut output: Vec<models::QueryOutputValue>) -> Option<models::Subgraph> {
+    if let Some(models::QueryOutputValue::Subgraph(subgraph)) = output.pop() {
+        Some(subgraph)
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        extract_count, extract_edge_properties, extract_edges, extract_vertex_properties, extract_vertices,
-        generate_uuid_v1, next_uuid,
+        build, extract_aggregation, extract_count, extract_edge_properties, extract_edges, extract_paths,
+        extract_vertex_properties, extract_vertices, generate_uuid_v1, next_uuid, read_json, read_uuid, Component,
     };
+    use crate::ijson;
     use core::str::FromStr;
+    use std::io::Cursor;
     use uuid::Uuid;
 
+    fn json_values() -> Vec<crate::Json> {
+        vec![
+            ijson!(null),
+            ijson!(false),
+            ijson!(true),
+            ijson!(-1e300),
+            ijson!(i64::MIN),
+            ijson!(-3.5),
+            ijson!(-3),
+            ijson!(0),
+            ijson!(0.5),
+            ijson!(3),
+            ijson!(9007199254740993u64),
+            ijson!(9007199254740994u64),
+            ijson!(u64::MAX),
+            ijson!(1e300),
+            ijson!(""),
+            ijson!("\u{0}"),
+            ijson!("\u{0}a"),
+            ijson!("a"),
+            ijson!("a\u{0}"),
+            ijson!("ab"),
+            ijson!("b"),
+            ijson!([]),
+            ijson!([null]),
+            ijson!([1, 2]),
+            ijson!([1, "a"]),
+            ijson!([2]),
+            ijson!({}),
+            ijson!({"a": 1}),
+            ijson!({"a": 1, "b": 2}),
+            ijson!({"b": 0}),
+        ]
+    }
+
+    #[test]
+    fn should_encode_json_in_order() {
+        let values = json_values();
+        for (i, first) in values.iter().enumerate() {
+            for second in &values[i + 1..] {
+                assert_eq!(first.total_cmp(second), std::cmp::Ordering::Less);
+                assert!(build(&[Component::Json(first)]) < build(&[Component::Json(second)]));
+            }
+        }
+    }
+
+    #[test]
+    fn should_read_encoded_json() {
+        let id = generate_uuid_v1();
+        for value in json_values() {
+            let bytes = build(&[Component::Json(&value), Component::Uuid(id)]);
+            assert_eq!(bytes.len(), Component::Json(&value).byte_len() + 16);
+            let mut cursor = Cursor::new(bytes);
+            assert_eq!(read_json(&mut cursor), value);
+            assert_eq!(read_uuid(&mut cursor), id);
+        }
+    }
+
+    #[test]
+    fn should_encode_equal_numbers_identically() {
+        assert_eq!(
+            build(&[Component::Json(&ijson!(3))]),
+            build(&[Component::Json(&ijson!(3.0))])
+        );
+        assert_eq!(
+            build(&[Component::Json(&ijson!(0))]),
+            build(&[Component::Json(&ijson!(-0.0))])
+        );
+    }
+
     #[test]
     fn should_generate_new_uuid_v1() {
         let first = generate_uuid_v1();
@@ -269,4 +559,14 @@ mod tests {
     fn should_not_extract_edge_properties_on_empty() {
         assert_eq!(extract_edge_properties(vec![]), None);
     }
+
+    #[test]
+    fn should_not_extract_paths_on_empty() {
+        assert_eq!(extract_paths(vec![]), None);
+    }
+
+    #[test]
+    fn should_not_extract_aggregation_on_empty() {
+        assert_eq!(extract_aggregation(vec![]), None);
+    }
 }
@@ -9,7 +9,7 @@ use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::StreamExt;
 use tonic::transport::{Channel, Endpoint, Error as TonicTransportError};
-use tonic::{Request, Status};
+use tonic::{Request, Status, Streaming};
 use uuid::Uuid;
 
 const CHANNEL_CAPACITY: usize = 100;
@@ -73,6 +73,55 @@ impl<T> From<mpsc::error::SendError<T>> for ClientError {
     }
 }
 
+fn append_output_value(
+    value: &mut indradb::QueryOutputValue,
+    chunk: indradb::QueryOutputValue,
+) -> Result<(), ConversionError> {
+    match (value, chunk) {
+        (indradb::QueryOutputValue::Vertices(ref mut vertices), indradb::QueryOutputValue::Vertices(chunk)) => {
+            vertices.extend(chunk)
+        }
+        (indradb::QueryOutputValue::Edges(ref mut edges), indradb::QueryOutputValue::Edges(chunk)) => {
+            edges.extend(chunk)
+        }
+        (
+            indradb::QueryOutputValue::VertexProperties(ref mut vertex_properties),
+            indradb::QueryOutputValue::VertexProperties(chunk),
+        ) => vertex_properties.extend(chunk),
+        (
+            indradb::QueryOutputValue::EdgeProperties(ref mut edge_properties),
+            indradb::QueryOutputValue::EdgeProperties(chunk),
+        ) => edge_properties.extend(chunk),
+        (indradb::QueryOutputValue::Paths(ref mut paths), indradb::QueryOutputValue::Paths(chunk)) => {
+            paths.extend(chunk)
+        }
+        _ => return Err(ConversionError::UnexpectedResponseType),
+    }
+    Ok(())
+}
+
+type PagedOutput = (Vec<indradb::QueryOutputValue>, Option<indradb::ContinuationToken>);
+
+// Collects the chunks of a query's output, along with the continuation token
+// from the last chunk to have one.
+async fn collect_output(mut res: Streaming<crate::QueryOutputValue>) -> Result<PagedOutput, ClientError> {
+    let mut output = Vec::<indradb::QueryOutputValue>::new();
+    let mut continuation = None;
+    let mut more = false;
+    while let Some(res) = res.next().await {
+        let chunk: indradb::QueryOutputChunk = res?.try_into()?;
+        match output.last_mut() {
+            // The server split this output value into multiple chunks, so
+            // stitch them back together.
+            Some(last) if more => append_output_value(last, chunk.value)?,
+            _ => output.push(chunk.value),
+        }
+        more = chunk.more;
+        continuation = chunk.continuation.or(continuation);
+    }
+    Ok((output, continuation))
+}
+
 /// A higher-level client implementation.
 ///
 /// This should be better suited than the low-level client auto-generated by
@@ -147,12 +196,67 @@ impl Client {
     /// # Arguments
     /// * `q`: The query to run.
     pub async fn get<Q: Into<indradb::Query>>(&mut self, q: Q) -> Result<Vec<indradb::QueryOutputValue>, ClientError> {
+        let (output, _) = self.get_proto(q.into().into()).await?;
+        Ok(output)
+    }
+
+    /// Gets values specified by a query, along with a token for resuming the
+    /// query after the last value output. The token is only returned for
+    /// resumable queries with output; running the query with a limit and
+    /// then resuming it with `ResumeQuery` pages through its output.
+    ///
+    /// # Arguments
+    /// * `q`: The query to run.
+    pub async fn get_page<Q: Into<indradb::Query>>(
+        &mut self,
+        q: Q,
+    ) -> Result<(Vec<indradb::QueryOutputValue>, Option<indradb::ContinuationToken>), ClientError> {
+        self.get_proto(q.into().into()).await
+    }
+
+    /// Gets values specified by a query written in the text query language.
+    /// The query is parsed by the server.
+    ///
+    /// # Arguments
+    /// * `text`: The query to run, e.g. `V(type=person).out(follows)`.
+    pub async fn query(&mut self, text: &str) -> Result<Vec<indradb::QueryOutputValue>, ClientError> {
+        let q = crate::Query {
+            query: Some(crate::QueryVariant::Text(text.to_string())),
+        };
+        let (output, _) = self.get_proto(q).await?;
+        Ok(output)
+    }
+
+    async fn get_proto(&mut self, q: crate::Query) -> Result<PagedOutput, ClientError> {
+        let res = self.0.get(q).await?.into_inner();
+        collect_output(res).await
+    }
+
+    /// Prepares a query to be run many times with different parameters.
+    /// The query is validated once by the server, and kept for as long as
+    /// this client is connected. Returns the ID of the prepared query.
+    ///
+    /// # Arguments
+    /// * `q`: The query to prepare, with parameter placeholders.
+    pub async fn prepare<Q: Into<indradb::Query>>(&mut self, q: Q) -> Result<Uuid, ClientError> {
         let q: crate::Query = q.into().into();
-        let mut output = Vec::<indradb::QueryOutputValue>::new();
-        let mut res = self.0.get(q).await?.into_inner();
-        while let Some(res) = res.next().await {
-            output.push(res?.try_into()?);
-        }
+        let res = self.0.prepare_query(q).await?;
+        Ok(res.into_inner().try_into()?)
+    }
+
+    /// Runs a query prepared with `prepare`.
+    ///
+    /// # Arguments
+    /// * `id`: The ID of the prepared query.
+    /// * `params`: The values of the query's parameters.
+    pub async fn execute_prepared(
+        &mut self,
+        id: Uuid,
+        params: &indradb::Params,
+    ) -> Result<Vec<indradb::QueryOutputValue>, ClientError> {
+        let req = Request::new(crate::ExecutePreparedRequest::from((id, params)));
+        let res = self.0.execute_prepared(req).await?.into_inner();
+        let (output, _) = collect_output(res).await?;
         Ok(output)
     }
 
@@ -236,6 +340,27 @@ impl Client {
         Ok(())
     }
 
+    /// Explains how a query would be run, without running it.
+    ///
+    /// # Arguments
+    /// * `q`: The query to explain.
+    pub async fn explain<Q: Into<indradb::Query>>(&mut self, q: Q) -> Result<indradb::QueryPlan, ClientError> {
+        let req = Request::new(crate::ExplainRequest::from((q.into(), false)));
+        let res = self.0.explain(req).await?;
+        Ok(res.into_inner().into())
+    }
+
+    /// Runs a query, and explains how it was run, including the number of
+    /// values output by and time taken by each stage.
+    ///
+    /// # Arguments
+    /// * `q`: The query to analyze.
+    pub async fn analyze<Q: Into<indradb::Query>>(&mut self, q: Q) -> Result<indradb::QueryPlan, ClientError> {
+        let req = Request::new(crate::ExplainRequest::from((q.into(), true)));
+        let res = self.0.explain(req).await?;
+        Ok(res.into_inner().into())
+    }
+
     pub async fn execute_plugin(&mut self, name: &str, arg: indradb::Json) -> Result<indradb::Json, ClientError> {
         let req = Request::new(crate::ExecutePluginRequest {
             name: name.to_string(),
@@ -4,8 +4,10 @@
 use std::convert::TryInto;
 use std::error::Error as StdError;
 use std::fmt;
+use std::ops::Bound;
+use std::time::Duration;
 
-use indradb::ValidationError;
+use indradb::{ParseError, ValidationError};
 use serde_json::Error as SerdeJsonError;
 use uuid::Error as UuidError;
 use uuid::Uuid;
@@ -18,6 +20,7 @@ pub enum ConversionError {
     Validation { inner: ValidationError },
     NoneField { name: String },
     UnexpectedResponseType,
+    Parse { inner: ParseError },
 }
 
 impl StdError for ConversionError {
@@ -26,6 +29,7 @@ impl StdError for ConversionError {
             ConversionError::Json { ref inner } => Some(inner),
             ConversionError::Uuid { ref inner } => Some(inner),
             ConversionError::Validation { ref inner } => Some(inner),
+            ConversionError::Parse { ref inner } => Some(inner),
             _ => None,
         }
     }
@@ -39,6 +43,7 @@ impl fmt::Display for ConversionError {
             ConversionError::Validation { ref inner } => write!(f, "validation conversion failed: {inner}"),
             ConversionError::NoneField { ref name } => write!(f, "proto field '{name}' should not be none"),
             ConversionError::UnexpectedResponseType => write!(f, "unexpected response type"),
+            ConversionError::Parse { ref inner } => write!(f, "query parsing failed: {inner}"),
         }
     }
 }
@@ -61,12 +66,46 @@ impl From<ValidationError> for ConversionError {
     }
 }
 
+impl From<ParseError> for ConversionError {
+    fn from(err: ParseError) -> Self {
+        ConversionError::Parse { inner: err }
+    }
+}
+
 fn required_field<T>(field_name: &str, value: Option<T>) -> Result<T, ConversionError> {
     value.ok_or_else(|| ConversionError::NoneField {
         name: field_name.to_string(),
     })
 }
 
+fn json_bound_to_proto(bound: Bound<indradb::Json>) -> Option<crate::JsonBound> {
+    match bound {
+        Bound::Included(value) => Some(crate::JsonBound {
+            value: Some(value.into()),
+            inclusive: true,
+        }),
+        Bound::Excluded(value) => Some(crate::JsonBound {
+            value: Some(value.into()),
+            inclusive: false,
+        }),
+        Bound::Unbounded => None,
+    }
+}
+
+fn json_bound_from_proto(bound: Option<crate::JsonBound>) -> Result<Bound<indradb::Json>, ConversionError> {
+    Ok(match bound {
+        Some(bound) => {
+            let value = required_field("value", bound.value)?.try_into()?;
+            if bound.inclusive {
+                Bound::Included(value)
+            } else {
+                Bound::Excluded(value)
+            }
+        }
+        None => Bound::Unbounded,
+    })
+}
+
 impl From<Uuid> for crate::Uuid {
     fn from(uuid: Uuid) -> Self {
         crate::Uuid {
@@ -155,6 +194,158 @@ impl TryInto<indradb::Vertex> for crate::Vertex {
     }
 }
 
+impl From<indradb::Path> for crate::Path {
+    fn from(path: indradb::Path) -> Self {
+        crate::Path {
+            vertices: path.vertices.into_iter().map(|v| v.into()).collect(),
+            edges: path.edges.into_iter().map(|e| e.into()).collect(),
+        }
+    }
+}
+
+impl TryInto<indradb::Path> for crate::Path {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<indradb::Path, Self::Error> {
+        let vertices: Result<Vec<indradb::Vertex>, ConversionError> =
+            self.vertices.into_iter().map(|v| v.try_into()).collect();
+        let edges: Result<Vec<indradb::Edge>, ConversionError> = self.edges.into_iter().map(|e| e.try_into()).collect();
+        Ok(indradb::Path::new(vertices?, edges?))
+    }
+}
+
+impl From<indradb::AggregationGroup> for crate::AggregationGroup {
+    fn from(group: indradb::AggregationGroup) -> Self {
+        crate::AggregationGroup {
+            key: group.key.map(|key| key.into()),
+            count: group.count,
+            value: match group.value {
+                indradb::AggregationValue::Number(number) => number.map(crate::AggregationValueVariant::Number),
+                indradb::AggregationValue::Histogram(buckets) => {
+                    Some(crate::AggregationValueVariant::Histogram(crate::Histogram {
+                        buckets: buckets
+                            .into_iter()
+                            .map(|b| crate::HistogramBucket {
+                                low: b.low,
+                                count: b.count,
+                            })
+                            .collect(),
+                    }))
+                }
+            },
+        }
+    }
+}
+
+impl TryInto<indradb::AggregationGroup> for crate::AggregationGroup {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<indradb::AggregationGroup, Self::Error> {
+        let key = self.key.map(|key| key.try_into()).transpose()?;
+        let value = match self.value {
+            Some(crate::AggregationValueVariant::Number(number)) => indradb::AggregationValue::Number(Some(number)),
+            Some(crate::AggregationValueVariant::Histogram(histogram)) => indradb::AggregationValue::Histogram(
+                histogram
+                    .buckets
+                    .into_iter()
+                    .map(|b| indradb::HistogramBucket::new(b.low, b.count))
+                    .collect(),
+            ),
+            None => indradb::AggregationValue::Number(None),
+        };
+        Ok(indradb::AggregationGroup::new(key, self.count, value))
+    }
+}
+
+impl From<indradb::VertexDegree> for crate::VertexDegree {
+    fn from(degree: indradb::VertexDegree) -> Self {
+        crate::VertexDegree {
+            vertex: Some(degree.vertex.into()),
+            outbound: degree.outbound,
+            inbound: degree.inbound,
+        }
+    }
+}
+
+impl TryInto<indradb::VertexDegree> for crate::VertexDegree {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<indradb::VertexDegree, Self::Error> {
+        Ok(indradb::VertexDegree::new(
+            required_field("vertex", self.vertex)?.try_into()?,
+            self.outbound,
+            self.inbound,
+        ))
+    }
+}
+
+impl From<indradb::Row> for crate::Row {
+    fn from(row: indradb::Row) -> Self {
+        crate::Row {
+            values: row
+                .values
+                .into_iter()
+                .map(|value| crate::RowValue {
+                    value: Some(match value {
+                        indradb::RowValue::Vertex(vertex) => crate::RowValueVariant::Vertex(vertex.into()),
+                        indradb::RowValue::Edge(edge) => crate::RowValueVariant::Edge(edge.into()),
+                        indradb::RowValue::Property(prop) => crate::RowValueVariant::Property(prop.into()),
+                    }),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryInto<indradb::Row> for crate::Row {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<indradb::Row, Self::Error> {
+        let values: Result<Vec<indradb::RowValue>, ConversionError> = self
+            .values
+            .into_iter()
+            .map(|value| {
+                Ok(match required_field("value", value.value)? {
+                    crate::RowValueVariant::Vertex(vertex) => indradb::RowValue::Vertex(vertex.try_into()?),
+                    crate::RowValueVariant::Edge(edge) => indradb::RowValue::Edge(edge.try_into()?),
+                    crate::RowValueVariant::Property(prop) => indradb::RowValue::Property(prop.try_into()?),
+                })
+            })
+            .collect();
+        Ok(indradb::Row::new(values?))
+    }
+}
+
+impl From<indradb::Subgraph> for crate::Subgraph {
+    fn from(subgraph: indradb::Subgraph) -> Self {
+        crate::Subgraph {
+            vertices: subgraph.vertices.into_iter().map(|v| v.into()).collect(),
+            edges: subgraph.edges.into_iter().map(|e| e.into()).collect(),
+            vertex_properties: subgraph.vertex_properties.into_iter().map(|vp| vp.into()).collect(),
+            edge_properties: subgraph.edge_properties.into_iter().map(|ep| ep.into()).collect(),
+        }
+    }
+}
+
+impl TryInto<indradb::Subgraph> for crate::Subgraph {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<indradb::Subgraph, Self::Error> {
+        let vertices: Result<Vec<indradb::Vertex>, ConversionError> =
+            self.vertices.into_iter().map(|v| v.try_into()).collect();
+        let edges: Result<Vec<indradb::Edge>, ConversionError> = self.edges.into_iter().map(|e| e.try_into()).collect();
+        let vertex_properties: Result<Vec<indradb::VertexProperties>, ConversionError> =
+            self.vertex_properties.into_iter().map(|vp| vp.try_into()).collect();
+        let edge_properties: Result<Vec<indradb::EdgeProperties>, ConversionError> =
+            self.edge_properties.into_iter().map(|ep| ep.try_into()).collect();
+        Ok(indradb::Subgraph {
+            vertex_properties: vertex_properties?,
+            edge_properties: edge_properties?,
+            ..indradb::Subgraph::new(vertices?, edges?)
+        })
+    }
+}
+
 impl From<indradb::Query> for crate::Query {
     fn from(q: indradb::Query) -> Self {
         crate::Query {
@@ -164,9 +355,11 @@ impl From<indradb::Query> for crate::Query {
                     limit: q.limit,
                     t: q.t.map(|t| t.into()),
                     start_id: q.start_id.map(|start_id| start_id.into()),
+                    limit_param: q.limit_param.map(|name| name.into()),
                 }),
                 indradb::Query::SpecificVertex(q) => crate::QueryVariant::SpecificVertex(crate::SpecificVertexQuery {
                     ids: q.ids.into_iter().map(|id| id.into()).collect(),
+                    ids_param: q.ids_param.map(|name| name.into()),
                 }),
                 indradb::Query::VertexWithPropertyPresence(q) => {
                     crate::QueryVariant::VertexWithPropertyPresence(crate::VertexWithPropertyPresenceQuery {
@@ -177,12 +370,21 @@ impl From<indradb::Query> for crate::Query {
                     crate::QueryVariant::VertexWithPropertyValue(crate::VertexWithPropertyValueQuery {
                         name: Some(q.name.into()),
                         value: Some(q.value.into()),
+                        value_param: q.value_param.map(|name| name.into()),
+                    })
+                }
+                indradb::Query::VertexWithPropertyRange(q) => {
+                    crate::QueryVariant::VertexWithPropertyRange(crate::VertexWithPropertyRangeQuery {
+                        name: Some(q.name.into()),
+                        low: json_bound_to_proto(q.low),
+                        high: json_bound_to_proto(q.high),
                     })
                 }
 
                 indradb::Query::AllEdge => crate::QueryVariant::AllEdge(()),
                 indradb::Query::SpecificEdge(q) => crate::QueryVariant::SpecificEdge(crate::SpecificEdgeQuery {
                     edges: q.edges.into_iter().map(|id| id.into()).collect(),
+                    edges_param: q.edges_param.map(|name| name.into()),
                 }),
                 indradb::Query::EdgeWithPropertyPresence(q) => {
                     crate::QueryVariant::EdgeWithPropertyPresence(crate::EdgeWithPropertyPresenceQuery {
@@ -193,8 +395,21 @@ impl From<indradb::Query> for crate::Query {
                     crate::QueryVariant::EdgeWithPropertyValue(crate::EdgeWithPropertyValueQuery {
                         name: Some(q.name.into()),
                         value: Some(q.value.into()),
+                        value_param: q.value_param.map(|name| name.into()),
                     })
                 }
+                indradb::Query::EdgeWithPropertyRange(q) => {
+                    crate::QueryVariant::EdgeWithPropertyRange(crate::EdgeWithPropertyRangeQuery {
+                        name: Some(q.name.into()),
+                        low: json_bound_to_proto(q.low),
+                        high: json_bound_to_proto(q.high),
+                    })
+                }
+                indradb::Query::EdgesByType(q) => crate::QueryVariant::EdgesByType(crate::EdgesByTypeQuery {
+                    t: Some(q.t.into()),
+                    limit: q.limit,
+                    start: q.start.map(|start| start.into()),
+                }),
 
                 indradb::Query::Pipe(q) => {
                     let mut proto_q = crate::PipeQuery {
@@ -202,6 +417,8 @@ impl From<indradb::Query> for crate::Query {
                         direction: 0,
                         limit: q.limit,
                         t: q.t.map(|t| t.into()),
+                        distinct: q.distinct,
+                        limit_param: q.limit_param.map(|name| name.into()),
                     };
                     proto_q.set_direction(q.direction.into());
                     crate::QueryVariant::Pipe(Box::new(proto_q))
@@ -218,6 +435,7 @@ impl From<indradb::Query> for crate::Query {
                         inner: Some(Box::new((*q.inner).into())),
                         name: Some(q.name.into()),
                         exists: q.exists,
+                        allow_scan: q.allow_scan,
                     };
                     crate::QueryVariant::PipeWithPropertyPresence(Box::new(proto_q))
                 }
@@ -227,9 +445,126 @@ impl From<indradb::Query> for crate::Query {
                         name: Some(q.name.into()),
                         value: Some(q.value.into()),
                         equal: q.equal,
+                        allow_scan: q.allow_scan,
+                        value_param: q.value_param.map(|name| name.into()),
                     };
                     crate::QueryVariant::PipeWithPropertyValue(Box::new(proto_q))
                 }
+                indradb::Query::PipeWithPropertyRange(q) => {
+                    let proto_q = crate::PipeWithPropertyRangeQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        name: Some(q.name.into()),
+                        low: json_bound_to_proto(q.low),
+                        high: json_bound_to_proto(q.high),
+                    };
+                    crate::QueryVariant::PipeWithPropertyRange(Box::new(proto_q))
+                }
+                indradb::Query::PipeWithPropertyPredicate(q) => {
+                    let proto_q = crate::PipeWithPropertyPredicateQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        name: Some(q.name.into()),
+                        predicate: Some(q.predicate.into()),
+                    };
+                    crate::QueryVariant::PipeWithPropertyPredicate(Box::new(proto_q))
+                }
+                indradb::Query::Traversal(q) => {
+                    let mut proto_q = crate::TraversalQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        direction: 0,
+                        t: q.t.map(|t| t.into()),
+                        min_depth: q.min_depth,
+                        max_depth: q.max_depth,
+                        emit_frontiers: q.emit_frontiers,
+                    };
+                    proto_q.set_direction(q.direction.into());
+                    crate::QueryVariant::Traversal(Box::new(proto_q))
+                }
+                indradb::Query::ShortestPath(q) => {
+                    let mut proto_q = crate::ShortestPathQuery {
+                        source_id: Some(q.source_id.into()),
+                        target_id: Some(q.target_id.into()),
+                        direction: 0,
+                        t: q.t.map(|t| t.into()),
+                    };
+                    proto_q.set_direction(q.direction.into());
+                    crate::QueryVariant::ShortestPath(proto_q)
+                }
+                indradb::Query::WeightedShortestPath(q) => {
+                    let missing_weight = match q.missing_weight {
+                        indradb::MissingWeight::Skip => crate::MissingWeightVariant::Skip(()),
+                        indradb::MissingWeight::Default(value) => {
+                            crate::MissingWeightVariant::DefaultWeight(value.into())
+                        }
+                        indradb::MissingWeight::Error => crate::MissingWeightVariant::Error(()),
+                    };
+                    let mut proto_q = crate::WeightedShortestPathQuery {
+                        source_id: Some(q.source_id.into()),
+                        target_id: Some(q.target_id.into()),
+                        direction: 0,
+                        t: q.t.map(|t| t.into()),
+                        weight: Some(q.weight.into()),
+                        missing_weight: Some(missing_weight),
+                    };
+                    proto_q.set_direction(q.direction.into());
+                    crate::QueryVariant::WeightedShortestPath(proto_q)
+                }
+                indradb::Query::Subgraph(q) => {
+                    let mut proto_q = crate::SubgraphQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        direction: 0,
+                        t: q.t.map(|t| t.into()),
+                        depth: q.depth,
+                        vertex_limit: q.vertex_limit,
+                        edge_limit: q.edge_limit,
+                        properties: q.properties,
+                    };
+                    proto_q.set_direction(q.direction.into());
+                    crate::QueryVariant::Subgraph(Box::new(proto_q))
+                }
+                indradb::Query::Resume(q) => {
+                    let proto_q = crate::ResumeQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        continuation: q.continuation.to_bytes(),
+                    };
+                    crate::QueryVariant::Resume(Box::new(proto_q))
+                }
+                indradb::Query::Union(q) => {
+                    let proto_q = crate::UnionQuery {
+                        left: Some(Box::new((*q.left).into())),
+                        right: Some(Box::new((*q.right).into())),
+                    };
+                    crate::QueryVariant::Union(Box::new(proto_q))
+                }
+                indradb::Query::Intersect(q) => {
+                    let proto_q = crate::IntersectQuery {
+                        left: Some(Box::new((*q.left).into())),
+                        right: Some(Box::new((*q.right).into())),
+                    };
+                    crate::QueryVariant::Intersect(Box::new(proto_q))
+                }
+                indradb::Query::Except(q) => {
+                    let proto_q = crate::ExceptQuery {
+                        left: Some(Box::new((*q.left).into())),
+                        right: Some(Box::new((*q.right).into())),
+                    };
+                    crate::QueryVariant::Except(Box::new(proto_q))
+                }
+                indradb::Query::Distinct(q) => {
+                    let proto_q = crate::DistinctQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                    };
+                    crate::QueryVariant::Distinct(Box::new(proto_q))
+                }
+                indradb::Query::OrderBy(q) => {
+                    let mut proto_q = crate::OrderByQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        name: Some(q.name.into()),
+                        direction: 0,
+                        limit: q.limit,
+                    };
+                    proto_q.set_direction(q.direction.into());
+                    crate::QueryVariant::OrderBy(Box::new(proto_q))
+                }
 
                 indradb::Query::Include(q) => {
                     let proto_q = crate::IncludeQuery {
@@ -240,9 +575,38 @@ impl From<indradb::Query> for crate::Query {
                 indradb::Query::Count(q) => {
                     let proto_q = crate::CountQuery {
                         inner: Some(Box::new((*q.inner).into())),
+                        distinct: q.distinct,
                     };
                     crate::QueryVariant::Count(Box::new(proto_q))
                 }
+                indradb::Query::Aggregate(q) => {
+                    let bucket_width = match q.function {
+                        indradb::AggregateFunction::Histogram(ref width) => Some(width.clone().into()),
+                        _ => None,
+                    };
+                    let mut proto_q = crate::AggregateQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        name: Some(q.name.into()),
+                        function: 0,
+                        bucket_width,
+                        group_by: q.group_by.map(|group_by| match group_by {
+                            indradb::GroupBy::Type => crate::GroupByVariant::ByType(()),
+                            indradb::GroupBy::Property(name) => crate::GroupByVariant::ByProperty(name.into()),
+                        }),
+                    };
+                    proto_q.set_function((&q.function).into());
+                    crate::QueryVariant::Aggregate(Box::new(proto_q))
+                }
+                indradb::Query::Degree(q) => {
+                    let proto_q = crate::DegreeQuery {
+                        inner: Some(Box::new((*q.inner).into())),
+                        t: q.t.map(|t| t.into()),
+                    };
+                    crate::QueryVariant::Degree(Box::new(proto_q))
+                }
+                indradb::Query::Project(q) => crate::QueryVariant::Project(Box::new(crate::ProjectQuery {
+                    inner: Some(Box::new((*q.inner).into())),
+                })),
             }),
         }
     }
@@ -258,10 +622,14 @@ impl TryInto<indradb::Query> for crate::Query {
                 limit: q.limit,
                 t: q.t.map(|t| t.try_into()).transpose()?,
                 start_id: q.start_id.map(|start_id| start_id.try_into()).transpose()?,
+                limit_param: q.limit_param.map(|name| name.try_into()).transpose()?,
             }),
             crate::QueryVariant::SpecificVertex(q) => {
                 let ids: Result<Vec<Uuid>, ConversionError> = q.ids.into_iter().map(|id| id.try_into()).collect();
-                indradb::Query::SpecificVertex(indradb::SpecificVertexQuery { ids: ids? })
+                indradb::Query::SpecificVertex(indradb::SpecificVertexQuery {
+                    ids: ids?,
+                    ids_param: q.ids_param.map(|name| name.try_into()).transpose()?,
+                })
             }
             crate::QueryVariant::VertexWithPropertyPresence(q) => {
                 let name = required_field("name", q.name)?;
@@ -275,14 +643,24 @@ impl TryInto<indradb::Query> for crate::Query {
                 indradb::Query::VertexWithPropertyValue(indradb::VertexWithPropertyValueQuery {
                     name: name.try_into()?,
                     value: value.try_into()?,
+                    value_param: q.value_param.map(|name| name.try_into()).transpose()?,
                 })
             }
+            crate::QueryVariant::VertexWithPropertyRange(q) => {
+                let name: indradb::Identifier = required_field("name", q.name)?.try_into()?;
+                let low = json_bound_from_proto(q.low)?;
+                let high = json_bound_from_proto(q.high)?;
+                indradb::Query::VertexWithPropertyRange(indradb::VertexWithPropertyRangeQuery::new(name, low, high)?)
+            }
 
             crate::QueryVariant::AllEdge(_q) => indradb::Query::AllEdge,
             crate::QueryVariant::SpecificEdge(q) => {
                 let edges: Result<Vec<indradb::Edge>, ConversionError> =
                     q.edges.into_iter().map(|id| id.try_into()).collect();
-                indradb::Query::SpecificEdge(indradb::SpecificEdgeQuery { edges: edges? })
+                indradb::Query::SpecificEdge(indradb::SpecificEdgeQuery {
+                    edges: edges?,
+                    edges_param: q.edges_param.map(|name| name.try_into()).transpose()?,
+                })
             }
             crate::QueryVariant::EdgeWithPropertyPresence(q) => {
                 let name = required_field("name", q.name)?;
@@ -296,8 +674,20 @@ impl TryInto<indradb::Query> for crate::Query {
                 indradb::Query::EdgeWithPropertyValue(indradb::EdgeWithPropertyValueQuery {
                     name: name.try_into()?,
                     value: value.try_into()?,
+                    value_param: q.value_param.map(|name| name.try_into()).transpose()?,
                 })
             }
+            crate::QueryVariant::EdgeWithPropertyRange(q) => {
+                let name: indradb::Identifier = required_field("name", q.name)?.try_into()?;
+                let low = json_bound_from_proto(q.low)?;
+                let high = json_bound_from_proto(q.high)?;
+                indradb::Query::EdgeWithPropertyRange(indradb::EdgeWithPropertyRangeQuery::new(name, low, high)?)
+            }
+            crate::QueryVariant::EdgesByType(q) => indradb::Query::EdgesByType(indradb::EdgesByTypeQuery {
+                t: required_field("t", q.t)?.try_into()?,
+                limit: q.limit,
+                start: q.start.map(|start| start.try_into()).transpose()?,
+            }),
 
             crate::QueryVariant::Pipe(q) => {
                 let direction = q.direction().into();
@@ -308,6 +698,8 @@ impl TryInto<indradb::Query> for crate::Query {
                     direction,
                     limit,
                     t,
+                    distinct: q.distinct,
+                    limit_param: q.limit_param.map(|name| name.try_into()).transpose()?,
                     inner: Box::new((*inner).try_into()?),
                 })
             }
@@ -326,6 +718,7 @@ impl TryInto<indradb::Query> for crate::Query {
                     inner: Box::new((*inner).try_into()?),
                     name: name.try_into()?,
                     exists: q.exists,
+                    allow_scan: q.allow_scan,
                 })
             }
             crate::QueryVariant::PipeWithPropertyValue(q) => {
@@ -337,9 +730,133 @@ impl TryInto<indradb::Query> for crate::Query {
                     name: name.try_into()?,
                     value: value.try_into()?,
                     equal: q.equal,
+                    allow_scan: q.allow_scan,
+                    value_param: q.value_param.map(|name| name.try_into()).transpose()?,
                 })
             }
+            crate::QueryVariant::PipeWithPropertyRange(q) => {
+                let inner = required_field("inner", q.inner)?;
+                let name: indradb::Identifier = required_field("name", q.name)?.try_into()?;
+                let low = json_bound_from_proto(q.low)?;
+                let high = json_bound_from_proto(q.high)?;
+                indradb::Query::PipeWithPropertyRange(indradb::PipeWithPropertyRangeQuery::new(
+                    Box::new((*inner).try_into()?),
+                    name,
+                    low,
+                    high,
+                )?)
+            }
+            crate::QueryVariant::PipeWithPropertyPredicate(q) => {
+                let inner = required_field("inner", q.inner)?;
+                let name: indradb::Identifier = required_field("name", q.name)?.try_into()?;
+                let predicate = required_field("predicate", q.predicate)?.try_into()?;
+                indradb::Query::PipeWithPropertyPredicate(indradb::PipeWithPropertyPredicateQuery::new(
+                    Box::new((*inner).try_into()?),
+                    name,
+                    predicate,
+                )?)
+            }
+            crate::QueryVariant::Traversal(q) => {
+                let direction = q.direction().into();
+                let inner = required_field("inner", q.inner)?;
+                let mut traversal_q =
+                    indradb::TraversalQuery::new(Box::new((*inner).try_into()?), direction, q.min_depth, q.max_depth)?;
+                if let Some(t) = q.t {
+                    traversal_q = traversal_q.t(t.try_into()?);
+                }
+                if q.emit_frontiers {
+                    traversal_q = traversal_q.emit_frontiers();
+                }
+                indradb::Query::Traversal(traversal_q)
+            }
+            crate::QueryVariant::ShortestPath(q) => {
+                let direction = q.direction().into();
+                let source_id = required_field("source_id", q.source_id)?.try_into()?;
+                let target_id = required_field("target_id", q.target_id)?.try_into()?;
+                let mut shortest_path_q = indradb::ShortestPathQuery::new(source_id, target_id).direction(direction);
+                if let Some(t) = q.t {
+                    shortest_path_q = shortest_path_q.t(t.try_into()?);
+                }
+                indradb::Query::ShortestPath(shortest_path_q)
+            }
+            crate::QueryVariant::WeightedShortestPath(q) => {
+                let direction = q.direction().into();
+                let source_id = required_field("source_id", q.source_id)?.try_into()?;
+                let target_id = required_field("target_id", q.target_id)?.try_into()?;
+                let weight: indradb::Identifier = required_field("weight", q.weight)?.try_into()?;
+                let missing_weight = match q.missing_weight {
+                    Some(crate::MissingWeightVariant::Skip(_)) => indradb::MissingWeight::Skip,
+                    Some(crate::MissingWeightVariant::DefaultWeight(value)) => {
+                        indradb::MissingWeight::Default(value.try_into()?)
+                    }
+                    Some(crate::MissingWeightVariant::Error(_)) | None => indradb::MissingWeight::Error,
+                };
+                let mut weighted_q = indradb::WeightedShortestPathQuery::new(source_id, target_id, weight)
+                    .direction(direction)
+                    .missing_weight(missing_weight)?;
+                if let Some(t) = q.t {
+                    weighted_q = weighted_q.t(t.try_into()?);
+                }
+                indradb::Query::WeightedShortestPath(weighted_q)
+            }
+            crate::QueryVariant::Subgraph(q) => {
+                let direction = q.direction().into();
+                let inner = required_field("inner", q.inner)?;
+                let mut subgraph_q = indradb::SubgraphQuery::new(Box::new((*inner).try_into()?), direction, q.depth)?
+                    .vertex_limit(q.vertex_limit)
+                    .edge_limit(q.edge_limit);
+                if let Some(t) = q.t {
+                    subgraph_q = subgraph_q.t(t.try_into()?);
+                }
+                if q.properties {
+                    subgraph_q = subgraph_q.with_properties();
+                }
+                indradb::Query::Subgraph(subgraph_q)
+            }
+            crate::QueryVariant::Resume(q) => {
+                let inner = required_field("inner", q.inner)?;
+                let continuation = indradb::ContinuationToken::from_bytes(&q.continuation)?;
+                indradb::Query::Resume(indradb::ResumeQuery::new(Box::new((*inner).try_into()?), continuation)?)
+            }
+            crate::QueryVariant::Union(q) => {
+                let left = required_field("left", q.left)?;
+                let right = required_field("right", q.right)?;
+                indradb::Query::Union(indradb::UnionQuery::new(
+                    Box::new((*left).try_into()?),
+                    Box::new((*right).try_into()?),
+                )?)
+            }
+            crate::QueryVariant::Intersect(q) => {
+                let left = required_field("left", q.left)?;
+                let right = required_field("right", q.right)?;
+                indradb::Query::Intersect(indradb::IntersectQuery::new(
+                    Box::new((*left).try_into()?),
+                    Box::new((*right).try_into()?),
+                )?)
+            }
+            crate::QueryVariant::Except(q) => {
+                let left = required_field("left", q.left)?;
+                let right = required_field("right", q.right)?;
+                indradb::Query::Except(indradb::ExceptQuery::new(
+                    Box::new((*left).try_into()?),
+                    Box::new((*right).try_into()?),
+                )?)
+            }
 
+            crate::QueryVariant::Distinct(q) => {
+                let inner = required_field("inner", q.inner)?;
+                indradb::Query::Distinct(indradb::DistinctQuery::new(Box::new((*inner).try_into()?))?)
+            }
+            crate::QueryVariant::OrderBy(q) => {
+                let direction = q.direction().into();
+                let inner = required_field("inner", q.inner)?;
+                let name: indradb::Identifier = required_field("name", q.name)?.try_into()?;
+                indradb::Query::OrderBy(indradb::OrderByQuery {
+                    direction,
+                    limit: q.limit,
+                    ..indradb::OrderByQuery::new(Box::new((*inner).try_into()?), name)?
+                })
+            }
             crate::QueryVariant::Include(q) => {
                 let inner = required_field("inner", q.inner)?;
                 indradb::Query::Include(indradb::IncludeQuery {
@@ -350,8 +867,43 @@ impl TryInto<indradb::Query> for crate::Query {
                 let inner = required_field("inner", q.inner)?;
                 indradb::Query::Count(indradb::CountQuery {
                     inner: Box::new((*inner).try_into()?),
+                    distinct: q.distinct,
                 })
             }
+            crate::QueryVariant::Aggregate(q) => {
+                let function = match q.function() {
+                    crate::AggregateFunction::Sum => indradb::AggregateFunction::Sum,
+                    crate::AggregateFunction::Min => indradb::AggregateFunction::Min,
+                    crate::AggregateFunction::Max => indradb::AggregateFunction::Max,
+                    crate::AggregateFunction::Average => indradb::AggregateFunction::Average,
+                    crate::AggregateFunction::Histogram => indradb::AggregateFunction::Histogram(
+                        required_field("bucket_width", q.bucket_width)?.try_into()?,
+                    ),
+                };
+                let inner = required_field("inner", q.inner)?;
+                let name: indradb::Identifier = required_field("name", q.name)?.try_into()?;
+                let mut aggregate_q = indradb::AggregateQuery::new(Box::new((*inner).try_into()?), name, function)?;
+                if let Some(group_by) = q.group_by {
+                    aggregate_q = aggregate_q.group_by(match group_by {
+                        crate::GroupByVariant::ByType(_) => indradb::GroupBy::Type,
+                        crate::GroupByVariant::ByProperty(name) => indradb::GroupBy::Property(name.try_into()?),
+                    });
+                }
+                indradb::Query::Aggregate(aggregate_q)
+            }
+            crate::QueryVariant::Degree(q) => {
+                let inner = required_field("inner", q.inner)?;
+                let mut degree_q = indradb::DegreeQuery::new(Box::new((*inner).try_into()?))?;
+                if let Some(t) = q.t {
+                    degree_q = degree_q.t(t.try_into()?);
+                }
+                indradb::Query::Degree(degree_q)
+            }
+            crate::QueryVariant::Project(q) => {
+                let inner = required_field("inner", q.inner)?;
+                indradb::Query::Project(indradb::ProjectQuery::new(Box::new((*inner).try_into()?))?)
+            }
+            crate::QueryVariant::Text(text) => text.parse()?,
         })
     }
 }
@@ -378,9 +930,59 @@ impl From<indradb::QueryOutputValue> for crate::QueryOutputValue {
                     edge_properties: edge_properties.into_iter().map(|ep| ep.into()).collect(),
                 })
             }
+            indradb::QueryOutputValue::Paths(paths) => crate::QueryOutputValueVariant::Paths(crate::QueryOutputPaths {
+                paths: paths.into_iter().map(|p| p.into()).collect(),
+            }),
+            indradb::QueryOutputValue::Aggregation(groups) => {
+                crate::QueryOutputValueVariant::Aggregation(crate::QueryOutputAggregation {
+                    groups: groups.into_iter().map(|g| g.into()).collect(),
+                })
+            }
+            indradb::QueryOutputValue::Degrees(degrees) => {
+                crate::QueryOutputValueVariant::Degrees(crate::QueryOutputDegrees {
+                    degrees: degrees.into_iter().map(|d| d.into()).collect(),
+                })
+            }
+            indradb::QueryOutputValue::Subgraph(subgraph) => crate::QueryOutputValueVariant::Subgraph(subgraph.into()),
+            indradb::QueryOutputValue::Rows(rows) => crate::QueryOutputValueVariant::Rows(crate::QueryOutputRows {
+                rows: rows.into_iter().map(|r| r.into()).collect(),
+            }),
         };
 
-        crate::QueryOutputValue { value: Some(variant) }
+        crate::QueryOutputValue {
+            value: Some(variant),
+            more: false,
+            continuation: Vec::new(),
+        }
+    }
+}
+
+impl From<indradb::QueryOutputChunk> for crate::QueryOutputValue {
+    fn from(chunk: indradb::QueryOutputChunk) -> Self {
+        crate::QueryOutputValue {
+            more: chunk.more,
+            continuation: chunk.continuation.map(|c| c.to_bytes()).unwrap_or_default(),
+            ..chunk.value.into()
+        }
+    }
+}
+
+impl TryInto<indradb::QueryOutputChunk> for crate::QueryOutputValue {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<indradb::QueryOutputChunk, Self::Error> {
+        let more = self.more;
+        let continuation = if self.continuation.is_empty() {
+            None
+        } else {
+            Some(indradb::ContinuationToken::from_bytes(&self.continuation)?)
+        };
+        let value: indradb::QueryOutputValue = self.try_into()?;
+        Ok(indradb::QueryOutputChunk {
+            value,
+            more,
+            continuation,
+        })
     }
 }
 
@@ -416,15 +1018,118 @@ impl TryInto<indradb::QueryOutputValue> for crate::QueryOutputValue {
                     .collect();
                 indradb::QueryOutputValue::EdgeProperties(edge_properties?)
             }
+            crate::QueryOutputValueVariant::Paths(paths) => {
+                let paths: Result<Vec<indradb::Path>, ConversionError> =
+                    paths.paths.into_iter().map(|p| p.try_into()).collect();
+                indradb::QueryOutputValue::Paths(paths?)
+            }
+            crate::QueryOutputValueVariant::Aggregation(aggregation) => {
+                let groups: Result<Vec<indradb::AggregationGroup>, ConversionError> =
+                    aggregation.groups.into_iter().map(|g| g.try_into()).collect();
+                indradb::QueryOutputValue::Aggregation(groups?)
+            }
+            crate::QueryOutputValueVariant::Degrees(degrees) => {
+                let degrees: Result<Vec<indradb::VertexDegree>, ConversionError> =
+                    degrees.degrees.into_iter().map(|d| d.try_into()).collect();
+                indradb::QueryOutputValue::Degrees(degrees?)
+            }
+            crate::QueryOutputValueVariant::Subgraph(subgraph) => {
+                indradb::QueryOutputValue::Subgraph(subgraph.try_into()?)
+            }
+            crate::QueryOutputValueVariant::Rows(rows) => {
+                let rows: Result<Vec<indradb::Row>, ConversionError> =
+                    rows.rows.into_iter().map(|r| r.try_into()).collect();
+                indradb::QueryOutputValue::Rows(rows?)
+            }
         })
     }
 }
 
+impl From<indradb::PropertyPredicate> for crate::PropertyPredicate {
+    fn from(predicate: indradb::PropertyPredicate) -> Self {
+        let variant = match predicate {
+            indradb::PropertyPredicate::StartsWith(s) => crate::PropertyPredicateVariant::StartsWith(s),
+            indradb::PropertyPredicate::EndsWith(s) => crate::PropertyPredicateVariant::EndsWith(s),
+            indradb::PropertyPredicate::Contains(s) => crate::PropertyPredicateVariant::Contains(s),
+            indradb::PropertyPredicate::Regex(s) => crate::PropertyPredicateVariant::Regex(s),
+            indradb::PropertyPredicate::In(values) => crate::PropertyPredicateVariant::InSet(crate::JsonValues {
+                values: values.into_iter().map(|v| v.into()).collect(),
+            }),
+            indradb::PropertyPredicate::ArrayContains(value) => {
+                crate::PropertyPredicateVariant::ArrayContains(value.into())
+            }
+            indradb::PropertyPredicate::IsNull => crate::PropertyPredicateVariant::IsNull(()),
+            indradb::PropertyPredicate::IsType(t) => {
+                let t: crate::JsonType = t.into();
+                crate::PropertyPredicateVariant::IsType(t.into())
+            }
+        };
+        crate::PropertyPredicate {
+            predicate: Some(variant),
+        }
+    }
+}
+
+impl TryInto<indradb::PropertyPredicate> for crate::PropertyPredicate {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<indradb::PropertyPredicate, Self::Error> {
+        Ok(match required_field("predicate", self.predicate)? {
+            crate::PropertyPredicateVariant::StartsWith(s) => indradb::PropertyPredicate::StartsWith(s),
+            crate::PropertyPredicateVariant::EndsWith(s) => indradb::PropertyPredicate::EndsWith(s),
+            crate::PropertyPredicateVariant::Contains(s) => indradb::PropertyPredicate::Contains(s),
+            crate::PropertyPredicateVariant::Regex(s) => indradb::PropertyPredicate::Regex(s),
+            crate::PropertyPredicateVariant::InSet(values) => indradb::PropertyPredicate::In(
+                values
+                    .values
+                    .into_iter()
+                    .map(|v| v.try_into())
+                    .collect::<Result<Vec<indradb::Json>, ConversionError>>()?,
+            ),
+            crate::PropertyPredicateVariant::ArrayContains(value) => {
+                indradb::PropertyPredicate::ArrayContains(value.try_into()?)
+            }
+            crate::PropertyPredicateVariant::IsNull(_) => indradb::PropertyPredicate::IsNull,
+            crate::PropertyPredicateVariant::IsType(t) => {
+                let t = crate::JsonType::from_i32(t).ok_or(ValidationError::InvalidValue)?;
+                indradb::PropertyPredicate::IsType(t.into())
+            }
+        })
+    }
+}
+
+impl From<indradb::JsonType> for crate::JsonType {
+    fn from(t: indradb::JsonType) -> Self {
+        match t {
+            indradb::JsonType::Null => crate::JsonType::Null,
+            indradb::JsonType::Bool => crate::JsonType::Bool,
+            indradb::JsonType::Number => crate::JsonType::Number,
+            indradb::JsonType::String => crate::JsonType::String,
+            indradb::JsonType::Array => crate::JsonType::Array,
+            indradb::JsonType::Object => crate::JsonType::Object,
+        }
+    }
+}
+
+impl From<crate::JsonType> for indradb::JsonType {
+    fn from(t: crate::JsonType) -> Self {
+        match t {
+            crate::JsonType::Null => indradb::JsonType::Null,
+            crate::JsonType::Bool => indradb::JsonType::Bool,
+            crate::JsonType::Number => indradb::JsonType::Number,
+            crate::JsonType::String => indradb::JsonType::String,
+            crate::JsonType::Array => indradb::JsonType::Array,
+            crate::JsonType::Object => indradb::JsonType::Object,
+        }
+    }
+}
+
 impl From<indradb::EdgeDirection> for crate::EdgeDirection {
     fn from(direction: indradb::EdgeDirection) -> Self {
         match direction {
             indradb::EdgeDirection::Outbound => crate::EdgeDirection::Outbound,
             indradb::EdgeDirection::Inbound => crate::EdgeDirection::Inbound,
+            indradb::EdgeDirection::Both => crate::EdgeDirection::Both,
         }
     }
 }
@@ -434,6 +1139,37 @@ impl From<crate::EdgeDirection> for indradb::EdgeDirection {
         match direction {
             crate::EdgeDirection::Outbound => indradb::EdgeDirection::Outbound,
             crate::EdgeDirection::Inbound => indradb::EdgeDirection::Inbound,
+            crate::EdgeDirection::Both => indradb::EdgeDirection::Both,
+        }
+    }
+}
+
+impl From<&indradb::AggregateFunction> for crate::AggregateFunction {
+    fn from(function: &indradb::AggregateFunction) -> Self {
+        match function {
+            indradb::AggregateFunction::Sum => crate::AggregateFunction::Sum,
+            indradb::AggregateFunction::Min => crate::AggregateFunction::Min,
+            indradb::AggregateFunction::Max => crate::AggregateFunction::Max,
+            indradb::AggregateFunction::Average => crate::AggregateFunction::Average,
+            indradb::AggregateFunction::Histogram(_) => crate::AggregateFunction::Histogram,
+        }
+    }
+}
+
+impl From<indradb::SortDirection> for crate::SortDirection {
+    fn from(direction: indradb::SortDirection) -> Self {
+        match direction {
+            indradb::SortDirection::Ascending => crate::SortDirection::Ascending,
+            indradb::SortDirection::Descending => crate::SortDirection::Descending,
+        }
+    }
+}
+
+impl From<crate::SortDirection> for indradb::SortDirection {
+    fn from(direction: crate::SortDirection) -> Self {
+        match direction {
+            crate::SortDirection::Ascending => indradb::SortDirection::Ascending,
+            crate::SortDirection::Descending => indradb::SortDirection::Descending,
         }
     }
 }
@@ -617,3 +1353,121 @@ impl From<(indradb::Query, indradb::Identifier, indradb::Json)> for crate::SetPr
         }
     }
 }
+
+impl TryInto<(indradb::Query, bool)> for crate::ExplainRequest {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<(indradb::Query, bool), Self::Error> {
+        let q = required_field("q", self.q)?.try_into()?;
+        Ok((q, self.analyze))
+    }
+}
+
+impl From<(indradb::Query, bool)> for crate::ExplainRequest {
+    fn from(value: (indradb::Query, bool)) -> Self {
+        crate::ExplainRequest {
+            q: Some(value.0.into()),
+            analyze: value.1,
+        }
+    }
+}
+
+impl From<(indradb::Identifier, indradb::ParamValue)> for crate::Param {
+    fn from((name, value): (indradb::Identifier, indradb::ParamValue)) -> Self {
+        crate::Param {
+            name: Some(name.into()),
+            value: Some(match value {
+                indradb::ParamValue::Ids(ids) => crate::ParamValueVariant::Ids(crate::ParamIds {
+                    ids: ids.into_iter().map(|id| id.into()).collect(),
+                }),
+                indradb::ParamValue::Edges(edges) => crate::ParamValueVariant::Edges(crate::ParamEdges {
+                    edges: edges.into_iter().map(|e| e.into()).collect(),
+                }),
+                indradb::ParamValue::Value(value) => crate::ParamValueVariant::Json(value.into()),
+                indradb::ParamValue::Limit(limit) => crate::ParamValueVariant::Limit(limit),
+            }),
+        }
+    }
+}
+
+impl TryInto<(indradb::Identifier, indradb::ParamValue)> for crate::Param {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<(indradb::Identifier, indradb::ParamValue), Self::Error> {
+        let name = required_field("name", self.name)?.try_into()?;
+        let value = match required_field("value", self.value)? {
+            crate::ParamValueVariant::Ids(ids) => {
+                let ids: Result<Vec<Uuid>, ConversionError> = ids.ids.into_iter().map(|id| id.try_into()).collect();
+                indradb::ParamValue::Ids(ids?)
+            }
+            crate::ParamValueVariant::Edges(edges) => {
+                let edges: Result<Vec<indradb::Edge>, ConversionError> =
+                    edges.edges.into_iter().map(|e| e.try_into()).collect();
+                indradb::ParamValue::Edges(edges?)
+            }
+            crate::ParamValueVariant::Json(value) => indradb::ParamValue::Value(value.try_into()?),
+            crate::ParamValueVariant::Limit(limit) => indradb::ParamValue::Limit(limit),
+        };
+        Ok((name, value))
+    }
+}
+
+impl TryInto<(Uuid, indradb::Params)> for crate::ExecutePreparedRequest {
+    type Error = ConversionError;
+
+    fn try_into(self) -> Result<(Uuid, indradb::Params), Self::Error> {
+        let id = required_field("id", self.id)?.try_into()?;
+        let mut params = indradb::Params::new();
+        for param in self.params {
+            let (name, value) = param.try_into()?;
+            params = params.set(name, value);
+        }
+        Ok((id, params))
+    }
+}
+
+impl From<(Uuid, &indradb::Params)> for crate::ExecutePreparedRequest {
+    fn from((id, params): (Uuid, &indradb::Params)) -> Self {
+        crate::ExecutePreparedRequest {
+            id: Some(id.into()),
+            params: params
+                .iter()
+                .map(|(name, value)| (*name, value.clone()).into())
+                .collect(),
+        }
+    }
+}
+
+impl From<indradb::QueryPlan> for crate::QueryPlan {
+    fn from(plan: indradb::QueryPlan) -> Self {
+        crate::QueryPlan {
+            stage: plan.stage,
+            calls: plan.calls,
+            index: plan.indexed.map(crate::query_plan::Index::Indexed),
+            scan: plan.scan,
+            estimate: plan.estimated_rows.map(crate::query_plan::Estimate::EstimatedRows),
+            actual: plan.actual_rows.map(crate::query_plan::Actual::ActualRows),
+            elapsed: plan
+                .elapsed
+                .map(|elapsed| crate::query_plan::Elapsed::ElapsedNanos(elapsed.as_nanos() as u64)),
+            children: plan.children.into_iter().map(|c| c.into()).collect(),
+        }
+    }
+}
+
+impl From<crate::QueryPlan> for indradb::QueryPlan {
+    fn from(plan: crate::QueryPlan) -> Self {
+        let mut converted = indradb::QueryPlan::new(plan.stage, plan.calls);
+        converted.indexed = plan.index.map(|crate::query_plan::Index::Indexed(indexed)| indexed);
+        converted.scan = plan.scan;
+        converted.estimated_rows = plan
+            .estimate
+            .map(|crate::query_plan::Estimate::EstimatedRows(rows)| rows);
+        converted.actual_rows = plan.actual.map(|crate::query_plan::Actual::ActualRows(rows)| rows);
+        converted.elapsed = plan
+            .elapsed
+            .map(|crate::query_plan::Elapsed::ElapsedNanos(nanos)| Duration::from_nanos(nanos));
+        converted.children = plan.children.into_iter().map(|c| c.into()).collect();
+        converted
+    }
+}
@@ -4,10 +4,16 @@ extern crate indradb;
 
 tonic::include_proto!("indradb");
 
+pub use aggregate_query::GroupBy as GroupByVariant;
+pub use aggregation_group::Value as AggregationValueVariant;
 pub use bulk_insert_item::Item as BulkInsertItemVariant;
 pub use indra_db_client::IndraDbClient as ProtoClient;
+pub use param::Value as ParamValueVariant;
+pub use property_predicate::Predicate as PropertyPredicateVariant;
 pub use query::Query as QueryVariant;
 pub use query_output_value::Value as QueryOutputValueVariant;
+pub use row_value::Value as RowValueVariant;
+pub use weighted_shortest_path_query::MissingWeight as MissingWeightVariant;
 
 mod converters;
 pub use converters::*;
@@ -2,28 +2,61 @@ use std::collections::HashMap;
 use std::convert::TryInto;
 use std::error::Error as StdError;
 use std::fmt;
+use std::io;
 use std::path::PathBuf;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
 
 use libloading::Library;
-use tokio::net::TcpListener;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
 use tokio_stream::{Stream, StreamExt};
+use tonic::transport::server::Connected;
 use tonic::transport::{Error as TonicTransportError, Server as TonicServer};
 use tonic::{Request, Response, Status, Streaming};
+use uuid::Uuid;
 
 const CHANNEL_CAPACITY: usize = 100;
 
-fn send(tx: &mpsc::Sender<Result<crate::QueryOutputValue, Status>>, result: Result<crate::QueryOutputValue, Status>) {
+// The maximum number of queries that can be prepared on one connection.
+const MAX_PREPARED_QUERIES: usize = 1000;
+
+type QueryOutputStream = Pin<Box<dyn Stream<Item = Result<crate::QueryOutputValue, Status>> + Send + Sync + 'static>>;
+
+// Prepared queries, by the ID of the connection they were prepared on.
+// Requests that didn't come through a `Connection` share the `None` entry.
+type PreparedQueries = Arc<Mutex<HashMap<Option<u64>, HashMap<Uuid, indradb::PreparedQuery>>>>;
+
+// The ID of the next connection to the server. IDs are unique for the life
+// of the process, unlike the addresses of connections, which may be reused,
+// or may not be known.
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(0);
+
+fn send(
+    tx: &mpsc::Sender<Result<crate::QueryOutputValue, Status>>,
+    result: Result<crate::QueryOutputValue, Status>,
+) -> bool {
     if let Err(err) = tx.blocking_send(result) {
         eprintln!("could not send message to client: {err}");
+        false
+    } else {
+        true
     }
 }
 
 fn map_indradb_result<T>(res: Result<T, indradb::Error>) -> Result<T, Status> {
-    res.map_err(|err| Status::internal(format!("{err}")))
+    res.map_err(|err| match err {
+        indradb::Error::Invalid(_) => Status::invalid_argument(format!("{err}")),
+        indradb::Error::BudgetExceeded(indradb::BudgetLimit::Timeout) => Status::deadline_exceeded(format!("{err}")),
+        indradb::Error::BudgetExceeded(_) => Status::resource_exhausted(format!("{err}")),
+        indradb::Error::Cancelled => Status::cancelled(format!("{err}")),
+        indradb::Error::Conflict => Status::aborted(format!("{err}")),
+        _ => Status::internal(format!("{err}")),
+    })
 }
 
 fn map_conversion_result<T>(res: Result<T, crate::ConversionError>) -> Result<T, Status> {
@@ -122,11 +155,75 @@ struct Plugins {
     _libraries: Vec<Library>,
 }
 
+// A connection to the server, which drops the queries prepared on it once
+// it's closed.
+struct Connection {
+    stream: TcpStream,
+    id: u64,
+    prepared: PreparedQueries,
+}
+
+impl Connection {
+    fn new(stream: TcpStream, prepared: PreparedQueries) -> Self {
+        Self {
+            stream,
+            id: NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
+            prepared,
+        }
+    }
+}
+
+// The information about a connection that's attached to its requests.
+#[derive(Clone)]
+struct ConnectionInfo {
+    id: u64,
+}
+
+// Gets the ID of the connection that a request came through.
+fn connection_id<T>(request: &Request<T>) -> Option<u64> {
+    request.extensions().get::<ConnectionInfo>().map(|info| info.id)
+}
+
+impl Connected for Connection {
+    type ConnectInfo = ConnectionInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        ConnectionInfo { id: self.id }
+    }
+}
+
+impl AsyncRead for Connection {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Connection {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_shutdown(cx)
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.prepared.lock().unwrap().remove(&Some(self.id));
+    }
+}
+
 /// The IndraDB server implementation.
 #[derive(Clone)]
 pub struct Server<D: indradb::Datastore + Send + Sync + 'static> {
     db: Arc<indradb::Database<D>>,
     plugins: Arc<Plugins>,
+    prepared: PreparedQueries,
 }
 
 impl<D: indradb::Datastore + Send + Sync + 'static> Server<D> {
@@ -138,6 +235,7 @@ impl<D: indradb::Datastore + Send + Sync + 'static> Server<D> {
         Self {
             db,
             plugins: Arc::new(Plugins::default()),
+            prepared: PreparedQueries::default(),
         }
     }
 
@@ -187,8 +285,58 @@ impl<D: indradb::Datastore + Send + Sync + 'static> Server<D> {
                 _libraries: libraries,
                 entries: plugin_entries,
             }),
+            prepared: PreparedQueries::default(),
         })
     }
+
+    // Wraps incoming connections so that the queries prepared on them are
+    // dropped once they're closed.
+    fn incoming(&self, listener: TcpListener) -> impl Stream<Item = io::Result<Connection>> {
+        let prepared = self.prepared.clone();
+        TcpListenerStream::new(listener)
+            .map(move |stream| stream.map(|stream| Connection::new(stream, prepared.clone())))
+    }
+
+    // Runs a query, streaming its output back to the client.
+    fn stream(&self, q: indradb::Query) -> QueryOutputStream {
+        let db = self.db.clone();
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+
+        // Cancel the query if the client goes away while it's running. The
+        // watcher holds onto a sender, so it's stopped once the query is
+        // done, letting the stream end.
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let budget = db.budget().clone().cancelled_by(cancelled.clone());
+        let watcher_tx = tx.clone();
+        let watcher = tokio::spawn(async move {
+            watcher_tx.closed().await;
+            cancelled.store(true, Ordering::Relaxed);
+        });
+
+        tokio::task::spawn_blocking(move || {
+            match map_indradb_result(db.get_iter_with_budget(q, &budget)) {
+                Ok(chunks) => {
+                    for chunk in chunks {
+                        // Stop producing chunks if the client went away or
+                        // the query failed midway.
+                        let (res, failed) = match map_indradb_result(chunk) {
+                            Ok(chunk) => (Ok(chunk.into()), false),
+                            Err(err) => (Err(err), true),
+                        };
+                        if !send(&tx, res) || failed {
+                            break;
+                        }
+                    }
+                }
+                Err(err) => {
+                    send(&tx, Err(err));
+                }
+            }
+            watcher.abort();
+        });
+
+        Box::pin(ReceiverStream::new(rx))
+    }
 }
 
 #[tonic::async_trait]
@@ -227,21 +375,10 @@ impl<D: indradb::Datastore + Send + Sync + 'static> crate::indra_db_server::Indr
         Ok(Response::new(crate::CreateResponse { created: res }))
     }
 
-    type GetStream = Pin<Box<dyn Stream<Item = Result<crate::QueryOutputValue, Status>> + Send + Sync + 'static>>;
+    type GetStream = QueryOutputStream;
     async fn get(&self, request: Request<crate::Query>) -> Result<Response<Self::GetStream>, Status> {
-        let db = self.db.clone();
         let q: indradb::Query = map_conversion_result(request.into_inner().try_into())?;
-        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
-        tokio::task::spawn_blocking(move || match map_indradb_result(db.get(q)) {
-            Ok(output) => {
-                for output in output.into_iter() {
-                    send(&tx, Ok(output.into()));
-                }
-            }
-            Err(err) => send(&tx, Err(err)),
-        });
-
-        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+        Ok(Response::new(self.stream(q)))
     }
 
     async fn delete(&self, request: Request<crate::Query>) -> Result<Response<()>, Status> {
@@ -303,6 +440,8 @@ impl<D: indradb::Datastore + Send + Sync + 'static> crate::indra_db_server::Indr
                     .call(&mut txn, arg)
                     .map_err(|err| Status::internal(format!("{err}")))?
             };
+            // The plugin's changes are rolled back if it fails
+            map_indradb_result(indradb::Transaction::commit(&mut txn))?;
             Ok(Response::new(crate::ExecutePluginResponse {
                 value: Some(response.into()),
             }))
@@ -310,6 +449,53 @@ impl<D: indradb::Datastore + Send + Sync + 'static> crate::indra_db_server::Indr
             Err(Status::not_found("unknown plugin"))
         }
     }
+
+    async fn explain(&self, request: Request<crate::ExplainRequest>) -> Result<Response<crate::QueryPlan>, Status> {
+        let db = self.db.clone();
+        let (q, analyze) = map_conversion_result(request.into_inner().try_into())?;
+        let plan = map_jh_indra_result(
+            tokio::task::spawn_blocking(move || if analyze { db.analyze(q) } else { db.explain(q) }).await,
+        )?;
+        Ok(Response::new(plan.into()))
+    }
+
+    async fn prepare_query(&self, request: Request<crate::Query>) -> Result<Response<crate::Uuid>, Status> {
+        let connection_id = connection_id(&request);
+        let q: indradb::Query = map_conversion_result(request.into_inner().try_into())?;
+        let prepared = map_indradb_result(self.db.prepare(q))?;
+
+        let mut connections = self.prepared.lock().unwrap();
+        let queries = connections.entry(connection_id).or_default();
+        if queries.len() >= MAX_PREPARED_QUERIES {
+            return Err(Status::resource_exhausted(
+                "too many queries prepared on this connection",
+            ));
+        }
+        let id = indradb::util::generate_uuid_v1();
+        queries.insert(id, prepared);
+        Ok(Response::new(id.into()))
+    }
+
+    type ExecutePreparedStream = QueryOutputStream;
+    async fn execute_prepared(
+        &self,
+        request: Request<crate::ExecutePreparedRequest>,
+    ) -> Result<Response<Self::ExecutePreparedStream>, Status> {
+        let connection_id = connection_id(&request);
+        let (id, params) = map_conversion_result(request.into_inner().try_into())?;
+        let prepared = self
+            .prepared
+            .lock()
+            .unwrap()
+            .get(&connection_id)
+            .and_then(|queries| queries.get(&id))
+            .cloned()
+            .ok_or_else(|| Status::not_found("unknown prepared query"))?;
+        let q = prepared
+            .bind(&params)
+            .map_err(|err| Status::invalid_argument(format!("{err}")))?;
+        Ok(Response::new(self.stream(q)))
+    }
 }
 
 /// Runs the IndraDB server.
@@ -325,8 +511,9 @@ pub async fn run<D>(db: Arc<indradb::Database<D>>, listener: TcpListener) -> Res
 where
     D: indradb::Datastore + Send + Sync + 'static,
 {
-    let service = crate::indra_db_server::IndraDbServer::new(Server::new(db));
-    let incoming = TcpListenerStream::new(listener);
+    let server = Server::new(db);
+    let incoming = server.incoming(listener);
+    let service = crate::indra_db_server::IndraDbServer::new(server);
     TonicServer::builder()
         .add_service(service)
         .serve_with_incoming(incoming)
@@ -364,8 +551,8 @@ where
     }
 
     let server = Server::new_with_plugins(db, plugin_paths)?;
+    let incoming = server.incoming(listener);
     let service = crate::indra_db_server::IndraDbServer::new(server);
-    let incoming = TcpListenerStream::new(listener);
     TonicServer::builder()
         .add_service(service)
         .serve_with_incoming(incoming)
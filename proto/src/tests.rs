@@ -8,15 +8,16 @@
 
 use std::cell::RefCell;
 use std::convert::TryInto;
+use std::ops::Bound;
 use std::rc::Rc;
 use std::result::Result as StdResult;
 use std::time::Duration;
 
 use indradb::{
     util, AllEdgeQuery, AllVertexQuery, BulkInsertItem, CountQueryExt, Datastore, DynIter, Edge,
-    EdgeWithPropertyPresenceQuery, EdgeWithPropertyValueQuery, Error, Identifier, Json, Query, QueryExt,
-    QueryOutputValue, RangeVertexQuery, Result, SpecificEdgeQuery, SpecificVertexQuery, Transaction, Vertex,
-    VertexWithPropertyPresenceQuery, VertexWithPropertyValueQuery,
+    EdgeWithPropertyPresenceQuery, EdgeWithPropertyRangeQuery, EdgeWithPropertyValueQuery, Error, Identifier, Json,
+    Query, QueryExt, QueryOutputValue, RangeVertexQuery, Result, SpecificEdgeQuery, SpecificVertexQuery, Transaction,
+    Vertex, VertexWithPropertyPresenceQuery, VertexWithPropertyRangeQuery, VertexWithPropertyValueQuery,
 };
 
 use tokio::runtime::Runtime;
@@ -106,6 +107,17 @@ impl<'a> Transaction<'a> for ClientTransaction {
         Ok(Some(Box::new(vertices.into_iter().map(|v| Ok(v.id)))))
     }
 
+    fn vertex_ids_with_property_range(
+        &'a self,
+        name: Identifier,
+        low: Bound<&Json>,
+        high: Bound<&Json>,
+    ) -> Result<Option<DynIter<'a, Uuid>>> {
+        let q = VertexWithPropertyRangeQuery::new(name, low.cloned(), high.cloned()).unwrap();
+        let vertices = util::extract_vertices(self.get(q)?).unwrap();
+        Ok(Some(Box::new(vertices.into_iter().map(|v| Ok(v.id)))))
+    }
+
     fn edge_count(&self) -> u64 {
         self.get_count(AllEdgeQuery.count().unwrap())
     }
@@ -121,12 +133,13 @@ impl<'a> Transaction<'a> for ClientTransaction {
     }
 
     fn range_reversed_edges(&'a self, offset: Edge) -> Result<DynIter<'a, Edge>> {
-        let edges = util::extract_edges(self.get(AllEdgeQuery)?).unwrap();
-        let iter = edges
+        let mut edges: Vec<Edge> = util::extract_edges(self.get(AllEdgeQuery)?)
+            .unwrap()
             .into_iter()
             .map(|e| e.reversed())
-            .filter(move |e| e >= &offset)
-            .map(Ok);
+            .collect();
+        edges.sort();
+        let iter = edges.into_iter().filter(move |e| e >= &offset).map(Ok);
         Ok(Box::new(iter))
     }
 
@@ -146,6 +159,17 @@ impl<'a> Transaction<'a> for ClientTransaction {
         Ok(Some(Box::new(edges.into_iter().map(Ok))))
     }
 
+    fn edges_with_property_range(
+        &'a self,
+        name: Identifier,
+        low: Bound<&Json>,
+        high: Bound<&Json>,
+    ) -> Result<Option<DynIter<'a, Edge>>> {
+        let q = EdgeWithPropertyRangeQuery::new(name, low.cloned(), high.cloned()).unwrap();
+        let edges = util::extract_edges(self.get(q)?).unwrap();
+        Ok(Some(Box::new(edges.into_iter().map(Ok))))
+    }
+
     fn vertex_property(&self, vertex: &Vertex, name: Identifier) -> Result<Option<Json>> {
         let q = SpecificVertexQuery::single(vertex.id).properties().unwrap().name(name);
         let props = util::extract_vertex_properties(self.get(q)?).unwrap();
@@ -305,14 +329,12 @@ impl Datastore for ClientDatastore {
     }
 }
 
-full_test_impl!({
-    use indradb::Database;
+/// Starts a server backed by an in-memory datastore, returning its port.
+pub fn start_server(rt: &Runtime) -> u16 {
     use std::net::ToSocketAddrs;
     use std::sync::Arc;
     use tokio::net::TcpListener;
 
-    let rt = Runtime::new().unwrap();
-
     let addr = "127.0.0.1:0".to_socket_addrs().unwrap().next().unwrap();
     let listener = rt.block_on(TcpListener::bind(&addr)).unwrap();
     let port = listener.local_addr().unwrap().port();
@@ -320,6 +342,100 @@ full_test_impl!({
         Arc::new(indradb::MemoryDatastore::new_db()),
         listener,
     ));
+    port
+}
+
+full_test_impl!({
+    use indradb::Database;
 
-    Database::new(ClientDatastore::new(port as u16, rt))
+    let rt = Runtime::new().unwrap();
+    let port = start_server(&rt);
+    Database::new(ClientDatastore::new(port, rt))
 });
+
+#[test]
+fn should_prepare_queries_per_connection() {
+    use indradb::Params;
+
+    let rt = Runtime::new().unwrap();
+    let port = start_server(&rt);
+    let endpoint: Endpoint = format!("http://127.0.0.1:{port}").try_into().unwrap();
+    let code = |result: StdResult<Vec<QueryOutputValue>, crate::ClientError>| match result {
+        Err(crate::ClientError::Grpc { inner }) => inner.code(),
+        result => panic!("expected a gRPC error, got {result:?}"),
+    };
+
+    rt.block_on(async {
+        let mut client = crate::Client::new(endpoint.clone()).await.unwrap();
+        let id = client
+            .create_vertex_from_type(Identifier::new("test_vertex_type").unwrap())
+            .await
+            .unwrap();
+        let ids = Identifier::new("ids").unwrap();
+        let prepared_id = client.prepare(SpecificVertexQuery::from_param(ids)).await.unwrap();
+
+        let output = client
+            .execute_prepared(prepared_id, &Params::new().ids(ids, vec![id]))
+            .await
+            .unwrap();
+        let vertices = util::extract_vertices(output).unwrap();
+        assert_eq!(vertices.len(), 1);
+        assert_eq!(vertices[0].id, id);
+
+        let result = client.execute_prepared(prepared_id, &Params::new()).await;
+        assert_eq!(code(result), tonic::Code::InvalidArgument);
+
+        // Prepared queries aren't shared with other connections
+        let mut other_client = crate::Client::new(endpoint).await.unwrap();
+        let result = other_client
+            .execute_prepared(prepared_id, &Params::new().ids(ids, vec![id]))
+            .await;
+        assert_eq!(code(result), tonic::Code::NotFound);
+    });
+}
+
+#[test]
+fn should_page_through_query_output() {
+    use indradb::ResumeQuery;
+
+    let rt = Runtime::new().unwrap();
+    let port = start_server(&rt);
+    let endpoint: Endpoint = format!("http://127.0.0.1:{port}").try_into().unwrap();
+
+    rt.block_on(async {
+        let mut client = crate::Client::new(endpoint).await.unwrap();
+        let t = Identifier::new("test_vertex_type").unwrap();
+        let edge_t = Identifier::new("test_edge_type").unwrap();
+        let hub_id = client.create_vertex_from_type(t).await.unwrap();
+        for _ in 0..5 {
+            let id = client.create_vertex_from_type(t).await.unwrap();
+            client.create_edge(&Edge::new(hub_id, edge_t, id)).await.unwrap();
+        }
+        let q = SpecificVertexQuery::single(hub_id).outbound().unwrap();
+        let expected = util::extract_edges(client.get(q.clone()).await.unwrap()).unwrap();
+        assert_eq!(expected.len(), 5);
+
+        // Page through the edges two at a time, resuming from the token
+        // returned with each page
+        let q: Query = q.limit(2).into();
+        let (output, mut continuation) = client.get_page(q.clone()).await.unwrap();
+        let mut edges = util::extract_edges(output).unwrap();
+        let mut pages = 1;
+        while let Some(token) = continuation {
+            let resumed = ResumeQuery::new(Box::new(q.clone()), token).unwrap();
+            let (output, next_continuation) = client.get_page(resumed).await.unwrap();
+            let page = util::extract_edges(output).unwrap();
+            if !page.is_empty() {
+                pages += 1;
+            }
+            edges.extend(page);
+            continuation = next_continuation;
+        }
+        assert_eq!(pages, 3);
+        assert_eq!(edges, expected);
+
+        // Queries that can't be resumed don't return a token
+        let (_, continuation) = client.get_page(SpecificVertexQuery::single(hub_id)).await.unwrap();
+        assert_eq!(continuation, None);
+    });
+}
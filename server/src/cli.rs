@@ -1,10 +1,13 @@
-use clap::{value_t, App, Arg, SubCommand};
+use clap::{value_t, App, Arg, ArgMatches, SubCommand};
 use std::ffi::OsString;
 
 pub struct CliArgs {
     pub addr: String,
     pub datastore_args: CliDatastoreArgs,
     pub plugin_path: Option<String>,
+    pub query_timeout: Option<u64>,
+    pub max_stage_rows: Option<u64>,
+    pub max_output_rows: Option<u64>,
 }
 
 pub enum CliDatastoreArgs {
@@ -20,6 +23,9 @@ pub enum CliDatastoreArgs {
 
 const ADDRESS: &str = "ADDRESS";
 const PLUGIN_PATH: &str = "PLUGIN_PATH";
+const QUERY_TIMEOUT: &str = "QUERY_TIMEOUT";
+const MAX_STAGE_ROWS: &str = "MAX_STAGE_ROWS";
+const MAX_OUTPUT_ROWS: &str = "MAX_OUTPUT_ROWS";
 const DATABASE_PATH: &str = "DATABASE_PATH";
 const ROCKSDB_MAX_OPEN_FILES: &str = "ROCKSDB_MAX_OPEN_FILES";
 const ROCKSDB_REPAIR: &str = "ROCKSDB_REPAIR";
@@ -46,6 +52,24 @@ pub fn parse_cli_args() -> CliArgs {
         .help("Path to plugins")
         .takes_value(true);
 
+    let query_timeout = Arg::with_name(QUERY_TIMEOUT)
+        .long("query-timeout")
+        .value_name(QUERY_TIMEOUT)
+        .help("The number of milliseconds a query can run for. If unset, queries can run for any amount of time.")
+        .takes_value(true);
+
+    let max_stage_rows = Arg::with_name(MAX_STAGE_ROWS)
+        .long("max-stage-rows")
+        .value_name(MAX_STAGE_ROWS)
+        .help("The maximum number of values any one stage of a query can output. If unset, there is no limit.")
+        .takes_value(true);
+
+    let max_output_rows = Arg::with_name(MAX_OUTPUT_ROWS)
+        .long("max-output-rows")
+        .value_name(MAX_OUTPUT_ROWS)
+        .help("The maximum number of values a query can output in total. If unset, there is no limit.")
+        .takes_value(true);
+
     let memory_subcommand = SubCommand::with_name("memory")
         .about("Start an indradb instance backed by memory. This is the default, so including this subcommand is only useful if you want to set options.")
         .arg(
@@ -78,6 +102,9 @@ pub fn parse_cli_args() -> CliArgs {
     let matches = App::new("indradb-server")
         .arg(&addr)
         .arg(&plugin_path)
+        .arg(&query_timeout)
+        .arg(&max_stage_rows)
+        .arg(&max_output_rows)
         .subcommand(memory_subcommand)
         .subcommand(rocksdb_subcommand)
         .get_matches();
@@ -102,5 +129,16 @@ pub fn parse_cli_args() -> CliArgs {
             CliDatastoreArgs::Memory { path: None }
         },
         plugin_path: matches.value_of(PLUGIN_PATH).map(|s| s.to_string()),
+        query_timeout: optional_u64(&matches, QUERY_TIMEOUT),
+        max_stage_rows: optional_u64(&matches, MAX_STAGE_ROWS),
+        max_output_rows: optional_u64(&matches, MAX_OUTPUT_ROWS),
+    }
+}
+
+fn optional_u64(matches: &ArgMatches, name: &str) -> Option<u64> {
+    if matches.is_present(name) {
+        Some(value_t!(matches, name, u64).unwrap_or_else(|e| e.exit()))
+    } else {
+        None
     }
 }
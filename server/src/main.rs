@@ -6,6 +6,7 @@ use std::error::Error;
 use std::net::ToSocketAddrs;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::cli::CliDatastoreArgs;
 
@@ -41,6 +42,11 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
     let addr = args.addr.to_socket_addrs()?.next().unwrap();
     let listener = TcpListener::bind(addr).await?;
 
+    let mut budget = indradb::QueryBudget::new();
+    budget.timeout = args.query_timeout.map(Duration::from_millis);
+    budget.max_stage_rows = args.max_stage_rows;
+    budget.max_output_rows = args.max_output_rows;
+
     match args.datastore_args {
         CliDatastoreArgs::Rocksdb {
             path,
@@ -59,7 +65,7 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
                 &indradb::RocksdbDatastore::get_options(Some(max_open_files)),
             )
             .expect("Expected to be able to create the RocksDB datastore");
-            run_server(datastore, listener, &args.plugin_path).await
+            run_server(datastore.with_budget(budget), listener, &args.plugin_path).await
         }
         CliDatastoreArgs::Memory { path } => {
             let datastore = match path {
@@ -67,7 +73,7 @@ pub async fn main() -> Result<(), Box<dyn Error>> {
                 Some(path) if Path::new(path.as_os_str()).exists() => indradb::MemoryDatastore::read_msgpack_db(path)?,
                 Some(path) => indradb::MemoryDatastore::create_msgpack_db(path),
             };
-            run_server(datastore, listener, &args.plugin_path).await
+            run_server(datastore.with_budget(budget), listener, &args.plugin_path).await
         }
     }
 }